@@ -0,0 +1,52 @@
+//! Benchmark the model lookup in a large library.
+//!
+//! ```sh
+//! cargo bench --bench library
+//! ```
+use std::{fs, io, time::Instant};
+
+use elektron_spice::Circuit;
+
+const FILES: usize = 200;
+const MODELS: usize = 50;
+const INSTANCES: usize = 500;
+const RUNS: u32 = 10;
+
+fn main() {
+    let dir = std::env::temp_dir().join(format!("elektron_spice_bench_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for file in 0..FILES {
+        let content: String = (0..MODELS)
+            .map(|model| format!(".model Q{}_{} NPN(IS=1e-14 BF=200 VAF=100)\n", file, model))
+            .collect();
+        fs::write(dir.join(format!("bjt_{}.lib", file)), content).unwrap();
+    }
+
+    let mut circuit = Circuit::new(String::from("bench"), Vec::new());
+    circuit.set_library(vec![dir.clone()]);
+    for index in 0..INSTANCES {
+        //the models are spread over all the library files.
+        let model = format!("Q{}_{}", FILES - 1 - index % FILES, index % MODELS);
+        circuit
+            .bjt(format!("Q{}", index), "c", "b", "0", model)
+            .unwrap();
+    }
+
+    let start = Instant::now();
+    circuit.save(io::sink()).unwrap();
+    let first = start.elapsed();
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        circuit.save(io::sink()).unwrap();
+    }
+    let cached = start.elapsed() / RUNS;
+    println!(
+        "{} models in {} files, {} instances: first netlist {:?}, next netlists {:?}",
+        FILES * MODELS,
+        FILES,
+        INSTANCES,
+        first,
+        cached
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,104 @@
+use crate::Simulation;
+
+/// Presets of the simulator options for the accuracy and the convergence.
+///
+/// `Fast` relaxes the tolerances for quick previews, `Default` are the
+/// ngspice defaults, `Precise` tightens the tolerances and `Desperate`
+/// helps circuits that do not converge with relaxed tolerances, a larger
+/// gmin, more iterations and the gear integration.
+///
+/// ```ignore
+/// let mut simulation = Simulation::new(circuit);
+/// simulation.set_accuracy(Accuracy::Precise);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Accuracy {
+    Fast,
+    Default,
+    Precise,
+    Desperate,
+}
+
+impl Accuracy {
+    /// The simulator options of the preset.
+    pub fn options(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Accuracy::Fast => &[
+                ("reltol", "0.01"),
+                ("abstol", "1e-10"),
+                ("vntol", "1e-4"),
+                ("gmin", "1e-12"),
+                ("itl1", "100"),
+                ("itl4", "10"),
+                ("method", "trap"),
+            ],
+            Accuracy::Default => &[
+                ("reltol", "0.001"),
+                ("abstol", "1e-12"),
+                ("vntol", "1e-6"),
+                ("gmin", "1e-12"),
+                ("itl1", "100"),
+                ("itl4", "10"),
+                ("method", "trap"),
+            ],
+            Accuracy::Precise => &[
+                ("reltol", "1e-4"),
+                ("abstol", "1e-14"),
+                ("vntol", "1e-7"),
+                ("gmin", "1e-12"),
+                ("itl1", "200"),
+                ("itl4", "50"),
+                ("method", "trap"),
+            ],
+            Accuracy::Desperate => &[
+                ("reltol", "0.01"),
+                ("abstol", "1e-9"),
+                ("vntol", "1e-3"),
+                ("gmin", "1e-9"),
+                ("itl1", "1000"),
+                ("itl2", "500"),
+                ("itl4", "200"),
+                ("method", "gear"),
+            ],
+        }
+    }
+}
+
+impl Simulation {
+    /// Set the options of the preset, the other options are kept.
+    pub fn set_accuracy(&mut self, accuracy: Accuracy) {
+        for (name, value) in accuracy.options() {
+            self.set_option(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Accuracy;
+    use crate::{Circuit, Simulation};
+
+    #[test]
+    fn accuracy() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(String::from("R1"), "in", "GND", String::from("1k"))
+            .unwrap();
+        let mut simulation = Simulation::new(circuit);
+        simulation.set_option("temp", "50");
+        simulation.set_accuracy(Accuracy::Fast);
+        simulation.set_accuracy(Accuracy::Desperate);
+        simulation.set_option("savecurrents", "");
+        assert_eq!(
+            vec![
+                String::from("R1 in 0 1k"),
+                String::from(
+                    ".options temp=50 reltol=0.01 abstol=1e-9 vntol=1e-3 gmin=1e-9 itl1=1000 \
+                     itl4=200 method=gear itl2=500 savecurrents"
+                ),
+                String::from(".end"),
+            ],
+            simulation.deck().unwrap()
+        );
+    }
+}
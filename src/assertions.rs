@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use crate::{step::crossing, AnalysisResult, HierarchicalName};
+
+/// The number of samples before and after the failing point.
+const CONTEXT: usize = 2;
+
+/// The samples around the index.
+fn samples(scale: &str, x: &[f64], values: &[f64], index: usize) -> String {
+    let range = index.saturating_sub(CONTEXT)..(index + CONTEXT + 1).min(values.len());
+    range
+        .map(|i| format!("\n  {} = {:e}: {}", scale, x[i], values[i]))
+        .collect()
+}
+
+#[track_caller]
+fn vector<'a>(vectors: &'a HashMap<String, Vec<f64>>, name: &str) -> &'a Vec<f64> {
+    let found = match vectors.get(name) {
+        Some(vector) => Some(vector),
+        None => HierarchicalName::parse(name).find(vectors),
+    };
+    match found {
+        Some(vector) => vector,
+        None => {
+            let mut names: Vec<&String> = vectors.keys().collect();
+            names.sort();
+            panic!("no vector {}, the results contain {:?}", name, names)
+        }
+    }
+}
+
+/// Assert that all values of the node are between min and max.
+///
+/// The assertions panic with the samples next to the failing point.
+///
+/// ```ignore
+/// let result = simulation.tran("1u", "10m", "0");
+/// assert_voltage_between(&result, "out", 4.9, 5.1);
+/// assert_rises_within(&result, "out", 2.5, 1e-3);
+/// let ac = simulation.ac_complex("10", "100k", 20, "dec")?;
+/// assert_gain_db(&ac, "out", 1e3, -3.0, 0.5);
+/// ```
+#[track_caller]
+pub fn assert_voltage_between(vectors: &HashMap<String, Vec<f64>>, node: &str, min: f64, max: f64) {
+    let time = vector(vectors, "time");
+    let values = vector(vectors, node);
+    if let Some(index) = values.iter().position(|v| !(min..=max).contains(v)) {
+        panic!(
+            "V({}) = {} at time = {:e} is not between {} and {}{}",
+            node,
+            values[index],
+            time[index],
+            min,
+            max,
+            samples("time", time, values, index)
+        );
+    }
+}
+
+/// Assert that the node rises above the level before the time.
+#[track_caller]
+pub fn assert_rises_within(
+    vectors: &HashMap<String, Vec<f64>>,
+    node: &str,
+    level: f64,
+    within: f64,
+) {
+    let time = vector(vectors, "time");
+    let values = vector(vectors, node);
+    match crossing(time, values, level, true, 0.0) {
+        Some(t) if t <= within => {}
+        Some(t) => {
+            let index = time.partition_point(|x| *x < t);
+            panic!(
+                "V({}) rises above {} at time = {:e}, later than {:e}{}",
+                node,
+                level,
+                t,
+                within,
+                samples("time", time, values, index)
+            );
+        }
+        None => {
+            let index = values
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(i, _)| i)
+                .unwrap_or_default();
+            panic!(
+                "V({}) does not rise above {}, the maximum is {} at time = {:e}{}",
+                node,
+                level,
+                values.get(index).copied().unwrap_or(f64::NAN),
+                time.get(index).copied().unwrap_or(f64::NAN),
+                samples("time", time, values, index)
+            );
+        }
+    }
+}
+
+/// Assert the gain of the node in dB at the frequency within the
+/// tolerance, the gain is interpolated over the logarithmic frequency.
+#[track_caller]
+pub fn assert_gain_db(
+    result: &AnalysisResult,
+    node: &str,
+    frequency: f64,
+    expected: f64,
+    tolerance: f64,
+) {
+    let bode = match result.bode(node) {
+        Ok(bode) => bode,
+        Err(err) => panic!("{}", err),
+    };
+    let (f, magnitude) = (&bode.frequency, &bode.magnitude);
+    let index = f.partition_point(|x| *x < frequency);
+    if f.is_empty() || index == 0 && f[0] != frequency || index >= f.len() {
+        panic!(
+            "{:e}Hz is outside of the simulated frequencies {:e}Hz to {:e}Hz",
+            frequency,
+            f.first().copied().unwrap_or(f64::NAN),
+            f.last().copied().unwrap_or(f64::NAN)
+        );
+    }
+    let gain = if index == 0 || f[index] == frequency {
+        magnitude[index]
+    } else {
+        let (f0, f1) = (f[index - 1].log10(), f[index].log10());
+        let (g0, g1) = (magnitude[index - 1], magnitude[index]);
+        g0 + (g1 - g0) * (frequency.log10() - f0) / (f1 - f0)
+    };
+    if (gain - expected).abs() > tolerance {
+        panic!(
+            "the gain of {} at {:e}Hz is {:.3}dB, expected {}dB ±{}dB{}",
+            node,
+            frequency,
+            gain,
+            expected,
+            tolerance,
+            samples("frequency", f, magnitude, index)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, panic::catch_unwind};
+
+    use num_complex::Complex64;
+
+    use super::{assert_gain_db, assert_rises_within, assert_voltage_between};
+    use crate::AnalysisResult;
+
+    #[test]
+    fn assertions() {
+        let vectors = HashMap::from([
+            (String::from("time"), vec![0.0, 1e-3, 2e-3, 3e-3, 4e-3]),
+            (String::from("V(out)"), vec![0.0, 2.0, 4.0, 5.5, 5.0]),
+        ]);
+        assert_voltage_between(&vectors, "out", 0.0, 6.0);
+        assert_rises_within(&vectors, "out", 3.0, 2e-3);
+        let message = catch_unwind(|| assert_voltage_between(&vectors, "out", 0.0, 5.0))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert_eq!(
+            "V(out) = 5.5 at time = 3e-3 is not between 0 and 5\n  \
+             time = 1e-3: 2\n  time = 2e-3: 4\n  time = 3e-3: 5.5\n  time = 4e-3: 5",
+            *message
+        );
+        assert!(catch_unwind(|| assert_rises_within(&vectors, "out", 3.0, 1e-3)).is_err());
+        assert!(catch_unwind(|| assert_rises_within(&vectors, "out", 6.0, 1e-3)).is_err());
+        let ac = AnalysisResult::from(HashMap::from([
+            (
+                String::from("frequency"),
+                vec![Complex64::new(100.0, 0.0), Complex64::new(1e4, 0.0)],
+            ),
+            (
+                String::from("out"),
+                vec![Complex64::new(1.0, 0.0), Complex64::new(0.01, 0.0)],
+            ),
+        ]));
+        assert_gain_db(&ac, "out", 1e3, -20.0, 0.01);
+        assert!(catch_unwind(|| assert_gain_db(&ac, "out", 1e3, -3.0, 0.5)).is_err());
+        assert!(catch_unwind(|| assert_gain_db(&ac, "out", 1e5, -3.0, 0.5)).is_err());
+    }
+}
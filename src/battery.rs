@@ -0,0 +1,153 @@
+use crate::{error::Error, node::IntoNode, Circuit, Value};
+
+/// A battery with the open circuit voltage over the state of charge.
+///
+/// The charge is integrated with the XSPICE `int` model from the current of
+/// the battery, the state of charge from 0 to 1 is the voltage of the node
+/// `<reference>_soc`. The open circuit voltage is interpolated from the
+/// table and the internal resistance is in series.
+///
+/// ```ignore
+/// let table = vec![(0.0, 6.0), (0.1, 7.2), (0.5, 8.4), (0.9, 9.0), (1.0, 9.5)];
+/// let mut battery = Battery::new(0.5, table, 1.5);
+/// battery.set_charge(0.8);
+/// battery.build(&mut circuit, "BAT1", "vcc", "GND")?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Battery {
+    /// The capacity in Ah.
+    pub capacity: f64,
+    /// The open circuit voltage by state of charge.
+    pub table: Vec<(f64, f64)>,
+    /// The internal resistance.
+    pub resistance: f64,
+    /// The state of charge at the start of the simulation.
+    pub charge: f64,
+}
+
+impl Battery {
+    pub fn new(capacity: f64, table: Vec<(f64, f64)>, resistance: f64) -> Self {
+        Self {
+            capacity,
+            table,
+            resistance,
+            charge: 1.0,
+        }
+    }
+    /// Set the initial state of charge, the default is 1.
+    pub fn set_charge(&mut self, charge: f64) {
+        self.charge = charge;
+    }
+    /// The open circuit voltage at the state of charge.
+    pub fn open_circuit(&self, charge: f64) -> Result<f64, Error> {
+        self.validate()?;
+        let index = self
+            .table
+            .iter()
+            .position(|(soc, _)| *soc >= charge)
+            .unwrap_or(self.table.len() - 1)
+            .max(1);
+        let ((s0, v0), (s1, v1)) = (self.table[index - 1], self.table[index]);
+        let charge = charge.clamp(self.table[0].0, self.table[self.table.len() - 1].0);
+        Ok(v0 + (v1 - v0) * (charge - s0) / (s1 - s0))
+    }
+    /// Add the battery with the positive and the negative terminal.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        reference: &str,
+        plus: impl IntoNode,
+        minus: impl IntoNode,
+    ) -> Result<(), Error> {
+        self.validate()?;
+        let node = |suffix: &str| format!("{}_{}", reference, suffix).to_lowercase();
+        let (current, soc, ocv, internal) = (node("i"), node("soc"), node("ocv"), node("r"));
+        let model = node("int");
+        circuit.behavioral(
+            format!("B{}I", reference),
+            current.as_str(),
+            "0",
+            format!("V=i(V{})", reference),
+        )?;
+        circuit.xspice(
+            format!("A{}", reference),
+            vec![current, soc.clone()],
+            model.clone(),
+        )?;
+        circuit.model(
+            model,
+            String::from("int"),
+            format!(
+                "gain={:e} out_lower_limit=0 out_upper_limit=1 limit_range=1e-3 out_ic={}",
+                -1.0 / (self.capacity * 3600.0),
+                self.charge
+            ),
+        );
+        let points = self
+            .table
+            .iter()
+            .map(|(soc, voltage)| format!("{},{}", soc, voltage))
+            .collect::<Vec<String>>()
+            .join(",");
+        circuit.behavioral(
+            format!("B{}", reference),
+            ocv.as_str(),
+            minus,
+            format!("V=pwl(V({}),{})", soc, points),
+        )?;
+        circuit.resistor(
+            format!("R{}", reference),
+            ocv.as_str(),
+            internal.as_str(),
+            Value::new(self.resistance).to_string(),
+        )?;
+        circuit.voltage(
+            format!("V{}", reference),
+            internal,
+            plus,
+            String::from("DC 0"),
+        )
+    }
+    fn validate(&self) -> Result<(), Error> {
+        if self.capacity <= 0.0
+            || self.resistance < 0.0
+            || !(0.0..=1.0).contains(&self.charge)
+            || self.table.len() < 2
+            || self.table.iter().any(|(soc, _)| !(0.0..=1.0).contains(soc))
+            || self.table.windows(2).any(|w| w[0].0 >= w[1].0)
+        {
+            return Err(Error::InvalidValue(format!("{:?}", self)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Battery;
+    use crate::Circuit;
+
+    #[test]
+    fn battery() {
+        let mut battery = Battery::new(0.5, vec![(0.0, 6.0), (0.5, 8.0), (1.0, 9.0)], 2.0);
+        assert_eq!(7.0, battery.open_circuit(0.25).unwrap());
+        assert_eq!(9.0, battery.open_circuit(1.0).unwrap());
+        assert_eq!(6.0, battery.open_circuit(0.0).unwrap());
+        battery.set_charge(0.8);
+        let mut circuit = Circuit::new(String::from("pedal"), Vec::new());
+        battery.build(&mut circuit, "BAT1", "vcc", "GND").unwrap();
+        assert_eq!(
+            vec![
+                "BBAT1I bat1_i 0 V=i(VBAT1)",
+                "ABAT1 bat1_i bat1_soc bat1_int",
+                ".model bat1_int int(gain=-5.555555555555556e-4 out_lower_limit=0 out_upper_limit=1 limit_range=1e-3 out_ic=0.8)",
+                "BBAT1 bat1_ocv 0 V=pwl(V(bat1_soc),0,6,0.5,8,1,9)",
+                "RBAT1 bat1_ocv bat1_r 2",
+                "VBAT1 bat1_r vcc DC 0",
+            ],
+            circuit.to_str(false).unwrap()
+        );
+        battery.table = vec![(0.5, 8.0), (0.0, 6.0)];
+        assert!(battery.open_circuit(0.5).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{circuit::CircuitItem, Circuit, Value};
+
+/// A problem in the circuit found before the simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// The circuit has no ground node `0`.
+    MissingGround,
+    /// The node has no DC path to ground.
+    FloatingNode(String),
+    /// The node is only connected to one element.
+    SingleConnection(String, String),
+    /// The element name is used by more than one element.
+    DuplicateReference(String),
+    /// The value of the element can not be parsed.
+    InvalidValue(String, String),
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagnostic::MissingGround => write!(f, "The circuit has no ground node 0"),
+            Diagnostic::FloatingNode(node) => write!(f, "Node {} has no DC path to ground", node),
+            Diagnostic::SingleConnection(node, reference) => {
+                write!(f, "Node {} is only connected to {}", node, reference)
+            }
+            Diagnostic::DuplicateReference(reference) => {
+                write!(f, "Reference {} is used more than once", reference)
+            }
+            Diagnostic::InvalidValue(reference, value) => {
+                write!(f, "Can not parse value \"{}\" of {}", value, reference)
+            }
+        }
+    }
+}
+
+fn find(parents: &mut HashMap<String, String>, node: &str) -> String {
+    let mut root = node.to_string();
+    while let Some(parent) = parents.get(&root) {
+        if *parent == root {
+            break;
+        }
+        root = parent.clone();
+    }
+    parents.insert(node.to_string(), root.clone());
+    root
+}
+
+impl Circuit {
+    /// Check the circuit before it is simulated.
+    ///
+    /// Finds floating nodes, nodes with a single connection, a missing
+    /// ground node, duplicate element names and values that can not be
+    /// parsed. The element names of the raw lines are checked as well.
+    /// Capacitors and current sources are no DC path. The subcircuits are
+    /// not checked.
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut result = Vec::new();
+        //the names are compared like spice does, without the case
+        let mut names: HashSet<String> = HashSet::new();
+        for item in &self.items {
+            let name = match item {
+                CircuitItem::Raw(line) => line
+                    .split_whitespace()
+                    .next()
+                    .filter(|t| t.starts_with(|c: char| c.is_ascii_alphabetic()))
+                    .map(|t| t.to_uppercase()),
+                _ => item.name(),
+            };
+            if let Some(name) = name {
+                if !names.insert(name.clone()) {
+                    result.push(Diagnostic::DuplicateReference(name));
+                }
+            }
+        }
+        let mut connections: HashMap<&String, Vec<&String>> = HashMap::new();
+        let mut parents: HashMap<String, String> = HashMap::new();
+        for item in &self.items {
+            let reference = match item.reference() {
+                Some(reference) => reference,
+                None => continue,
+            };
+            if let CircuitItem::R(_, _, _, value, _)
+            | CircuitItem::C(_, _, _, value, _)
+            | CircuitItem::L(_, _, _, value, _) = item
+            {
+                if !value.starts_with('{') && value.parse::<Value>().is_err() {
+                    result.push(Diagnostic::InvalidValue(reference.clone(), value.clone()));
+                }
+            }
+            let nodes = item.nodes();
+            for node in &nodes {
+                connections.entry(node).or_default().push(reference);
+                parents
+                    .entry(node.to_string())
+                    .or_insert_with(|| node.to_string());
+            }
+            //capacitors and current sources do not conduct DC
+            if !matches!(item, CircuitItem::C(..) | CircuitItem::I(..)) {
+                for node in nodes.iter().skip(1) {
+                    let a = find(&mut parents, nodes[0]);
+                    let b = find(&mut parents, node);
+                    parents.insert(b, a);
+                }
+            }
+        }
+
+        let mut nodes: Vec<&&String> = connections.keys().collect();
+        nodes.sort();
+        let ground = nodes
+            .iter()
+            .find(|n| self.is_ground(n))
+            .map(|n| find(&mut parents, n));
+        if ground.is_none() && !nodes.is_empty() {
+            result.push(Diagnostic::MissingGround);
+        }
+        for node in nodes {
+            if self.is_ground(node) {
+                continue;
+            }
+            let elements = &connections[*node];
+            if elements.len() == 1 {
+                result.push(Diagnostic::SingleConnection(
+                    node.to_string(),
+                    elements[0].clone(),
+                ));
+            }
+            if ground.is_some() && Some(find(&mut parents, node)) != ground {
+                result.push(Diagnostic::FloatingNode(node.to_string()));
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Diagnostic;
+    use crate::Circuit;
+
+    #[test]
+    fn check() {
+        let mut circuit = Circuit::new(String::from("check"), Vec::new());
+        circuit
+            .voltage(
+                String::from("V1"),
+                String::from("in"),
+                String::from("0"),
+                String::from("DC 5"),
+            )
+            .unwrap();
+        circuit
+            .resistor(
+                String::from("R1"),
+                String::from("in"),
+                String::from("out"),
+                String::from("10k"),
+            )
+            .unwrap();
+        circuit
+            .capacitor(
+                String::from("C1"),
+                String::from("out"),
+                String::from("float"),
+                String::from("100n"),
+            )
+            .unwrap();
+        circuit
+            .resistor(
+                String::from("R2"),
+                String::from("out"),
+                String::from("0"),
+                String::from("4k7"),
+            )
+            .unwrap();
+        assert_eq!(
+            vec![
+                Diagnostic::InvalidValue(String::from("R2"), String::from("4k7")),
+                Diagnostic::SingleConnection(String::from("float"), String::from("C1")),
+                Diagnostic::FloatingNode(String::from("float")),
+            ],
+            circuit.check()
+        );
+        let mut circuit = Circuit::new(String::from("check"), Vec::new());
+        circuit
+            .resistor(
+                String::from("R1"),
+                String::from("in"),
+                String::from("out"),
+                String::from("2.2Meg"),
+            )
+            .unwrap();
+        assert!(circuit.check().contains(&Diagnostic::MissingGround));
+        let mut circuit = Circuit::new(String::from("check"), Vec::new());
+        circuit
+            .current(
+                String::from("I1"),
+                String::from("0"),
+                String::from("bias"),
+                String::from("DC 1m"),
+            )
+            .unwrap();
+        circuit
+            .capacitor(
+                String::from("C1"),
+                String::from("bias"),
+                String::from("0"),
+                String::from("1u"),
+            )
+            .unwrap();
+        assert_eq!(
+            vec![Diagnostic::FloatingNode(String::from("bias"))],
+            circuit.check()
+        );
+    }
+    #[test]
+    fn duplicate_reference() {
+        let mut circuit = Circuit::new(String::from("check"), Vec::new());
+        circuit
+            .resistor(
+                String::from("R1"),
+                String::from("in"),
+                String::from("0"),
+                String::from("1k"),
+            )
+            .unwrap();
+        circuit.raw("r1 in 0 2k");
+        circuit.raw("M1 in in 0 0 nmos");
+        circuit.raw("m1 in in 0 0 nmos");
+        circuit.raw(".ic v(in)=0");
+        assert_eq!(
+            vec![
+                Diagnostic::DuplicateReference(String::from("R1")),
+                Diagnostic::DuplicateReference(String::from("M1")),
+            ],
+            circuit
+                .check()
+                .into_iter()
+                .filter(|d| matches!(d, Diagnostic::DuplicateReference(_)))
+                .collect::<Vec<_>>()
+        );
+    }
+}
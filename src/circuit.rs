@@ -1,23 +1,26 @@
 #![allow(clippy::borrow_deref_ref)]
-use crate::error::Error;
+use crate::{config, definition::Analysis, error::Error, hierarchy::HierarchicalName, messages::SpiceMessage, node::IntoNode, parser::logical_lines, result::AnalysisResult, tolerance::Tolerance, value::Value};
 use elektron_ngspice::{Callbacks, ComplexSlice, NgSpice};
 use lazy_static::lazy_static;
+use num_complex::Complex64;
+use rayon::prelude::*;
 use regex::Regex;
 use std::{
-    collections::HashMap,
+    collections::{hash_map::Entry, HashMap, HashSet},
     fs::{self, File},
-    io::Write,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, OnceLock},
 };
 
 lazy_static! {
-    pub static ref RE_SUBCKT: regex::Regex =
-        Regex::new(r"(?i:\.SUBCKT) ([a-zA-Z0-9]*) .*").unwrap();
-    pub static ref RE_MODEL: regex::Regex = Regex::new(r"(?i:\.model) ([a-zA-Z0-9]*) .*").unwrap();
-    pub static ref RE_INCLUDE: regex::Regex = Regex::new(r"(?i:\.include) (.*)").unwrap();
+    static ref RE_AC: regex::Regex =
+        Regex::new(r"(?i)\s*\bAC\s+[^\s()]+(\s+[-+.0-9][^\s()]*)?").unwrap();
 }
 
 pub struct Cb {
-    strs: Vec<String>,
+    pub(crate) strs: Vec<String>,
     status: i32, 
     unload: bool,
     quit: bool,
@@ -34,108 +37,950 @@ impl Cb {
     }
 }
 
+/// The log level of a line of the ngspice console, ngspice prefixes the
+/// lines with `stdout` or `stderr`.
+fn console_level(line: &str) -> log::Level {
+    match line.strip_prefix("stderr") {
+        Some(message) => {
+            let message = message.to_lowercase();
+            if message.contains("error") || message.contains("fatal") {
+                log::Level::Error
+            } else if message.contains("warning") {
+                log::Level::Warn
+            } else {
+                log::Level::Info
+            }
+        }
+        None => log::Level::Debug,
+    }
+}
+
 impl Callbacks for Cb {
     fn send_char(&mut self, s: &str) {
-        if std::env::var("ELEKTRON_DEBUG").is_ok() {
-            println!("{}", s);
-        }
+        log::log!(console_level(s), "{}", s);
         self.strs.push(s.to_string())
     }
     fn controlled_exit(&mut self, status: i32, unload: bool, quit: bool) {
+        log::debug!("ngspice exit: status {}, unload {}, quit {}", status, unload, quit);
         self.status = status;
         self.unload = unload;
         self.quit = quit;
     }
 }
 #[derive(Debug, Clone, PartialEq)]
-enum CircuitItem {
-    R(String, String, String, String),
-    C(String, String, String, String),
+pub(crate) enum CircuitItem {
+    R(String, String, String, String, Option<Tolerance>),
+    C(String, String, String, String, Option<Tolerance>),
+    L(String, String, String, String, Option<Tolerance>),
     D(String, String, String, String),
     Q(String, String, String, String, String),
-    X(String, Vec<String>, String),
+    /// Subcircuit instance with the parameters of the instance.
+    X(String, Vec<String>, String, Vec<(String, String)>),
     V(String, String, String, String),
+    I(String, String, String, String),
+    E(String, String, String, String, String, String),
+    /// Behavioral source with the expression `V=..` or `I=..`.
+    B(String, String, String, String),
+    /// XSPICE code model, vector ports are enclosed by `[` and `]` tokens.
+    A(String, Vec<String>, String),
+    /// Device of a compiled OSDI model.
+    N(String, Vec<String>, String),
+    /// Mutual inductance between two inductors with the coupling factor.
+    K(String, String, String, String),
+    /// Voltage controlled switch with the model.
+    S(String, String, String, String, String, String),
+    Model(String, String, String),
+    /// Include a file or a section of a library file.
+    Include(PathBuf, Option<String>),
+    /// Comment line in the netlist.
+    Comment(String),
+    /// Line that is written unchanged to the netlist.
+    Raw(String),
+}
+
+impl CircuitItem {
+    /// The reference of the element, models and includes have none.
+    pub(crate) fn reference(&self) -> Option<&String> {
+        match self {
+            CircuitItem::R(r, _, _, _, _)
+            | CircuitItem::C(r, _, _, _, _)
+            | CircuitItem::L(r, _, _, _, _)
+            | CircuitItem::D(r, _, _, _)
+            | CircuitItem::Q(r, _, _, _, _)
+            | CircuitItem::X(r, _, _, _)
+            | CircuitItem::V(r, _, _, _)
+            | CircuitItem::I(r, _, _, _)
+            | CircuitItem::B(r, _, _, _)
+            | CircuitItem::E(r, _, _, _, _, _)
+            | CircuitItem::S(r, _, _, _, _, _)
+            | CircuitItem::A(r, _, _)
+            | CircuitItem::N(r, _, _)
+            | CircuitItem::K(r, _, _, _) => Some(r),
+            CircuitItem::Model(_, _, _)
+            | CircuitItem::Include(..)
+            | CircuitItem::Comment(_)
+            | CircuitItem::Raw(_) => None,
+        }
+    }
+    pub(crate) fn reference_mut(&mut self) -> Option<&mut String> {
+        match self {
+            CircuitItem::R(r, _, _, _, _)
+            | CircuitItem::C(r, _, _, _, _)
+            | CircuitItem::L(r, _, _, _, _)
+            | CircuitItem::D(r, _, _, _)
+            | CircuitItem::Q(r, _, _, _, _)
+            | CircuitItem::X(r, _, _, _)
+            | CircuitItem::V(r, _, _, _)
+            | CircuitItem::I(r, _, _, _)
+            | CircuitItem::B(r, _, _, _)
+            | CircuitItem::E(r, _, _, _, _, _)
+            | CircuitItem::S(r, _, _, _, _, _)
+            | CircuitItem::A(r, _, _)
+            | CircuitItem::N(r, _, _)
+            | CircuitItem::K(r, _, _, _) => Some(r),
+            CircuitItem::Model(_, _, _)
+            | CircuitItem::Include(..)
+            | CircuitItem::Comment(_)
+            | CircuitItem::Raw(_) => None,
+        }
+    }
+    pub(crate) fn nodes_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            CircuitItem::R(_, n0, n1, _, _)
+            | CircuitItem::C(_, n0, n1, _, _)
+            | CircuitItem::L(_, n0, n1, _, _)
+            | CircuitItem::D(_, n0, n1, _)
+            | CircuitItem::V(_, n0, n1, _)
+            | CircuitItem::I(_, n0, n1, _)
+            | CircuitItem::B(_, n0, n1, _) => vec![n0, n1],
+            CircuitItem::Q(_, n0, n1, n2, _) => vec![n0, n1, n2],
+            CircuitItem::E(_, n0, n1, n2, n3, _) | CircuitItem::S(_, n0, n1, n2, n3, _) => {
+                vec![n0, n1, n2, n3]
+            }
+            CircuitItem::X(_, nodes, _, _) | CircuitItem::N(_, nodes, _) => {
+                nodes.iter_mut().collect()
+            }
+            CircuitItem::A(_, ports, _) => ports.iter_mut().filter(|p| !is_bracket(p)).collect(),
+            CircuitItem::K(_, _, _, _)
+            | CircuitItem::Model(_, _, _)
+            | CircuitItem::Include(..)
+            | CircuitItem::Comment(_)
+            | CircuitItem::Raw(_) => Vec::new(),
+        }
+    }
+    /// The spice letter of the element type.
+    pub(crate) fn letter(&self) -> Option<char> {
+        match self {
+            CircuitItem::R(_, _, _, _, _) => Some('R'),
+            CircuitItem::C(_, _, _, _, _) => Some('C'),
+            CircuitItem::L(_, _, _, _, _) => Some('L'),
+            CircuitItem::D(_, _, _, _) => Some('D'),
+            CircuitItem::Q(_, _, _, _, _) => Some('Q'),
+            CircuitItem::X(_, _, _, _) => Some('X'),
+            CircuitItem::V(_, _, _, _) => Some('V'),
+            CircuitItem::I(_, _, _, _) => Some('I'),
+            CircuitItem::E(_, _, _, _, _, _) => Some('E'),
+            CircuitItem::B(_, _, _, _) => Some('B'),
+            CircuitItem::A(_, _, _) => Some('A'),
+            CircuitItem::N(_, _, _) => Some('N'),
+            CircuitItem::K(_, _, _, _) => Some('K'),
+            CircuitItem::S(_, _, _, _, _, _) => Some('S'),
+            CircuitItem::Model(_, _, _)
+            | CircuitItem::Include(..)
+            | CircuitItem::Comment(_)
+            | CircuitItem::Raw(_) => None,
+        }
+    }
+    /// The element name as it is written to the netlist, the type letter is added
+    /// when the reference does not start with it in any case.
+    pub(crate) fn netlist_name(&self) -> Option<String> {
+        let letter = self.letter()?;
+        let reference = self.reference()?;
+        if reference
+            .chars()
+            .next()
+            .is_some_and(|c| c.eq_ignore_ascii_case(&letter))
+        {
+            Some(reference.to_string())
+        } else {
+            Some(format!("{}{}", letter, reference))
+        }
+    }
+    /// The element name in the netlist in upper case, spice names are not case
+    /// sensitive.
+    pub(crate) fn name(&self) -> Option<String> {
+        self.netlist_name().map(|name| name.to_uppercase())
+    }
+    /// The nodes the element is connected to.
+    pub(crate) fn nodes(&self) -> Vec<&String> {
+        match self {
+            CircuitItem::R(_, n0, n1, _, _)
+            | CircuitItem::C(_, n0, n1, _, _)
+            | CircuitItem::L(_, n0, n1, _, _)
+            | CircuitItem::D(_, n0, n1, _)
+            | CircuitItem::V(_, n0, n1, _)
+            | CircuitItem::I(_, n0, n1, _)
+            | CircuitItem::B(_, n0, n1, _) => vec![n0, n1],
+            CircuitItem::Q(_, n0, n1, n2, _) => vec![n0, n1, n2],
+            CircuitItem::E(_, n0, n1, n2, n3, _) | CircuitItem::S(_, n0, n1, n2, n3, _) => {
+                vec![n0, n1, n2, n3]
+            }
+            CircuitItem::X(_, nodes, _, _) | CircuitItem::N(_, nodes, _) => nodes.iter().collect(),
+            CircuitItem::A(_, ports, _) => ports.iter().filter(|p| !is_bracket(p)).collect(),
+            CircuitItem::K(_, _, _, _)
+            | CircuitItem::Model(_, _, _)
+            | CircuitItem::Include(..)
+            | CircuitItem::Comment(_)
+            | CircuitItem::Raw(_) => Vec::new(),
+        }
+    }
+}
+
+/// The file and the optional section of a library.
+pub(crate) type Library = (PathBuf, Option<String>);
+
+/// The statement to include the file or the section of the library file.
+fn include(filename: &Path, section: Option<&str>) -> String {
+    match section {
+        Some(section) => format!(".lib {} {}", filename.display(), section),
+        None => format!(".include {}", filename.display()),
+    }
+}
+
+/// The models and subcircuits defined in the library with the library section
+/// of the definition.
+///
+/// The statements can be split in `+` continuation lines and commented out
+/// definitions are skipped. The names are lowercase, spice compares them
+/// case-insensitive.
+fn definitions(content: &str) -> Vec<(String, Option<String>)> {
+    let mut result = Vec::new();
+    let mut section = None;
+    for line in logical_lines(content) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first().map(|t| t.to_lowercase()).as_deref() {
+            Some(".lib") if tokens.len() == 2 => section = Some(tokens[1].to_string()),
+            Some(".endl") => section = None,
+            Some(".subckt") | Some(".model") => {
+                //the parameters of the model can follow the name without space.
+                if let Some(name) = tokens.get(1).and_then(|t| t.split('(').next()) {
+                    result.push((name.to_ascii_lowercase(), section.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Index the definitions of the library files by name, the first file in the
+/// pathlist wins. Files which can not be read are skipped.
+fn library_index(pathlist: &[PathBuf]) -> Result<HashMap<String, Library>, Error> {
+    //the files are read in parallel, the order of the files is kept.
+    let files: Vec<_> = library_files(pathlist)?
+        .into_par_iter()
+        .filter_map(|path| match fs::read_to_string(&path) {
+            Ok(content) => {
+                let definitions = definitions(&content);
+                Some((path, definitions))
+            }
+            Err(err) => {
+                log::warn!("can not read the library file {}: {}", path.display(), err);
+                None
+            }
+        })
+        .collect();
+    let mut index = HashMap::new();
+    for (path, definitions) in files {
+        for (name, section) in definitions {
+            index.entry(name).or_insert_with(|| (path.clone(), section));
+        }
+    }
+    Ok(index)
+}
+
+/// The index of the library, it is built with the first lookup and shared
+/// by the clones of the circuit.
+#[derive(Debug, Clone, Default)]
+struct LibraryIndex(Arc<OnceLock<Result<HashMap<String, Library>, Error>>>);
+
+impl LibraryIndex {
+    fn get(&self, pathlist: &[PathBuf]) -> Result<&HashMap<String, Library>, Error> {
+        self.0
+            .get_or_init(|| library_index(pathlist))
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+}
+
+/// The index is derived from the pathlist and the library of the circuit.
+impl PartialEq for LibraryIndex {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// The library files in the pathlist.
+///
+/// The entries are directories, which are read recursively, or glob patterns
+/// like `~/spice/**/*.lib`. The files of a directory come before the files in
+/// its subdirectories. Missing or unreadable directories are skipped.
+fn library_files(pathlist: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    for path in pathlist {
+        let path = match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+            (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+            _ => path.to_path_buf(),
+        };
+        //paths which are not valid unicode are no patterns.
+        let pattern = path.to_str().filter(|path| path.contains(['*', '?', '[']));
+        if let Some(pattern) = pattern {
+            let paths = glob::glob(pattern).map_err(|err| Error::InvalidValue(err.to_string()))?;
+            for entry in paths.flatten() {
+                if entry.is_dir() {
+                    library_dir(&entry, &mut files, &mut visited);
+                } else {
+                    files.push(entry);
+                }
+            }
+        } else {
+            library_dir(&path, &mut files, &mut visited);
+        }
+    }
+    Ok(files)
+}
+
+/// Add the files of the directory and its subdirectories.
+///
+/// Every directory is read once, symlinked directories can form a loop.
+fn library_dir(dir: &Path, files: &mut Vec<PathBuf>, visited: &mut HashSet<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::warn!("can not read the library directory {}: {}", dir.display(), err);
+            return;
+        }
+    };
+    if !fs::canonicalize(dir).is_ok_and(|path| visited.insert(path)) {
+        return;
+    }
+    let mut entries = entries.flatten().map(|entry| entry.path()).collect::<Vec<PathBuf>>();
+    entries.sort();
+    let (dirs, entries): (Vec<PathBuf>, Vec<PathBuf>) = entries.into_iter().partition(|p| p.is_dir());
+    files.extend(entries.into_iter().filter(|p| p.is_file()));
+    for dir in dirs {
+        library_dir(&dir, files, visited);
+    }
+}
+
+/// Add the `.include` and `.lib` files of the library and the files they
+/// include, the stack holds the files which are being read.
+fn nested_includes(
+    file: &Path,
+    result: &mut HashMap<String, Library>,
+    stack: &mut Vec<PathBuf>,
+) -> Result<(), Error> {
+    let canonical = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+    if stack.contains(&canonical) {
+        return Err(Error::IncludeCycle(file.to_string_lossy().to_string()));
+    }
+    let Ok(content) = fs::read_to_string(file) else {
+        return Ok(());
+    };
+    stack.push(canonical);
+    for line in logical_lines(&content) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let keyword = tokens.first().map(|t| t.to_lowercase()).unwrap_or_default();
+        //`.lib section` starts a section of the library file.
+        let section = match keyword.as_str() {
+            ".include" | ".inc" if tokens.len() > 1 => None,
+            ".lib" if tokens.len() > 2 => Some(tokens[2].to_string()),
+            _ => continue,
+        };
+        let name = tokens[1].trim_matches(|c| c == '"' || c == '\'');
+        //relative paths are relative to the including file.
+        let relative = file.parent().map(|parent| parent.join(name));
+        let path = match relative {
+            Some(relative) if Path::new(name).is_relative() && relative.exists() => relative,
+            _ => PathBuf::from(name),
+        };
+        if let Entry::Vacant(entry) = result.entry(tokens[1..].join(" ")) {
+            entry.insert((path.clone(), section));
+            nested_includes(&path, result, stack)?;
+        }
+    }
+    stack.pop();
+    Ok(())
+}
+
+/// Reject the values with numbers which are not finite, e.g. a `Value` of NaN.
+fn finite(item: &CircuitItem) -> Result<(), Error> {
+    let values: Vec<&String> = match item {
+        CircuitItem::R(_, _, _, value, _)
+        | CircuitItem::C(_, _, _, value, _)
+        | CircuitItem::L(_, _, _, value, _)
+        | CircuitItem::V(_, _, _, value)
+        | CircuitItem::I(_, _, _, value)
+        | CircuitItem::B(_, _, _, value)
+        | CircuitItem::E(_, _, _, _, _, value)
+        | CircuitItem::K(_, _, _, value)
+        | CircuitItem::Model(_, _, value) => vec![value],
+        CircuitItem::X(_, _, _, params) => params.iter().map(|(_, value)| value).collect(),
+        _ => Vec::new(),
+    };
+    for value in values {
+        let invalid = value
+            .split(|c: char| c.is_whitespace() || "=(),".contains(c))
+            .map(|token| token.trim_start_matches(['+', '-']).to_lowercase())
+            .any(|token| matches!(token.as_str(), "nan" | "inf" | "infinity"));
+        if invalid {
+            return Err(Error::InvalidValue(value.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Validate the node argument, the node name is stored as string.
+fn node(node: impl IntoNode) -> Result<String, Error> {
+    Ok(String::from(node.into_node()?))
+}
+
+fn nodes<N: IntoNode>(nodes: Vec<N>) -> Result<Vec<String>, Error> {
+    nodes.into_iter().map(node).collect()
+}
+
+/// Split the tokens at the first parameter, the parameters can follow the
+/// keyword `params:`.
+pub(crate) fn split_params<'a>(tokens: &'a [&'a str]) -> (&'a [&'a str], Vec<(String, String)>) {
+    let start = tokens
+        .iter()
+        .position(|t| t.eq_ignore_ascii_case("params:") || t.contains('='))
+        .unwrap_or(tokens.len());
+    let params = tokens[start..]
+        .iter()
+        .filter_map(|t| t.split_once('='))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    (&tokens[..start], params)
+}
+
+/// The parameters with the keyword `params:`, empty without parameters.
+fn format_params(params: &[(String, String)]) -> String {
+    if params.is_empty() {
+        return String::new();
+    }
+    let params: Vec<String> = params.iter().map(|(n, v)| format!("{}={}", n, v)).collect();
+    format!(" params: {}", params.join(" "))
+}
+
+/// Split the line at the spaces in lines with at most width characters,
+/// the following lines start with `+`. Longer tokens are not split.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    for token in line.split_whitespace() {
+        if current.is_empty() {
+            current.push_str(token);
+        } else if current.len() + token.len() < width {
+            current.push(' ');
+            current.push_str(token);
+        } else {
+            result.push(current);
+            current = format!("+ {}", token);
+        }
+    }
+    result.push(current);
+    result
+}
+
+fn is_bracket(token: &str) -> bool {
+    token == "[" || token == "]"
+}
+
+/// Split the XSPICE ports in nodes and the brackets of the vector ports.
+fn ports(ports: &[String]) -> Result<Vec<String>, Error> {
+    let mut result = Vec::new();
+    for port in ports {
+        let port = port.replace('[', " [ ").replace(']', " ] ");
+        for token in port.split_whitespace() {
+            if is_bracket(token) {
+                result.push(token.to_string());
+            } else {
+                result.push(node(token)?);
+            }
+        }
+    }
+    Ok(result)
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Circuit {
-    name: String,
-    pathlist: Vec<String>,
-    items: Vec<CircuitItem>,
-    subcircuits: HashMap<String, (Vec<String>, Circuit)>,
+    pub(crate) name: String,
+    pub(crate) pathlist: Vec<PathBuf>,
+    /// The library paths from the environment and the configuration file.
+    library: Vec<PathBuf>,
+    /// The definitions of the libraries, reset when the paths change.
+    index: LibraryIndex,
+    pub(crate) items: Vec<CircuitItem>,
+    pub(crate) subcircuits: HashMap<String, (Vec<String>, Circuit)>,
+    ground: Vec<String>,
+    pub(crate) osdi: Vec<String>,
+    pub(crate) params: Vec<(String, String)>,
+    width: Option<usize>,
+    /// The total resistance of the potentiometers by reference.
+    pub(crate) potentiometers: HashMap<String, f64>,
 }
 
 impl Circuit {
     pub fn new(name: String, pathlist: Vec<String>) -> Self {
         Self {
             name,
-            pathlist,
+            pathlist: pathlist.into_iter().map(PathBuf::from).collect(),
+            library: config::library(),
+            index: LibraryIndex::default(),
             items: Vec::new(),
             subcircuits: HashMap::new(),
+            ground: ["GND", "AGND", "DGND", "GNDA", "GNDD"]
+                .iter()
+                .map(|g| g.to_string())
+                .collect(),
+            osdi: Vec::new(),
+            params: Vec::new(),
+            width: None,
+            potentiometers: HashMap::new(),
         }
     }
 
-    pub fn resistor(&mut self, reference: String, n0: String, n1: String, value: String) {
-        self.items.push(CircuitItem::R(reference, n0, n1, value));
+    /// Add a node name that is connected to ground.
+    ///
+    /// The ground aliases are written as node `0` to the netlist, by default
+    /// these are GND, AGND, DGND, GNDA and GNDD. Other names like VSS must
+    /// be added when they are the ground of the circuit.
+    pub fn add_ground_alias(&mut self, alias: &str) {
+        self.ground.push(alias.to_string());
+    }
+    /// Wrap subcircuit instances and PWL sources longer than the width
+    /// with `+` continuation lines, `None` disables the wrapping.
+    pub fn set_line_width(&mut self, width: Option<usize>) {
+        self.width = width;
+    }
+    /// Replace the library paths, the paths can be directories or glob
+    /// patterns.
+    pub fn set_pathlist(&mut self, pathlist: Vec<PathBuf>) {
+        self.pathlist = pathlist;
+        self.index = LibraryIndex::default();
+    }
+    /// Replace the library paths that are read from `ELEKTRON_SPICE_PATH`
+    /// and the configuration file, an empty list only searches the pathlist.
+    pub fn set_library(&mut self, library: Vec<PathBuf>) {
+        self.library = library;
+        self.index = LibraryIndex::default();
+    }
+    /// Replace the ground aliases, an empty list disables the mapping.
+    pub fn set_ground_aliases(&mut self, aliases: Vec<String>) {
+        self.ground = aliases;
+    }
+    /// Check if the node is the ground node `0` or one of the aliases.
+    pub fn is_ground(&self, node: &str) -> bool {
+        node == "0" || self.ground.iter().any(|g| g.eq_ignore_ascii_case(node))
+    }
+
+    pub fn resistor(
+        &mut self,
+        reference: String,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+        value: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::R(reference, node(n0)?, node(n1)?, value, None))
+    }
+
+    pub fn capacitor(
+        &mut self,
+        reference: String,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+        value: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::C(reference, node(n0)?, node(n1)?, value, None))
     }
 
-    pub fn capacitor(&mut self, reference: String, n0: String, n1: String, value: String) {
-        self.items.push(CircuitItem::C(reference, n0, n1, value));
+    pub fn inductor(
+        &mut self,
+        reference: String,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+        value: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::L(reference, node(n0)?, node(n1)?, value, None))
     }
 
-    pub fn diode(&mut self, reference: String, n0: String, n1: String, value: String) {
-        self.items.push(CircuitItem::D(reference, n0, n1, value));
+    pub fn diode(
+        &mut self,
+        reference: String,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+        value: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::D(reference, node(n0)?, node(n1)?, value))
     }
 
-    pub fn bjt(&mut self, reference: String, n0: String, n1: String, n2: String, value: String) {
-        self.items
-            .push(CircuitItem::Q(reference, n0, n1, n2, value));
+    pub fn bjt(
+        &mut self,
+        reference: String,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+        n2: impl IntoNode,
+        value: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::Q(reference, node(n0)?, node(n1)?, node(n2)?, value))
     }
 
-    pub fn circuit(
+    /// Add a subcircuit instance, the value is the name of the subcircuit
+    /// and optional parameters, e.g. `filter params: r=10k c=1n`.
+    pub fn circuit<N: IntoNode>(
         &mut self,
         reference: String,
-        n: Vec<String>,
+        n: Vec<N>,
         value: String,
     ) -> Result<(), Error> {
-        //TODO self.get_includes(&value)?;
-        self.items.push(CircuitItem::X(reference, n, value));
-        Ok(())
+        //the subcircuit is looked up in the library when the netlist is written.
+        let tokens: Vec<&str> = value.split_whitespace().collect();
+        let (name, params) = split_params(&tokens);
+        let name = name.first().ok_or_else(|| Error::Spice(value.clone()))?;
+        self.insert(CircuitItem::X(reference, nodes(n)?, name.to_string(), params))
     }
-    pub fn subcircuit(
+    /// Add a subcircuit definition, the parameters of the circuit are the
+    /// parameters of the subcircuit with the default values.
+    pub fn subcircuit<N: IntoNode>(
         &mut self,
         name: String,
-        n: Vec<String>,
+        n: Vec<N>,
         circuit: Circuit,
     ) -> Result<(), Error> {
-        self.subcircuits.insert(name, (n, circuit));
+        self.subcircuits.insert(name, (nodes(n)?, circuit));
         Ok(())
     }
-    pub fn voltage(&mut self, reference: String, n1: String, n2: String, value: String) {
-        self.items.push(CircuitItem::V(reference, n1, n2, value));
+    pub fn voltage(
+        &mut self,
+        reference: String,
+        n1: impl IntoNode,
+        n2: impl IntoNode,
+        value: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::V(reference, node(n1)?, node(n2)?, value))
     }
-    pub fn save(&self, filename: Option<String>) -> Result<(), Error> {
-        let mut out: Box<dyn Write> = if let Some(filename) = filename {
-            Box::new(File::create(filename).unwrap())
-        } else {
-            Box::new(std::io::stdout())
+    /// Add a current source, the current flows from the first through the source to the second node.
+    pub fn current(
+        &mut self,
+        reference: String,
+        n1: impl IntoNode,
+        n2: impl IntoNode,
+        value: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::I(reference, node(n1)?, node(n2)?, value))
+    }
+    /// Add a voltage controlled voltage source, the output is the gain times the control voltage.
+    pub fn vcvs(
+        &mut self,
+        reference: String,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+        control0: impl IntoNode,
+        control1: impl IntoNode,
+        gain: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::E(
+            reference,
+            node(n0)?,
+            node(n1)?,
+            node(control0)?,
+            node(control1)?,
+            gain,
+        ))
+    }
+    /// Add a voltage controlled switch, the model is a `SW` model.
+    pub fn switch(
+        &mut self,
+        reference: String,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+        control0: impl IntoNode,
+        control1: impl IntoNode,
+        model: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::S(
+            reference,
+            node(n0)?,
+            node(n1)?,
+            node(control0)?,
+            node(control1)?,
+            model,
+        ))
+    }
+    /// Add a behavioral source, the value is `V=expression` or `I=expression`.
+    pub fn behavioral(
+        &mut self,
+        reference: String,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+        value: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::B(reference, node(n0)?, node(n1)?, value))
+    }
+    /// Add an XSPICE code model instance.
+    ///
+    /// The ports are the nodes of scalar ports or the nodes of vector ports
+    /// in brackets, e.g. `vec!["[d0 d1]", "out"]`.
+    pub fn xspice(
+        &mut self,
+        reference: String,
+        ports: Vec<String>,
+        model: String,
+    ) -> Result<(), Error> {
+        let ports = self::ports(&ports)?;
+        self.insert(CircuitItem::A(reference, ports, model))
+    }
+    /// Couple two inductors with the coupling factor from 0 to 1.
+    pub fn coupling(
+        &mut self,
+        reference: String,
+        l1: String,
+        l2: String,
+        coupling: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::K(reference, l1, l2, coupling))
+    }
+    /// Load the compiled Verilog-A models of the `.osdi` file, e.g. from OpenVAF.
+    ///
+    /// The file is loaded with `pre_osdi` before the circuit is parsed, the
+    /// models are defined with `model` and the devices added with `device`.
+    pub fn osdi(&mut self, filename: &str) {
+        if !self.osdi.iter().any(|f| f == filename) {
+            self.osdi.push(filename.to_string());
+        }
+    }
+    /// Add a device of an OSDI model.
+    pub fn device<N: IntoNode>(
+        &mut self,
+        reference: String,
+        n: Vec<N>,
+        model: String,
+    ) -> Result<(), Error> {
+        self.insert(CircuitItem::N(reference, nodes(n)?, model))
+    }
+    /// Add the element, the reference must not be used by an other element.
+    pub(crate) fn insert(&mut self, item: CircuitItem) -> Result<(), Error> {
+        finite(&item)?;
+        if let Some(name) = item.name() {
+            if self.items.iter().any(|i| i.name().as_ref() == Some(&name)) {
+                return Err(Error::DuplicateReference(name));
+            }
+        }
+        self.items.push(item);
+        Ok(())
+    }
+    /// Number the references of the elements by type, starting with 1.
+    ///
+    /// The couplings follow the inductors. Returns the old and the new
+    /// references in the order of the elements.
+    pub fn renumber(&mut self) -> Vec<(String, String)> {
+        let mut counters: HashMap<char, u32> = HashMap::new();
+        let mut result = Vec::new();
+        let mut inductors = Vec::new();
+        for item in self.items.iter_mut() {
+            let letter = match item.letter() {
+                Some(letter) => letter,
+                None => continue,
+            };
+            let counter = counters.entry(letter).or_insert(0);
+            *counter += 1;
+            let new = format!("{}{}", letter, counter);
+            if let Some(reference) = item.reference_mut() {
+                if letter == 'L' {
+                    inductors.push((reference.clone(), new.clone()));
+                }
+                result.push((reference.clone(), new.clone()));
+                *reference = new;
+            }
+        }
+        for item in self.items.iter_mut() {
+            if let CircuitItem::K(_, l1, l2, _) = item {
+                for inductor in [l1, l2] {
+                    if let Some((_, new)) = inductors.iter().find(|(old, _)| old == inductor) {
+                        *inductor = new.clone();
+                    }
+                }
+            }
+        }
+        result
+    }
+    /// Annotate the references per prefix, e.g. `RV` for `RV1`, in the
+    /// order of the elements.
+    ///
+    /// References without a number or ending with `?` get the next free
+    /// number of the prefix, with `renumber` all references are numbered
+    /// from 1. The couplings follow the inductors. Returns the old and the
+    /// new references which are changed.
+    pub fn annotate(&mut self, renumber: bool) -> Vec<(String, String)> {
+        let split = |item: &CircuitItem| -> Option<(String, String, Option<u32>)> {
+            let reference = item.reference()?;
+            let numbered = !reference.ends_with('?');
+            let reference = reference.trim_end_matches('?');
+            let prefix = reference.trim_end_matches(|c: char| c.is_ascii_digit());
+            let number = reference[prefix.len()..].parse().ok().filter(|_| numbered);
+            let prefix = match prefix.is_empty() {
+                true => item.letter()?.to_string(),
+                false => prefix.to_string(),
+            };
+            Some((prefix.to_uppercase(), prefix, number))
         };
-        for s in self.to_str(true).unwrap() {
-            writeln!(out, "{}", s)?;
+        let mut used: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut keep = vec![false; self.items.len()];
+        if !renumber {
+            for (index, item) in self.items.iter().enumerate() {
+                if let Some((key, _, Some(number))) = split(item) {
+                    let numbers = used.entry(key).or_default();
+                    if !numbers.contains(&number) {
+                        numbers.push(number);
+                        keep[index] = true;
+                    }
+                }
+            }
+        }
+        let mut result = Vec::new();
+        for (index, item) in self.items.iter_mut().enumerate() {
+            let Some((key, prefix, _)) = split(item) else {
+                continue;
+            };
+            if keep[index] {
+                continue;
+            }
+            let numbers = used.entry(key).or_default();
+            let number = (1..).find(|n| !numbers.contains(n)).unwrap_or_default();
+            numbers.push(number);
+            let new = format!("{}{}", prefix, number);
+            if let Some(reference) = item.reference_mut() {
+                if *reference != new {
+                    result.push((reference.clone(), new.clone()));
+                    *reference = new;
+                }
+            }
+        }
+        for item in self.items.iter_mut() {
+            if let CircuitItem::K(_, l1, l2, _) = item {
+                for inductor in [l1, l2] {
+                    if let Some((_, new)) = result.iter().find(|(old, _)| old == inductor) {
+                        *inductor = new.clone();
+                    }
+                }
+            }
+        }
+        result
+    }
+    /// Remove the element with the reference or the netlist name.
+    ///
+    /// The couplings of a removed inductor are removed as well.
+    pub fn remove(&mut self, reference: &str) -> Result<(), Error> {
+        let index = self.position(reference)?;
+        let item = self.items.remove(index);
+        if let (CircuitItem::L(old, _, _, _, _), Some(name)) = (&item, item.name()) {
+            let coupled = |inductor: &String| {
+                inductor == old || inductor.eq_ignore_ascii_case(&name)
+            };
+            self.items.retain(|item| {
+                !matches!(item, CircuitItem::K(_, l1, l2, _) if coupled(l1) || coupled(l2))
+            });
+        }
+        Ok(())
+    }
+    /// Replace the element with the elements added by the function, the
+    /// new elements take the place of the old one. The circuit is unchanged
+    /// when the function fails.
+    ///
+    /// ```ignore
+    /// circuit.replace("R1", |c| c.capacitor(String::from("C1"), "in", "out", String::from("10n")))?;
+    /// ```
+    pub fn replace<F>(&mut self, reference: &str, add: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Circuit) -> Result<(), Error>,
+    {
+        let index = self.position(reference)?;
+        let old = self.items.clone();
+        self.items.remove(index);
+        let len = self.items.len();
+        if let Err(err) = add(self) {
+            self.items = old;
+            return Err(err);
+        }
+        let added: Vec<CircuitItem> = self.items.drain(len..).collect();
+        self.items.splice(index..index, added);
+        Ok(())
+    }
+    fn position(&self, reference: &str) -> Result<usize, Error> {
+        self.items
+            .iter()
+            .position(|item| {
+                item.reference().map(|r| r.as_str()) == Some(reference)
+                    || item.name().is_some_and(|n| n.eq_ignore_ascii_case(reference))
+            })
+            .ok_or_else(|| Error::UnknownCircuitElement(reference.to_string()))
+    }
+    /// Add a parameter with the value, the parameters of a subcircuit are
+    /// written to the `.subckt` line.
+    pub fn add_parameter(&mut self, name: &str, value: &str) {
+        self.params.push((name.to_string(), value.to_string()));
+    }
+    pub fn model(&mut self, name: String, kind: String, parameters: String) {
+        self.items.push(CircuitItem::Model(name, kind, parameters));
+    }
+    pub fn include(&mut self, filename: impl AsRef<Path>) {
+        self.items
+            .push(CircuitItem::Include(filename.as_ref().to_path_buf(), None));
+    }
+    /// Include the section of a library file with `.lib filename section`.
+    pub fn lib(&mut self, filename: impl AsRef<Path>, section: String) {
+        self.items.push(CircuitItem::Include(
+            filename.as_ref().to_path_buf(),
+            Some(section),
+        ));
+    }
+    /// Add a comment to the netlist, every line of the text is a comment line.
+    pub fn comment(&mut self, text: &str) {
+        self.items.push(CircuitItem::Comment(text.to_string()));
+    }
+    /// Add a line to the netlist that is written as it is, e.g. a directive
+    /// that is not supported by the circuit.
+    pub fn raw(&mut self, line: &str) {
+        self.items.push(CircuitItem::Raw(line.to_string()));
+    }
+    /// Write the netlist with the name as title line, unlike `to_string()`
+    /// the models are included and the subcircuit ports are checked.
+    pub fn save(&self, mut out: impl Write) -> Result<(), Error> {
+        writeln!(out, "* {}", self.name)?;
+        for s in self.to_str(true)? {
+            writeln!(out, "{}", s.trim_end())?;
         }
         out.flush()?;
         Ok(())
     }
-    pub fn set_value(&mut self, reference: &str, value: &str) -> Result<(), Error> {
+    /// Write the netlist to the file.
+    pub fn save_file(&self, filename: &Path) -> Result<(), Error> {
+        self.save(BufWriter::new(File::create(filename)?))
+    }
+    /// Set the value of the element, numbers can be given as `Value`.
+    pub fn set_value(
+        &mut self,
+        reference: &str,
+        value: impl std::fmt::Display,
+    ) -> Result<(), Error> {
+        let value = value.to_string();
         for item in &mut self.items.iter_mut() {
             match item {
-                CircuitItem::R(r, _, _, ref mut v) => {
-                    if reference == r {
-                        *v = value.to_string();
-                        return Ok(());
-                    }
-                }
-                CircuitItem::C(r, _, _, ref mut v) => {
+                CircuitItem::R(r, _, _, ref mut v, _)
+                | CircuitItem::C(r, _, _, ref mut v, _)
+                | CircuitItem::L(r, _, _, ref mut v, _) => {
                     if reference == r {
                         *v = value.to_string();
                         return Ok(());
@@ -148,13 +993,127 @@ impl Circuit {
                     }
                 }
                 CircuitItem::Q(_, _, _, _, _) => {}
-                CircuitItem::X(_, _, _) => {}
-                CircuitItem::V(r, _, _, ref mut v) => {
+                CircuitItem::X(_, _, _, _) | CircuitItem::A(_, _, _) | CircuitItem::N(_, _, _) => {}
+                CircuitItem::V(r, _, _, ref mut v)
+                | CircuitItem::I(r, _, _, ref mut v)
+                | CircuitItem::B(r, _, _, ref mut v)
+                | CircuitItem::E(r, _, _, _, _, ref mut v)
+                | CircuitItem::S(r, _, _, _, _, ref mut v)
+                | CircuitItem::K(r, _, _, ref mut v) => {
                     if reference == r {
                         *v = value.to_string();
                         return Ok(());
                     }
                 }
+                CircuitItem::Model(_, _, _) => {}
+                CircuitItem::Include(..) | CircuitItem::Comment(_) | CircuitItem::Raw(_) => {}
+            }
+        }
+        Err(Error::UnknownCircuitElement(reference.to_string()))
+    }
+    /// Set the tolerance of a resistor, capacitor or inductor.
+    pub fn set_tolerance(&mut self, reference: &str, tolerance: Tolerance) -> Result<(), Error> {
+        for item in self.items.iter_mut() {
+            if let CircuitItem::R(r, _, _, _, t)
+            | CircuitItem::C(r, _, _, _, t)
+            | CircuitItem::L(r, _, _, _, t) = item
+            {
+                if reference == r {
+                    *t = Some(tolerance);
+                    return Ok(());
+                }
+            }
+        }
+        Err(Error::UnknownCircuitElement(reference.to_string()))
+    }
+    /// Get the tolerance of the element.
+    pub fn tolerance(&self, reference: &str) -> Option<Tolerance> {
+        self.items.iter().find_map(|item| match item {
+            CircuitItem::R(r, _, _, _, t)
+            | CircuitItem::C(r, _, _, _, t)
+            | CircuitItem::L(r, _, _, _, t)
+                if reference == r =>
+            {
+                *t
+            }
+            _ => None,
+        })
+    }
+    /// The elements with a tolerance and their nominal value.
+    pub(crate) fn toleranced(&self) -> Result<Vec<(String, f64, Tolerance)>, Error> {
+        let mut result = Vec::new();
+        for item in &self.items {
+            if let CircuitItem::R(r, _, _, v, Some(t))
+            | CircuitItem::C(r, _, _, v, Some(t))
+            | CircuitItem::L(r, _, _, v, Some(t)) = item
+            {
+                result.push((r.clone(), Value::from_str(v)?.as_f64(), *t));
+            }
+        }
+        Ok(result)
+    }
+    /// The value of a resistor, capacitor or inductor as number.
+    pub(crate) fn nominal(&self, reference: &str) -> Result<f64, Error> {
+        for item in &self.items {
+            if let CircuitItem::R(r, _, _, v, _)
+            | CircuitItem::C(r, _, _, v, _)
+            | CircuitItem::L(r, _, _, v, _) = item
+            {
+                if reference == r {
+                    return Ok(Value::from_str(v)?.as_f64());
+                }
+            }
+        }
+        Err(Error::UnknownCircuitElement(reference.to_string()))
+    }
+    /// A copy of the circuit with the element values replaced.
+    pub(crate) fn with_values(&self, values: &HashMap<String, f64>) -> Result<Circuit, Error> {
+        let mut circuit = self.clone();
+        for (reference, value) in values {
+            circuit.set_value(reference, Value::new(*value))?;
+        }
+        Ok(circuit)
+    }
+    /// Connect the pin of the element at the node to the new node.
+    pub(crate) fn reconnect(
+        &mut self,
+        reference: &str,
+        node: &str,
+        new: &str,
+    ) -> Result<(), Error> {
+        let item = self
+            .items
+            .iter_mut()
+            .find(|item| item.reference().map(|r| r.as_str()) == Some(reference))
+            .ok_or_else(|| Error::UnknownCircuitElement(reference.to_string()))?;
+        let pin = item
+            .nodes_mut()
+            .into_iter()
+            .find(|n| n.as_str() == node)
+            .ok_or_else(|| Error::InvalidNode(node.to_string()))?;
+        *pin = new.to_string();
+        Ok(())
+    }
+    /// A copy of the circuit without the AC stimulus of the sources.
+    pub(crate) fn without_ac(&self) -> Circuit {
+        let mut circuit = self.clone();
+        for item in circuit.items.iter_mut() {
+            if let CircuitItem::V(_, _, _, v) | CircuitItem::I(_, _, _, v) = item {
+                *v = RE_AC.replace_all(v, "").trim().to_string();
+            }
+        }
+        circuit
+    }
+    /// Replace the AC stimulus of the source.
+    pub(crate) fn set_ac(&mut self, reference: &str, ac: &str) -> Result<(), Error> {
+        for item in self.items.iter_mut() {
+            if let CircuitItem::V(r, _, _, v) | CircuitItem::I(r, _, _, v) = item {
+                if reference == r {
+                    *v = format!("{} AC {}", RE_AC.replace_all(v, "").trim(), ac)
+                        .trim()
+                        .to_string();
+                    return Ok(());
+                }
             }
         }
         Err(Error::UnknownCircuitElement(reference.to_string()))
@@ -162,146 +1121,248 @@ impl Circuit {
 }
 
 impl Circuit {
-    fn get_includes(&self, key: String) -> Result<HashMap<String, String>, Error> {
-        let mut result: HashMap<String, String> = HashMap::new();
-        for path in &self.pathlist {
-            for entry in fs::read_dir(path).unwrap() {
-                let dir = entry.unwrap();
-                if dir.path().is_file() {
-                    let content = fs::read_to_string(dir.path())?;
-                    for cap in RE_SUBCKT.captures_iter(&content) {
-                        let text1 = cap.get(1).map_or("", |m| m.as_str());
-                        if text1 == key {
-                            result.insert(key, dir.path().to_str().unwrap().to_string());
-                            if let Some(caps) = RE_INCLUDE.captures(&content) {
-                                for cap in caps.iter().skip(1) {
-                                    let text1 = cap.map_or("", |m| m.as_str());
-                                    if !text1.contains('/') {
-                                        //when there is no slash i could be
-                                        //a relative path.
-                                        let mut parent = dir
-                                            .path()
-                                            .parent()
-                                            .unwrap()
-                                            .to_str()
-                                            .unwrap()
-                                            .to_string();
-                                        parent += "/";
-                                        parent += text1;
-                                        result.insert(text1.to_string(), parent.to_string());
-                                    } else {
-                                        result.insert(text1.to_string(), text1.to_string());
-                                    }
-                                }
-                            }
-                            return Ok(result);
-                        }
-                    }
-                    for cap in RE_MODEL.captures_iter(&content) {
-                        let text1 = cap.get(1).map_or("", |m| m.as_str());
-                        if text1 == key {
-                            result.insert(key, dir.path().to_str().unwrap().to_string());
-                            if let Some(caps) = RE_INCLUDE.captures(&content) {
-                                for cap in caps.iter().skip(1) {
-                                    let text1 = cap.map_or("", |m| m.as_str());
-                                    if !text1.contains('/') {
-                                        //when there is no slash i could be
-                                        //a relative path.
-                                        let mut parent = dir
-                                            .path()
-                                            .parent()
-                                            .unwrap()
-                                            .to_str()
-                                            .unwrap()
-                                            .to_string();
-                                        parent += "/";
-                                        parent += text1;
-                                        result.insert(text1.to_string(), parent.to_string());
-                                    } else {
-                                        result.insert(text1.to_string(), text1.to_string());
-                                    }
-                                }
-                            }
-                            return Ok(result);
-                        }
-                    }
+    /// The libraries for the model or subcircuit with the files they include.
+    pub(crate) fn get_includes(&self, key: String) -> Result<HashMap<String, Library>, Error> {
+        let mut result: HashMap<String, Library> = HashMap::new();
+        let found = self
+            .index
+            .get(&config::pathlist(&self.pathlist, &self.library))?
+            .get(&key.to_ascii_lowercase())
+            .cloned();
+        if let Some((path, section)) = found {
+            result.insert(key, (path.clone(), section));
+            nested_includes(&path, &mut result, &mut Vec::new())?;
+            return Ok(result);
+        }
+        Err(Error::SpiceModelNotFound(key))
+    }
+
+    /// The ports of the subcircuit, from the circuit, the explicit includes
+    /// or the libraries in the pathlist.
+    pub(crate) fn subcircuit_ports(&self, name: &str) -> Option<Vec<String>> {
+        if let Some((_, (ports, _))) = self
+            .subcircuits
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        {
+            return Some(ports.clone());
+        }
+        let mut files: Vec<PathBuf> = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                CircuitItem::Include(filename, _) => Some(filename.clone()),
+                _ => None,
+            })
+            .collect();
+        if let Some(file) = self
+            .get_includes(name.to_string())
+            .ok()
+            .and_then(|includes| includes.get(name).cloned())
+        {
+            files.push(file.0);
+        }
+        for file in files {
+            let Ok(content) = fs::read_to_string(&file) else {
+                continue;
+            };
+            for line in logical_lines(&content) {
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+                if tokens.len() > 1
+                    && tokens[0].eq_ignore_ascii_case(".subckt")
+                    && tokens[1].eq_ignore_ascii_case(name)
+                {
+                    let ports = split_params(&tokens[2..]).0;
+                    return Some(ports.iter().map(|p| p.to_string()).collect());
                 }
             }
         }
-        Err(Error::SpiceModelNotFound(key))
+        None
     }
 
-    fn includes(&self) -> Vec<String> {
-        let mut includes: HashMap<String, String> = HashMap::new();
+    /// check if the model or subcircuit is defined in the circuit itself.
+    pub(crate) fn defines(&self, key: &str) -> bool {
+        self.subcircuits.keys().any(|name| name.eq_ignore_ascii_case(key))
+            || self.items.iter().any(
+                |item| matches!(item, CircuitItem::Model(name, _, _) if name.eq_ignore_ascii_case(key)),
+            )
+    }
+
+    fn includes(&self) -> Result<Vec<String>, Error> {
+        //models can also come from the explicit includes,
+        //which are not searched in the pathlist.
+        let explicit = self
+            .items
+            .iter()
+            .any(|item| matches!(item, CircuitItem::Include(..)));
+        let mut includes: HashMap<String, Library> = HashMap::new();
         for item in &self.items {
-            if let CircuitItem::X(_, _, value) = item {
-                if !includes.contains_key(value) && !self.subcircuits.contains_key(value) {
-                    let incs = self.get_includes(value.to_string()).unwrap();
-                    for (key, value) in incs {
-                        includes.entry(key).or_insert(value);
+            let value = match item {
+                CircuitItem::X(_, _, value, _) => value,
+                CircuitItem::Q(_, _, _, _, value) => value,
+                _ => continue,
+            };
+            if !includes.keys().any(|key| key.eq_ignore_ascii_case(value)) && !self.defines(value) {
+                match self.get_includes(value.to_string()) {
+                    Ok(incs) => {
+                        for (key, value) in incs {
+                            includes.entry(key).or_insert(value);
+                        }
                     }
-                }
-            } else if let CircuitItem::Q(_, _, _, _, value) = item {
-                if !includes.contains_key(value) && !self.subcircuits.contains_key(value) {
-                    let incs = self.get_includes(value.to_string()).unwrap();
-                    for (key, value) in incs {
-                        includes.entry(key).or_insert(value);
+                    Err(err) => {
+                        if !explicit {
+                            return Err(err);
+                        }
                     }
                 }
             }
         }
         let mut result = Vec::new();
-        for (_, v) in includes {
-            result.push(format!(".include {}\n", v).to_string());
+        for (_, (filename, section)) in includes {
+            result.push(format!("{}\n", include(&filename, section.as_deref())));
         }
-        result
+        Ok(result)
+    }
+
+    pub(crate) fn to_str(&self, close: bool) -> Result<Vec<String>, Error> {
+        self.lines(close, true)
     }
 
-    fn to_str(&self, close: bool) -> Result<Vec<String>, Error> {
+    /// The netlist lines, the models and subcircuits from the libraries are
+    /// only included and checked when `resolve` is set.
+    fn lines(&self, close: bool, resolve: bool) -> Result<Vec<String>, Error> {
         let mut res = Vec::new();
-        res.append(&mut self.includes());
+        if !self.osdi.is_empty() {
+            res.push(String::from(".control"));
+            for filename in &self.osdi {
+                res.push(format!("pre_osdi {}", filename));
+            }
+            res.push(String::from(".endc"));
+        }
+        if resolve {
+            res.append(&mut self.includes()?);
+        }
         for (key, value) in &self.subcircuits {
             let nodes = value.0.join(" ");
-            res.push(format!(".subckt {} {}", key, nodes));
-            res.append(&mut value.1.to_str(false).unwrap());
+            let mut body = value.1.clone();
+            body.width = self.width;
+            let params = std::mem::take(&mut body.params);
+            res.push(format!(".subckt {} {}{}", key, nodes, format_params(&params)));
+            res.append(&mut body.lines(false, resolve)?);
             res.push(".ends".to_string());
         }
+        for (name, value) in &self.params {
+            res.push(format!(".param {}={}", name, value));
+        }
+        //the library lookup is expensive, resolve the ports once per subcircuit.
+        let mut ports_cache: HashMap<String, Option<Vec<String>>> = HashMap::new();
         for item in &self.items {
-            match item {
-                CircuitItem::R(reference, n0, n1, value) => {
-                    if reference.starts_with('R') {
-                        res.push(format!("{} {} {} {}", reference, n0, n1, value));
-                    } else {
-                        res.push(format!("R{} {} {} {}", reference, n0, n1, value));
-                    }
+            if resolve {
+                finite(item)?;
+            }
+            let mut item = item.clone();
+            for node in item.nodes_mut() {
+                if self.is_ground(node) {
+                    *node = String::from("0");
                 }
-                CircuitItem::C(reference, n0, n1, value) => {
-                    if reference.starts_with('C') {
-                        res.push(format!("{} {} {} {}", reference, n0, n1, value));
-                    } else {
-                        res.push(format!("C{} {} {} {}", reference, n0, n1, value));
-                    }
+            }
+            let start = res.len();
+            let name = item.netlist_name().unwrap_or_default();
+            match &item {
+                CircuitItem::R(_, n0, n1, value, _) => {
+                    res.push(format!("{} {} {} {}", name, n0, n1, value));
                 }
-                CircuitItem::D(reference, n0, n1, value) => {
-                    if reference.starts_with('D') {
-                        res.push(format!("{} {} {} {}", reference, n0, n1, value));
-                    } else {
-                        res.push(format!("D{} {} {} {}", reference, n0, n1, value));
-                    }
+                CircuitItem::C(_, n0, n1, value, _) => {
+                    res.push(format!("{} {} {} {}", name, n0, n1, value));
                 }
-                CircuitItem::Q(reference, n0, n1, n2, value) => {
-                    res.push(format!("Q{} {} {} {} {}", reference, n0, n1, n2, value));
+                CircuitItem::L(_, n0, n1, value, _) => {
+                    res.push(format!("{} {} {} {}", name, n0, n1, value));
                 }
-                CircuitItem::X(reference, n, value) => {
+                CircuitItem::D(_, n0, n1, value) => {
+                    res.push(format!("{} {} {} {}", name, n0, n1, value));
+                }
+                CircuitItem::Q(_, n0, n1, n2, value) => {
+                    res.push(format!("{} {} {} {} {}", name, n0, n1, n2, value));
+                }
+                CircuitItem::X(reference, n, value, params) => {
+                    let ports = match resolve {
+                        true => ports_cache
+                            .entry(value.to_string())
+                            .or_insert_with(|| self.subcircuit_ports(value))
+                            .clone(),
+                        false => None,
+                    };
+                    if let Some(ports) = ports {
+                        if ports.len() != n.len() {
+                            return Err(Error::SubcircuitPorts(
+                                reference.to_string(),
+                                n.len(),
+                                value.to_string(),
+                                ports.join(" "),
+                            ));
+                        }
+                    }
                     let mut nodes: String = String::new();
                     for _n in n {
                         nodes += _n;
                         nodes += " ";
                     }
-                    res.push(format!("X{} {}{}", reference, nodes, value));
+                    let value = format!("{}{}", value, format_params(params));
+                    res.push(format!("{} {}{}", name, nodes, value));
+                }
+                CircuitItem::V(_, n0, n1, value) => {
+                    res.push(format!("{} {} {} {}", name, n0, n1, value));
+                }
+                CircuitItem::I(_, n0, n1, value) => {
+                    res.push(format!("{} {} {} {}", name, n0, n1, value));
+                }
+                CircuitItem::B(_, n0, n1, value) => {
+                    res.push(format!("{} {} {} {}", name, n0, n1, value));
+                }
+                CircuitItem::E(_, n0, n1, n2, n3, value) => {
+                    res.push(format!("{} {} {} {} {} {}", name, n0, n1, n2, n3, value));
+                }
+                CircuitItem::S(_, n0, n1, n2, n3, model) => {
+                    res.push(format!("{} {} {} {} {} {}", name, n0, n1, n2, n3, model));
+                }
+                CircuitItem::A(_, ports, model) => {
+                    let ports = ports.join(" ").replace("[ ", "[").replace(" ]", "]");
+                    res.push(format!("{} {} {}", name, ports, model));
+                }
+                CircuitItem::N(_, nodes, model) => {
+                    res.push(format!("{} {} {}", name, nodes.join(" "), model));
+                }
+                CircuitItem::K(_, l1, l2, value) => {
+                    res.push(format!("{} {} {} {}", name, l1, l2, value));
+                }
+                CircuitItem::Model(name, kind, parameters) => {
+                    res.push(format!(".model {} {}({})", name, kind, parameters));
                 }
-                CircuitItem::V(reference, n0, n1, value) => {
-                    res.push(format!("V{} {} {} {}", reference, n0, n1, value));
+                CircuitItem::Include(filename, section) => {
+                    res.push(include(filename, section.as_deref()));
+                }
+                CircuitItem::Comment(text) => {
+                    for line in text.lines() {
+                        res.push(format!("* {}", line).trim_end().to_string());
+                    }
+                }
+                CircuitItem::Raw(line) => {
+                    res.push(line.to_string());
+                }
+            }
+            let wrap_item = match &item {
+                CircuitItem::X(..) => true,
+                CircuitItem::V(_, _, _, value) | CircuitItem::I(_, _, _, value) => {
+                    value.to_lowercase().contains("pwl")
+                }
+                _ => false,
+            };
+            if let (true, Some(width)) = (wrap_item, self.width) {
+                if res[start..].iter().any(|line| line.len() > width) {
+                    let lines: Vec<String> = res.drain(start..).collect();
+                    for line in lines {
+                        res.append(&mut wrap(&line, width));
+                    }
                 }
             }
         }
@@ -313,9 +1374,32 @@ impl Circuit {
     }
 }
 
+/// The netlist with the name as title line.
+///
+/// The items are written as they are, the models from the libraries are not
+/// included and the subcircuit ports are not checked. Use `save` for the
+/// checked netlist.
+impl std::fmt::Display for Circuit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "* {}", self.name)?;
+        //the unresolved netlist has no errors.
+        for line in self.lines(true, false).unwrap_or_default() {
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// The simulated vectors by name.
+pub(crate) type Vectors = HashMap<String, Vec<f64>>;
+
 pub struct Simulation {
     pub circuit: Circuit,
     pub buffer: Option<Vec<String>>,
+    saves: Vec<String>,
+    /// Save all vectors in addition to the saved vectors.
+    save_all: bool,
+    options: Vec<(String, String)>,
 }
 
 /// simulate the circuit with ngspice
@@ -340,98 +1424,795 @@ impl Simulation {
         Self {
             circuit,
             buffer: None,
+            saves: Vec::new(),
+            save_all: true,
+            options: Vec::new(),
         }
     }
+    /// A simulation of the changed circuit with the same saved vectors
+    /// and options.
+    pub(crate) fn with_circuit(&self, circuit: Circuit) -> Simulation {
+        Self {
+            circuit,
+            buffer: None,
+            saves: self.saves.clone(),
+            save_all: self.save_all,
+            options: self.options.clone(),
+        }
+    }
+    /// Save the device currents of the element in the results.
+    ///
+    /// Returns the names of the vectors, e.g. `@r1[i]` for resistors or
+    /// `@q1[ic]`, `@q1[ib]` and `@q1[ie]` for transistors. The branch current
+    /// of voltage sources is always saved. Elements in subcircuits are
+    /// selected with the `HierarchicalName`, e.g. `X1.R3` saves `@r.x1.r3[i]`.
+    pub fn save_current(&mut self, reference: &str) -> Result<Vec<String>, Error> {
+        let top = self
+            .circuit
+            .items
+            .iter()
+            .find(|item| item.reference().map(|r| r.as_str()) == Some(reference));
+        let (item, name) = match top {
+            Some(item) => (item, item.name().unwrap_or_default()),
+            None => {
+                let name = HierarchicalName::parse(reference);
+                let item = self
+                    .circuit
+                    .find_item(&name)
+                    .filter(|_| !name.path.is_empty())
+                    .ok_or_else(|| Error::UnknownCircuitElement(reference.to_string()))?;
+                (item, name.element(item.letter().unwrap_or_default()))
+            }
+        };
+        let name = name.to_lowercase();
+        let parameters: &[&str] = match item {
+            CircuitItem::R(..) | CircuitItem::C(..) | CircuitItem::L(..) => &["i"],
+            CircuitItem::D(..) => &["id"],
+            CircuitItem::Q(..) => &["ic", "ib", "ie"],
+            CircuitItem::I(..) => &["current"],
+            CircuitItem::V(..) | CircuitItem::E(..) => {
+                return Ok(vec![format!("{}#branch", name)])
+            }
+            _ => {
+                return Err(Error::NoDeviceCurrent(reference.to_string()))
+            }
+        };
+        let vectors: Vec<String> = parameters
+            .iter()
+            .map(|p| format!("@{}[{}]", name, p))
+            .collect();
+        for vector in &vectors {
+            self.save_vector(vector);
+        }
+        Ok(vectors)
+    }
+    /// Save the vector in the results, e.g. `@m.x1.m1[id]` for a device in a subcircuit.
+    pub fn save_vector(&mut self, vector: &str) {
+        let vector = vector.to_lowercase();
+        if !self.saves.contains(&vector) {
+            self.saves.push(vector);
+        }
+    }
+    /// Save only these vectors and the vectors added with `save_vector` and
+    /// `save_current`, e.g. `v(out)` or `i(V1)`. This reduces the memory
+    /// of long transients of large circuits. An empty list is rejected,
+    /// ngspice would save all the vectors.
+    pub fn save(&mut self, vectors: Vec<&str>) -> Result<(), Error> {
+        if vectors.is_empty() {
+            return Err(Error::InvalidProperty(String::from("save"), String::new()));
+        }
+        self.save_all = false;
+        for vector in vectors {
+            self.save_vector(vector);
+        }
+        Ok(())
+    }
+    /// Set the simulator option, e.g. `reltol` or `method`, an option that
+    /// is already set is replaced. Flags like `savecurrents` have an empty
+    /// value.
+    pub fn set_option(&mut self, name: &str, value: &str) {
+        match self
+            .options
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            Some(option) => option.1 = value.to_string(),
+            None => self.options.push((name.to_string(), value.to_string())),
+        }
+    }
+    pub fn remove_option(&mut self, name: &str) {
+        self.options.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+    }
+    /// The simulator options.
+    pub fn options(&self) -> &[(String, String)] {
+        &self.options
+    }
+    /// The netlist with the options and the saved vectors.
+    pub(crate) fn deck(&self) -> Result<Vec<String>, Error> {
+        let mut deck = self.circuit.to_str(false)?;
+        if !self.options.is_empty() {
+            let options: Vec<String> = self
+                .options
+                .iter()
+                .map(|(name, value)| match value.is_empty() {
+                    true => name.clone(),
+                    false => format!("{}={}", name, value),
+                })
+                .collect();
+            deck.push(format!(".options {}", options.join(" ")));
+        }
+        if !self.saves.is_empty() {
+            let all = if self.save_all { "all " } else { "" };
+            deck.push(format!(".save {}{}", all, self.saves.join(" ")));
+        }
+        deck.push(String::from(".end"));
+        Ok(deck)
+    }
 
+    /// Run the transient analysis, `transient` also sets the largest time
+    /// step and starts from the initial conditions.
     pub fn tran(&mut self, step: &str, stop: &str, start: &str) -> HashMap<String, Vec<f64>> {
         let mut c = Cb::new();
         let ngspice = NgSpice::new(&mut c).unwrap();
-        let circ = self.circuit.to_str(true).unwrap();
+        let circ = self.deck().unwrap();
         ngspice.circuit(circ).unwrap();
         ngspice
             .command(format!("tran {} {} {}", step, stop, start).as_str())
             .unwrap(); //TODO
-        let plot = ngspice.current_plot().unwrap();
-        let res = ngspice.all_vecs(plot.as_str()).unwrap();
-        let mut map: HashMap<String, Vec<f64>> = HashMap::new();
-        for name in res {
-            let re = ngspice.vector_info(name.as_str());
-            if let Ok(r) = re {
-                let name = r.name;
-                let data1 = match r.data {
-                    ComplexSlice::Real(list) => list.iter().map(|i| *i).collect(),
-                    ComplexSlice::Complex(list) => {
-                        list.iter().map(|f| {
-                            if !f.cx_real.is_nan() {
-                                f.cx_real
-                            } else if !f.cx_imag.is_nan() {
-                                f.cx_imag
-                            } else {
-                                todo!("can not get value from complex: {:?}", f);
-                            }}).collect()
-                    }
-                };
-                map.insert(name, data1);
-            } else {
-                panic!("Can not run tran with schema.");
-            }
-        }
-        println!("tran return: {}, {}, {}", c.status, c.unload, c.quit);
+        let map = vectors(&ngspice).expect("Can not run tran with schema.");
+        log::debug!("tran return: {}, {}, {}", c.status, c.unload, c.quit);
         self.buffer = Some(c.strs.clone());
         map
     }
     pub fn ac(&mut self, start_frequency: &str, stop_frequency: &str, number_of_points: u32,  variation: &str) -> HashMap<String, Vec<f64>> {
         let mut c = Cb::new();
         let ngspice = NgSpice::new(&mut c).unwrap();
-        let circ = self.circuit.to_str(true).unwrap();
+        let circ = self.deck().unwrap();
         ngspice.circuit(circ).unwrap();
         ngspice
             //DEC ND FSTART FSTOP
             .command(format!("ac {} {} {} {}", variation, number_of_points, start_frequency, stop_frequency).as_str())
             .unwrap(); //TODO
-        let plot = ngspice.current_plot().unwrap();
-        let res = ngspice.all_vecs(plot.as_str()).unwrap();
-        let mut map: HashMap<String, Vec<f64>> = HashMap::new();
-        for name in res {
-            let re = ngspice.vector_info(name.as_str());
-            if let Ok(r) = re {
-                let name = r.name;
-                let data1 = match r.data {
-                    ComplexSlice::Real(list) => list.iter().map(|i| *i).collect(),
-                    ComplexSlice::Complex(list) => {
-                        list.iter().map(|f| {
-                            if !f.cx_real.is_nan() {
-                                f.cx_real
-                            } else if !f.cx_imag.is_nan() {
-                                f.cx_imag
-                            } else {
-                                todo!("can not get value from complex: {:?}", f);
-                            }}).collect()
-                    }
-                };
-                map.insert(name, data1);
-            } else {
-                panic!("Can not run ac with schema.");
-            }
-        }
-        println!("ac return: {}, {}, {}", c.status, c.unload, c.quit);
+        let map = vectors(&ngspice).expect("Can not run ac with schema.");
+        log::debug!("ac return: {}, {}, {}", c.status, c.unload, c.quit);
         self.buffer = Some(c.strs.clone());
         map
     }
+    /// Run the analysis with the control lines added to the netlist
+    /// before `.end`, e.g. `.options savecurrents`.
+    pub(crate) fn run_with(&mut self, lines: &[String], command: &str) -> Result<Vectors, Error> {
+        let mut c = Cb::new();
+        let ngspice = NgSpice::new(&mut c).map_err(ngspice_error)?;
+        let mut deck = self.deck()?;
+        let end = deck.len() - 1;
+        deck.splice(end..end, lines.iter().cloned());
+        ngspice.circuit(deck).map_err(ngspice_error)?;
+        ngspice.command(command).map_err(ngspice_error)?;
+        let map = vectors(&ngspice);
+        self.buffer = Some(c.strs.clone());
+        map.map_err(|err| failed(err, &c.strs))
+    }
+    /// Run the AC analysis and get the complex vectors, real vectors
+    /// like the frequency have no imaginary part.
+    pub fn ac_complex(
+        &mut self,
+        start_frequency: &str,
+        stop_frequency: &str,
+        number_of_points: u32,
+        variation: &str,
+    ) -> Result<AnalysisResult, Error> {
+        let mut c = Cb::new();
+        let ngspice = NgSpice::new(&mut c).map_err(ngspice_error)?;
+        ngspice.circuit(self.deck()?).map_err(ngspice_error)?;
+        ngspice
+            .command(
+                format!(
+                    "ac {} {} {} {}",
+                    variation, number_of_points, start_frequency, stop_frequency
+                )
+                .as_str(),
+            )
+            .map_err(ngspice_error)?;
+        let map = complex_vectors(&ngspice);
+        self.buffer = Some(c.strs.clone());
+        let map = map.map_err(|err| failed(err, &c.strs))?;
+        Ok(AnalysisResult::from(map))
+    }
+    /// Run the analysis for every value of the element.
+    ///
+    /// The circuit is loaded once and the value is changed with `alter`
+    /// between the runs, the results are returned in the order of the values.
+    pub fn sweep(
+        &mut self,
+        reference: &str,
+        values: &[Value],
+        analysis: &Analysis,
+    ) -> Result<Vec<(Value, Vectors)>, Error> {
+        let name = self
+            .circuit
+            .items
+            .iter()
+            .find(|item| item.reference().map(|r| r.as_str()) == Some(reference))
+            .and_then(|item| item.name())
+            .ok_or_else(|| Error::UnknownCircuitElement(reference.to_string()))?;
+        let mut c = Cb::new();
+        let ngspice = NgSpice::new(&mut c).map_err(ngspice_error)?;
+        ngspice.circuit(self.deck()?).map_err(ngspice_error)?;
+        let mut result = Vec::new();
+        for value in values {
+            ngspice
+                .command(format!("alter {} = {}", name.to_lowercase(), value).as_str())
+                .map_err(ngspice_error)?;
+            ngspice.command(analysis.command().as_str()).map_err(ngspice_error)?;
+            result.push((*value, vectors(&ngspice)?));
+        }
+        self.buffer = Some(c.strs.clone());
+        Ok(result)
+    }
+    /// Run the analysis for every circuit temperature in °C.
+    pub fn sweep_temperature(
+        &mut self,
+        temperatures: &[f64],
+        analysis: &Analysis,
+    ) -> Result<Vec<(f64, Vectors)>, Error> {
+        let mut c = Cb::new();
+        let ngspice = NgSpice::new(&mut c).map_err(ngspice_error)?;
+        ngspice.circuit(self.deck()?).map_err(ngspice_error)?;
+        let mut result = Vec::new();
+        for temperature in temperatures {
+            ngspice
+                .command(format!("option temp = {}", temperature).as_str())
+                .map_err(ngspice_error)?;
+            ngspice.command(analysis.command().as_str()).map_err(ngspice_error)?;
+            result.push((*temperature, vectors(&ngspice)?));
+        }
+        self.buffer = Some(c.strs.clone());
+        Ok(result)
+    }
+}
+
+/// The error of a failed ngspice call.
+pub(crate) fn ngspice_error<E: std::fmt::Debug>(err: E) -> Error {
+    Error::Simulation(format!("{:?}", err), Vec::new())
+}
+
+/// The error of a failed analysis with the messages of ngspice.
+pub(crate) fn failed(err: Error, output: &[String]) -> Error {
+    let message = match err {
+        Error::Simulation(message, _) => message,
+        err => err.to_string(),
+    };
+    Error::Simulation(message, SpiceMessage::parse_all(output))
+}
+
+/// Get the vectors of the current plot, complex values like the AC results are
+/// returned as the magnitude. The phase is available from `ac_complex`.
+pub(crate) fn vectors<C: Callbacks>(ngspice: &NgSpice<C>) -> Result<Vectors, Error> {
+    let plot = ngspice.current_plot().map_err(ngspice_error)?;
+    let res = ngspice.all_vecs(plot.as_str()).map_err(ngspice_error)?;
+    let mut map: HashMap<String, Vec<f64>> = HashMap::new();
+    for name in res {
+        let r = ngspice.vector_info(name.as_str()).map_err(ngspice_error)?;
+        let data1 = match r.data {
+            ComplexSlice::Real(list) => list.to_vec(),
+            ComplexSlice::Complex(list) => list
+                .iter()
+                .map(|f| {
+                    let value = Complex64::new(f.cx_real, f.cx_imag);
+                    if value.is_nan() {
+                        Err(Error::Simulation(
+                            format!("the vector {} is not a number", name),
+                            Vec::new(),
+                        ))
+                    } else {
+                        Ok(value.norm())
+                    }
+                })
+                .collect::<Result<Vec<f64>, Error>>()?,
+        };
+        map.insert(r.name, data1);
+    }
+    Ok(map)
+}
+
+/// Get the vectors of the current plot as complex values.
+fn complex_vectors<C: Callbacks>(
+    ngspice: &NgSpice<C>,
+) -> Result<HashMap<String, Vec<Complex64>>, Error> {
+    let plot = ngspice.current_plot().map_err(ngspice_error)?;
+    let mut map = HashMap::new();
+    for name in ngspice
+        .all_vecs(plot.as_str())
+        .map_err(ngspice_error)?
+    {
+        let r = ngspice.vector_info(name.as_str()).map_err(ngspice_error)?;
+        let data = match r.data {
+            ComplexSlice::Real(list) => list.iter().map(|v| Complex64::new(*v, 0.0)).collect(),
+            ComplexSlice::Complex(list) => list
+                .iter()
+                .map(|f| Complex64::new(f.cx_real, f.cx_imag))
+                .collect(),
+        };
+        map.insert(r.name, data);
+    }
+    Ok(map)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Circuit;
+    use std::path::{Path, PathBuf};
+
+    use super::CircuitItem;
+    use crate::{error::Error, Analysis, Circuit, Simulation, Tolerance, Value};
 
+    #[test]
+    fn duplicate_reference() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(String::from("R1"), String::from("1"), String::from("0"), String::from("1k"))
+            .unwrap();
+        assert!(circuit
+            .resistor(String::from("r1"), String::from("1"), String::from("2"), String::from("1k"))
+            .is_err());
+        circuit
+            .resistor(String::from("R7"), String::from("1"), String::from("2"), String::from("1k"))
+            .unwrap();
+        circuit
+            .circuit(String::from("R1"), vec![String::from("1")], String::from("sub"))
+            .unwrap();
+        assert_eq!(
+            vec![
+                (String::from("R1"), String::from("R1")),
+                (String::from("R7"), String::from("R2")),
+                (String::from("R1"), String::from("X1")),
+            ],
+            circuit.renumber()
+        );
+    }
+    #[test]
+    fn renumber_coupled() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit.inductor(String::from("L5"), "a", "GND", String::from("1m")).unwrap();
+        circuit.inductor(String::from("L1"), "b", "GND", String::from("1m")).unwrap();
+        circuit
+            .coupling(String::from("K7"), String::from("L5"), String::from("L1"), String::from("0.9"))
+            .unwrap();
+        circuit.renumber();
+        assert_eq!(
+            vec![
+                String::from("L1 a 0 1m"),
+                String::from("L2 b 0 1m"),
+                String::from("K1 L1 L2 0.9"),
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+    #[test]
+    fn annotate() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit.resistor(String::from("R?"), "in", "a", String::from("1k")).unwrap();
+        circuit.resistor(String::from("R1"), "a", "b", String::from("1k")).unwrap();
+        circuit.circuit(String::from("RV3"), vec!["b", "c", "d"], String::from("pot")).unwrap();
+        circuit.inductor(String::from("L"), "b", "GND", String::from("1m")).unwrap();
+        circuit.inductor(String::from("L1"), "c", "GND", String::from("1m")).unwrap();
+        circuit.coupling(String::from("K1"), String::from("L"), String::from("L1"), String::from("0.9")).unwrap();
+        circuit.resistor(String::from("R1"), "c", "d", String::from("1k")).unwrap_err();
+        circuit.resistor(String::from("R3"), "c", "d", String::from("1k")).unwrap();
+        assert_eq!(
+            vec![(String::from("R?"), String::from("R2")), (String::from("L"), String::from("L2"))],
+            circuit.annotate(false)
+        );
+        assert_eq!(
+            vec![
+                (String::from("R2"), String::from("R1")),
+                (String::from("R1"), String::from("R2")),
+                (String::from("RV3"), String::from("RV1")),
+                (String::from("L2"), String::from("L1")),
+                (String::from("L1"), String::from("L2")),
+            ],
+            circuit.annotate(true)
+        );
+        assert!(circuit.items.contains(&CircuitItem::K(
+            String::from("K1"),
+            String::from("L1"),
+            String::from("L2"),
+            String::from("0.9")
+        )));
+    }
+    #[test]
+    fn nested_includes() {
+        let circuit = Circuit::new(String::from("test"), vec![String::from("files/spice/nested")]);
+        let includes = circuit.get_includes(String::from("BUFFER")).unwrap();
+        assert_eq!(Path::new("files/spice/nested/buffer.lib"), includes["BUFFER"].0);
+        assert_eq!(
+            Path::new("files/spice/nested/models/transistors.lib"),
+            includes["models/transistors.lib"].0
+        );
+        assert_eq!(Path::new("files/spice/nested/models/diodes.lib"), includes["diodes.lib"].0);
+        let circuit = Circuit::new(String::from("test"), vec![String::from("files/spice/cycle")]);
+        assert!(matches!(
+            circuit.get_includes(String::from("FIRST")),
+            Err(Error::IncludeCycle(_))
+        ));
+    }
+    #[test]
+    fn library_pathlist() {
+        let mut circuit = Circuit::new(String::from("test"), vec![String::from("files/spice/nested")]);
+        circuit.set_library(Vec::new());
+        let includes = circuit.get_includes(String::from("D1N4148")).unwrap();
+        assert_eq!(Path::new("files/spice/nested/models/diodes.lib"), includes["D1N4148"].0);
+        circuit.set_pathlist(vec![PathBuf::from("files/spice/**/d*.lib")]);
+        let includes = circuit.get_includes(String::from("D1N4148")).unwrap();
+        assert_eq!(Path::new("files/spice/nested/models/diodes.lib"), includes["D1N4148"].0);
+        assert!(circuit.get_includes(String::from("Q2N3904")).is_err());
+    }
+    #[test]
+    #[cfg(unix)]
+    fn library_directories() {
+        let dir = std::env::temp_dir().join("elektron_spice_library_loop");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("models")).unwrap();
+        std::fs::copy("files/spice/nested/models/diodes.lib", dir.join("models/diodes.lib")).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("models/loop")).unwrap();
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit.set_pathlist(vec![PathBuf::from("files/spice/missing"), dir.clone()]);
+        circuit.set_library(Vec::new());
+        let includes = circuit.get_includes(String::from("D1N4148")).unwrap();
+        assert_eq!(dir.join("models/diodes.lib"), includes["D1N4148"].0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn library() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit.set_library(vec![PathBuf::from("files/spice/nested")]);
+        let includes = circuit.get_includes(String::from("D1N4148")).unwrap();
+        assert_eq!(Path::new("files/spice/nested/models/diodes.lib"), includes["D1N4148"].0);
+        circuit.set_library(Vec::new());
+        assert!(circuit.get_includes(String::from("D1N4148")).is_err());
+    }
+    #[test]
+    fn vendor_names() {
+        let mut circuit =
+            Circuit::new(String::from("test"), vec![String::from("files/spice/vendor")]);
+        circuit.set_library(Vec::new());
+        for name in ["TL072_ALT", "tl072_alt", "LM-358+", "IRF540N", "1n5817-t"] {
+            assert_eq!(
+                Path::new("files/spice/vendor/vendor.lib"),
+                circuit.get_includes(name.to_string()).unwrap()[name].0
+            );
+        }
+        assert!(circuit.get_includes(String::from("TL072")).is_err());
+        assert_eq!(5, circuit.subcircuit_ports("lm-358+").unwrap().len());
+        circuit.model(String::from("BC547B"), String::from("NPN"), String::from("BF=300"));
+        assert!(circuit.defines("bc547b"));
+    }
+    #[test]
+    fn library_sections() {
+        let mut circuit =
+            Circuit::new(String::from("test"), vec![String::from("files/spice/sections")]);
+        circuit
+            .bjt(String::from("Q1"), "c", "b", "GND", String::from("QFAST"))
+            .unwrap();
+        circuit.lib(String::from("models.lib"), String::from("tt"));
+        assert_eq!(
+            vec![
+                String::from(".lib files/spice/sections/corners.lib fast\n"),
+                String::from("Q1 c b 0 QFAST"),
+                String::from(".lib models.lib tt"),
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+    #[test]
+    fn library_scanner() {
+        let mut circuit = Circuit::new(String::from("test"), vec![String::from("files/spice/sections")]);
+        circuit.set_library(Vec::new());
+        assert!(matches!(
+            circuit.get_includes(String::from("COMMENTED")),
+            Err(Error::SpiceModelNotFound(_))
+        ));
+        assert_eq!(
+            Path::new("files/spice/sections/scan.lib"),
+            circuit.get_includes(String::from("Q_SPLIT")).unwrap()["Q_SPLIT"].0
+        );
+        assert_eq!(
+            Some(vec![
+                String::from("in"),
+                String::from("out"),
+                String::from("vcc"),
+                String::from("vee")
+            ]),
+            circuit.subcircuit_ports("SPLIT_AMP")
+        );
+    }
+    #[test]
+    fn subcircuit_ports() {
+        let mut circuit = Circuit::new(String::from("test"), vec![String::from("files/spice/")]);
+        let mut divider = Circuit::new(String::from("divider"), Vec::new());
+        divider
+            .resistor(String::from("R1"), String::from("in"), String::from("out"), String::from("10k"))
+            .unwrap();
+        circuit.subcircuit(String::from("divider"), vec!["in", "out"], divider).unwrap();
+        circuit
+            .circuit(String::from("X1"), vec!["a", "b", "c"], String::from("divider"))
+            .unwrap();
+        assert!(matches!(
+            circuit.to_str(true),
+            Err(Error::SubcircuitPorts(_, 3, _, ports)) if ports == "in out"
+        ));
+        let mut circuit = Circuit::new(String::from("test"), vec![String::from("files/spice/")]);
+        circuit
+            .circuit(String::from("U1"), vec!["out", "inn", "inp", "vee"], String::from("TL072"))
+            .unwrap();
+        assert!(matches!(
+            circuit.to_str(true),
+            Err(Error::SubcircuitPorts(_, 4, _, ports)) if ports == "1 2 3 4 5"
+        ));
+    }
+    #[test]
+    fn ground_alias() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(String::from("R1"), String::from("in"), String::from("gnd"), String::from("1k"))
+            .unwrap();
+        circuit
+            .resistor(String::from("R2"), String::from("in"), String::from("VSS"), String::from("1k"))
+            .unwrap();
+        circuit.add_ground_alias("VSS");
+        assert_eq!(
+            vec![
+                String::from("R1 in 0 1k"),
+                String::from("R2 in 0 1k"),
+                String::from(".end"),
+            ],
+            circuit.to_str(true).unwrap()
+        );
+    }
+    #[test]
+    fn tolerance() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .inductor(String::from("L1"), String::from("1"), String::from("0"), String::from("10m"))
+            .unwrap();
+        circuit
+            .voltage(String::from("V1"), String::from("1"), String::from("0"), String::from("1"))
+            .unwrap();
+        assert_eq!(None, circuit.tolerance("L1"));
+        circuit.set_tolerance("L1", Tolerance::gaussian(0.1)).unwrap();
+        assert_eq!(Some(Tolerance::gaussian(0.1)), circuit.tolerance("L1"));
+        assert!(circuit.set_tolerance("V1", Tolerance::new(0.1)).is_err());
+        assert_eq!(
+            vec![String::from("L1 1 0 10m"), String::from("V1 1 0 1"), String::from(".end")],
+            circuit.to_str(true).unwrap()
+        );
+    }
+    #[test]
+    fn sweep_unknown_element() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(String::from("R1"), String::from("1"), String::from("0"), String::from("1k"))
+            .unwrap();
+        let mut simulation = Simulation::new(circuit);
+        let analysis = Analysis::Tran {
+            step: String::from("1u"),
+            stop: String::from("1m"),
+            start: String::from("0"),
+        };
+        assert!(simulation
+            .sweep("R2", &[Value::new(1e3), Value::new(2e3)], &analysis)
+            .is_err());
+    }
+    #[test]
+    fn remove_replace() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit.voltage(String::from("V1"), "in", "GND", String::from("DC 5")).unwrap();
+        circuit.resistor(String::from("R1"), "in", "out", String::from("10k")).unwrap();
+        circuit.resistor(String::from("R2"), "out", "GND", String::from("10k")).unwrap();
+        circuit.voltage(String::from("VPROBE"), "out", "probe", String::from("DC 0")).unwrap();
+        circuit.remove("VPROBE").unwrap();
+        circuit
+            .replace("R1", |c| {
+                c.resistor(String::from("R3"), "in", "mid", String::from("4.7k"))?;
+                c.capacitor(String::from("C1"), "mid", "out", String::from("10n"))
+            })
+            .unwrap();
+        assert!(circuit
+            .replace("R2", |c| c.resistor(String::from("R3"), "out", "GND", String::from("1k")))
+            .is_err());
+        assert!(matches!(circuit.remove("R1"), Err(Error::UnknownCircuitElement(_))));
+        assert_eq!(
+            vec![
+                String::from("V1 in 0 DC 5"),
+                String::from("R3 in mid 4.7k"),
+                String::from("C1 mid out 10n"),
+                String::from("R2 out 0 10k"),
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+    #[test]
+    fn remove_coupled() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit.inductor(String::from("L1"), "a", "GND", String::from("1m")).unwrap();
+        circuit.inductor(String::from("L2"), "b", "GND", String::from("1m")).unwrap();
+        circuit.inductor(String::from("L3"), "c", "GND", String::from("1m")).unwrap();
+        circuit
+            .coupling(String::from("K1"), String::from("L1"), String::from("L2"), String::from("0.99"))
+            .unwrap();
+        circuit
+            .coupling(String::from("K2"), String::from("L3"), String::from("l1"), String::from("0.5"))
+            .unwrap();
+        circuit
+            .coupling(String::from("K3"), String::from("L2"), String::from("L3"), String::from("0.9"))
+            .unwrap();
+        circuit.remove("L1").unwrap();
+        assert_eq!(
+            vec![
+                String::from("L2 b 0 1m"),
+                String::from("L3 c 0 1m"),
+                String::from("K3 L2 L3 0.9"),
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+    #[test]
+    fn comment_raw() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit.comment("input divider\nrev b");
+        circuit.resistor(String::from("R1"), "in", "out", String::from("10k")).unwrap();
+        circuit.raw(".nodeset v(out)=2.5");
+        circuit.resistor(String::from("R2"), "out", "GND", String::from("10k")).unwrap();
+        assert_eq!(
+            vec![
+                String::from("* input divider"),
+                String::from("* rev b"),
+                String::from("R1 in out 10k"),
+                String::from(".nodeset v(out)=2.5"),
+                String::from("R2 out 0 10k"),
+            ],
+            circuit.to_str(false).unwrap()
+        );
+        assert_eq!(2, circuit.items().count());
+    }
+    #[test]
+    fn wrap_lines() {
+        let mut circuit = Circuit::new(String::from("test"), vec![String::from("files/spice/")]);
+        circuit.voltage(String::from("V1"), "in", "GND", String::from("PWL(0 0 1u 0 1.1u 5 3u 5)")).unwrap();
+        circuit
+            .circuit(String::from("1"), vec!["in", "out", "vcc", "vee", "out"], String::from("TL072"))
+            .unwrap();
+        circuit.resistor(String::from("R1"), "out", "GND", String::from("10k")).unwrap();
+        circuit.set_line_width(Some(28));
+        assert_eq!(
+            vec![
+                String::from("V1 in 0 PWL(0 0 1u 0 1.1u 5"),
+                String::from("+ 3u 5)"),
+                String::from("X1 in out vcc vee out TL072"),
+                String::from("R1 out 0 10k"),
+            ],
+            circuit.to_str(false).unwrap()[1..].to_vec()
+        );
+        circuit.set_line_width(Some(20));
+        let lines = circuit.to_str(false).unwrap();
+        assert_eq!(
+            vec![String::from("X1 in out vcc vee"), String::from("+ out TL072")],
+            lines[lines.len() - 3..lines.len() - 1].to_vec()
+        );
+    }
+    #[test]
+    fn save() {
+        let mut circuit = Circuit::new(String::from("divider"), Vec::new());
+        circuit.voltage(String::from("V1"), "in", "GND", String::from("DC 5")).unwrap();
+        circuit.resistor(String::from("R1"), "in", "out", String::from("10k")).unwrap();
+        let mut buffer = Vec::new();
+        circuit.save(&mut buffer).unwrap();
+        assert_eq!("* divider\nV1 in 0 DC 5\nR1 in out 10k\n.end\n", String::from_utf8(buffer).unwrap());
+        let filename = std::env::temp_dir().join("elektron_spice_save.cir");
+        circuit.save_file(&filename).unwrap();
+        assert_eq!(circuit.to_string(), std::fs::read_to_string(&filename).unwrap());
+        std::fs::remove_file(filename).unwrap();
+        //the display does not resolve the models
+        circuit.set_library(Vec::new());
+        circuit
+            .circuit(String::from("X1"), vec!["in", "out"], String::from("missing"))
+            .unwrap();
+        assert!(circuit.save(Vec::new()).is_err());
+        assert_eq!(
+            "* divider\nV1 in 0 DC 5\nR1 in out 10k\nX1 in out missing\n.end\n",
+            circuit.to_string()
+        );
+    }
+    #[test]
+    fn console_level() {
+        assert_eq!(log::Level::Debug, super::console_level("stdout Circuit: divider"));
+        assert_eq!(log::Level::Info, super::console_level("stderr Note: Starting dynamic gmin stepping"));
+        assert_eq!(log::Level::Warn, super::console_level("stderr Warning: singular matrix:  check node out"));
+        assert_eq!(log::Level::Error, super::console_level("stderr Error: no such vector v(x)"));
+    }
+    #[test]
+    fn save_current() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(String::from("R1"), String::from("1"), String::from("0"), String::from("1k"))
+            .unwrap();
+        circuit.model(String::from("NPN1"), String::from("NPN"), String::from("BF=200"));
+        circuit
+            .bjt(String::from("1"), String::from("c"), String::from("b"), String::from("0"), String::from("NPN1"))
+            .unwrap();
+        let mut simulation = Simulation::new(circuit);
+        assert_eq!(vec!["@r1[i]"], simulation.save_current("R1").unwrap());
+        assert_eq!(vec!["@q1[ic]", "@q1[ib]", "@q1[ie]"], simulation.save_current("1").unwrap());
+        assert!(simulation.save_current("R2").is_err());
+        let mut switch = Circuit::new(String::from("test"), Vec::new());
+        switch
+            .switch(String::from("S1"), "1", "0", "c", "0", String::from("SW1"))
+            .unwrap();
+        assert!(matches!(
+            Simulation::new(switch).save_current("S1"),
+            Err(Error::NoDeviceCurrent(_))
+        ));
+        simulation.save_vector("@R1[i]");
+        assert_eq!(
+            vec![
+                "R1 1 0 1k",
+                ".model NPN1 NPN(BF=200)",
+                "Q1 c b 0 NPN1",
+                ".save all @r1[i] @q1[ic] @q1[ib] @q1[ie]",
+                ".end"
+            ],
+            simulation.deck().unwrap()
+        );
+    }
+    #[test]
+    fn save_vectors() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit.voltage(String::from("V1"), "in", "GND", String::from("DC 5")).unwrap();
+        circuit.resistor(String::from("R1"), "in", "out", String::from("1k")).unwrap();
+        let mut simulation = Simulation::new(circuit);
+        assert!(matches!(
+            simulation.save(Vec::new()),
+            Err(Error::InvalidProperty(_, _))
+        ));
+        simulation.save(vec!["v(out)", "i(V1)"]).unwrap();
+        simulation.save_current("R1").unwrap();
+        assert_eq!(
+            vec!["V1 in 0 DC 5", "R1 in out 1k", ".save v(out) i(v1) @r1[i]", ".end"],
+            simulation.deck().unwrap()
+        );
+    }
+    #[test]
+    fn not_finite() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        assert!(matches!(
+            circuit.resistor(String::from("R1"), "in", "out", Value::new(f64::NAN).to_string()),
+            Err(Error::InvalidValue(_))
+        ));
+        circuit.resistor(String::from("R1"), "in", "out", String::from("1k")).unwrap();
+        circuit.set_value("R1", Value::new(f64::INFINITY)).unwrap();
+        assert!(matches!(circuit.to_str(true), Err(Error::InvalidValue(_))));
+    }
+    #[test]
+    fn lower_case_reference() {
+        let circuit: Circuit = "* test\nV1 in 0 DC 5\nr1 in out 1k\n".parse().unwrap();
+        let mut simulation = Simulation::new(circuit);
+        simulation.save_current("r1").unwrap();
+        assert_eq!(
+            vec!["V1 in 0 DC 5", "r1 in out 1k", ".save all @r1[i]", ".end"],
+            simulation.deck().unwrap()
+        );
+        assert!(simulation
+            .circuit
+            .resistor(String::from("R1"), "in", "out", String::from("1k"))
+            .is_err());
+    }
     #[test]
     fn load_model() {
         let circuit = Circuit::new(String::from("test"), vec![String::from("files/spice/")]);
         let include = circuit.get_includes(String::from("TL072")).unwrap();
-        assert_eq!("files/spice/TL072.lib", include.get("TL072").unwrap());
+        assert_eq!(Path::new("files/spice/TL072.lib"), include.get("TL072").unwrap().0);
         let include = circuit.get_includes(String::from("BC547B")).unwrap();
-        assert_eq!("files/spice/BC547.mod", include.get("BC547B").unwrap());
+        assert_eq!(Path::new("files/spice/BC547.mod"), include.get("BC547B").unwrap().0);
         let include = circuit.get_includes(String::from("BC556B")).unwrap();
-        assert_eq!("files/spice/bc5x7.lib", include.get("BC556B").unwrap());
+        assert_eq!(Path::new("files/spice/bc5x7.lib"), include.get("BC556B").unwrap().0);
     }
 }
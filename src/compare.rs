@@ -0,0 +1,193 @@
+use std::{collections::HashMap, fs};
+
+use crate::error::Error;
+
+/// The deviation of a point from the golden vector.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deviation {
+    /// The value of the scale, e.g. the time.
+    pub x: f64,
+    pub expected: f64,
+    pub actual: f64,
+    /// The absolute error.
+    pub error: f64,
+    /// The error is above the allowed tolerance.
+    pub failed: bool,
+}
+
+/// The comparison of a vector with the golden vector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub vector: String,
+    pub deviations: Vec<Deviation>,
+    /// The largest absolute error.
+    pub max_error: f64,
+    pub rms_error: f64,
+    /// The number of failed points.
+    pub failures: usize,
+}
+
+impl Comparison {
+    pub fn passed(&self) -> bool {
+        self.failures == 0
+    }
+    /// The point with the largest error.
+    pub fn worst(&self) -> Option<&Deviation> {
+        self.deviations
+            .iter()
+            .max_by(|a, b| a.error.total_cmp(&b.error))
+    }
+}
+
+/// Compare the simulated vectors with stored golden results.
+///
+/// A point fails when the error is above `absolute + relative * |expected|`.
+/// The simulated vectors are interpolated at the points of the golden
+/// scale, so the time steps of the simulations do not need to match.
+///
+/// ```ignore
+/// let golden = Compare::load("tests/golden/filter.json")?;
+/// let result = simulation.tran("1u", "10m", "0");
+/// for comparison in Compare::new(1e-3, 1e-2).compare(&golden, &result, &["out"])? {
+///     assert!(comparison.passed(), "{}: {:?}", comparison.vector, comparison.worst());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compare {
+    pub absolute: f64,
+    pub relative: f64,
+    /// The name of the scale vector, `time` by default.
+    pub scale: String,
+}
+
+impl Compare {
+    pub fn new(absolute: f64, relative: f64) -> Self {
+        Self {
+            absolute,
+            relative,
+            scale: String::from("time"),
+        }
+    }
+    /// Set the scale vector, e.g. `frequency` for an AC analysis.
+    pub fn set_scale(&mut self, scale: &str) {
+        self.scale = scale.to_string();
+    }
+    /// Load the golden vectors from a JSON file.
+    pub fn load(filename: &str) -> Result<HashMap<String, Vec<f64>>, Error> {
+        let content = fs::read_to_string(filename)?;
+        serde_json::from_str(&content).map_err(|e| Error::Serialization(e.to_string()))
+    }
+    /// Store the vectors as golden results in a JSON file.
+    pub fn save(filename: &str, vectors: &HashMap<String, Vec<f64>>) -> Result<(), Error> {
+        let content = serde_json::to_string_pretty(vectors)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        fs::write(filename, content)?;
+        Ok(())
+    }
+    /// Compare the vectors of the simulation with the golden vectors.
+    pub fn compare(
+        &self,
+        golden: &HashMap<String, Vec<f64>>,
+        actual: &HashMap<String, Vec<f64>>,
+        vectors: &[&str],
+    ) -> Result<Vec<Comparison>, Error> {
+        let get = |vectors: &'_ HashMap<String, Vec<f64>>, name: &str| {
+            vectors
+                .get(name)
+                .cloned()
+                .ok_or_else(|| Error::Signal(format!("vector {} not found", name)))
+        };
+        let (golden_scale, actual_scale) = (get(golden, &self.scale)?, get(actual, &self.scale)?);
+        if actual_scale.is_empty() || actual_scale.windows(2).any(|w| w[0] > w[1]) {
+            return Err(Error::Signal(format!(
+                "the scale {} is not ascending",
+                self.scale
+            )));
+        }
+        let mut result = Vec::new();
+        for name in vectors {
+            let (expected, values) = (get(golden, name)?, get(actual, name)?);
+            if values.len() != actual_scale.len() {
+                return Err(Error::Signal(format!(
+                    "vector {} does not match the scale {}",
+                    name, self.scale
+                )));
+            }
+            let deviations: Vec<Deviation> = golden_scale
+                .iter()
+                .zip(&expected)
+                .map(|(x, expected)| {
+                    let actual = interpolate(&actual_scale, &values, *x);
+                    let error = (actual - expected).abs();
+                    Deviation {
+                        x: *x,
+                        expected: *expected,
+                        actual,
+                        error,
+                        failed: error.is_nan()
+                            || error > self.absolute + self.relative * expected.abs(),
+                    }
+                })
+                .collect();
+            let count = deviations.len().max(1) as f64;
+            result.push(Comparison {
+                vector: name.to_string(),
+                max_error: deviations.iter().map(|d| d.error).fold(0.0, f64::max),
+                rms_error: (deviations.iter().map(|d| d.error.powi(2)).sum::<f64>() / count).sqrt(),
+                failures: deviations.iter().filter(|d| d.failed).count(),
+                deviations,
+            });
+        }
+        Ok(result)
+    }
+}
+
+/// The linear interpolated value at x, clamped to the first and last value.
+fn interpolate(scale: &[f64], values: &[f64], x: f64) -> f64 {
+    let index = scale.partition_point(|s| *s < x);
+    if index == 0 {
+        return values[0];
+    } else if index >= scale.len() {
+        return values[scale.len() - 1];
+    }
+    let (x0, x1) = (scale[index - 1], scale[index]);
+    let (y0, y1) = (values[index - 1], values[index]);
+    if x1 == x0 {
+        return y1;
+    }
+    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::Compare;
+
+    #[test]
+    fn compare() {
+        let golden = HashMap::from([
+            (String::from("time"), vec![0.0, 1.0, 2.0, 3.0]),
+            (String::from("out"), vec![0.0, 1.0, 2.0, 3.0]),
+        ]);
+        let actual = HashMap::from([
+            (String::from("time"), vec![0.0, 0.5, 1.5, 2.5, 3.0]),
+            (String::from("out"), vec![0.0, 0.5, 1.5, 2.5, 3.5]),
+        ]);
+        let comparison = Compare::new(0.01, 0.1)
+            .compare(&golden, &actual, &["out"])
+            .unwrap();
+        assert_eq!(1, comparison[0].failures);
+        assert!(!comparison[0].passed());
+        let worst = comparison[0].worst().unwrap();
+        assert_eq!((3.0, 3.5), (worst.x, worst.actual));
+        assert!((comparison[0].rms_error - 0.25).abs() < 1e-12);
+        assert!(Compare::new(0.6, 0.0)
+            .compare(&golden, &actual, &["out"])
+            .unwrap()[0]
+            .passed());
+        assert!(Compare::new(0.6, 0.0)
+            .compare(&golden, &actual, &["in"])
+            .is_err());
+    }
+}
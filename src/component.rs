@@ -0,0 +1,183 @@
+use crate::{circuit::CircuitItem, tolerance::Tolerance, Circuit};
+
+/// The type of a circuit element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentKind {
+    Resistor,
+    Capacitor,
+    Inductor,
+    Diode,
+    Bjt,
+    Subcircuit,
+    Voltage,
+    Current,
+    Vcvs,
+    Behavioral,
+    Xspice,
+    Device,
+    Coupling,
+    Switch,
+}
+
+/// A read-only view of an element of the circuit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Component<'a> {
+    item: &'a CircuitItem,
+}
+
+impl<'a> Component<'a> {
+    /// The view of the element, models, includes and comments are no
+    /// components.
+    pub(crate) fn new(item: &'a CircuitItem) -> Option<Self> {
+        item.reference().map(|_| Self { item })
+    }
+    /// The reference as it was added to the circuit.
+    pub fn reference(&self) -> &'a str {
+        self.item
+            .reference()
+            .map(|r| r.as_str())
+            .unwrap_or_default()
+    }
+    /// The name in the netlist, with the type letter.
+    pub fn name(&self) -> String {
+        self.item.name().unwrap_or_default()
+    }
+    pub fn kind(&self) -> ComponentKind {
+        match self.item {
+            CircuitItem::R(..) => ComponentKind::Resistor,
+            CircuitItem::C(..) => ComponentKind::Capacitor,
+            CircuitItem::L(..) => ComponentKind::Inductor,
+            CircuitItem::D(..) => ComponentKind::Diode,
+            CircuitItem::Q(..) => ComponentKind::Bjt,
+            CircuitItem::X(..) => ComponentKind::Subcircuit,
+            CircuitItem::V(..) => ComponentKind::Voltage,
+            CircuitItem::I(..) => ComponentKind::Current,
+            CircuitItem::E(..) => ComponentKind::Vcvs,
+            CircuitItem::B(..) => ComponentKind::Behavioral,
+            CircuitItem::A(..) => ComponentKind::Xspice,
+            CircuitItem::N(..) => ComponentKind::Device,
+            CircuitItem::K(..) => ComponentKind::Coupling,
+            CircuitItem::S(..) => ComponentKind::Switch,
+            CircuitItem::Model(..)
+            | CircuitItem::Include(..)
+            | CircuitItem::Comment(_)
+            | CircuitItem::Raw(_) => unreachable!(),
+        }
+    }
+    /// The nodes the element is connected to, the inductors of a coupling
+    /// are no nodes.
+    pub fn nodes(&self) -> Vec<&'a str> {
+        self.item.nodes().into_iter().map(|n| n.as_str()).collect()
+    }
+    /// The value, the model or the subcircuit name of the element.
+    pub fn value(&self) -> &'a str {
+        match self.item {
+            CircuitItem::R(_, _, _, v, _)
+            | CircuitItem::C(_, _, _, v, _)
+            | CircuitItem::L(_, _, _, v, _)
+            | CircuitItem::D(_, _, _, v)
+            | CircuitItem::Q(_, _, _, _, v)
+            | CircuitItem::X(_, _, v, _)
+            | CircuitItem::V(_, _, _, v)
+            | CircuitItem::I(_, _, _, v)
+            | CircuitItem::E(_, _, _, _, _, v)
+            | CircuitItem::B(_, _, _, v)
+            | CircuitItem::A(_, _, v)
+            | CircuitItem::N(_, _, v)
+            | CircuitItem::K(_, _, _, v)
+            | CircuitItem::S(_, _, _, _, _, v) => v,
+            CircuitItem::Model(..)
+            | CircuitItem::Include(..)
+            | CircuitItem::Comment(_)
+            | CircuitItem::Raw(_) => "",
+        }
+    }
+    /// The parameters of a subcircuit instance.
+    pub fn params(&self) -> &'a [(String, String)] {
+        match self.item {
+            CircuitItem::X(_, _, _, params) => params,
+            _ => &[],
+        }
+    }
+    /// The tolerance of a resistor, capacitor or inductor.
+    pub fn tolerance(&self) -> Option<Tolerance> {
+        match self.item {
+            CircuitItem::R(_, _, _, _, t)
+            | CircuitItem::C(_, _, _, _, t)
+            | CircuitItem::L(_, _, _, _, t) => *t,
+            _ => None,
+        }
+    }
+}
+
+impl Circuit {
+    /// The elements of the circuit in the order they were added.
+    pub fn items(&self) -> impl Iterator<Item = Component<'_>> {
+        self.items.iter().filter_map(Component::new)
+    }
+    /// The element with the reference or the netlist name.
+    pub fn get(&self, reference: &str) -> Option<Component<'_>> {
+        self.items()
+            .find(|c| c.reference() == reference || c.name().eq_ignore_ascii_case(reference))
+    }
+    /// The elements of the type.
+    pub fn items_of(&self, kind: ComponentKind) -> impl Iterator<Item = Component<'_>> {
+        self.items().filter(move |c| c.kind() == kind)
+    }
+    /// The elements connected to the node, all ground aliases are the same
+    /// node.
+    pub fn connected<'a>(&'a self, node: &'a str) -> impl Iterator<Item = Component<'a>> {
+        let ground = self.is_ground(node);
+        self.items().filter(move |c| {
+            c.nodes()
+                .iter()
+                .any(|n| *n == node || (ground && self.is_ground(n)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ComponentKind;
+    use crate::Circuit;
+
+    #[test]
+    fn components() {
+        let mut circuit = Circuit::new(String::from("divider"), Vec::new());
+        circuit
+            .voltage(String::from("V1"), "in", "GND", String::from("DC 5"))
+            .unwrap();
+        circuit
+            .resistor(String::from("R1"), "in", "out", String::from("10k"))
+            .unwrap();
+        circuit
+            .resistor(String::from("2"), "out", "0", String::from("4.7k"))
+            .unwrap();
+        circuit.model(
+            String::from("DMOD"),
+            String::from("D"),
+            String::from("IS=1e-14"),
+        );
+        assert_eq!(3, circuit.items().count());
+        let r2 = circuit.get("R2").unwrap();
+        assert_eq!("2", r2.reference());
+        assert_eq!(ComponentKind::Resistor, r2.kind());
+        assert_eq!(vec!["out", "0"], r2.nodes());
+        assert_eq!("4.7k", r2.value());
+        assert_eq!(
+            vec!["R1", "R2"],
+            circuit
+                .items_of(ComponentKind::Resistor)
+                .map(|c| c.name())
+                .collect::<Vec<String>>()
+        );
+        assert_eq!(
+            vec!["V1", "R2"],
+            circuit
+                .connected("GND")
+                .map(|c| c.name())
+                .collect::<Vec<String>>()
+        );
+        assert!(circuit.get("R3").is_none());
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::error::Error;
+
+/// The environment variable with the library paths, separated like `PATH`.
+pub const PATH_VARIABLE: &str = "ELEKTRON_SPICE_PATH";
+/// The environment variable with the location of the configuration file.
+pub const CONFIG_VARIABLE: &str = "ELEKTRON_SPICE_CONFIG";
+
+/// The configuration file with the library paths.
+///
+/// The file is read from `ELEKTRON_SPICE_CONFIG` or from
+/// `$XDG_CONFIG_HOME/elektron/spice.toml`, which defaults to `~/.config`.
+///
+/// ```toml
+/// pathlist = ["~/spice/", "/usr/share/spice/**/*.lib"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub pathlist: Vec<PathBuf>,
+}
+
+impl Config {
+    /// Load the configuration file.
+    pub fn load(filename: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(filename)?;
+        toml::from_str(&content)
+            .map_err(|e| Error::Config(filename.to_string_lossy().to_string(), e.to_string()))
+    }
+    /// The location of the configuration file.
+    pub fn file() -> Option<PathBuf> {
+        if let Some(file) = std::env::var_os(CONFIG_VARIABLE) {
+            return Some(PathBuf::from(file));
+        }
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .map(|config| config.join("elektron").join("spice.toml"))
+    }
+}
+
+/// The library paths from the environment and the configuration file.
+///
+/// The paths are resolved once when the circuit is created and can be
+/// replaced with [`crate::Circuit::set_library`].
+pub(crate) fn library() -> Vec<PathBuf> {
+    library_from(std::env::var_os(PATH_VARIABLE), Config::file())
+}
+
+/// The library paths from the variable and the configuration file.
+fn library_from(variable: Option<OsString>, file: Option<PathBuf>) -> Vec<PathBuf> {
+    let config = match file {
+        Some(file) if file.is_file() => match Config::load(&file) {
+            Ok(config) => Some(config),
+            Err(err) => {
+                log::error!("{}", err);
+                None
+            }
+        },
+        _ => None,
+    };
+    merge(&[], variable, config)
+}
+
+/// The pathlist of the circuit followed by the library paths.
+pub(crate) fn pathlist(pathlist: &[PathBuf], library: &[PathBuf]) -> Vec<PathBuf> {
+    let mut result = merge(pathlist, None, None);
+    for path in library {
+        if !result.contains(path) {
+            result.push(path.clone());
+        }
+    }
+    result
+}
+
+fn merge(pathlist: &[PathBuf], variable: Option<OsString>, config: Option<Config>) -> Vec<PathBuf> {
+    let mut result: Vec<PathBuf> = Vec::new();
+    let variable = variable
+        .map(|paths| {
+            std::env::split_paths(&paths)
+                .filter(|path| !path.as_os_str().is_empty())
+                .collect::<Vec<PathBuf>>()
+        })
+        .unwrap_or_default();
+    let config = config.map(|config| config.pathlist).unwrap_or_default();
+    for path in pathlist.iter().chain(variable.iter()).chain(config.iter()) {
+        if !result.contains(path) {
+            result.push(path.clone());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::OsString,
+        path::{Path, PathBuf},
+    };
+
+    use super::{library_from, merge, pathlist, Config};
+
+    #[test]
+    fn merge_pathlist() {
+        let config = Config::load(Path::new("files/config/spice.toml")).unwrap();
+        assert_eq!(
+            vec![
+                PathBuf::from("files/spice/"),
+                PathBuf::from("/opt/spice"),
+                PathBuf::from("files/spice/nested/"),
+                PathBuf::from("~/spice/**/*.lib"),
+            ],
+            merge(
+                &[PathBuf::from("files/spice/")],
+                Some(OsString::from("/opt/spice::files/spice/")),
+                Some(config)
+            )
+        );
+        assert_eq!(
+            vec![PathBuf::from("lib")],
+            merge(&[PathBuf::from("lib")], None, None)
+        );
+        assert_eq!(
+            vec![PathBuf::from("lib"), PathBuf::from("/opt/spice")],
+            pathlist(
+                &[PathBuf::from("lib")],
+                &[PathBuf::from("/opt/spice"), PathBuf::from("lib")]
+            )
+        );
+    }
+    #[test]
+    fn library() {
+        assert_eq!(
+            vec![
+                PathBuf::from("/opt/spice"),
+                PathBuf::from("files/spice/nested/"),
+                PathBuf::from("~/spice/**/*.lib"),
+                PathBuf::from("files/spice/"),
+            ],
+            library_from(
+                Some(OsString::from("/opt/spice")),
+                Some(PathBuf::from("files/config/spice.toml"))
+            )
+        );
+        assert_eq!(
+            vec![PathBuf::from("/opt/spice")],
+            library_from(
+                Some(OsString::from("/opt/spice")),
+                Some(PathBuf::from("files/config/missing.toml"))
+            )
+        );
+        assert!(library_from(None, None).is_empty());
+    }
+}
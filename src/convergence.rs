@@ -0,0 +1,67 @@
+use crate::{error::Error, node::IntoNode, Simulation, Value};
+
+impl Simulation {
+    /// Set the number of gmin steps that are tried when the operating point
+    /// does not converge, `None` disables the gmin stepping.
+    pub fn set_gmin_stepping(&mut self, steps: Option<u32>) {
+        self.set_option("gminsteps", &steps.unwrap_or(0).to_string());
+    }
+    /// Set the number of source steps that are tried when the operating
+    /// point does not converge, `None` disables the source stepping.
+    pub fn set_source_stepping(&mut self, steps: Option<u32>) {
+        self.set_option("srcsteps", &steps.unwrap_or(0).to_string());
+    }
+    /// Start with the gmin and source stepping and skip the Newton iterations
+    /// of the operating point, which fail for some circuits.
+    pub fn set_skip_iterations(&mut self, skip: bool) {
+        match skip {
+            true => self.set_option("noopiter", ""),
+            false => self.remove_option("noopiter"),
+        }
+    }
+    /// Set the iteration limits of the operating point (`itl1`) and of the
+    /// DC sweep (`itl2`).
+    pub fn set_dc_iterations(&mut self, operating_point: u32, sweep: u32) {
+        self.set_option("itl1", &operating_point.to_string());
+        self.set_option("itl2", &sweep.to_string());
+    }
+    /// Set the initial guess of the node voltage for the operating point
+    /// with `.nodeset`, the node is not held at the voltage.
+    pub fn nodeset(&mut self, node: impl IntoNode, voltage: f64) -> Result<(), Error> {
+        let node = node.into_node()?;
+        self.circuit
+            .raw(&format!(".nodeset v({})={}", node, Value::new(voltage)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Circuit, Simulation};
+
+    #[test]
+    fn convergence() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(String::from("R1"), "in", "GND", String::from("1k"))
+            .unwrap();
+        let mut simulation = Simulation::new(circuit);
+        simulation.set_gmin_stepping(Some(20));
+        simulation.set_source_stepping(None);
+        simulation.set_skip_iterations(true);
+        simulation.set_dc_iterations(500, 200);
+        simulation.nodeset("in", 2.5).unwrap();
+        assert!(simulation.nodeset("a b", 1.0).is_err());
+        assert_eq!(
+            vec![
+                String::from("R1 in 0 1k"),
+                String::from(".nodeset v(in)=2.5"),
+                String::from(".options gminsteps=20 srcsteps=0 noopiter itl1=500 itl2=200"),
+                String::from(".end"),
+            ],
+            simulation.deck().unwrap()
+        );
+        simulation.set_skip_iterations(false);
+        assert_eq!(4, simulation.options().len());
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    circuit::Vectors,
+    error::Error,
+    power::{average, get, PowerReport},
+    Circuit, Simulation,
+};
+
+/// The efficiency and ripple of a power converter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Efficiency {
+    /// The average power delivered by the input source.
+    pub input_power: f64,
+    /// The average power dissipated in the load.
+    pub output_power: f64,
+    /// The ratio of the output and the input power.
+    pub efficiency: f64,
+    /// The peak to peak ripple of the output voltage.
+    pub ripple: f64,
+    /// The peak to peak ripple of the inductor current.
+    pub inductor_ripple: Option<f64>,
+}
+
+/// Measure a switching power converter over the last switching cycles of a transient.
+///
+/// ```ignore
+/// let mut converter = Converter::new("VIN", "out", "RLOAD", 100e3);
+/// converter.set_inductor("L1");
+/// let efficiency = converter.run(&mut simulation, "10n", "2m")?;
+/// assert!(efficiency.efficiency > 0.9);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Converter {
+    /// The voltage source of the input.
+    pub input: String,
+    /// The output node.
+    pub output: String,
+    /// The load element at the output.
+    pub load: String,
+    pub inductor: Option<String>,
+    /// The switching frequency in Hz.
+    pub frequency: f64,
+    /// The number of cycles at the end of the transient that are evaluated.
+    pub cycles: usize,
+}
+
+/// The samples from the time on, the first sample is interpolated at the time.
+fn window(time: &[f64], values: &[f64], from: f64) -> (Vec<f64>, Vec<f64>) {
+    let start = time.iter().position(|t| *t >= from).unwrap_or(time.len());
+    let mut t = Vec::with_capacity(time.len() - start + 1);
+    let mut v = Vec::with_capacity(time.len() - start + 1);
+    if start > 0 && start < time.len() && time[start] > from {
+        let fraction = (from - time[start - 1]) / (time[start] - time[start - 1]);
+        t.push(from);
+        v.push(values[start - 1] + fraction * (values[start] - values[start - 1]));
+    }
+    t.extend_from_slice(&time[start..]);
+    v.extend_from_slice(&values[start..]);
+    (t, v)
+}
+
+fn peak_to_peak(values: &[f64]) -> f64 {
+    let max = values.iter().cloned().fold(f64::MIN, f64::max);
+    let min = values.iter().cloned().fold(f64::MAX, f64::min);
+    max - min
+}
+
+impl Converter {
+    pub fn new(input: &str, output: &str, load: &str, frequency: f64) -> Self {
+        Self {
+            input: input.to_string(),
+            output: output.to_string(),
+            load: load.to_string(),
+            inductor: None,
+            frequency,
+            cycles: 10,
+        }
+    }
+    /// Measure the current ripple of the inductor.
+    pub fn set_inductor(&mut self, reference: &str) {
+        self.inductor = Some(reference.to_string());
+    }
+    /// Set the number of evaluated cycles, the default is 10.
+    pub fn set_cycles(&mut self, cycles: usize) {
+        self.cycles = cycles.max(1);
+    }
+    /// Run the transient with the device currents and evaluate the last cycles.
+    pub fn run(
+        &self,
+        simulation: &mut Simulation,
+        step: &str,
+        stop: &str,
+    ) -> Result<Efficiency, Error> {
+        let vectors = simulation.run_with(
+            &[String::from(".options savecurrents")],
+            &format!("tran {} {} 0", step, stop),
+        )?;
+        self.evaluate(&simulation.circuit, &vectors)
+    }
+    fn evaluate(&self, circuit: &Circuit, vectors: &Vectors) -> Result<Efficiency, Error> {
+        let report = PowerReport::new(circuit, vectors)?;
+        let time = &report.time;
+        let from = time.last().cloned().unwrap_or_default() - self.cycles as f64 / self.frequency;
+        if from < time.first().cloned().unwrap_or_default() {
+            return Err(Error::Signal(format!(
+                "the transient is shorter than {} cycles",
+                self.cycles
+            )));
+        }
+        let power = |reference: &str| -> Result<f64, Error> {
+            let dissipation = report
+                .get(reference)
+                .ok_or_else(|| Error::UnknownCircuitElement(reference.to_string()))?;
+            let (t, p) = window(time, &dissipation.power, from);
+            Ok(average(&t, &p))
+        };
+        //the source delivers power with a negative dissipation
+        let input_power = -power(&self.input)?;
+        let output_power = power(&self.load)?;
+        let (_, output) = window(time, get(vectors, &self.output.to_lowercase())?, from);
+        let inductor_ripple = match &self.inductor {
+            Some(inductor) => {
+                let current = get(vectors, &format!("@{}[i]", inductor.to_lowercase()))?;
+                Some(peak_to_peak(&window(time, current, from).1))
+            }
+            None => None,
+        };
+        Ok(Efficiency {
+            input_power,
+            output_power,
+            efficiency: output_power / input_power,
+            ripple: peak_to_peak(&output),
+            inductor_ripple,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::Converter;
+    use crate::Circuit;
+
+    #[test]
+    fn efficiency() {
+        let mut circuit = Circuit::new(String::from("buck"), Vec::new());
+        circuit
+            .voltage(String::from("VIN"), "in", "0", String::from("DC 12"))
+            .unwrap();
+        circuit
+            .inductor(
+                String::from("L1"),
+                String::from("sw"),
+                String::from("out"),
+                String::from("10u"),
+            )
+            .unwrap();
+        circuit
+            .resistor(
+                String::from("RLOAD"),
+                String::from("out"),
+                String::from("0"),
+                String::from("5"),
+            )
+            .unwrap();
+        //two cycles of 100kHz, the output ripples between 4.9V and 5.1V
+        let time: Vec<f64> = (0..=40).map(|i| i as f64 * 0.5e-6).collect();
+        let out: Vec<f64> = (0..=40)
+            .map(|i| if i % 4 < 2 { 4.9 } else { 5.1 })
+            .collect();
+        let vectors = HashMap::from([
+            (String::from("in"), vec![12.0; 41]),
+            (String::from("sw"), out.clone()),
+            (String::from("out"), out.clone()),
+            (String::from("time"), time),
+            (String::from("vin#branch"), vec![-0.5; 41]),
+            (
+                String::from("@l1[i]"),
+                (0..=40).map(|i| 1.0 + 0.1 * (i % 2) as f64).collect(),
+            ),
+            (
+                String::from("@rload[i]"),
+                out.iter().map(|v| v / 5.0).collect(),
+            ),
+        ]);
+        let mut converter = Converter::new("VIN", "out", "RLOAD", 100e3);
+        converter.set_inductor("L1");
+        converter.set_cycles(1);
+        let result = converter.evaluate(&circuit, &vectors).unwrap();
+        assert!((result.input_power - 6.0).abs() < 1e-9);
+        assert!((result.output_power - 5.002).abs() < 1e-3);
+        assert!((result.efficiency - 0.8337).abs() < 1e-3);
+        assert!((result.ripple - 0.2).abs() < 1e-9);
+        assert!((result.inductor_ripple.unwrap() - 0.1).abs() < 1e-9);
+        converter.set_cycles(3);
+        assert!(converter.evaluate(&circuit, &vectors).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{circuit::Vectors, definition::Analysis, error::Error, Circuit, Simulation};
+
+/// The corners are counted with 64 bits, at most 63 components can vary.
+pub const MAX_CORNER_COMPONENTS: usize = 63;
+
+/// A measurement on the simulated vectors, e.g. the maximum output voltage.
+pub type Measure = Arc<dyn Fn(&HashMap<String, Vec<f64>>) -> f64 + Send + Sync>;
+
+/// A measurement result with the component values of the corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extreme {
+    pub value: f64,
+    pub values: HashMap<String, f64>,
+}
+
+/// The nominal and extreme values of a measurement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Corners {
+    pub name: String,
+    pub nominal: f64,
+    pub min: Extreme,
+    pub max: Extreme,
+}
+
+/// Worst-case analysis over the tolerance limits of the components.
+///
+/// All combinations of the minimum and maximum values of the toleranced
+/// resistors, capacitors and inductors are simulated. With pruning, the
+/// sensitivity of every component is measured first and components which
+/// change no measurement by more than the threshold stay at their nominal
+/// value, this reduces the 2^n corners for large circuits. The run fails
+/// when more components than the limit are varied, the default are 16
+/// components or 65536 corners and the limit can be raised up to
+/// `MAX_CORNER_COMPONENTS`.
+///
+/// ```ignore
+/// let mut worstcase = WorstCase::new(circuit, analysis);
+/// worstcase.add_measurement("vmax", Arc::new(|v| v["out"].iter().cloned().fold(f64::MIN, f64::max)));
+/// worstcase.set_pruning(0.001);
+/// for corners in worstcase.run()? {
+///     println!("{}: {} .. {}", corners.name, corners.min.value, corners.max.value);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct WorstCase {
+    circuit: Circuit,
+    analysis: Analysis,
+    measurements: Vec<(String, Measure)>,
+    pruning: Option<f64>,
+    limit: usize,
+}
+
+impl WorstCase {
+    pub fn new(circuit: Circuit, analysis: Analysis) -> Self {
+        Self {
+            circuit,
+            analysis,
+            measurements: Vec::new(),
+            pruning: None,
+            limit: 16,
+        }
+    }
+    pub fn add_measurement(&mut self, name: &str, measure: Measure) {
+        self.measurements.push((name.to_string(), measure));
+    }
+    /// Skip the components with a relative sensitivity below the threshold.
+    pub fn set_pruning(&mut self, threshold: f64) {
+        self.pruning = Some(threshold);
+    }
+    /// Set the maximum number of varied components, at most `MAX_CORNER_COMPONENTS`.
+    pub fn set_limit(&mut self, limit: usize) -> Result<(), Error> {
+        if limit > MAX_CORNER_COMPONENTS {
+            return Err(Error::CornerLimit(limit, MAX_CORNER_COMPONENTS));
+        }
+        self.limit = limit;
+        Ok(())
+    }
+    /// Simulate the corners with ngspice.
+    pub fn run(&self) -> Result<Vec<Corners>, Error> {
+        self.evaluate(|circuit| {
+            let mut simulation = Simulation::new(circuit);
+            self.analysis.run(&mut simulation)
+        })
+    }
+    fn measure(&self, vectors: &Vectors) -> Vec<f64> {
+        self.measurements.iter().map(|(_, m)| m(vectors)).collect()
+    }
+    fn evaluate<F>(&self, mut simulate: F) -> Result<Vec<Corners>, Error>
+    where
+        F: FnMut(Circuit) -> Result<Vectors, Error>,
+    {
+        let components = self.circuit.toleranced()?;
+        //without pruning all the components vary, fail before the simulation.
+        if self.pruning.is_none() && components.len() > self.limit {
+            return Err(Error::CornerLimit(components.len(), self.limit));
+        }
+        let nominal_values: HashMap<String, f64> =
+            components.iter().map(|(r, v, _)| (r.clone(), *v)).collect();
+        let nominal = self.measure(&simulate(self.circuit.with_values(&nominal_values)?)?);
+
+        let mut varied = Vec::new();
+        for (reference, value, tolerance) in &components {
+            if let Some(threshold) = self.pruning {
+                let mut values = nominal_values.clone();
+                values.insert(reference.clone(), tolerance.limits(*value).1);
+                let result = self.measure(&simulate(self.circuit.with_values(&values)?)?);
+                let sensitive = nominal.iter().zip(&result).any(|(n, r)| {
+                    let scale = if *n == 0.0 { 1.0 } else { n.abs() };
+                    (r - n).abs() / scale > threshold
+                });
+                if !sensitive {
+                    continue;
+                }
+            }
+            varied.push((reference.clone(), tolerance.limits(*value)));
+        }
+        if varied.len() > self.limit {
+            return Err(Error::CornerLimit(varied.len(), self.limit));
+        }
+
+        let mut result: Vec<Corners> = self
+            .measurements
+            .iter()
+            .zip(&nominal)
+            .map(|((name, _), nominal)| Corners {
+                name: name.clone(),
+                nominal: *nominal,
+                min: Extreme {
+                    value: *nominal,
+                    values: nominal_values.clone(),
+                },
+                max: Extreme {
+                    value: *nominal,
+                    values: nominal_values.clone(),
+                },
+            })
+            .collect();
+        for corner in 0..(1_u64 << varied.len()) {
+            let mut values = nominal_values.clone();
+            for (bit, (reference, (min, max))) in varied.iter().enumerate() {
+                let value = if corner & (1 << bit) == 0 { *min } else { *max };
+                values.insert(reference.clone(), value);
+            }
+            let measured = self.measure(&simulate(self.circuit.with_values(&values)?)?);
+            for (corners, value) in result.iter_mut().zip(measured) {
+                if value < corners.min.value {
+                    corners.min = Extreme {
+                        value,
+                        values: values.clone(),
+                    };
+                }
+                if value > corners.max.value {
+                    corners.max = Extreme {
+                        value,
+                        values: values.clone(),
+                    };
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl std::fmt::Debug for WorstCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorstCase")
+            .field("circuit", &self.circuit)
+            .field("analysis", &self.analysis)
+            .field(
+                "measurements",
+                &self.measurements.iter().map(|(n, _)| n).collect::<Vec<_>>(),
+            )
+            .field("pruning", &self.pruning)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+    use super::WorstCase;
+    use crate::{circuit::CircuitItem, error::Error, Analysis, Circuit, Tolerance, Value};
+
+    /// Calculate the divider output instead of running ngspice.
+    fn divider(circuit: Circuit) -> HashMap<String, Vec<f64>> {
+        let mut r = HashMap::new();
+        for item in &circuit.items {
+            if let CircuitItem::R(reference, _, _, value, _) = item {
+                r.insert(reference.clone(), Value::from_str(value).unwrap().as_f64());
+            }
+        }
+        let out = r["R2"] / (r["R1"] + r["R2"]) + r["R3"] * 1e-18;
+        HashMap::from([(String::from("out"), vec![out])])
+    }
+
+    #[test]
+    fn corners() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(
+                String::from("R1"),
+                String::from("in"),
+                String::from("out"),
+                String::from("1k"),
+            )
+            .unwrap();
+        circuit
+            .resistor(
+                String::from("R2"),
+                String::from("out"),
+                String::from("0"),
+                String::from("1k"),
+            )
+            .unwrap();
+        circuit
+            .resistor(
+                String::from("R3"),
+                String::from("in"),
+                String::from("out"),
+                String::from("1G"),
+            )
+            .unwrap();
+        circuit.set_tolerance("R1", Tolerance::new(0.1)).unwrap();
+        circuit.set_tolerance("R2", Tolerance::new(0.1)).unwrap();
+        circuit.set_tolerance("R3", Tolerance::new(0.1)).unwrap();
+        let analysis = Analysis::Tran {
+            step: String::from("1u"),
+            stop: String::from("1m"),
+            start: String::from("0"),
+        };
+        let mut worstcase = WorstCase::new(circuit, analysis);
+        worstcase.add_measurement("out", Arc::new(|v| v["out"][0]));
+        worstcase.set_pruning(0.01);
+        let mut runs = 0;
+        let result = worstcase
+            .evaluate(|c| {
+                runs += 1;
+                Ok(divider(c))
+            })
+            .unwrap();
+        //nominal, three sensitivities and four corners
+        assert_eq!(8, runs);
+        assert!((result[0].nominal - 0.5).abs() < 1e-6);
+        assert!((result[0].max.value - 1.1 / 2.0).abs() < 1e-3);
+        assert_eq!(900.0, result[0].max.values["R1"]);
+        assert_eq!(1100.0, result[0].max.values["R2"]);
+        assert!((result[0].min.value - 0.9 / 2.0).abs() < 1e-3);
+    }
+    #[test]
+    fn failed_run() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(
+                String::from("R1"),
+                String::from("in"),
+                String::from("0"),
+                String::from("1k"),
+            )
+            .unwrap();
+        circuit.set_tolerance("R1", Tolerance::new(0.1)).unwrap();
+        let analysis = Analysis::Tran {
+            step: String::from("1u"),
+            stop: String::from("1m"),
+            start: String::from("0"),
+        };
+        let mut worstcase = WorstCase::new(circuit, analysis);
+        worstcase.add_measurement("out", Arc::new(|v| v["out"][0]));
+        let mut runs = 0;
+        let result = worstcase.evaluate(|_| {
+            runs += 1;
+            if runs == 2 {
+                Err(Error::Spice(String::from("timestep too small")))
+            } else {
+                Ok(HashMap::from([(String::from("out"), vec![1.0])]))
+            }
+        });
+        assert!(matches!(result, Err(Error::Spice(_))));
+        assert_eq!(2, runs);
+    }
+    #[test]
+    fn limit() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        for i in 0..3 {
+            let reference = format!("R{}", i);
+            circuit
+                .resistor(
+                    reference.clone(),
+                    String::from("in"),
+                    String::from("0"),
+                    String::from("1k"),
+                )
+                .unwrap();
+            circuit
+                .set_tolerance(&reference, Tolerance::new(0.1))
+                .unwrap();
+        }
+        let analysis = Analysis::Tran {
+            step: String::from("1u"),
+            stop: String::from("1m"),
+            start: String::from("0"),
+        };
+        let mut worstcase = WorstCase::new(circuit, analysis);
+        worstcase.add_measurement("out", Arc::new(|v| v["out"][0]));
+        assert!(matches!(
+            worstcase.set_limit(64),
+            Err(Error::CornerLimit(64, 63))
+        ));
+        worstcase.set_limit(2).unwrap();
+        let mut runs = 0;
+        let result = worstcase.evaluate(|_| {
+            runs += 1;
+            Ok(HashMap::from([(String::from("out"), vec![1.0])]))
+        });
+        assert!(matches!(result, Err(Error::CornerLimit(3, 2))));
+        assert_eq!(0, runs);
+        //with pruning the varied components are known after the sensitivity runs
+        worstcase.set_pruning(0.0);
+        let mut runs = 0;
+        let result = worstcase.evaluate(|_| {
+            runs += 1;
+            Ok(HashMap::from([(String::from("out"), vec![runs as f64])]))
+        });
+        assert!(matches!(result, Err(Error::CornerLimit(3, 2))));
+        assert_eq!(4, runs);
+    }
+}
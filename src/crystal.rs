@@ -0,0 +1,149 @@
+use std::f64::consts::PI;
+
+use crate::{error::Error, node::IntoNode, Circuit, Value};
+
+/// The typical ratio of the shunt to the motional capacitance.
+const CAPACITANCE_RATIO: f64 = 250.0;
+
+/// A quartz crystal as the motional RLC in parallel to the shunt capacitance.
+///
+/// The frequency is the series resonance, or the resonance with the load
+/// capacitance when it is set. The motional capacitance is estimated from
+/// the shunt capacitance when the datasheet does not list it.
+///
+/// ```ignore
+/// let mut crystal = Crystal::new(16e6, 40.0, 5e-12);
+/// crystal.set_load(18e-12);
+/// crystal.build(&mut circuit, "Y1", "xtal1", "xtal2")?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crystal {
+    /// The nominal frequency in Hz.
+    pub frequency: f64,
+    /// The equivalent series resistance.
+    pub esr: f64,
+    /// The shunt capacitance.
+    pub c0: f64,
+    /// The load capacitance of the nominal frequency.
+    pub load: Option<f64>,
+    /// The motional capacitance.
+    pub motional: Option<f64>,
+}
+
+impl Crystal {
+    pub fn new(frequency: f64, esr: f64, c0: f64) -> Self {
+        Self {
+            frequency,
+            esr,
+            c0,
+            load: None,
+            motional: None,
+        }
+    }
+    /// Set the load capacitance the nominal frequency is specified with.
+    pub fn set_load(&mut self, load: f64) {
+        self.load = Some(load);
+    }
+    /// Set the motional capacitance, the default is C0/250.
+    pub fn set_motional(&mut self, motional: f64) {
+        self.motional = Some(motional);
+    }
+    /// The series resonance frequency of the motional branch.
+    pub fn series_resonance(&self) -> Result<f64, Error> {
+        self.validate()?;
+        let c1 = self.c1();
+        Ok(match self.load {
+            Some(load) => self.frequency / (1.0 + c1 / (self.c0 + load)).sqrt(),
+            None => self.frequency,
+        })
+    }
+    /// The parallel resonance frequency with the shunt capacitance.
+    pub fn parallel_resonance(&self) -> Result<f64, Error> {
+        Ok(self.series_resonance()? * (1.0 + self.c1() / self.c0).sqrt())
+    }
+    /// The resistance, inductance and capacitance of the motional branch.
+    pub fn motional(&self) -> Result<(f64, f64, f64), Error> {
+        let fs = self.series_resonance()?;
+        let c1 = self.c1();
+        Ok((self.esr, 1.0 / ((2.0 * PI * fs).powi(2) * c1), c1))
+    }
+    /// Add the elements with the reference as suffix, e.g. `RY1`, `LY1`,
+    /// `CY1` and `CY1P` for the reference `Y1`.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        reference: &str,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+    ) -> Result<(), Error> {
+        let (r1, l1, c1) = self.motional()?;
+        let (n0, n1) = (String::from(n0.into_node()?), String::from(n1.into_node()?));
+        let r = format!("{}_r", reference).to_lowercase();
+        let l = format!("{}_l", reference).to_lowercase();
+        circuit.resistor(
+            format!("R{}", reference),
+            n0.as_str(),
+            r.as_str(),
+            Value::new(r1).to_string(),
+        )?;
+        circuit.inductor(
+            format!("L{}", reference),
+            r,
+            l.clone(),
+            Value::new(l1).to_string(),
+        )?;
+        circuit.capacitor(
+            format!("C{}", reference),
+            l,
+            n1.clone(),
+            Value::new(c1).to_string(),
+        )?;
+        circuit.capacitor(
+            format!("C{}P", reference),
+            n0,
+            n1,
+            Value::new(self.c0).to_string(),
+        )
+    }
+    fn c1(&self) -> f64 {
+        self.motional.unwrap_or(self.c0 / CAPACITANCE_RATIO)
+    }
+    fn validate(&self) -> Result<(), Error> {
+        if [self.frequency, self.esr, self.c0, self.c1()]
+            .iter()
+            .chain(self.load.iter())
+            .any(|v| *v <= 0.0)
+        {
+            return Err(Error::InvalidValue(format!("{:?}", self)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Crystal;
+    use crate::Circuit;
+
+    #[test]
+    fn crystal() {
+        let mut crystal = Crystal::new(16e6, 40.0, 5e-12);
+        crystal.set_motional(20e-15);
+        let mut circuit = Circuit::new(String::from("oscillator"), Vec::new());
+        crystal.build(&mut circuit, "Y1", "x1", "x2").unwrap();
+        assert_eq!(
+            vec![
+                "RY1 x1 y1_r 40",
+                "LY1 y1_r y1_l 4.947323420036024m",
+                "CY1 y1_l x2 20f",
+                "CY1P x1 x2 5p",
+            ],
+            circuit.to_str(false).unwrap()
+        );
+        crystal.set_load(18e-12);
+        let fs = crystal.series_resonance().unwrap();
+        assert!(fs < 16e6 && fs > 15.99e6);
+        assert!(crystal.parallel_resonance().unwrap() > 16e6);
+        assert!(Crystal::new(16e6, 40.0, 0.0).motional().is_err());
+    }
+}
@@ -0,0 +1,337 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{circuit::Vectors, error::Error, Circuit, Simulation};
+
+/// A component entry of the circuit definition.
+///
+/// The element type is taken from `type` or, when missing,
+/// from the first letter of the reference. ICs with the prefix `U`
+/// are subcircuit instances.
+#[derive(Debug, Clone, Deserialize)]
+struct ComponentDefinition {
+    reference: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    nodes: Vec<String>,
+    value: String,
+}
+
+/// A `.model` statement of the circuit definition.
+#[derive(Debug, Clone, Deserialize)]
+struct ModelDefinition {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    parameters: String,
+}
+
+/// A subcircuit of the circuit definition.
+#[derive(Debug, Clone, Deserialize)]
+struct SubcircuitDefinition {
+    name: String,
+    nodes: Vec<String>,
+    #[serde(default)]
+    components: Vec<ComponentDefinition>,
+    #[serde(default)]
+    models: Vec<ModelDefinition>,
+}
+
+/// The analyses that can be declared in a circuit definition.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Analysis {
+    Tran {
+        step: String,
+        stop: String,
+        #[serde(default = "default_start")]
+        start: String,
+    },
+    Ac {
+        start: String,
+        stop: String,
+        points: u32,
+        #[serde(default = "default_variation")]
+        variation: String,
+    },
+}
+
+impl Analysis {
+    /// The ngspice command of the analysis.
+    pub(crate) fn command(&self) -> String {
+        match self {
+            Analysis::Tran { step, stop, start } => format!("tran {} {} {}", step, stop, start),
+            Analysis::Ac {
+                start,
+                stop,
+                points,
+                variation,
+            } => format!("ac {} {} {} {}", variation, points, start, stop),
+        }
+    }
+    /// Run the analysis with the simulation, the AC vectors are the magnitudes.
+    pub(crate) fn run(&self, simulation: &mut Simulation) -> Result<Vectors, Error> {
+        simulation.run_with(&[], &self.command())
+    }
+}
+
+fn default_start() -> String {
+    String::from("0")
+}
+
+fn default_variation() -> String {
+    String::from("dec")
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CircuitDefinition {
+    name: String,
+    #[serde(default)]
+    pathlist: Vec<String>,
+    #[serde(default)]
+    models: Vec<ModelDefinition>,
+    #[serde(default)]
+    subcircuits: Vec<SubcircuitDefinition>,
+    #[serde(default)]
+    components: Vec<ComponentDefinition>,
+    #[serde(default)]
+    analyses: Vec<Analysis>,
+}
+
+/// A circuit loaded from a declarative TOML, YAML or JSON file.
+///
+/// ```toml
+/// name = "divider"
+/// pathlist = ["files/spice/"]
+///
+/// [[components]]
+/// reference = "V1"
+/// nodes = ["in", "0"]
+/// value = "DC 5"
+///
+/// [[components]]
+/// reference = "R1"
+/// nodes = ["in", "out"]
+/// value = "10k"
+///
+/// [[analyses]]
+/// type = "tran"
+/// step = "1us"
+/// stop = "1ms"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub circuit: Circuit,
+    pub analyses: Vec<Analysis>,
+}
+
+impl Definition {
+    /// Load the definition from a file, the format is selected by the extension.
+    pub fn load(filename: &str) -> Result<Self, Error> {
+        let content = fs::read_to_string(filename)?;
+        let extension = Path::new(filename)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        match extension.as_deref() {
+            Some("toml") => Self::from_toml(&content),
+            Some("yaml") | Some("yml") => Self::from_yaml(&content),
+            Some("json") => Self::from_json(&content),
+            _ => Err(Error::Definition(format!("unknown file type: {}", filename))),
+        }
+    }
+    pub fn from_toml(content: &str) -> Result<Self, Error> {
+        let definition: CircuitDefinition =
+            toml::from_str(content).map_err(|e| Error::Definition(e.to_string()))?;
+        Self::build(definition)
+    }
+    pub fn from_yaml(content: &str) -> Result<Self, Error> {
+        let definition: CircuitDefinition =
+            serde_yaml::from_str(content).map_err(|e| Error::Definition(e.to_string()))?;
+        Self::build(definition)
+    }
+    pub fn from_json(content: &str) -> Result<Self, Error> {
+        let definition: CircuitDefinition =
+            serde_json::from_str(content).map_err(|e| Error::Definition(e.to_string()))?;
+        Self::build(definition)
+    }
+
+    /// Run all the declared analyses, the results are returned in declaration order.
+    pub fn simulate(&self) -> Result<Vec<HashMap<String, Vec<f64>>>, Error> {
+        let mut simulation = Simulation::new(self.circuit.clone());
+        let mut result = Vec::new();
+        for analysis in &self.analyses {
+            result.push(analysis.run(&mut simulation)?);
+        }
+        Ok(result)
+    }
+
+    fn build(definition: CircuitDefinition) -> Result<Self, Error> {
+        let mut circuit = Circuit::new(definition.name, definition.pathlist.clone());
+        for subcircuit in definition.subcircuits {
+            let mut sub = Circuit::new(subcircuit.name.clone(), definition.pathlist.clone());
+            add_models(&mut sub, subcircuit.models);
+            add_components(&mut sub, subcircuit.components)?;
+            circuit.subcircuit(subcircuit.name, subcircuit.nodes, sub)?;
+        }
+        add_models(&mut circuit, definition.models);
+        add_components(&mut circuit, definition.components)?;
+        Ok(Self {
+            circuit,
+            analyses: definition.analyses,
+        })
+    }
+}
+
+fn add_models(circuit: &mut Circuit, models: Vec<ModelDefinition>) {
+    for model in models {
+        circuit.model(model.name, model.kind, model.parameters);
+    }
+}
+
+fn add_components(circuit: &mut Circuit, components: Vec<ComponentDefinition>) -> Result<(), Error> {
+    for component in components {
+        let kind = match &component.kind {
+            Some(kind) => kind.to_uppercase(),
+            None => component.reference.chars().take(1).collect::<String>().to_uppercase(),
+        };
+        let expected = match kind.as_str() {
+            "R" | "C" | "L" | "D" | "V" | "I" => 2,
+            "Q" => 3,
+            "E" => 4,
+            "X" | "U" => component.nodes.len(),
+            _ => return Err(Error::UnknownCircuitElement(component.reference)),
+        };
+        if component.nodes.len() != expected {
+            return Err(Error::NodeCount(
+                component.reference,
+                expected,
+                component.nodes.len(),
+            ));
+        }
+        let mut nodes = component.nodes.into_iter();
+        let mut node = || nodes.next().unwrap();
+        match kind.as_str() {
+            "R" => circuit.resistor(component.reference, node(), node(), component.value)?,
+            "C" => circuit.capacitor(component.reference, node(), node(), component.value)?,
+            "L" => circuit.inductor(component.reference, node(), node(), component.value)?,
+            "D" => circuit.diode(component.reference, node(), node(), component.value)?,
+            "V" => circuit.voltage(component.reference, node(), node(), component.value)?,
+            "I" => circuit.current(component.reference, node(), node(), component.value)?,
+            "Q" => circuit.bjt(component.reference, node(), node(), node(), component.value)?,
+            "E" => circuit.vcvs(
+                component.reference,
+                node(),
+                node(),
+                node(),
+                node(),
+                component.value,
+            )?,
+            _ => {
+                let nodes: Vec<String> = nodes.collect();
+                circuit.circuit(component.reference, nodes, component.value)?
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Analysis, Definition};
+    use crate::error::Error;
+
+    #[test]
+    fn load_toml() {
+        let definition = Definition::load("files/definition/divider.toml").unwrap();
+        assert_eq!(
+            vec![
+                String::from("V1 in 0 DC 5"),
+                String::from("R1 in out 10k"),
+                String::from("R2 out 0 10k"),
+                String::from(".end"),
+            ],
+            definition.circuit.to_str(true).unwrap()
+        );
+        assert_eq!(
+            vec![Analysis::Tran {
+                step: String::from("1us"),
+                stop: String::from("1ms"),
+                start: String::from("0"),
+            }],
+            definition.analyses
+        );
+    }
+    #[test]
+    fn references() {
+        let definition = Definition::from_toml(
+            r#"
+name = "buffer"
+
+[[subcircuits]]
+name = "follower"
+nodes = ["in", "out"]
+
+[[subcircuits.components]]
+reference = "E1"
+nodes = ["out", "0", "in", "out"]
+value = "1e6"
+
+[[components]]
+reference = "V1"
+nodes = ["in", "0"]
+value = "DC 5"
+
+[[components]]
+reference = "U1"
+nodes = ["in", "out"]
+value = "follower"
+
+[[components]]
+reference = "load"
+type = "R"
+nodes = ["out", "0"]
+value = "10k"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                String::from(".subckt follower in out"),
+                String::from("E1 out 0 in out 1e6"),
+                String::from(".ends"),
+                String::from("V1 in 0 DC 5"),
+                String::from("XU1 in out follower"),
+                String::from("Rload out 0 10k"),
+                String::from(".end"),
+            ],
+            definition.circuit.to_str(true).unwrap()
+        );
+    }
+    #[test]
+    fn load_yaml() {
+        let definition = Definition::from_yaml(
+            "name: diode\ncomponents:\n  - reference: D1\n    nodes: [a, '0']\n    value: DMOD\nmodels:\n  - name: DMOD\n    type: D\n    parameters: IS=1e-14\n",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                String::from(".model DMOD D(IS=1e-14)"),
+                String::from("D1 a 0 DMOD"),
+                String::from(".end"),
+            ],
+            definition.circuit.to_str(true).unwrap()
+        );
+    }
+    #[test]
+    fn node_count() {
+        assert!(matches!(
+            Definition::from_yaml(
+                "name: divider\ncomponents:\n  - reference: R1\n    nodes: [in, out, '0']\n    value: 10k\n",
+            ),
+            Err(Error::NodeCount(_, 2, 3))
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{circuit::CircuitItem, error::Error, Circuit, Component};
+
+/// A difference of an element or a model between two circuits.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    /// The element is only in the new circuit.
+    Added {
+        name: String,
+        nodes: Vec<String>,
+        value: String,
+    },
+    /// The element is only in the old circuit.
+    Removed {
+        name: String,
+        nodes: Vec<String>,
+        value: String,
+    },
+    /// The value or the model of the element changed.
+    Value {
+        name: String,
+        old: String,
+        new: String,
+    },
+    /// The element is connected to other nodes.
+    Connection {
+        name: String,
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+    /// The model is added, removed or has other parameters.
+    Model {
+        name: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+/// The structural difference of two circuits.
+///
+/// The elements are compared by the name in the netlist, the ground
+/// aliases are compared as node `0`.
+///
+/// ```ignore
+/// let diff = NetlistDiff::new(&old, &new);
+/// println!("{}", diff);
+/// std::fs::write("diff.json", diff.to_json()?)?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NetlistDiff {
+    pub changes: Vec<Change>,
+}
+
+/// The nodes of the element with the ground as `0`.
+fn nodes(circuit: &Circuit, component: &Component) -> Vec<String> {
+    component
+        .nodes()
+        .iter()
+        .map(|n| match circuit.is_ground(n) {
+            true => String::from("0"),
+            false => n.to_string(),
+        })
+        .collect()
+}
+
+/// The value with the parameters of subcircuit instances.
+fn value(component: &Component) -> String {
+    let mut value = component.value().to_string();
+    for (name, param) in component.params() {
+        value.push_str(&format!(" {}={}", name, param));
+    }
+    value
+}
+
+/// The models of the circuit with the type and the parameters.
+fn models(circuit: &Circuit) -> Vec<(String, String)> {
+    circuit
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            CircuitItem::Model(name, kind, params) => {
+                Some((name.to_uppercase(), format!("{}({})", kind, params)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+impl NetlistDiff {
+    pub fn new(old: &Circuit, new: &Circuit) -> Self {
+        let mut changes = Vec::new();
+        for component in old.items() {
+            let name = component.name();
+            match new.get(&name) {
+                Some(other) => {
+                    let (from, to) = (value(&component), value(&other));
+                    if from != to {
+                        changes.push(Change::Value {
+                            name: name.clone(),
+                            old: from,
+                            new: to,
+                        });
+                    }
+                    let (from, to) = (nodes(old, &component), nodes(new, &other));
+                    if from != to {
+                        changes.push(Change::Connection {
+                            name,
+                            old: from,
+                            new: to,
+                        });
+                    }
+                }
+                None => changes.push(Change::Removed {
+                    nodes: nodes(old, &component),
+                    value: value(&component),
+                    name,
+                }),
+            }
+        }
+        for component in new.items() {
+            if old.get(&component.name()).is_none() {
+                changes.push(Change::Added {
+                    name: component.name(),
+                    nodes: nodes(new, &component),
+                    value: value(&component),
+                });
+            }
+        }
+        let (from, to) = (models(old), models(new));
+        for (name, model) in &from {
+            let other = to.iter().find(|(n, _)| n == name).map(|(_, m)| m);
+            if other != Some(model) {
+                changes.push(Change::Model {
+                    name: name.clone(),
+                    old: Some(model.clone()),
+                    new: other.cloned(),
+                });
+            }
+        }
+        for (name, model) in &to {
+            if !from.iter().any(|(n, _)| n == name) {
+                changes.push(Change::Model {
+                    name: name.clone(),
+                    old: None,
+                    new: Some(model.clone()),
+                });
+            }
+        }
+        Self { changes }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+    /// The changes as JSON.
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::Serialization(e.to_string()))
+    }
+}
+
+impl fmt::Display for NetlistDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            match change {
+                Change::Added { name, nodes, value } => {
+                    writeln!(f, "+ {} {} {}", name, nodes.join(" "), value)?
+                }
+                Change::Removed { name, nodes, value } => {
+                    writeln!(f, "- {} {} {}", name, nodes.join(" "), value)?
+                }
+                Change::Value { name, old, new } => writeln!(f, "~ {} {} -> {}", name, old, new)?,
+                Change::Connection { name, old, new } => {
+                    writeln!(f, "~ {} ({}) -> ({})", name, old.join(" "), new.join(" "))?
+                }
+                Change::Model { name, old, new } => writeln!(
+                    f,
+                    "~ .model {} {} -> {}",
+                    name,
+                    old.as_deref().unwrap_or("none"),
+                    new.as_deref().unwrap_or("none")
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, NetlistDiff};
+    use crate::Circuit;
+
+    #[test]
+    fn diff() {
+        let mut old = Circuit::new(String::from("rev a"), Vec::new());
+        old.voltage(String::from("V1"), "in", "GND", String::from("DC 5"))
+            .unwrap();
+        old.resistor(String::from("R1"), "in", "out", String::from("10k"))
+            .unwrap();
+        old.resistor(String::from("R2"), "out", "GND", String::from("10k"))
+            .unwrap();
+        let mut new = Circuit::new(String::from("rev b"), Vec::new());
+        new.voltage(String::from("V1"), "in", "0", String::from("DC 5"))
+            .unwrap();
+        new.resistor(String::from("R1"), "in", "mid", String::from("4.7k"))
+            .unwrap();
+        new.capacitor(String::from("C1"), "mid", "GND", String::from("10n"))
+            .unwrap();
+        new.model(
+            String::from("DMOD"),
+            String::from("D"),
+            String::from("IS=1e-14"),
+        );
+        let diff = NetlistDiff::new(&old, &new);
+        assert_eq!(
+            "~ R1 10k -> 4.7k\n\
+             ~ R1 (in out) -> (in mid)\n\
+             - R2 out 0 10k\n\
+             + C1 mid 0 10n\n\
+             ~ .model DMOD none -> D(IS=1e-14)\n",
+            diff.to_string()
+        );
+        assert_eq!(
+            Change::Value {
+                name: String::from("R1"),
+                old: String::from("10k"),
+                new: String::from("4.7k")
+            },
+            diff.changes[0]
+        );
+        assert!(diff
+            .to_json()
+            .unwrap()
+            .contains("\"change\": \"connection\""));
+        assert!(NetlistDiff::new(&old, &old).is_empty());
+    }
+}
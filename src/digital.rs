@@ -0,0 +1,253 @@
+use std::fs;
+
+use crate::{error::Error, Circuit, Value};
+
+/// The state of a digital node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Logic {
+    Low,
+    High,
+    Unknown,
+    /// High impedance, the node is not driven.
+    HighZ,
+}
+
+impl std::fmt::Display for Logic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        //the level and the strength of the XSPICE state
+        match self {
+            Logic::Low => write!(f, "0s"),
+            Logic::High => write!(f, "1s"),
+            Logic::Unknown => write!(f, "Us"),
+            Logic::HighZ => write!(f, "Uz"),
+        }
+    }
+}
+
+impl TryFrom<char> for Logic {
+    type Error = Error;
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c.to_ascii_uppercase() {
+            '0' => Ok(Logic::Low),
+            '1' => Ok(Logic::High),
+            'U' | 'X' => Ok(Logic::Unknown),
+            'Z' => Ok(Logic::HighZ),
+            _ => Err(Error::InvalidValue(c.to_string())),
+        }
+    }
+}
+
+/// Digital stimulus with the XSPICE `d_source` model.
+///
+/// The states of the nodes are written to the input file of the source,
+/// the digital nodes can be connected to digital models or to analog
+/// nodes with a `dac_bridge`.
+///
+/// ```ignore
+/// let mut source = DigitalSource::new(vec!["d0", "d1"]);
+/// source.add_bits(0.0, "00")?;
+/// source.add_bits(1e-6, "01")?;
+/// source.add_bits(2e-6, "1Z")?;
+/// source.build(&mut circuit, "ASRC", "stimulus.txt")?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigitalSource {
+    pub nodes: Vec<String>,
+    /// The times and the states of the nodes.
+    pub vectors: Vec<(f64, Vec<Logic>)>,
+}
+
+impl DigitalSource {
+    pub fn new(nodes: Vec<&str>) -> Self {
+        Self {
+            nodes: nodes.iter().map(|n| n.to_string()).collect(),
+            vectors: Vec::new(),
+        }
+    }
+    /// Set the states of the nodes at the time, the times must increase.
+    pub fn add(&mut self, time: f64, states: Vec<Logic>) -> Result<(), Error> {
+        if states.len() != self.nodes.len() {
+            return Err(Error::InvalidValue(format!(
+                "{} states for {} nodes",
+                states.len(),
+                self.nodes.len()
+            )));
+        }
+        if let Some((last, _)) = self.vectors.last() {
+            if time <= *last {
+                return Err(Error::InvalidValue(format!(
+                    "time {} is not after {}",
+                    Value::new(time),
+                    Value::new(*last)
+                )));
+            }
+        }
+        self.vectors.push((time, states));
+        Ok(())
+    }
+    /// Set the states from the characters `0`, `1`, `U` and `Z`.
+    pub fn add_bits(&mut self, time: f64, bits: &str) -> Result<(), Error> {
+        let states = bits
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(Logic::try_from)
+            .collect::<Result<Vec<Logic>, Error>>()?;
+        self.add(time, states)
+    }
+    /// The content of the input file.
+    pub fn content(&self) -> String {
+        let mut content = format!("* T {}\n", self.nodes.join(" "));
+        for (time, states) in &self.vectors {
+            let states: Vec<String> = states.iter().map(|s| s.to_string()).collect();
+            content += &format!("{} {}\n", Value::new(*time), states.join(" "));
+        }
+        content
+    }
+    /// Write the input file and add the source and its model to the circuit.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        reference: &str,
+        filename: &str,
+    ) -> Result<(), Error> {
+        fs::write(filename, self.content())?;
+        let model = format!("{}_source", reference).to_lowercase();
+        circuit.model(
+            model.clone(),
+            String::from("d_source"),
+            format!("input_file=\"{}\"", filename),
+        );
+        circuit.xspice(
+            reference.to_string(),
+            vec![format!("[{}]", self.nodes.join(" "))],
+            model,
+        )
+    }
+}
+
+impl Circuit {
+    /// Convert the analog nodes to digital nodes with an XSPICE `adc_bridge`.
+    ///
+    /// Voltages below `low` are a 0, voltages above `high` a 1.
+    pub fn adc_bridge(
+        &mut self,
+        reference: &str,
+        analog: Vec<&str>,
+        digital: Vec<&str>,
+        low: f64,
+        high: f64,
+    ) -> Result<(), Error> {
+        self.bridge(
+            reference,
+            "adc_bridge",
+            (analog, digital),
+            format!("in_low={} in_high={}", Value::new(low), Value::new(high)),
+        )
+    }
+    /// Drive the analog nodes from digital nodes with an XSPICE `dac_bridge`.
+    ///
+    /// The outputs are at `low` for a 0, at `high` for a 1 and in the
+    /// middle for unknown states.
+    pub fn dac_bridge(
+        &mut self,
+        reference: &str,
+        digital: Vec<&str>,
+        analog: Vec<&str>,
+        low: f64,
+        high: f64,
+    ) -> Result<(), Error> {
+        self.bridge(
+            reference,
+            "dac_bridge",
+            (digital, analog),
+            format!(
+                "out_low={} out_high={} out_undef={}",
+                Value::new(low),
+                Value::new(high),
+                Value::new((low + high) / 2.0)
+            ),
+        )
+    }
+    fn bridge(
+        &mut self,
+        reference: &str,
+        kind: &str,
+        (inputs, outputs): (Vec<&str>, Vec<&str>),
+        parameters: String,
+    ) -> Result<(), Error> {
+        if inputs.is_empty() || inputs.len() != outputs.len() {
+            return Err(Error::InvalidValue(format!(
+                "{} inputs for {} outputs",
+                inputs.len(),
+                outputs.len()
+            )));
+        }
+        let model = format!("{}_{}", reference, kind).to_lowercase();
+        self.xspice(
+            reference.to_string(),
+            vec![
+                format!("[{}]", inputs.join(" ")),
+                format!("[{}]", outputs.join(" ")),
+            ],
+            model.clone(),
+        )?;
+        self.model(model, kind.to_string(), parameters);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DigitalSource, Logic};
+    use crate::Circuit;
+
+    #[test]
+    fn source() {
+        let mut source = DigitalSource::new(vec!["d0", "d1"]);
+        source.add_bits(0.0, "00").unwrap();
+        source.add(1e-6, vec![Logic::Low, Logic::High]).unwrap();
+        source.add_bits(2e-6, "1z").unwrap();
+        assert!(source.add_bits(3e-6, "1").is_err());
+        assert!(source.add_bits(1e-6, "11").is_err());
+        assert!(source.add_bits(3e-6, "12").is_err());
+        assert_eq!("* T d0 d1\n0 0s 0s\n1u 0s 1s\n2u 1s Uz\n", source.content());
+
+        let filename = std::env::temp_dir().join("elektron_d_source.txt");
+        let filename = filename.to_str().unwrap();
+        let mut circuit = Circuit::new(String::from("digital"), Vec::new());
+        source.build(&mut circuit, "ASRC", filename).unwrap();
+        assert_eq!(source.content(), std::fs::read_to_string(filename).unwrap());
+        assert_eq!(
+            vec![
+                format!(".model asrc_source d_source(input_file=\"{}\")", filename),
+                String::from("ASRC [d0 d1] asrc_source"),
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+    #[test]
+    fn bridges() {
+        let mut circuit = Circuit::new(String::from("bridges"), Vec::new());
+        circuit
+            .adc_bridge("A1", vec!["in"], vec!["d_in"], 0.8, 2.0)
+            .unwrap();
+        circuit
+            .dac_bridge("A2", vec!["d0", "d1"], vec!["out0", "out1"], 0.0, 3.3)
+            .unwrap();
+        assert!(circuit
+            .dac_bridge("A3", vec!["d0"], vec!["a", "b"], 0.0, 5.0)
+            .is_err());
+        assert!(circuit
+            .adc_bridge("A1", vec!["x"], vec!["y"], 0.8, 2.0)
+            .is_err());
+        assert_eq!(
+            vec![
+                "A1 [in] [d_in] a1_adc_bridge",
+                ".model a1_adc_bridge adc_bridge(in_low=800m in_high=2)",
+                "A2 [d0 d1] [out0 out1] a2_dac_bridge",
+                ".model a2_dac_bridge dac_bridge(out_low=0 out_high=3.3 out_undef=1.65)",
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+}
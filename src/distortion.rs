@@ -0,0 +1,224 @@
+use crate::{
+    error::Error,
+    fft::{Fft, Spectrum, Window},
+};
+
+/// The total harmonic distortion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thd {
+    pub percent: f64,
+    pub db: f64,
+}
+
+impl Thd {
+    fn new(ratio: f64) -> Self {
+        Self {
+            percent: 100.0 * ratio,
+            db: 20.0 * ratio.log10(),
+        }
+    }
+}
+
+/// The noise and distortion metrics of a sine signal, the ratios are in dB.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Noise {
+    /// The total harmonic distortion plus noise.
+    pub thd_n: Thd,
+    pub snr: f64,
+    pub sinad: f64,
+    /// The effective number of bits from the SINAD.
+    pub enob: f64,
+}
+
+/// Measure the noise and distortion of a sine signal.
+///
+/// All bins within the bandwidth except DC and the notch around the
+/// fundamental count as noise and distortion, the harmonics are removed
+/// for the SNR.
+///
+/// ```ignore
+/// let mut distortion = Distortion::new(1e3);
+/// distortion.set_bandwidth(20.0, 20e3);
+/// let noise = distortion.run(&result["time"], &result["out"])?;
+/// println!("SINAD: {}dB, ENOB: {}", noise.sinad, noise.enob);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distortion {
+    fundamental: f64,
+    harmonics: usize,
+    bandwidth: Option<(f64, f64)>,
+    notch: f64,
+}
+
+/// The power of the bins within the main lobe of the window around the frequency.
+pub(crate) fn power(spectrum: &Spectrum, frequency: f64) -> f64 {
+    let (start, end) = lobe(spectrum, frequency);
+    spectrum.magnitude[start..end].iter().map(|m| m * m).sum()
+}
+
+/// The range of bins of the main lobe of the window around the frequency.
+pub(crate) fn lobe(spectrum: &Spectrum, frequency: f64) -> (usize, usize) {
+    let bin = (frequency / spectrum.frequency[1]).round() as usize;
+    let width = spectrum.window.lobe();
+    (
+        bin.saturating_sub(width).min(spectrum.magnitude.len()),
+        (bin + width + 1).min(spectrum.magnitude.len()),
+    )
+}
+
+fn spectrum(time: &[f64], vector: &[f64], fundamental: f64) -> Result<Spectrum, Error> {
+    let spectrum = Fft::new(Window::BlackmanHarris).run(time, vector)?;
+    if fundamental <= 0.0 || fundamental >= spectrum.frequency[spectrum.frequency.len() - 1] {
+        return Err(Error::Signal(format!(
+            "the fundamental {}Hz is outside of the spectrum",
+            fundamental
+        )));
+    }
+    if lobe(&spectrum, fundamental).0 == 0 {
+        return Err(Error::Signal(format!(
+            "the fundamental {}Hz is not resolved, simulate more periods",
+            fundamental
+        )));
+    }
+    Ok(spectrum)
+}
+
+/// The total harmonic distortion of the transient vector.
+///
+/// The vector is windowed with Blackman-Harris and the power of the
+/// harmonics from 2 to `harmonics` is summed, harmonics above the
+/// Nyquist frequency are ignored. The simulation should cover at least
+/// ten periods of the fundamental.
+///
+/// ```ignore
+/// let result = simulation.tran("10u", "10m", "0");
+/// let thd = thd(&result["time"], &result["out"], 1e3, 10)?;
+/// assert!(thd.percent < 0.1);
+/// ```
+pub fn thd(time: &[f64], vector: &[f64], fundamental: f64, harmonics: usize) -> Result<Thd, Error> {
+    let spectrum = spectrum(time, vector, fundamental)?;
+    let nyquist = spectrum.frequency[spectrum.frequency.len() - 1];
+    let distortion: f64 = (2..=harmonics)
+        .map(|n| n as f64 * fundamental)
+        .take_while(|f| *f < nyquist)
+        .map(|f| power(&spectrum, f))
+        .sum();
+    Ok(Thd::new(
+        (distortion / power(&spectrum, fundamental)).sqrt(),
+    ))
+}
+
+impl Distortion {
+    pub fn new(fundamental: f64) -> Self {
+        Self {
+            fundamental,
+            harmonics: 10,
+            bandwidth: None,
+            notch: 0.0,
+        }
+    }
+    /// The number of harmonics removed for the SNR, the default is 10.
+    pub fn set_harmonics(&mut self, harmonics: usize) {
+        self.harmonics = harmonics;
+    }
+    /// Limit the analysis to the frequency band, the default is up to Nyquist.
+    pub fn set_bandwidth(&mut self, low: f64, high: f64) {
+        self.bandwidth = Some((low, high));
+    }
+    /// The width of the notch around the fundamental in Hz, the notch is
+    /// never narrower than the main lobe of the window.
+    pub fn set_notch(&mut self, width: f64) {
+        self.notch = width;
+    }
+    pub fn run(&self, time: &[f64], vector: &[f64]) -> Result<Noise, Error> {
+        let spectrum = spectrum(time, vector, self.fundamental)?;
+        let bins = spectrum.magnitude.len();
+        let resolution = spectrum.frequency[1];
+        let (low, high) = self.bandwidth.unwrap_or((0.0, f64::INFINITY));
+        let dc = spectrum.window.lobe() + 1;
+        let (mut start, mut end) = lobe(&spectrum, self.fundamental);
+        let half = (self.notch / (2.0 * resolution)).round() as usize;
+        let center = (self.fundamental / resolution).round() as usize;
+        start = start.min(center.saturating_sub(half));
+        end = end.max((center + half + 1).min(bins));
+        let harmonics: Vec<(usize, usize)> = (2..=self.harmonics)
+            .map(|n| lobe(&spectrum, n as f64 * self.fundamental))
+            .filter(|(start, end)| start < end)
+            .collect();
+        let (mut distortion, mut noise) = (0.0, 0.0);
+        for (k, (frequency, magnitude)) in spectrum
+            .frequency
+            .iter()
+            .zip(&spectrum.magnitude)
+            .enumerate()
+        {
+            if k < dc || (start..end).contains(&k) || *frequency < low || *frequency > high {
+                continue;
+            }
+            if harmonics
+                .iter()
+                .any(|(start, end)| (*start..*end).contains(&k))
+            {
+                distortion += magnitude * magnitude;
+            } else {
+                noise += magnitude * magnitude;
+            }
+        }
+        let signal = power(&spectrum, self.fundamental);
+        let sinad = 10.0 * (signal / (distortion + noise)).log10();
+        Ok(Noise {
+            thd_n: Thd::new(((distortion + noise) / signal).sqrt()),
+            snr: 10.0 * (signal / noise).log10(),
+            sinad,
+            enob: (sinad - 1.76) / 6.02,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{thd, Distortion};
+
+    #[test]
+    fn harmonics() {
+        let time: Vec<f64> = (0..=20000).map(|i| i as f64 * 1e-6).collect();
+        let vector: Vec<f64> = time
+            .iter()
+            .map(|t| {
+                let w = 2.0 * PI * 1e3 * t;
+                w.sin() + 0.01 * (2.0 * w).sin() + 0.005 * (3.0 * w).cos()
+            })
+            .collect();
+        let result = thd(&time, &vector, 1e3, 10).unwrap();
+        assert!((result.percent - 1.118).abs() < 0.005);
+        assert!((result.db + 39.03).abs() < 0.05);
+        assert!(thd(&time, &vector, 1e6, 10).is_err());
+    }
+    #[test]
+    fn noise() {
+        //white noise with an rms value of 1mV
+        let mut rng = StdRng::seed_from_u64(1);
+        let time: Vec<f64> = (0..=20000).map(|i| i as f64 * 1e-6).collect();
+        let vector: Vec<f64> = time
+            .iter()
+            .map(|t| {
+                let w = 2.0 * PI * 1e3 * t;
+                w.sin() + 0.01 * (2.0 * w).sin() + rng.gen_range(-1.0..1.0) * 3f64.sqrt() * 1e-3
+            })
+            .collect();
+        let mut distortion = Distortion::new(1e3);
+        let noise = distortion.run(&time, &vector).unwrap();
+        assert!((noise.snr - 57.0).abs() < 0.5);
+        assert!((noise.sinad - 39.9).abs() < 0.2);
+        assert!((noise.thd_n.percent - 1.01).abs() < 0.02);
+        assert!((noise.enob - 6.34).abs() < 0.05);
+        distortion.set_bandwidth(0.0, 1.5e3);
+        distortion.set_notch(200.0);
+        let noise = distortion.run(&time, &vector).unwrap();
+        assert!(noise.sinad > 60.0);
+    }
+}
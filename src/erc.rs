@@ -0,0 +1,348 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serde_json::{json, Value};
+
+use crate::netlist::{Erc, Netlist};
+
+/// The electrical type of a symbol pin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PinType {
+    Input,
+    Output,
+    Bidirectional,
+    TriState,
+    Passive,
+    Free,
+    Unspecified,
+    PowerIn,
+    PowerOut,
+    OpenCollector,
+    OpenEmitter,
+    NoConnect,
+}
+
+const PIN_TYPES: usize = 12;
+
+impl PinType {
+    /// Get the pin type from the KiCad electrical type.
+    pub fn from(electrical_type: &str) -> Self {
+        match electrical_type {
+            "input" => PinType::Input,
+            "output" => PinType::Output,
+            "bidirectional" => PinType::Bidirectional,
+            "tri_state" => PinType::TriState,
+            "passive" => PinType::Passive,
+            "free" => PinType::Free,
+            "power_in" => PinType::PowerIn,
+            "power_out" => PinType::PowerOut,
+            "open_collector" => PinType::OpenCollector,
+            "open_emitter" => PinType::OpenEmitter,
+            "no_connect" => PinType::NoConnect,
+            _ => PinType::Unspecified,
+        }
+    }
+}
+
+/// The pin types which are not allowed on the same net.
+///
+/// The default matrix reports the conflicts between outputs, power outputs
+/// and open collector/emitter outputs, like the KiCad ERC.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PinMatrix {
+    conflicts: [[bool; PIN_TYPES]; PIN_TYPES],
+}
+
+impl PinMatrix {
+    /// Create a matrix without any conflict.
+    pub fn new() -> Self {
+        Self {
+            conflicts: [[false; PIN_TYPES]; PIN_TYPES],
+        }
+    }
+    /// Set the conflict for the two pin types, the order of the types does not matter.
+    pub fn set(&mut self, a: PinType, b: PinType, conflict: bool) {
+        self.conflicts[a as usize][b as usize] = conflict;
+        self.conflicts[b as usize][a as usize] = conflict;
+    }
+    pub fn conflict(&self, a: PinType, b: PinType) -> bool {
+        self.conflicts[a as usize][b as usize]
+    }
+}
+
+impl Default for PinMatrix {
+    fn default() -> Self {
+        let mut matrix = Self::new();
+        matrix.set(PinType::Output, PinType::Output, true);
+        matrix.set(PinType::Output, PinType::PowerOut, true);
+        matrix.set(PinType::Output, PinType::OpenCollector, true);
+        matrix.set(PinType::Output, PinType::OpenEmitter, true);
+        matrix.set(PinType::PowerOut, PinType::PowerOut, true);
+        matrix.set(PinType::PowerOut, PinType::TriState, true);
+        matrix.set(PinType::PowerOut, PinType::OpenCollector, true);
+        matrix.set(PinType::PowerOut, PinType::OpenEmitter, true);
+        matrix.set(PinType::TriState, PinType::Output, true);
+        matrix
+    }
+}
+
+/// The severity of an ERC rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Ignore,
+}
+
+/// A project specific ERC check.
+///
+/// The rule gets the netlist with the nets, nodes and symbol properties
+/// and returns the violations, usually as `Erc::Custom` with the code of
+/// the rule. Rules are registered with `ErcConfig::add_rule`.
+///
+/// ```ignore
+/// struct NoTrimmer;
+/// impl ErcRule for NoTrimmer {
+///     fn check(&self, netlist: &Netlist) -> Vec<Erc> {
+///         netlist
+///             .references()
+///             .iter()
+///             .filter(|r| r.starts_with("RV"))
+///             .map(|r| Erc::Custom {
+///                 code: String::from("no_trimmer"),
+///                 message: format!("{} is a trimmer", r),
+///                 net: None,
+///                 pins: Vec::new(),
+///             })
+///             .collect()
+///     }
+/// }
+/// ```
+pub trait ErcRule: Send + Sync {
+    fn check(&self, netlist: &Netlist) -> Vec<Erc>;
+}
+
+/// Configure the severity of the ERC rules and waive violations.
+///
+/// The rules are selected by the code of the violation, see `Erc::code`.
+/// Violations on waived references or nets are not reported, so
+/// existing schemas can adopt the ERC step by step.
+#[derive(Clone)]
+pub struct ErcConfig {
+    pub matrix: PinMatrix,
+    severities: HashMap<String, Severity>,
+    references: Vec<String>,
+    nets: Vec<String>,
+    rules: Vec<Arc<dyn ErcRule>>,
+}
+
+impl ErcConfig {
+    pub fn new() -> Self {
+        Self {
+            matrix: PinMatrix::default(),
+            severities: HashMap::new(),
+            references: Vec::new(),
+            nets: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+    pub fn set_severity(&mut self, code: &str, severity: Severity) {
+        self.severities.insert(code.to_string(), severity);
+    }
+    /// The severity of the rule, dangling wires and single pin nets are warnings by default.
+    pub fn severity(&self, code: &str) -> Severity {
+        match self.severities.get(code) {
+            Some(severity) => *severity,
+            None => match code {
+                "wire_dangling" | "single_pin_net" => Severity::Warning,
+                _ => Severity::Error,
+            },
+        }
+    }
+    pub fn waive_reference(&mut self, reference: &str) {
+        self.references.push(reference.to_string());
+    }
+    pub fn waive_net(&mut self, net: &str) {
+        self.nets.push(net.to_string());
+    }
+    /// Register a custom rule, the violations are checked like the builtin rules.
+    pub fn add_rule(&mut self, rule: Arc<dyn ErcRule>) {
+        self.rules.push(rule);
+    }
+    pub fn rules(&self) -> &[Arc<dyn ErcRule>] {
+        &self.rules
+    }
+    /// Get the severity of the violation, waived violations are ignored.
+    pub fn check(&self, erc: &Erc) -> Severity {
+        if erc.references().iter().any(|r| self.references.contains(r)) {
+            return Severity::Ignore;
+        }
+        if let Some(net) = erc.net() {
+            if self.nets.contains(net) {
+                return Severity::Ignore;
+            }
+        }
+        self.severity(erc.code())
+    }
+}
+
+impl std::fmt::Debug for ErcConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ErcConfig")
+            .field("matrix", &self.matrix)
+            .field("severities", &self.severities)
+            .field("references", &self.references)
+            .field("nets", &self.nets)
+            .field("rules", &self.rules.len())
+            .finish()
+    }
+}
+
+impl Default for ErcConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Severity {
+    fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Ignore => "none",
+        }
+    }
+}
+
+/// The ERC violations of a schema in JSON or SARIF format.
+///
+/// ```ignore
+/// let netlist = Netlist::load("amp.kicad_sch")?;
+/// let report = ErcReport::new("amp.kicad_sch", netlist.erc_with_config(&ErcConfig::new()));
+/// std::fs::write("erc.sarif", report.to_sarif())?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ErcReport {
+    pub filename: String,
+    pub violations: Vec<(Severity, Erc)>,
+}
+
+impl ErcReport {
+    pub fn new(filename: &str, violations: Vec<(Severity, Erc)>) -> Self {
+        Self {
+            filename: filename.to_string(),
+            violations,
+        }
+    }
+    fn location(erc: &Erc) -> Value {
+        let mut points: Vec<Value> = erc
+            .pins()
+            .iter()
+            .map(|p| json!({"reference": p.reference, "pin": p.number, "x": p.at.x, "y": p.at.y}))
+            .collect();
+        if let Erc::WireNotConnected(p0, p1) = erc {
+            points.push(json!({"x": p0.x, "y": p0.y}));
+            points.push(json!({"x": p1.x, "y": p1.y}));
+        }
+        Value::Array(points)
+    }
+    fn json(&self) -> Value {
+        let violations: Vec<Value> = self
+            .violations
+            .iter()
+            .map(|(severity, erc)| {
+                json!({
+                    "code": erc.code(),
+                    "severity": severity.name(),
+                    "message": erc.to_string(),
+                    "net": erc.net(),
+                    "references": erc.references(),
+                    "locations": Self::location(erc),
+                })
+            })
+            .collect();
+        json!({"source": self.filename, "violations": violations})
+    }
+    /// Get the violations as JSON.
+    pub fn to_json(&self) -> String {
+        self.json().to_string()
+    }
+    /// Get the violations as a SARIF 2.1.0 log.
+    pub fn to_sarif(&self) -> String {
+        let mut rules: Vec<&str> = self.violations.iter().map(|(_, e)| e.code()).collect();
+        rules.sort_unstable();
+        rules.dedup();
+        let results: Vec<Value> = self
+            .violations
+            .iter()
+            .map(|(severity, erc)| {
+                let mut names: Vec<&String> = erc.references();
+                if let Some(net) = erc.net() {
+                    names.push(net);
+                }
+                json!({
+                    "ruleId": erc.code(),
+                    "level": severity.name(),
+                    "message": {"text": erc.to_string()},
+                    "locations": [{
+                        "physicalLocation": {"artifactLocation": {"uri": self.filename}},
+                        "logicalLocations": names.iter().map(|n| json!({"name": n})).collect::<Vec<Value>>(),
+                    }],
+                    "properties": {"schema": Self::location(erc)},
+                })
+            })
+            .collect();
+        json!({
+            "version": "2.1.0",
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "runs": [{
+                "tool": {"driver": {
+                    "name": "elektron_spice",
+                    "rules": rules.iter().map(|r| json!({"id": r})).collect::<Vec<Value>>(),
+                }},
+                "results": results,
+            }],
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErcConfig, ErcReport, PinMatrix, PinType, Severity};
+    use crate::{Erc, Point};
+
+    #[test]
+    fn pin_matrix() {
+        let mut matrix = PinMatrix::default();
+        assert!(matrix.conflict(PinType::Output, PinType::Output));
+        assert!(matrix.conflict(PinType::PowerOut, PinType::Output));
+        assert!(!matrix.conflict(PinType::Output, PinType::Input));
+        matrix.set(PinType::Input, PinType::Output, true);
+        assert!(matrix.conflict(PinType::Output, PinType::from("input")));
+    }
+    #[test]
+    fn erc_config() {
+        let mut config = ErcConfig::new();
+        let erc = Erc::WireNotConnected(Point::new(0.0, 0.0), Point::new(1.0, 0.0));
+        assert_eq!(Severity::Warning, config.check(&erc));
+        config.set_severity("wire_dangling", Severity::Error);
+        assert_eq!(Severity::Error, config.check(&erc));
+        config.set_severity("wire_dangling", Severity::Ignore);
+        assert_eq!(Severity::Ignore, config.check(&erc));
+    }
+    #[test]
+    fn erc_report() {
+        let erc = Erc::WireNotConnected(Point::new(0.0, 0.0), Point::new(1.0, 0.0));
+        let report = ErcReport::new("test.kicad_sch", vec![(Severity::Warning, erc)]);
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!("wire_dangling", json["violations"][0]["code"]);
+        assert_eq!("warning", json["violations"][0]["severity"]);
+        let sarif: serde_json::Value = serde_json::from_str(&report.to_sarif()).unwrap();
+        assert_eq!("2.1.0", sarif["version"]);
+        assert_eq!("wire_dangling", sarif["runs"][0]["results"][0]["ruleId"]);
+        assert_eq!(
+            "test.kicad_sch",
+            sarif["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use crate::SpiceMessage;
+
 #[derive(thiserror::Error, Debug, Clone)]
 pub enum Error {
     #[error("Can not parse file.")]
@@ -26,6 +28,8 @@ pub enum Error {
     Theme(String, String),
     #[error("Spice model not found: {0}")]
     SpiceModelNotFound(String),
+    #[error("Include cycle at {0}")]
+    IncludeCycle(String),
     #[error("Unknown circuit element {0}")]
     UnknownCircuitElement(String),
     #[error("No pins found in {0} for unit {1}")]
@@ -34,6 +38,44 @@ pub enum Error {
     PropertyNotFound(String, String),
     #[error("Library \"{0}\" not found in schema")]
     LinraryNotFound(String),
+    #[error("Hierarchical schematics are not supported: {0}")]
+    Hierarchy(String),
+    #[error("Can not load the configuration {0}: {1}")]
+    Config(String, String),
+    #[error("Can not load circuit definition: {0}")]
+    Definition(String),
+    #[error("Can not parse spice line: {0}")]
+    Spice(String),
+    #[error("{0} expects {1} nodes, found {2}")]
+    NodeCount(String, usize, usize),
+    #[error("{0} connects {1} nodes to the subcircuit {2} with the ports {3}")]
+    SubcircuitPorts(String, usize, String, String),
+    #[error("Invalid value \"{1}\" for property \"{0}\"")]
+    InvalidProperty(String, String),
+    #[error("Duplicate reference {0}")]
+    DuplicateReference(String),
+    #[error("Duplicate definition of {0}")]
+    DuplicateDefinition(String),
+    #[error("Invalid node name \"{0}\"")]
+    InvalidNode(String),
+    #[error("Can not parse value \"{0}\"")]
+    InvalidValue(String),
+    #[error("{0} has no device current, use save_vector")]
+    NoDeviceCurrent(String),
+    #[error("Can not sample the components: {0}")]
+    Sampling(String),
+    #[error("{0} components vary, the limit for the corners is {1}")]
+    CornerLimit(usize, usize),
+    #[error("Can not synthesize filter: {0}")]
+    Filter(String),
+    #[error("Can not synthesize matching network: {0}")]
+    Matching(String),
+    #[error("Can not evaluate the signal: {0}")]
+    Signal(String),
+    #[error("Can not read or write JSON: {0}")]
+    Serialization(String),
+    #[error("Simulation failed: {0}")]
+    Simulation(String, Vec<SpiceMessage>),
 }
 
 impl std::convert::From<std::io::Error> for Error {
@@ -0,0 +1,128 @@
+use crate::{circuit::CircuitItem, error::Error, Circuit};
+
+impl Circuit {
+    /// Move the elements into a new subcircuit and replace them with an
+    /// instance of it.
+    ///
+    /// The ports are the nets of the elements which are also connected to
+    /// the rest of the circuit, in the order they are used. The ground is
+    /// global and not a port. Returns the ports of the subcircuit.
+    ///
+    /// ```ignore
+    /// let ports = circuit.extract(&["R1", "C1"], "lowpass", "X1")?;
+    /// ```
+    pub fn extract(
+        &mut self,
+        references: &[&str],
+        name: &str,
+        reference: &str,
+    ) -> Result<Vec<String>, Error> {
+        for r in references {
+            if !self
+                .items
+                .iter()
+                .any(|i| i.reference().is_some_and(|x| x == r))
+            {
+                return Err(Error::UnknownCircuitElement(r.to_string()));
+            }
+        }
+        if self.subcircuits.contains_key(name) {
+            return Err(Error::DuplicateDefinition(name.to_string()));
+        }
+        let position = self
+            .items
+            .iter()
+            .position(|i| {
+                i.reference()
+                    .is_some_and(|r| references.contains(&r.as_str()))
+            })
+            .unwrap_or(self.items.len());
+        let (selected, rest): (Vec<CircuitItem>, Vec<CircuitItem>) =
+            self.items.iter().cloned().partition(|i| {
+                i.reference()
+                    .is_some_and(|r| references.contains(&r.as_str()))
+            });
+        let mut ports: Vec<String> = Vec::new();
+        for item in &selected {
+            for node in item.nodes() {
+                if !self.is_ground(node)
+                    && !ports.contains(node)
+                    && rest.iter().any(|i| i.nodes().contains(&node))
+                {
+                    ports.push(node.clone());
+                }
+            }
+        }
+        let mut subcircuit = self.clone();
+        subcircuit.items = selected;
+        subcircuit.subcircuits.clear();
+        subcircuit.params.clear();
+        subcircuit.osdi.clear();
+        let instance = CircuitItem::X(
+            reference.to_string(),
+            ports.clone(),
+            name.to_string(),
+            Vec::new(),
+        );
+        let mut items = rest;
+        items.insert(position.min(items.len()), instance.clone());
+        if items
+            .iter()
+            .filter(|i| i.name().is_some() && i.name() == instance.name())
+            .count()
+            > 1
+        {
+            return Err(Error::DuplicateReference(reference.to_string()));
+        }
+        self.items = items;
+        self.subcircuits
+            .insert(name.to_string(), (ports.clone(), subcircuit));
+        Ok(ports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, Circuit};
+
+    #[test]
+    fn extract() {
+        let mut circuit = Circuit::new(String::from("flat"), Vec::new());
+        circuit
+            .voltage(String::from("V1"), "in", "GND", String::from("AC 1"))
+            .unwrap();
+        circuit
+            .resistor(String::from("R1"), "in", "mid", String::from("10k"))
+            .unwrap();
+        circuit
+            .capacitor(String::from("C1"), "mid", "GND", String::from("10n"))
+            .unwrap();
+        circuit
+            .resistor(String::from("R2"), "mid", "out", String::from("1k"))
+            .unwrap();
+        circuit
+            .resistor(String::from("R3"), "out", "GND", String::from("100k"))
+            .unwrap();
+        let ports = circuit
+            .extract(&["R1", "C1", "R2"], "lowpass", "X1")
+            .unwrap();
+        assert_eq!(vec![String::from("in"), String::from("out")], ports);
+        assert_eq!(
+            vec![
+                ".subckt lowpass in out",
+                "R1 in mid 10k",
+                "C1 mid 0 10n",
+                "R2 mid out 1k",
+                ".ends",
+                "V1 in 0 AC 1",
+                "X1 in out lowpass",
+                "R3 out 0 100k",
+            ],
+            circuit.to_str(false).unwrap()
+        );
+        assert!(matches!(
+            circuit.extract(&["R4"], "other", "X2"),
+            Err(Error::UnknownCircuitElement(_))
+        ));
+    }
+}
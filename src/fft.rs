@@ -0,0 +1,239 @@
+use std::f64::consts::PI;
+
+use num_complex::Complex64;
+use rustfft::FftPlanner;
+
+use crate::error::Error;
+
+/// The window applied to the samples before the transformation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Window {
+    Rectangular,
+    #[default]
+    Hann,
+    /// The 4-term Blackman-Harris window with low leakage.
+    BlackmanHarris,
+    /// Accurate amplitudes for frequencies between the bins.
+    FlatTop,
+}
+
+impl Window {
+    /// The coefficients of the periodic window.
+    pub fn coefficients(&self, length: usize) -> Vec<f64> {
+        let terms: &[f64] = match self {
+            Window::Rectangular => &[1.0],
+            Window::Hann => &[0.5, 0.5],
+            Window::BlackmanHarris => &[0.35875, 0.48829, 0.14128, 0.01168],
+            Window::FlatTop => &[
+                0.21557895,
+                0.41663158,
+                0.277263158,
+                0.083578947,
+                0.006947368,
+            ],
+        };
+        (0..length)
+            .map(|i| {
+                terms
+                    .iter()
+                    .enumerate()
+                    .map(|(k, a)| {
+                        let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+                        sign * a * (2.0 * PI * (k * i) as f64 / length as f64).cos()
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+    /// The half width of the main lobe in bins.
+    pub(crate) fn lobe(&self) -> usize {
+        match self {
+            Window::Rectangular => 1,
+            Window::Hann => 2,
+            Window::BlackmanHarris => 4,
+            Window::FlatTop => 5,
+        }
+    }
+}
+
+/// The single sided amplitude spectrum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    /// The frequency of the bins in Hz.
+    pub frequency: Vec<f64>,
+    /// The peak amplitude of the bins, corrected by the coherent gain of the window.
+    pub magnitude: Vec<f64>,
+    pub window: Window,
+}
+
+/// Transform transient vectors to the frequency domain.
+///
+/// The time steps of ngspice are not equidistant, the vector is
+/// resampled with linear interpolation before the transformation.
+/// With averaging, the samples are split in segments and the complex
+/// spectra of the segments are averaged.
+///
+/// ```ignore
+/// let result = simulation.tran("10u", "100m", "0");
+/// let mut fft = Fft::new(Window::BlackmanHarris);
+/// fft.set_averages(4);
+/// let spectrum = fft.run(&result["time"], &result["out"])?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fft {
+    window: Window,
+    points: Option<usize>,
+    padding: usize,
+    averages: usize,
+}
+
+/// Resample the vector to equidistant points, returns the time step and the samples.
+pub(crate) fn resample(
+    time: &[f64],
+    vector: &[f64],
+    points: usize,
+) -> Result<(f64, Vec<f64>), Error> {
+    if time.len() != vector.len() {
+        return Err(Error::Signal(format!(
+            "{} time points for {} values",
+            time.len(),
+            vector.len()
+        )));
+    }
+    if points < 2 || time.len() < 2 {
+        return Err(Error::Signal(String::from("not enough values")));
+    }
+    let start = time[0];
+    let step = (time[time.len() - 1] - start) / (points - 1) as f64;
+    let mut index = 0;
+    let samples = (0..points)
+        .map(|i| {
+            let t = start + i as f64 * step;
+            while index + 2 < time.len() && time[index + 1] < t {
+                index += 1;
+            }
+            let (t0, t1) = (time[index], time[index + 1]);
+            if t1 == t0 {
+                vector[index]
+            } else {
+                vector[index] + (t - t0) / (t1 - t0) * (vector[index + 1] - vector[index])
+            }
+        })
+        .collect();
+    Ok((step, samples))
+}
+
+impl Fft {
+    pub fn new(window: Window) -> Self {
+        Self {
+            window,
+            points: None,
+            padding: 1,
+            averages: 1,
+        }
+    }
+    /// Resample to the number of points, the default is the length of the vector.
+    pub fn set_points(&mut self, points: usize) {
+        self.points = Some(points);
+    }
+    /// Extend the segments with zeros to the factor of their length.
+    pub fn set_zero_padding(&mut self, factor: usize) {
+        self.padding = factor.max(1);
+    }
+    /// Average the spectra of the number of segments.
+    pub fn set_averages(&mut self, averages: usize) {
+        self.averages = averages.max(1);
+    }
+    pub fn run(&self, time: &[f64], vector: &[f64]) -> Result<Spectrum, Error> {
+        let (step, samples) = resample(time, vector, self.points.unwrap_or(vector.len()))?;
+        let segment = samples.len() / self.averages;
+        if segment < 2 {
+            return Err(Error::Signal(format!(
+                "{} samples can not be split in {} segments",
+                samples.len(),
+                self.averages
+            )));
+        }
+        let window = self.window.coefficients(segment);
+        let gain: f64 = window.iter().sum();
+        let length = segment * self.padding;
+        let fft = FftPlanner::new().plan_fft_forward(length);
+        let mut sum = vec![Complex64::new(0.0, 0.0); length];
+        for chunk in samples.chunks_exact(segment) {
+            let mut buffer: Vec<Complex64> = chunk
+                .iter()
+                .zip(&window)
+                .map(|(s, w)| Complex64::new(s * w, 0.0))
+                .collect();
+            buffer.resize(length, Complex64::new(0.0, 0.0));
+            fft.process(&mut buffer);
+            for (sum, value) in sum.iter_mut().zip(buffer) {
+                *sum += value;
+            }
+        }
+        let bins = length / 2 + 1;
+        let scale = self.averages as f64 * gain;
+        Ok(Spectrum {
+            frequency: (0..bins)
+                .map(|k| k as f64 / (length as f64 * step))
+                .collect(),
+            magnitude: sum[..bins]
+                .iter()
+                .enumerate()
+                .map(|(k, value)| {
+                    let single = if k == 0 || 2 * k == length { 1.0 } else { 2.0 };
+                    single * value.norm() / scale
+                })
+                .collect(),
+            window: self.window,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::{Fft, Window};
+
+    #[test]
+    fn spectrum() {
+        //1kHz with an amplitude of 2 and an offset of 0.5, sampled with 1024 points over 8ms
+        let time: Vec<f64> = (0..=1024).map(|i| i as f64 * 8e-3 / 1024.0).collect();
+        let vector: Vec<f64> = time
+            .iter()
+            .map(|t| 0.5 + 2.0 * (2.0 * PI * 1e3 * t).sin())
+            .collect();
+        let mut fft = Fft::new(Window::Hann);
+        fft.set_points(1024);
+        let spectrum = fft.run(&time, &vector).unwrap();
+        assert_eq!(513, spectrum.frequency.len());
+        let peak = spectrum
+            .magnitude
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap()
+            .0;
+        assert!((spectrum.frequency[peak] - 1e3).abs() < 1.0);
+        assert!((spectrum.magnitude[peak] - 2.0).abs() < 0.02);
+        assert!((spectrum.magnitude[0] - 0.5).abs() < 0.01);
+
+        fft.set_zero_padding(4);
+        fft.set_averages(2);
+        let spectrum = fft.run(&time, &vector).unwrap();
+        assert_eq!(1025, spectrum.frequency.len());
+
+        let mut flat = Fft::new(Window::FlatTop);
+        flat.set_points(1000);
+        let spectrum = flat.run(&time, &vector).unwrap();
+        let max = spectrum
+            .magnitude
+            .iter()
+            .skip(2)
+            .cloned()
+            .fold(0.0, f64::max);
+        assert!((max - 2.0).abs() < 0.01);
+    }
+}
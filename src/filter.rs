@@ -0,0 +1,544 @@
+use std::f64::consts::PI;
+
+use num_complex::Complex64;
+
+use crate::{error::Error, node::IntoNode, opamp::OpAmp, value::Value, Circuit};
+
+/// The approximation of the filter response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Response {
+    /// Maximally flat pass band.
+    Butterworth,
+    /// Equiripple pass band with the ripple in dB, the cutoff is the ripple bandwidth.
+    Chebyshev(f64),
+    /// Maximally flat group delay, the cutoff is the -3 dB frequency.
+    Bessel,
+}
+
+/// The pass band of the filter, the frequencies are in Hz.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Band {
+    LowPass(f64),
+    HighPass(f64),
+    /// The lower and upper cutoff frequency.
+    BandPass(f64, f64),
+}
+
+/// The circuit of the filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topology {
+    /// Unity gain Sallen-Key stages.
+    SallenKey,
+    /// Inverting multiple feedback stages with unity gain.
+    MultipleFeedback,
+    /// Doubly terminated LC ladder.
+    Ladder,
+}
+
+/// The name of the ideal op-amp subcircuit, the nodes are `inp inn out`.
+const IDEAL_OPAMP: &str = "ideal_opamp";
+
+/// Synthesize a filter into a circuit.
+///
+/// The active topologies are built from second order stages and a
+/// buffered RC stage for odd orders. The resistors of the low pass stages
+/// and the capacitors of the high pass stages are set by the impedance.
+/// The op-amp is a subcircuit with the nodes `inp inn out`, when none is
+/// set an ideal op-amp is added to the circuit.
+///
+/// The ladder is terminated with the impedance at the input and output,
+/// the pass band gain is -6 dB. The Bessel response is not available as
+/// ladder.
+///
+/// ```ignore
+/// let filter = Filter::new(Response::Butterworth, Band::LowPass(1e3), Topology::SallenKey, 4, 10e3);
+/// filter.build(&mut circuit, "F1", "in", "out")?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub response: Response,
+    pub band: Band,
+    pub topology: Topology,
+    pub order: usize,
+    pub impedance: f64,
+    opamp: Option<String>,
+}
+
+/// Solve the roots of the monic polynomial with the Durand-Kerner method,
+/// the coefficients start with the constant term.
+fn roots(coefficients: &[f64]) -> Vec<Complex64> {
+    let n = coefficients.len() - 1;
+    let polynomial = |s: Complex64| {
+        coefficients
+            .iter()
+            .rev()
+            .fold(Complex64::new(0.0, 0.0), |acc, c| acc * s + c)
+    };
+    let seed = Complex64::new(0.4, 0.9);
+    let mut roots: Vec<Complex64> = (0..n).map(|i| seed.powu(i as u32 + 1)).collect();
+    for _ in 0..1000 {
+        let mut change: f64 = 0.0;
+        for i in 0..n {
+            let denominator = (0..n)
+                .filter(|j| *j != i)
+                .fold(Complex64::new(1.0, 0.0), |acc, j| {
+                    acc * (roots[i] - roots[j])
+                });
+            let delta = polynomial(roots[i]) / denominator;
+            roots[i] -= delta;
+            change = change.max(delta.norm());
+        }
+        if change < 1e-14 {
+            break;
+        }
+    }
+    roots
+}
+
+impl Filter {
+    pub fn new(
+        response: Response,
+        band: Band,
+        topology: Topology,
+        order: usize,
+        impedance: f64,
+    ) -> Self {
+        Self {
+            response,
+            band,
+            topology,
+            order,
+            impedance,
+            opamp: None,
+        }
+    }
+    /// Use the op-amp subcircuit with the nodes `inp inn out` for the active stages.
+    pub fn set_opamp(&mut self, name: &str) {
+        self.opamp = Some(name.to_string());
+    }
+    /// The poles of the low pass prototype with the cutoff at 1 rad/s.
+    pub fn poles(&self) -> Vec<Complex64> {
+        let n = self.order;
+        match self.response {
+            Response::Butterworth => (1..=n)
+                .map(|k| {
+                    let theta = PI * (2 * k + n - 1) as f64 / (2 * n) as f64;
+                    Complex64::new(theta.cos(), theta.sin())
+                })
+                .collect(),
+            Response::Chebyshev(ripple) => {
+                let epsilon = (10_f64.powf(ripple / 10.0) - 1.0).sqrt();
+                let a = (1.0 / epsilon).asinh() / n as f64;
+                (1..=n)
+                    .map(|k| {
+                        let theta = PI * (2 * k - 1) as f64 / (2 * n) as f64;
+                        Complex64::new(-a.sinh() * theta.sin(), a.cosh() * theta.cos())
+                    })
+                    .collect()
+            }
+            Response::Bessel => {
+                //reverse bessel polynomial
+                let factorial = |k: usize| (1..=k).map(|i| i as f64).product::<f64>();
+                let coefficients: Vec<f64> = (0..=n)
+                    .map(|k| {
+                        factorial(2 * n - k)
+                            / (2_f64.powi((n - k) as i32) * factorial(k) * factorial(n - k))
+                    })
+                    .collect();
+                let poles = roots(&coefficients);
+                //scale the poles to get -3 dB at 1 rad/s
+                let gain = |w: f64| {
+                    poles
+                        .iter()
+                        .map(|p| p.norm() / (Complex64::new(0.0, w) - p).norm())
+                        .product::<f64>()
+                };
+                let (mut low, mut high) = (0.0, 10.0 * n as f64);
+                for _ in 0..100 {
+                    let w = (low + high) / 2.0;
+                    if gain(w) > 0.5_f64.sqrt() {
+                        low = w;
+                    } else {
+                        high = w;
+                    }
+                }
+                poles.iter().map(|p| p / low).collect()
+            }
+        }
+    }
+    /// The normalized stages as natural frequency and quality factor,
+    /// first order stages have no quality factor.
+    pub(crate) fn stages(&self) -> Vec<(f64, Option<f64>)> {
+        let mut stages: Vec<(f64, Option<f64>)> = self
+            .poles()
+            .iter()
+            .filter(|p| p.im > -1e-9)
+            .map(|p| {
+                if p.im.abs() < 1e-9 {
+                    (-p.re, None)
+                } else {
+                    (p.norm(), Some(p.norm() / (-2.0 * p.re)))
+                }
+            })
+            .collect();
+        //the stages with the lowest quality factor first
+        stages.sort_by(|a, b| a.1.unwrap_or(0.0).total_cmp(&b.1.unwrap_or(0.0)));
+        stages
+    }
+    /// The element values of the low pass ladder prototype with the load element g(n+1).
+    ///
+    /// The load element is a resistance after a shunt capacitor and a
+    /// conductance after a series inductor.
+    fn prototype(&self) -> Result<(Vec<f64>, f64), Error> {
+        let n = self.order;
+        match self.response {
+            Response::Butterworth => Ok((
+                (1..=n)
+                    .map(|k| 2.0 * (PI * (2 * k - 1) as f64 / (2 * n) as f64).sin())
+                    .collect(),
+                1.0,
+            )),
+            Response::Chebyshev(ripple) => {
+                let beta = (1.0 / (ripple / 17.37).tanh()).ln();
+                let gamma = (beta / (2 * n) as f64).sinh();
+                let a = |k: usize| (PI * (2 * k - 1) as f64 / (2 * n) as f64).sin();
+                let b = |k: usize| gamma.powi(2) + (PI * k as f64 / n as f64).sin().powi(2);
+                let mut g = vec![2.0 * a(1) / gamma];
+                for k in 2..=n {
+                    g.push(4.0 * a(k - 1) * a(k) / (b(k - 1) * g[k - 2]));
+                }
+                let load = if n % 2 == 1 {
+                    1.0
+                } else {
+                    (1.0 / (beta / 4.0).tanh()).powi(2)
+                };
+                Ok((g, load))
+            }
+            Response::Bessel => Err(Error::Filter(String::from(
+                "the Bessel response is not available as ladder",
+            ))),
+        }
+    }
+    /// Add the filter between the input and output node.
+    ///
+    /// The name is used for the references and the internal nodes.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        name: &str,
+        input: impl IntoNode,
+        output: impl IntoNode,
+    ) -> Result<(), Error> {
+        if self.order == 0 {
+            return Err(Error::Filter(String::from(
+                "the order must be at least one",
+            )));
+        }
+        let input = String::from(input.into_node()?);
+        let output = String::from(output.into_node()?);
+        let mut builder = Builder {
+            circuit,
+            name: name.to_string(),
+            opamp: self
+                .opamp
+                .clone()
+                .unwrap_or_else(|| String::from(IDEAL_OPAMP)),
+            count: 0,
+        };
+        if self.topology == Topology::Ladder {
+            return self.ladder(&mut builder, input, output);
+        }
+        if self.opamp.is_none() && !builder.circuit.subcircuits.contains_key(IDEAL_OPAMP) {
+            OpAmp::ideal().add(builder.circuit, IDEAL_OPAMP)?;
+        }
+        let mut stages: Vec<(bool, f64, Option<f64>)> = Vec::new();
+        let stages_at = |highpass: bool, frequency: f64| {
+            let w = 2.0 * PI * frequency;
+            self.stages().into_iter().map(move |(w0, q)| {
+                if highpass {
+                    (true, w / w0, q)
+                } else {
+                    (false, w * w0, q)
+                }
+            })
+        };
+        match self.band {
+            Band::LowPass(f) => stages.extend(stages_at(false, f)),
+            Band::HighPass(f) => stages.extend(stages_at(true, f)),
+            Band::BandPass(low, high) => {
+                stages.extend(stages_at(true, low));
+                stages.extend(stages_at(false, high));
+            }
+        }
+        let mut node = input;
+        let count = stages.len();
+        for (i, (highpass, w0, q)) in stages.into_iter().enumerate() {
+            let next = if i + 1 == count {
+                output.clone()
+            } else {
+                builder.node()
+            };
+            builder.stage(self.topology, highpass, w0, q, self.impedance, &node, &next)?;
+            node = next;
+        }
+        Ok(())
+    }
+    fn ladder(&self, builder: &mut Builder, input: String, output: String) -> Result<(), Error> {
+        let (g, load) = self.prototype()?;
+        let z = self.impedance;
+        let series = g.len() / 2;
+        let mut nodes: Vec<String> = (0..series).map(|_| builder.node()).collect();
+        nodes.push(output);
+        builder.element('R', &input, &nodes[0], z)?;
+        for (k, g) in g.iter().enumerate() {
+            let node = &nodes[k / 2];
+            //odd elements are shunt capacitors, even elements series inductors of the prototype
+            if k % 2 == 0 {
+                match self.band {
+                    Band::LowPass(f) => builder.element('C', node, "0", g / (2.0 * PI * f * z))?,
+                    Band::HighPass(f) => builder.element('L', node, "0", z / (2.0 * PI * f * g))?,
+                    Band::BandPass(low, high) => {
+                        let (w0, bw) = bandpass(low, high);
+                        builder.element('C', node, "0", g / (bw * z))?;
+                        builder.element('L', node, "0", bw * z / (w0 * w0 * g))?;
+                    }
+                }
+            } else {
+                let next = &nodes[k / 2 + 1];
+                match self.band {
+                    Band::LowPass(f) => builder.element('L', node, next, g * z / (2.0 * PI * f))?,
+                    Band::HighPass(f) => {
+                        builder.element('C', node, next, 1.0 / (2.0 * PI * f * g * z))?
+                    }
+                    Band::BandPass(low, high) => {
+                        let (w0, bw) = bandpass(low, high);
+                        let middle = builder.node();
+                        builder.element('L', node, &middle, g * z / bw)?;
+                        builder.element('C', &middle, next, bw / (w0 * w0 * g * z))?;
+                    }
+                }
+            }
+        }
+        let last = nodes.last().unwrap().clone();
+        //the last element of an even order is a series inductor, the load is a conductance
+        let load = if g.len() % 2 == 0 { z / load } else { z * load };
+        builder.element('R', &last, "0", load)
+    }
+}
+
+/// The center frequency and the bandwidth in rad/s.
+fn bandpass(low: f64, high: f64) -> (f64, f64) {
+    let (low, high) = (2.0 * PI * low, 2.0 * PI * high);
+    ((low * high).sqrt(), high - low)
+}
+
+/// Add the elements with unique references and internal nodes.
+pub(crate) struct Builder<'a> {
+    pub(crate) circuit: &'a mut Circuit,
+    pub(crate) name: String,
+    pub(crate) opamp: String,
+    pub(crate) count: usize,
+}
+
+impl Builder<'_> {
+    pub(crate) fn next(&mut self) -> usize {
+        self.count += 1;
+        self.count
+    }
+    pub(crate) fn node(&mut self) -> String {
+        let index = self.next();
+        format!("{}_{}", self.name, index).to_lowercase()
+    }
+    pub(crate) fn element(&mut self, letter: char, n0: &str, n1: &str, value: f64) -> Result<(), Error> {
+        let index = self.next();
+        let reference = format!("{}{}_{}", letter, self.name, index);
+        let value = Value::new(value).to_string();
+        match letter {
+            'R' => self.circuit.resistor(reference, n0, n1, value),
+            'C' => self.circuit.capacitor(reference, n0, n1, value),
+            _ => self.circuit.inductor(reference, n0, n1, value),
+        }
+    }
+    fn opamp(&mut self, inp: &str, inn: &str, out: &str) -> Result<(), Error> {
+        let index = self.next();
+        let reference = format!("X{}_{}", self.name, index);
+        let opamp = self.opamp.clone();
+        self.circuit.circuit(reference, vec![inp, inn, out], opamp)
+    }
+    #[allow(clippy::too_many_arguments)]
+    fn stage(
+        &mut self,
+        topology: Topology,
+        highpass: bool,
+        w0: f64,
+        q: Option<f64>,
+        z: f64,
+        input: &str,
+        output: &str,
+    ) -> Result<(), Error> {
+        let a = self.node();
+        let Some(q) = q else {
+            //buffered first order stage
+            if highpass {
+                self.element('C', input, &a, 1.0 / (w0 * z))?;
+                self.element('R', &a, "0", z)?;
+            } else {
+                self.element('R', input, &a, z)?;
+                self.element('C', &a, "0", 1.0 / (w0 * z))?;
+            }
+            return self.opamp(&a, output, output);
+        };
+        let b = self.node();
+        match (topology, highpass) {
+            (Topology::MultipleFeedback, false) => {
+                self.element('R', input, &a, z)?;
+                self.element('R', &a, output, z)?;
+                self.element('R', &a, &b, z)?;
+                self.element('C', &a, "0", 3.0 * q / (w0 * z))?;
+                self.element('C', &b, output, 1.0 / (3.0 * q * w0 * z))?;
+                self.opamp("0", &b, output)
+            }
+            (Topology::MultipleFeedback, true) => {
+                let c = 1.0 / (w0 * z);
+                self.element('C', input, &a, c)?;
+                self.element('C', &a, output, c)?;
+                self.element('C', &a, &b, c)?;
+                self.element('R', &a, "0", 1.0 / (3.0 * q * w0 * c))?;
+                self.element('R', &b, output, 3.0 * q / (w0 * c))?;
+                self.opamp("0", &b, output)
+            }
+            (_, false) => {
+                self.element('R', input, &a, z)?;
+                self.element('R', &a, &b, z)?;
+                self.element('C', &a, output, 2.0 * q / (w0 * z))?;
+                self.element('C', &b, "0", 1.0 / (2.0 * q * w0 * z))?;
+                self.opamp(&b, output, output)
+            }
+            (_, true) => {
+                let c = 1.0 / (w0 * z);
+                self.element('C', input, &a, c)?;
+                self.element('C', &a, &b, c)?;
+                self.element('R', &a, output, 1.0 / (2.0 * q * w0 * c))?;
+                self.element('R', &b, "0", 2.0 * q / (w0 * c))?;
+                self.opamp(&b, output, output)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Band, Filter, Response, Topology};
+    use crate::Circuit;
+
+    #[test]
+    fn prototypes() {
+        let filter =
+            |response| Filter::new(response, Band::LowPass(1e3), Topology::Ladder, 3, 50.0);
+        let stages = Filter::new(
+            Response::Butterworth,
+            Band::LowPass(1e3),
+            Topology::SallenKey,
+            2,
+            1e3,
+        )
+        .stages();
+        assert_eq!(1, stages.len());
+        assert!((stages[0].0 - 1.0).abs() < 1e-9);
+        assert!((stages[0].1.unwrap() - 0.5_f64.sqrt()).abs() < 1e-9);
+        let stages = Filter::new(
+            Response::Bessel,
+            Band::LowPass(1e3),
+            Topology::SallenKey,
+            2,
+            1e3,
+        )
+        .stages();
+        assert!((stages[0].0 - 1.272).abs() < 1e-3);
+        assert!((stages[0].1.unwrap() - 0.5773).abs() < 1e-3);
+        let stages = filter(Response::Bessel).stages();
+        assert_eq!(2, stages.len());
+        assert!(stages[0].1.is_none());
+        let (g, load) = filter(Response::Chebyshev(1.0)).prototype().unwrap();
+        assert!((g[0] - 2.0236).abs() < 1e-3);
+        assert!((g[1] - 0.9941).abs() < 1e-3);
+        assert_eq!(1.0, load);
+        assert!(filter(Response::Bessel).prototype().is_err());
+    }
+    #[test]
+    fn build() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        Filter::new(
+            Response::Butterworth,
+            Band::LowPass(1e3),
+            Topology::SallenKey,
+            3,
+            10e3,
+        )
+        .build(&mut circuit, "F1", "in", "out")
+        .unwrap();
+        let lines = circuit.to_str(true).unwrap();
+        assert_eq!(
+            vec![
+                ".subckt ideal_opamp inp inn out",
+                "E1 out 0 inp inn 1e6",
+                ".ends",
+                "RF1_3 in f1_2 10k",
+                "CF1_4 f1_2 0 15.915494309189534n",
+                "XF1_5 f1_2 f1_1 f1_1 ideal_opamp",
+                "RF1_8 f1_1 f1_6 10k",
+                "RF1_9 f1_6 f1_7 10k",
+                "CF1_10 f1_6 out 31.83098861837909n",
+                "CF1_11 f1_7 0 7.957747154594765n",
+                "XF1_12 f1_7 out out ideal_opamp",
+                ".end",
+            ],
+            lines
+        );
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        Filter::new(
+            Response::Butterworth,
+            Band::LowPass(1e3),
+            Topology::Ladder,
+            3,
+            50.0,
+        )
+        .build(&mut circuit, "F1", "in", "out")
+        .unwrap();
+        assert_eq!(
+            vec![
+                "RF1_2 in f1_1 50",
+                "CF1_3 f1_1 0 3.1830988618379067u",
+                "LF1_4 f1_1 out 15.915494309189534m",
+                "CF1_5 out 0 3.1830988618379067u",
+                "RF1_6 out 0 50",
+                ".end",
+            ],
+            circuit.to_str(true).unwrap()
+        );
+    }
+    #[test]
+    fn even_ladder() {
+        let filter = Filter::new(
+            Response::Chebyshev(0.5),
+            Band::LowPass(1e3),
+            Topology::Ladder,
+            2,
+            50.0,
+        );
+        let (_, load) = filter.prototype().unwrap();
+        assert!((load - 1.9841).abs() < 1e-3);
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        filter.build(&mut circuit, "F1", "in", "out").unwrap();
+        assert_eq!(
+            vec![
+                "RF1_2 in f1_1 50",
+                "CF1_3 f1_1 0 4.4657111088784235u",
+                "LF1_4 f1_1 out 5.626796902536297m",
+                "RF1_5 out 0 25.200004054671084",
+                ".end",
+            ],
+            circuit.to_str(true).unwrap()
+        );
+    }
+}
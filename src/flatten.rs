@@ -0,0 +1,334 @@
+use std::{collections::HashMap, fs};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{
+    circuit::{split_params, CircuitItem},
+    error::Error,
+    hierarchy::HierarchicalName,
+    parser::{logical_lines, parse_lines},
+    Circuit,
+};
+
+lazy_static! {
+    static ref RE_EXPRESSION: Regex = Regex::new(r"\{[^}]*\}").unwrap();
+}
+
+type Subcircuits = HashMap<String, (Vec<String>, Circuit)>;
+
+/// The pattern of the parameter name and the value in parentheses.
+fn param_pattern(name: &str, value: &str) -> (Regex, String) {
+    let re = Regex::new(&format!(r"\b{}\b", regex::escape(name))).unwrap();
+    let value = value.trim_matches(|c| c == '{' || c == '}');
+    (re, format!("({})", value))
+}
+
+/// Replace the parameters in the `{..}` expressions of the value.
+fn substitute(value: &str, params: &[(Regex, String)]) -> String {
+    if params.is_empty() {
+        return value.to_string();
+    }
+    RE_EXPRESSION
+        .replace_all(value, |caps: &regex::Captures| {
+            let mut expression = caps[0].to_string();
+            for (re, value) in params {
+                expression = re
+                    .replace_all(&expression, regex::NoExpand(value))
+                    .to_string();
+            }
+            expression
+        })
+        .to_string()
+}
+
+/// The model of the element, the first token of the value.
+fn model_mut(item: &mut CircuitItem) -> Option<&mut String> {
+    match item {
+        CircuitItem::D(_, _, _, m)
+        | CircuitItem::Q(_, _, _, _, m)
+        | CircuitItem::S(_, _, _, _, _, m)
+        | CircuitItem::A(_, _, m)
+        | CircuitItem::N(_, _, m) => Some(m),
+        _ => None,
+    }
+}
+
+/// The values of the element which can contain parameters.
+fn values_mut(item: &mut CircuitItem) -> Vec<&mut String> {
+    match item {
+        CircuitItem::R(_, _, _, v, _)
+        | CircuitItem::C(_, _, _, v, _)
+        | CircuitItem::L(_, _, _, v, _)
+        | CircuitItem::D(_, _, _, v)
+        | CircuitItem::Q(_, _, _, _, v)
+        | CircuitItem::V(_, _, _, v)
+        | CircuitItem::I(_, _, _, v)
+        | CircuitItem::B(_, _, _, v)
+        | CircuitItem::E(_, _, _, _, _, v)
+        | CircuitItem::K(_, _, _, v) => vec![v],
+        CircuitItem::X(_, _, _, params) => params.iter_mut().map(|(_, v)| v).collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Circuit {
+    /// Expand the subcircuit instances into the elements of the subcircuits.
+    ///
+    /// The elements and the internal nets are named with the
+    /// `HierarchicalName`, e.g. the resistor `R3` in `X1` is `R.X1.R3` and
+    /// the net `mid` is `x1.mid`. The models of the subcircuits are named
+    /// like the nets, e.g. `x1.dmod`. The parameters of the instances are
+    /// replaced in the values. With `libraries` the subcircuits from the
+    /// libraries are expanded as well. Nets in behavioral expressions are
+    /// not renamed.
+    pub fn flatten(&mut self, libraries: bool) -> Result<(), Error> {
+        let mut items = Vec::new();
+        let scope = self.subcircuits.clone();
+        for item in &self.items {
+            self.flatten_item(
+                item.clone(),
+                &scope,
+                &[],
+                &HashMap::new(),
+                &[],
+                &HashMap::new(),
+                libraries,
+                &mut items,
+            )?;
+        }
+        let mut flat = self.clone();
+        flat.items.clear();
+        flat.subcircuits.clear();
+        for item in items {
+            match &item {
+                CircuitItem::Model(..) | CircuitItem::Include(..) => {
+                    if !flat.items.contains(&item) {
+                        flat.items.push(item);
+                    }
+                }
+                _ => flat.insert(item)?,
+            }
+        }
+        *self = flat;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn flatten_item(
+        &self,
+        mut item: CircuitItem,
+        scope: &Subcircuits,
+        path: &[String],
+        ports: &HashMap<String, String>,
+        params: &[(Regex, String)],
+        models: &HashMap<String, String>,
+        libraries: bool,
+        result: &mut Vec<CircuitItem>,
+    ) -> Result<(), Error> {
+        let name = item.name();
+        if !path.is_empty() {
+            for value in values_mut(&mut item) {
+                *value = substitute(value, params);
+            }
+            for node in item.nodes_mut() {
+                if let Some(port) = ports.get(node.as_str()) {
+                    *node = port.clone();
+                } else if !self.is_ground(node) {
+                    *node = HierarchicalName::new(path.to_vec(), node).net();
+                }
+            }
+            if let CircuitItem::Model(model, _, _) = &mut item {
+                if let Some(scoped) = models.get(&model.to_lowercase()) {
+                    *model = scoped.clone();
+                }
+            }
+            if let Some(value) = model_mut(&mut item) {
+                let model = value
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                if let Some(scoped) = models.get(&model.to_lowercase()) {
+                    *value = value.trim_start().replacen(&model, scoped, 1);
+                }
+            }
+            if let CircuitItem::K(_, l1, l2, _) = &mut item {
+                for inductor in [l1, l2] {
+                    let upper = inductor.to_uppercase();
+                    let name = match upper.starts_with('L') {
+                        true => upper,
+                        false => format!("L{}", upper),
+                    };
+                    *inductor = HierarchicalName::new(path.to_vec(), &name).element('L');
+                }
+            }
+            if let (Some(letter), Some(name)) = (item.letter(), &name) {
+                if let Some(reference) = item.reference_mut() {
+                    *reference = HierarchicalName::new(path.to_vec(), name).element(letter);
+                }
+            }
+        }
+        let CircuitItem::X(reference, nodes, subcircuit, instance) = &item else {
+            result.push(item);
+            return Ok(());
+        };
+        let definition = match scope
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(subcircuit))
+        {
+            Some((_, definition)) => Some(definition.clone()),
+            None if libraries => self.library_subcircuit(subcircuit)?,
+            None => None,
+        };
+        let Some((names, body)) = definition else {
+            result.push(item);
+            return Ok(());
+        };
+        if names.len() != nodes.len() {
+            return Err(Error::SubcircuitPorts(
+                reference.to_string(),
+                nodes.len(),
+                subcircuit.to_string(),
+                names.join(" "),
+            ));
+        }
+        let ports: HashMap<String, String> = names.into_iter().zip(nodes.clone()).collect();
+        let mut params = body.params.clone();
+        for (key, value) in instance {
+            match params.iter_mut().find(|(p, _)| p.eq_ignore_ascii_case(key)) {
+                Some(param) => param.1 = value.clone(),
+                None => params.push((key.clone(), value.clone())),
+            }
+        }
+        let params: Vec<(Regex, String)> = params
+            .iter()
+            .map(|(name, value)| param_pattern(name, value))
+            .collect();
+        let mut path = path.to_vec();
+        path.push(name.unwrap_or_default());
+        let mut models = models.clone();
+        for item in &body.items {
+            if let CircuitItem::Model(model, _, _) = item {
+                let scoped = HierarchicalName::new(path.clone(), model).net();
+                models.insert(model.to_lowercase(), scoped);
+            }
+        }
+        let mut scope = scope.clone();
+        scope.extend(body.subcircuits.clone());
+        for item in body.items {
+            self.flatten_item(
+                item, &scope, &path, &ports, &params, &models, libraries, result,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Load the subcircuit from the libraries of the pathlist.
+    fn library_subcircuit(&self, name: &str) -> Result<Option<(Vec<String>, Circuit)>, Error> {
+        let Ok(includes) = self.get_includes(name.to_string()) else {
+            return Ok(None);
+        };
+        let content = fs::read_to_string(&includes[name].0)?;
+        let mut lines = logical_lines(&content).into_iter();
+        while let Some(line) = lines.next() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.len() > 1
+                && tokens[0].eq_ignore_ascii_case(".subckt")
+                && tokens[1].eq_ignore_ascii_case(name)
+            {
+                let (ports, params) = split_params(&tokens[2..]);
+                let mut body = Circuit::new(name.to_string(), Vec::new());
+                body.set_pathlist(self.pathlist.clone());
+                for (key, value) in params {
+                    body.add_parameter(&key, &value);
+                }
+                parse_lines(&mut body, &mut lines)?;
+                let ports = ports.iter().map(|p| p.to_string()).collect();
+                return Ok(Some((ports, body)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Circuit;
+
+    #[test]
+    fn flatten() {
+        let mut circuit = Circuit::parse(
+            "* flatten\n\
+             .subckt rc in out params: r=10k\n\
+             R1 in mid {r}\n\
+             C1 mid 0 1n\n\
+             R2 mid out {r*2}\n\
+             .ends\n\
+             .subckt stage in out\n\
+             X1 in mid rc r=1k\n\
+             X2 mid out rc\n\
+             .ends\n\
+             V1 in 0 AC 1\n\
+             XS in out stage\n",
+            Vec::new(),
+        )
+        .unwrap();
+        circuit.flatten(false).unwrap();
+        assert_eq!(
+            vec![
+                "V1 in 0 AC 1",
+                "R.XS.X1.R1 in xs.x1.mid {(1k)}",
+                "C.XS.X1.C1 xs.x1.mid 0 1n",
+                "R.XS.X1.R2 xs.x1.mid xs.mid {(1k)*2}",
+                "R.XS.X2.R1 xs.mid xs.x2.mid {(10k)}",
+                "C.XS.X2.C1 xs.x2.mid 0 1n",
+                "R.XS.X2.R2 xs.x2.mid out {(10k)*2}",
+            ],
+            circuit.to_str(false).unwrap()
+        );
+        let mut circuit = Circuit::new(
+            String::from("buffer"),
+            vec![String::from("files/spice/nested")],
+        );
+        circuit
+            .circuit(
+                String::from("X1"),
+                vec!["a", "b", "vcc"],
+                String::from("BUFFER"),
+            )
+            .unwrap();
+        circuit.flatten(true).unwrap();
+        let lines = circuit.to_str(false).unwrap();
+        assert!(lines.contains(&String::from("Q.X1.Q1 vcc a b Q2N3904")));
+        assert!(lines.contains(&String::from("R.X1.R1 b 0 1k")));
+    }
+    #[test]
+    fn local_models() {
+        let mut circuit = Circuit::parse(
+            "* models\n\
+             .subckt slow a k\n\
+             .model dmod D(IS=1e-14)\n\
+             D1 a k dmod\n\
+             .ends\n\
+             .subckt fast a k\n\
+             .model dmod D(IS=1e-12)\n\
+             D1 a k DMOD\n\
+             .ends\n\
+             X1 in out slow\n\
+             X2 in out fast\n",
+            Vec::new(),
+        )
+        .unwrap();
+        circuit.flatten(false).unwrap();
+        assert_eq!(
+            vec![
+                ".model x1.dmod D(IS=1e-14)",
+                "D.X1.D1 in out x1.dmod",
+                ".model x2.dmod D(IS=1e-12)",
+                "D.X2.D1 in out x2.dmod",
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use num_complex::Complex64;
+
+use crate::{error::Error, result::AnalysisResult};
+
+/// The metrics of a frequency response from the complex AC results.
+///
+/// The DC gain is taken at the lowest simulated frequency. For the
+/// stability margins the response must be the loop gain, the margins are
+/// taken relative to a phase of -180 degrees.
+///
+/// ```ignore
+/// let result = simulation.ac_complex("1", "100Meg", 20, "dec")?;
+/// let response = FrequencyResponse::from_result(&result, "out")?;
+/// assert!(response.phase_margin.unwrap() > 45.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyResponse {
+    /// The gain at the lowest frequency in dB.
+    pub dc_gain: f64,
+    /// The -3 dB frequency in Hz.
+    pub bandwidth: Option<f64>,
+    /// The frequency where the gain falls below 0 dB.
+    pub unity_gain: Option<f64>,
+    /// The gain below 0 dB where the phase reaches -180 degrees.
+    pub gain_margin: Option<f64>,
+    /// The phase above -180 degrees at the unity gain frequency.
+    pub phase_margin: Option<f64>,
+}
+
+/// The phase in degrees without the jumps at +/-180 degrees.
+pub(crate) fn unwrap_phase(response: &[Complex64]) -> Vec<f64> {
+    let mut phase: Vec<f64> = Vec::with_capacity(response.len());
+    let mut offset = 0.0;
+    for value in response {
+        let current = value.arg().to_degrees();
+        if let Some(last) = phase.last() {
+            let step = current + offset - last;
+            offset -= 360.0 * (step / 360.0).round();
+        }
+        phase.push(current + offset);
+    }
+    phase
+}
+
+/// The gain in dB.
+pub(crate) fn gain_db(response: &[Complex64]) -> Vec<f64> {
+    response.iter().map(|v| 20.0 * v.norm().log10()).collect()
+}
+
+/// The first falling crossing of the level, interpolated on a logarithmic
+/// frequency axis. Returns the index before the crossing and the frequency.
+fn falling(frequency: &[f64], values: &[f64], level: f64) -> Option<(usize, f64)> {
+    values
+        .windows(2)
+        .position(|v| v[0] > level && v[1] <= level)
+        .map(|i| {
+            let fraction = (values[i] - level) / (values[i] - values[i + 1]);
+            let (f0, f1) = (frequency[i].ln(), frequency[i + 1].ln());
+            (i, (f0 + fraction * (f1 - f0)).exp())
+        })
+}
+
+/// The value at the frequency between the points `i` and `i + 1`.
+fn at(frequency: &[f64], values: &[f64], i: usize, f: f64) -> f64 {
+    let (f0, f1) = (frequency[i].ln(), frequency[i + 1].ln());
+    let fraction = if f1 == f0 {
+        0.0
+    } else {
+        (f.ln() - f0) / (f1 - f0)
+    };
+    values[i] + fraction * (values[i + 1] - values[i])
+}
+
+impl FrequencyResponse {
+    pub fn new(frequency: &[f64], response: &[Complex64]) -> Result<Self, Error> {
+        if frequency.len() != response.len() {
+            return Err(Error::Signal(format!(
+                "{} frequencies for {} values",
+                frequency.len(),
+                response.len()
+            )));
+        }
+        if frequency.len() < 2 || frequency.iter().any(|f| *f <= 0.0) {
+            return Err(Error::Signal(String::from(
+                "at least two positive frequencies are required",
+            )));
+        }
+        let gain = gain_db(response);
+        let phase = unwrap_phase(response);
+        let dc_gain = gain[0];
+        let unity = falling(frequency, &gain, 0.0);
+        let crossover = falling(frequency, &phase, -180.0);
+        Ok(Self {
+            dc_gain,
+            bandwidth: falling(frequency, &gain, dc_gain - 3.0).map(|(_, f)| f),
+            unity_gain: unity.map(|(_, f)| f),
+            gain_margin: crossover.map(|(i, f)| -at(frequency, &gain, i, f)),
+            phase_margin: unity.map(|(i, f)| 180.0 + at(frequency, &phase, i, f)),
+        })
+    }
+    /// Get the response of the node from the results of `Simulation::ac_complex`.
+    pub fn from_result(result: &AnalysisResult, node: &str) -> Result<Self, Error> {
+        Self::new(&result.frequency()?, result.get(node)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex64;
+
+    use super::FrequencyResponse;
+
+    #[test]
+    fn frequency_response() {
+        //loop gain with a DC gain of 1000 and poles at 100Hz, 100kHz and 1MHz
+        let frequency: Vec<f64> = (0..=600).map(|i| 10f64.powf(i as f64 / 100.0)).collect();
+        let response: Vec<Complex64> = frequency
+            .iter()
+            .map(|f| {
+                let s = Complex64::new(0.0, *f);
+                1000.0 / ((1.0 + s / 100.0) * (1.0 + s / 1e5) * (1.0 + s / 1e6))
+            })
+            .collect();
+        let result = FrequencyResponse::new(&frequency, &response).unwrap();
+        assert!((result.dc_gain - 60.0).abs() < 1e-3);
+        assert!((result.bandwidth.unwrap() - 100.0).abs() < 0.5);
+        let unity = result.unity_gain.unwrap();
+        assert!((unity - 78.44e3).abs() < 100.0);
+        let phase_margin = result.phase_margin.unwrap();
+        assert!((phase_margin - 47.48).abs() < 0.05);
+        let gain_margin = result.gain_margin.unwrap();
+        assert!((gain_margin - 20.84).abs() < 0.05);
+    }
+}
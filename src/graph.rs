@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use petgraph::{
+    dot::{Config, Dot},
+    graph::{NodeIndex, UnGraph},
+};
+
+use crate::Circuit;
+
+impl Circuit {
+    /// Get the topology of the circuit as a graph.
+    ///
+    /// The nets are the nodes and the elements are the edges of the graph,
+    /// the weights are the net names and references. The ground aliases are
+    /// merged to node `0`. Elements with more than two nodes are added as
+    /// edges from the first node to all other nodes.
+    pub fn to_graph(&self) -> UnGraph<String, String> {
+        let mut graph: UnGraph<String, String> = UnGraph::default();
+        let mut nets: HashMap<String, NodeIndex> = HashMap::new();
+        for item in &self.items {
+            let reference = match item.reference() {
+                Some(reference) => reference,
+                None => continue,
+            };
+            let nodes: Vec<NodeIndex> = item
+                .nodes()
+                .into_iter()
+                .map(|node| {
+                    let node = if self.is_ground(node) {
+                        String::from("0")
+                    } else {
+                        node.clone()
+                    };
+                    *nets
+                        .entry(node.clone())
+                        .or_insert_with(|| graph.add_node(node))
+                })
+                .collect();
+            for node in nodes.iter().skip(1) {
+                graph.add_edge(nodes[0], *node, reference.clone());
+            }
+        }
+        graph
+    }
+
+    /// Get the topology of the circuit in the graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let graph = self.to_graph();
+        format!("{}", Dot::with_config(&graph, &[Config::GraphContentOnly]))
+            .lines()
+            .fold(String::from("graph {\n"), |dot, line| dot + line + "\n")
+            + "}\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Circuit;
+
+    #[test]
+    fn to_graph() {
+        let circuit = Circuit::parse(
+            "divider\nV1 in 0 DC 5\nR1 in out 10k\nR2 out 0 10k\nR3 out 0 10k\n",
+            Vec::new(),
+        )
+        .unwrap();
+        let graph = circuit.to_graph();
+        assert_eq!(3, graph.node_count());
+        assert_eq!(4, graph.edge_count());
+        let dot = circuit.to_dot();
+        assert!(dot.starts_with("graph {\n"));
+        assert!(dot.contains("label = \"R1\""));
+    }
+}
@@ -0,0 +1,155 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{circuit::CircuitItem, Circuit};
+
+/// A net or an element in the hierarchy of the subcircuit instances.
+///
+/// The name is written with the instances separated by dots, e.g. `X1.R3`
+/// for the resistor `R3` in the instance `X1` or `X1.X2.mid` for a net in a
+/// nested instance. The instances get the `X` when the reference does not
+/// start with it. The flattened circuit and the simulation use the names
+/// of ngspice, the net is `x1.mid` and the element is `R.X1.R3`.
+///
+/// ```ignore
+/// let mid = HierarchicalName::parse("X1.mid");
+/// let vector = mid.find(&results).unwrap();
+/// simulation.save_current("X1.R3")?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HierarchicalName {
+    /// The references of the instances from the top.
+    pub path: Vec<String>,
+    /// The name of the net or the reference of the element.
+    pub name: String,
+}
+
+impl HierarchicalName {
+    pub fn new(path: Vec<String>, name: &str) -> Self {
+        let path = path
+            .into_iter()
+            .map(|instance| match instance.to_uppercase().starts_with('X') {
+                true => instance,
+                false => format!("X{}", instance),
+            })
+            .collect();
+        Self {
+            path,
+            name: name.to_string(),
+        }
+    }
+    /// Split the name at the dots.
+    pub fn parse(name: &str) -> Self {
+        let mut path: Vec<String> = name.split('.').map(|p| p.to_string()).collect();
+        let name = path.pop().unwrap_or_default();
+        Self::new(path, &name)
+    }
+    /// The name of the net in the netlist and the results, e.g. `x1.mid`.
+    pub fn net(&self) -> String {
+        if self.path.is_empty() {
+            return self.name.clone();
+        }
+        format!("{}.{}", self.path.join(".").to_lowercase(), self.name)
+    }
+    /// The name of the element with the type letter, e.g. `R.X1.R3`.
+    pub fn element(&self, letter: char) -> String {
+        let mut name = self.name.to_uppercase();
+        if !name.starts_with(letter) {
+            name = format!("{}{}", letter, name);
+        }
+        if self.path.is_empty() {
+            return name;
+        }
+        format!("{}.{}.{}", letter, self.path.join(".").to_uppercase(), name)
+    }
+    /// Find the vector of the net in the results, the case is ignored.
+    pub fn find<'a>(&self, vectors: &'a HashMap<String, Vec<f64>>) -> Option<&'a Vec<f64>> {
+        let net = self.net();
+        let voltage = format!("v({})", net);
+        vectors
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(&net) || key.eq_ignore_ascii_case(&voltage))
+            .map(|(_, vector)| vector)
+    }
+}
+
+impl fmt::Display for HierarchicalName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for instance in &self.path {
+            write!(f, "{}.", instance)?;
+        }
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Circuit {
+    /// The element with the hierarchical reference, e.g. `X1.R3`.
+    pub(crate) fn find_item(&self, name: &HierarchicalName) -> Option<&CircuitItem> {
+        let mut circuit = self;
+        let mut scope = vec![&self.subcircuits];
+        for instance in &name.path {
+            let instance = instance.to_uppercase();
+            let subcircuit = circuit.items.iter().find_map(|item| match item {
+                CircuitItem::X(_, _, subcircuit, _) if item.name().as_ref() == Some(&instance) => {
+                    Some(subcircuit)
+                }
+                _ => None,
+            })?;
+            circuit = scope.iter().rev().find_map(|subcircuits| {
+                subcircuits
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(subcircuit))
+                    .map(|(_, (_, body))| body)
+            })?;
+            scope.push(&circuit.subcircuits);
+        }
+        circuit.items.iter().find(|item| {
+            item.reference().map(|r| r.as_str()) == Some(&name.name)
+                || item.name().as_deref() == Some(&name.name.to_uppercase())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::HierarchicalName;
+    use crate::{Circuit, Simulation};
+
+    #[test]
+    fn hierarchical_names() {
+        let name = HierarchicalName::parse("U1.X2.mid");
+        assert_eq!("XU1.X2.mid", name.to_string());
+        assert_eq!("xu1.x2.mid", name.net());
+        assert_eq!("R.XU1.X2.RMID", name.element('R'));
+        let vectors = HashMap::from([(String::from("V(XU1.X2.MID)"), vec![1.0])]);
+        assert_eq!(Some(&vec![1.0]), name.find(&vectors));
+        let circuit = Circuit::parse(
+            "* hierarchy\n\
+             .subckt rc in out\n\
+             R3 in out 1k\n\
+             C1 out 0 1n\n\
+             .ends\n\
+             X1 a b rc\n",
+            Vec::new(),
+        )
+        .unwrap();
+        let mut simulation = Simulation::new(circuit.clone());
+        assert_eq!(
+            vec!["@r.x1.r3[i]"],
+            simulation.save_current("X1.R3").unwrap()
+        );
+        assert!(simulation.save_current("X1.R4").is_err());
+        let mut flat = circuit.clone();
+        flat.flatten(false).unwrap();
+        assert_eq!(
+            vec!["R.X1.R3 a b 1k", "C.X1.C1 b 0 1n"],
+            flat.to_str(false).unwrap()
+        );
+        let mut simulation = Simulation::new(flat);
+        assert_eq!(
+            vec!["@r.x1.r3[i]"],
+            simulation.save_current("R.X1.R3").unwrap()
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use num_complex::Complex64;
+
+use crate::{error::Error, result::AnalysisResult, Circuit, Simulation};
+
+/// The port of the impedance measurement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Port {
+    /// The input node driven by the voltage source, the impedance
+    /// is the node voltage over the source current.
+    Input { source: String, node: String },
+    /// The output node, driven with an AC current source that does
+    /// not change the bias point.
+    Output { node: String },
+}
+
+/// The impedance of a port against the frequency.
+///
+/// ```ignore
+/// let port = Port::Output { node: String::from("out") };
+/// let zout = Impedance::new(&circuit, &port, "10", "100k", 20, "dec")?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Impedance {
+    /// The frequency in Hz.
+    pub frequency: Vec<f64>,
+    /// The magnitude in ohm.
+    pub magnitude: Vec<f64>,
+    /// The phase in degrees.
+    pub phase: Vec<f64>,
+}
+
+/// The name of the branch current vector of a voltage source.
+pub(crate) fn branch(source: &str) -> String {
+    let source = source.to_lowercase();
+    if source.starts_with('v') {
+        format!("{}#branch", source)
+    } else {
+        format!("v{}#branch", source)
+    }
+}
+
+impl Impedance {
+    /// Run the AC analysis with the test stimulus at the port, all other
+    /// AC sources are removed.
+    pub fn new(
+        circuit: &Circuit,
+        port: &Port,
+        start: &str,
+        stop: &str,
+        points: u32,
+        variation: &str,
+    ) -> Result<Self, Error> {
+        let mut circuit = circuit.without_ac();
+        match port {
+            Port::Input { source, .. } => circuit.set_ac(source, "1")?,
+            Port::Output { node } => circuit.current(
+                String::from("IZ_TEST"),
+                "0",
+                node.as_str(),
+                String::from("DC 0 AC 1"),
+            )?,
+        }
+        let result = Simulation::new(circuit).ac_complex(start, stop, points, variation)?;
+        Self::from_result(&result, port)
+    }
+    /// Get the impedance from the results of the prepared circuit.
+    pub fn from_result(result: &AnalysisResult, port: &Port) -> Result<Self, Error> {
+        let impedance: Vec<Complex64> = match port {
+            Port::Input { source, node } => result
+                .get(node)?
+                .iter()
+                .zip(result.get(&branch(source))?)
+                //the source current flows into the positive node
+                .map(|(v, i)| -v / i)
+                .collect(),
+            Port::Output { node } => result.get(node)?.clone(),
+        };
+        Ok(Self {
+            frequency: result.frequency()?,
+            magnitude: impedance.iter().map(|z| z.norm()).collect(),
+            phase: impedance.iter().map(|z| z.arg().to_degrees()).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use num_complex::Complex64;
+
+    use super::{Impedance, Port};
+    use crate::{result::AnalysisResult, Circuit};
+
+    #[test]
+    fn impedance() {
+        //a 1k resistor in series with 1uF driven by V1 at 159.15Hz
+        let z = Complex64::new(1e3, -1e3);
+        let result = AnalysisResult::from(HashMap::from([
+            (String::from("frequency"), vec![Complex64::new(159.15, 0.0)]),
+            (String::from("in"), vec![Complex64::new(1.0, 0.0)]),
+            (String::from("v1#branch"), vec![-1.0 / z]),
+        ]));
+        let port = Port::Input {
+            source: String::from("V1"),
+            node: String::from("in"),
+        };
+        let zin = Impedance::from_result(&result, &port).unwrap();
+        assert!((zin.magnitude[0] - 1414.21).abs() < 0.01);
+        assert!((zin.phase[0] + 45.0).abs() < 1e-9);
+
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .voltage(
+                String::from("V1"),
+                "in",
+                "0",
+                String::from("DC 5 AC 1 0 SIN(0 1 1k)"),
+            )
+            .unwrap();
+        circuit
+            .voltage(String::from("V2"), "vcc", "0", String::from("DC 5"))
+            .unwrap();
+        let mut circuit = circuit.without_ac();
+        assert_eq!(
+            vec!["V1 in 0 DC 5 SIN(0 1 1k)", "V2 vcc 0 DC 5", ".end"],
+            circuit.to_str(true).unwrap()
+        );
+        circuit.set_ac("V2", "1").unwrap();
+        assert_eq!(
+            vec!["V1 in 0 DC 5 SIN(0 1 1k)", "V2 vcc 0 DC 5 AC 1", ".end"],
+            circuit.to_str(true).unwrap()
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{
+    distortion::{lobe, power},
+    error::Error,
+    fft::{Fft, Window},
+    node::IntoNode,
+    tran::Tran,
+    Circuit, Simulation,
+};
+
+/// The intermodulation products relative to the tones in dBc.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Intermodulation {
+    /// The amplitudes of the two tones at the output.
+    pub tones: (f64, f64),
+    /// The larger second order product at f2 - f1 or f1 + f2.
+    pub imd2: f64,
+    /// The larger third order product at 2f1 - f2 or 2f2 - f1.
+    pub imd3: f64,
+    /// The distance of the tones to the largest spur in dB.
+    pub sfdr: f64,
+}
+
+/// Measure the intermodulation distortion with two sine tones of equal amplitude.
+///
+/// ```ignore
+/// let two_tone = TwoTone::new(1e3, 1.1e3, 0.5);
+/// let imd = two_tone.run(&circuit, "in", "out")?;
+/// assert!(imd.imd3 < -60.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwoTone {
+    pub f1: f64,
+    pub f2: f64,
+    /// The amplitude of each tone.
+    pub amplitude: f64,
+}
+
+impl TwoTone {
+    pub fn new(f1: f64, f2: f64, amplitude: f64) -> Self {
+        Self {
+            f1: f1.min(f2),
+            f2: f1.max(f2),
+            amplitude,
+        }
+    }
+    /// Add the stimulus as two sine sources in series from the node to ground.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        name: &str,
+        node: impl IntoNode,
+    ) -> Result<(), Error> {
+        let middle = format!("{}_1", name.to_lowercase());
+        circuit.voltage(
+            format!("V{}_1", name),
+            node,
+            middle.as_str(),
+            format!("DC 0 SIN(0 {} {})", self.amplitude, self.f1),
+        )?;
+        circuit.voltage(
+            format!("V{}_2", name),
+            middle.as_str(),
+            "0",
+            format!("DC 0 SIN(0 {} {})", self.amplitude, self.f2),
+        )
+    }
+    /// Drive the input of a copy of the circuit and analyze the output.
+    ///
+    /// The transient covers 20 periods of the difference frequency and
+    /// samples fast enough to keep the third order products below Nyquist.
+    pub fn run(
+        &self,
+        circuit: &Circuit,
+        input: &str,
+        output: &str,
+    ) -> Result<Intermodulation, Error> {
+        let mut circuit = circuit.clone();
+        self.build(&mut circuit, "TT", input)?;
+        let stop = 20.0 / (self.f2 - self.f1).min(self.f1);
+        let step = 1.0 / (10.0 * (2.0 * self.f2 + self.f1));
+        let tran = Tran::new(&step.to_string(), &stop.to_string());
+        let result = Simulation::new(circuit).transient(&tran)?;
+        let time = result
+            .get("time")
+            .ok_or_else(|| Error::Signal(String::from("no time vector")))?;
+        let vector = result
+            .get(output)
+            .ok_or_else(|| Error::Signal(format!("no vector {}", output)))?;
+        self.analyze(time, vector)
+    }
+    /// Get the intermodulation products from the transient vector.
+    pub fn analyze(&self, time: &[f64], vector: &[f64]) -> Result<Intermodulation, Error> {
+        let spectrum = Fft::new(Window::BlackmanHarris).run(time, vector)?;
+        let resolution = spectrum.frequency[1];
+        let width = 2 * spectrum.window.lobe() + 1;
+        if self.f1 < width as f64 * resolution || self.f2 - self.f1 < width as f64 * resolution {
+            return Err(Error::Signal(String::from(
+                "the tones are not resolved, simulate more periods",
+            )));
+        }
+        let nyquist = spectrum.frequency[spectrum.frequency.len() - 1];
+        if 2.0 * self.f2 - self.f1 >= nyquist {
+            return Err(Error::Signal(String::from(
+                "the third order products are above Nyquist",
+            )));
+        }
+        let (p1, p2) = (power(&spectrum, self.f1), power(&spectrum, self.f2));
+        let tone = (p1 + p2) / 2.0;
+        let relative = |p: f64| 10.0 * (p / tone).log10();
+        let imd2 = if self.f1 + self.f2 < nyquist {
+            power(&spectrum, self.f2 - self.f1).max(power(&spectrum, self.f1 + self.f2))
+        } else {
+            power(&spectrum, self.f2 - self.f1)
+        };
+        let imd3 = power(&spectrum, 2.0 * self.f1 - self.f2)
+            .max(power(&spectrum, 2.0 * self.f2 - self.f1));
+        //compare the largest bins of the tones and outside of DC and the tones
+        let excluded = [
+            lobe(&spectrum, 0.0),
+            lobe(&spectrum, self.f1),
+            lobe(&spectrum, self.f2),
+        ];
+        let peak = |(start, end): (usize, usize)| {
+            spectrum.magnitude[start..end]
+                .iter()
+                .cloned()
+                .fold(0.0, f64::max)
+        };
+        let spur = spectrum
+            .magnitude
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| {
+                !excluded
+                    .iter()
+                    .any(|(start, end)| (*start..*end).contains(k))
+            })
+            .map(|(_, m)| *m)
+            .fold(0.0, f64::max);
+        Ok(Intermodulation {
+            tones: (peak(excluded[1]), peak(excluded[2])),
+            imd2: relative(imd2),
+            imd3: relative(imd3),
+            sfdr: 20.0 * (peak(excluded[1]).min(peak(excluded[2])) / spur).log10(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::TwoTone;
+    use crate::Circuit;
+
+    #[test]
+    fn two_tone() {
+        let two_tone = TwoTone::new(1.1e3, 1e3, 0.5);
+        let time: Vec<f64> = (0..=200000).map(|i| i as f64 * 1e-6).collect();
+        let vector: Vec<f64> = time
+            .iter()
+            .map(|t| {
+                let x = 0.5 * (2.0 * PI * 1e3 * t).sin() + 0.5 * (2.0 * PI * 1.1e3 * t).sin();
+                x + 0.1 * x * x + 0.01 * x * x * x
+            })
+            .collect();
+        let imd = two_tone.analyze(&time, &vector).unwrap();
+        assert!((imd.imd2 + 26.0).abs() < 0.1);
+        assert!((imd.imd3 + 54.6).abs() < 0.1);
+        assert!((imd.sfdr - 26.0).abs() < 0.1);
+
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        two_tone.build(&mut circuit, "TT", "in").unwrap();
+        assert_eq!(
+            vec![
+                "VTT_1 in tt_1 DC 0 SIN(0 0.5 1000)",
+                "VTT_2 tt_1 0 DC 0 SIN(0 0.5 1100)",
+                ".end"
+            ],
+            circuit.to_str(true).unwrap()
+        );
+    }
+}
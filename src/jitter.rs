@@ -0,0 +1,124 @@
+use crate::{error::Error, Statistics};
+
+/// The timing of a periodic signal from the threshold crossings.
+///
+/// The periods are measured between the rising crossings, the duty cycle
+/// is the high time of the period in percent.
+///
+/// ```ignore
+/// let result = simulation.tran("1n", "100u", "0");
+/// let jitter = Jitter::new(&result["time"], &result["clk"], 1.65, 10e-6)?;
+/// println!("{}Hz, {}s rms, duty {}%", jitter.frequency, jitter.rms, jitter.duty_cycle.mean());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jitter {
+    /// The times of the rising crossings.
+    pub rising: Vec<f64>,
+    /// The times of the falling crossings.
+    pub falling: Vec<f64>,
+    pub period: Statistics,
+    /// The frequency of the mean period.
+    pub frequency: f64,
+    /// The period jitter as the standard deviation of the periods.
+    pub rms: f64,
+    /// The difference of the longest and the shortest period.
+    pub peak_to_peak: f64,
+    /// The rms difference of consecutive periods.
+    pub cycle_to_cycle: f64,
+    /// The largest difference of consecutive periods.
+    pub cycle_to_cycle_max: f64,
+    /// The duty cycles in percent.
+    pub duty_cycle: Statistics,
+}
+
+/// The interpolated times of all crossings of the level.
+pub(crate) fn crossings(time: &[f64], vector: &[f64], level: f64, rising: bool) -> Vec<f64> {
+    time.windows(2)
+        .zip(vector.windows(2))
+        .filter(|(_, v)| {
+            if rising {
+                v[0] < level && v[1] >= level
+            } else {
+                v[0] > level && v[1] <= level
+            }
+        })
+        .map(|(t, v)| t[0] + (level - v[0]) / (v[1] - v[0]) * (t[1] - t[0]))
+        .collect()
+}
+
+impl Jitter {
+    /// Measure the crossings of the level after the time `from`, e.g. after
+    /// the start-up of an oscillator.
+    pub fn new(time: &[f64], vector: &[f64], level: f64, from: f64) -> Result<Self, Error> {
+        if time.len() != vector.len() {
+            return Err(Error::Signal(format!(
+                "{} time points for {} values",
+                time.len(),
+                vector.len()
+            )));
+        }
+        let after = |c: Vec<f64>| -> Vec<f64> { c.into_iter().filter(|t| *t >= from).collect() };
+        let rising = after(crossings(time, vector, level, true));
+        let falling = after(crossings(time, vector, level, false));
+        if rising.len() < 3 {
+            return Err(Error::Signal(format!(
+                "{} rising crossings, at least 3 are needed",
+                rising.len()
+            )));
+        }
+        let periods: Vec<f64> = rising.windows(2).map(|r| r[1] - r[0]).collect();
+        let differences: Vec<f64> = periods.windows(2).map(|p| p[1] - p[0]).collect();
+        let duty_cycles: Vec<f64> = rising
+            .windows(2)
+            .filter_map(|r| {
+                falling
+                    .iter()
+                    .find(|f| **f > r[0] && **f < r[1])
+                    .map(|f| (f - r[0]) / (r[1] - r[0]) * 100.0)
+            })
+            .collect();
+        let period = Statistics::new(&periods);
+        Ok(Self {
+            frequency: 1.0 / period.mean(),
+            rms: period.stddev(),
+            peak_to_peak: period.max() - period.min(),
+            cycle_to_cycle: (differences.iter().map(|d| d * d).sum::<f64>()
+                / differences.len() as f64)
+                .sqrt(),
+            cycle_to_cycle_max: differences.iter().fold(0.0, |m, d| f64::max(m, d.abs())),
+            duty_cycle: Statistics::new(&duty_cycles),
+            period,
+            rising,
+            falling,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Jitter;
+
+    #[test]
+    fn jitter() {
+        //a clock with the periods 10, 12, 10, 8 and a high time of 5
+        let edges = [0.0, 10.0, 22.0, 32.0, 40.0];
+        let mut time = Vec::new();
+        let mut vector = Vec::new();
+        for edge in edges {
+            time.extend([edge - 0.5, edge + 0.5, edge + 4.5, edge + 5.5]);
+            vector.extend([0.0, 1.0, 1.0, 0.0]);
+        }
+        let jitter = Jitter::new(&time, &vector, 0.5, -1.0).unwrap();
+        assert_eq!(edges.to_vec(), jitter.rising);
+        assert_eq!(10.0, jitter.period.mean());
+        assert_eq!(0.1, jitter.frequency);
+        assert_eq!(4.0, jitter.peak_to_peak);
+        //the differences are 2, -2 and -2
+        assert!((jitter.cycle_to_cycle - 2.0).abs() < 1e-12);
+        assert_eq!(2.0, jitter.cycle_to_cycle_max);
+        assert_eq!(4, jitter.duty_cycle.count());
+        assert_eq!(62.5, jitter.duty_cycle.max());
+        assert!((jitter.duty_cycle.min() - 41.667).abs() < 1e-3);
+        assert!(Jitter::new(&time, &vector, 0.5, 30.0).is_err());
+    }
+}
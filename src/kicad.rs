@@ -0,0 +1,335 @@
+use std::{collections::HashMap, fs};
+
+use crate::{
+    error::Error,
+    netlist::{Element, NetlistSymbol, Point, SymbolPin},
+};
+
+/// A node of the s-expression tree.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+impl Sexp {
+    /// The name of the list, which is the first atom.
+    fn name(&self) -> Option<&str> {
+        match self {
+            Sexp::List(items) => match items.first() {
+                Some(Sexp::Atom(name)) => Some(name),
+                _ => None,
+            },
+            Sexp::Atom(_) => None,
+        }
+    }
+    fn items(&self) -> &[Sexp] {
+        match self {
+            Sexp::List(items) => items,
+            Sexp::Atom(_) => &[],
+        }
+    }
+    /// The atom at the position, the name has index 0.
+    fn atom(&self, index: usize) -> Option<&str> {
+        match self.items().get(index) {
+            Some(Sexp::Atom(value)) => Some(value),
+            _ => None,
+        }
+    }
+    fn number(&self, index: usize) -> f64 {
+        self.atom(index)
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(0.0)
+    }
+    /// The child lists with the name.
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Sexp> + 'a {
+        self.items().iter().filter(move |i| i.name() == Some(name))
+    }
+    fn child(&self, name: &str) -> Option<&Sexp> {
+        self.items().iter().find(|i| i.name() == Some(name))
+    }
+    /// Flags are written as `(flag yes)` by KiCad 7/8 and as bare atoms by KiCad 6.
+    fn flag(&self, name: &str) -> bool {
+        if let Some(child) = self.child(name) {
+            return child.atom(1) != Some("no");
+        }
+        self.items()
+            .iter()
+            .any(|i| matches!(i, Sexp::Atom(a) if a == name))
+    }
+}
+
+/// Parse the s-expression content.
+pub(crate) fn parse(content: &str) -> Result<Sexp, Error> {
+    let mut stack: Vec<Vec<Sexp>> = Vec::new();
+    let mut result: Option<Sexp> = None;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => stack.push(Vec::new()),
+            ')' => {
+                let list = Sexp::List(stack.pop().ok_or(Error::ParseError)?);
+                match stack.last_mut() {
+                    Some(parent) => parent.push(list),
+                    None => result = Some(list),
+                }
+            }
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some('n') => text.push('\n'),
+                            Some(c) => text.push(c),
+                            None => return Err(Error::ParseError),
+                        },
+                        Some('"') => break,
+                        Some(c) => text.push(c),
+                        None => return Err(Error::ParseError),
+                    }
+                }
+                stack
+                    .last_mut()
+                    .ok_or(Error::ParseError)?
+                    .push(Sexp::Atom(text));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut text = String::from(c);
+                while let Some(next) = chars.peek() {
+                    if next.is_whitespace() || *next == '(' || *next == ')' {
+                        break;
+                    }
+                    text.push(*next);
+                    chars.next();
+                }
+                stack
+                    .last_mut()
+                    .ok_or(Error::ParseError)?
+                    .push(Sexp::Atom(text));
+            }
+        }
+    }
+    if !stack.is_empty() {
+        return Err(Error::ParseError);
+    }
+    result.ok_or(Error::ParseError)
+}
+
+/// Round the coordinates to get rid of the floating point noise from the rotation.
+fn round(value: f64) -> f64 {
+    (value * 10000.0).round() / 10000.0
+}
+
+/// Transform the library position of a pin to the schematic position.
+///
+/// The library uses an y-up coordinate system, the pin is rotated by the symbol
+/// angle, mirrored and then converted to the y-down schematic system.
+fn transform(symbol: &Sexp, x: f64, y: f64) -> Point {
+    let at = symbol.child("at");
+    let (sx, sy, angle) = match at {
+        Some(at) => (at.number(1), at.number(2), at.number(3)),
+        None => (0.0, 0.0, 0.0),
+    };
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (rx, ry) = (x * cos - y * sin, x * sin + y * cos);
+    let (mx, my) = match symbol.child("mirror").and_then(|m| m.atom(1)) {
+        Some("x") => (rx, ry),
+        Some("y") => (-rx, -ry),
+        _ => (rx, -ry),
+    };
+    Point::new(round(sx + mx), round(sy + my))
+}
+
+/// The pins of a library symbol per unit, unit 0 is common to all units.
+fn library_pins(symbol: &Sexp) -> Vec<(u32, &Sexp)> {
+    let mut pins = Vec::new();
+    for sub in symbol.children("symbol") {
+        //the sub symbol name is NAME_UNIT_STYLE
+        let name = sub.atom(1).unwrap_or_default();
+        let mut parts = name.rsplit('_');
+        let style: u32 = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+        let unit: u32 = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+        if style > 1 {
+            continue;
+        }
+        for pin in sub.children("pin") {
+            pins.push((unit, pin));
+        }
+    }
+    pins
+}
+
+/// Load a KiCad schematic (version 6 to 8) for the netlist.
+///
+/// Hierarchical schematics are rejected, the sub-sheets and the
+/// hierarchical labels can not be resolved to the nets.
+#[allow(clippy::type_complexity)]
+pub(crate) fn load(
+    filename: &str,
+) -> Result<(HashMap<String, Vec<NetlistSymbol>>, Vec<Element>), Error> {
+    let content = fs::read_to_string(filename)?;
+    let root = parse(&content)?;
+    if root.name() != Some("kicad_sch") {
+        return Err(Error::ParseError);
+    }
+    let libraries: HashMap<&str, &Sexp> = match root.child("lib_symbols") {
+        Some(lib) => lib
+            .children("symbol")
+            .filter_map(|s| s.atom(1).map(|name| (name, s)))
+            .collect(),
+        None => HashMap::new(),
+    };
+
+    let mut symbols: HashMap<String, Vec<NetlistSymbol>> = HashMap::new();
+    let mut elements: Vec<Element> = Vec::new();
+    for node in root.items() {
+        let at = |node: &Sexp| match node.child("at") {
+            Some(at) => Point::new(at.number(1), at.number(2)),
+            None => Point::new(0.0, 0.0),
+        };
+        match node.name() {
+            Some("symbol") => {
+                let lib_id = node
+                    .child("lib_id")
+                    .and_then(|l| l.atom(1))
+                    .ok_or(Error::ParseError)?;
+                //KiCad 7 references a renamed library symbol with lib_name
+                let lib_name = node
+                    .child("lib_name")
+                    .and_then(|l| l.atom(1))
+                    .unwrap_or(lib_id);
+                let lib_symbol = libraries
+                    .get(lib_name)
+                    .ok_or_else(|| Error::LibraryNotFound(lib_name.to_string()))?;
+                let mut properties: HashMap<String, String> = HashMap::new();
+                for property in node.children("property") {
+                    if let (Some(key), Some(value)) = (property.atom(1), property.atom(2)) {
+                        properties.insert(key.to_string(), value.to_string());
+                    }
+                }
+                let mut unit = node.child("unit").map(|u| u.number(1) as u32).unwrap_or(1);
+                //KiCad 7/8 store the annotation in the instances
+                if let Some(path) = node
+                    .child("instances")
+                    .and_then(|i| i.child("project"))
+                    .and_then(|p| p.child("path"))
+                {
+                    if let Some(reference) = path.child("reference").and_then(|r| r.atom(1)) {
+                        properties.insert(String::from("Reference"), reference.to_string());
+                    }
+                    if let Some(u) = path.child("unit") {
+                        unit = u.number(1) as u32;
+                    }
+                }
+                let reference = match properties.get("Reference") {
+                    Some(reference) => reference.clone(),
+                    None => {
+                        return Err(Error::PropertyNotFound(
+                            lib_id.to_string(),
+                            String::from("Reference"),
+                        ))
+                    }
+                };
+                let all_pins = library_pins(lib_symbol);
+                let pins = all_pins
+                    .iter()
+                    .filter(|(u, _)| *u == 0 || *u == unit)
+                    .map(|(u, pin)| {
+                        let pos = pin.child("at");
+                        SymbolPin {
+                            reference: reference.clone(),
+                            number: pin
+                                .child("number")
+                                .and_then(|n| n.atom(1))
+                                .unwrap_or_default()
+                                .to_string(),
+                            name: pin
+                                .child("name")
+                                .and_then(|n| n.atom(1))
+                                .unwrap_or_default()
+                                .to_string(),
+                            electrical_type: pin.atom(1).unwrap_or_default().to_string(),
+                            unit: if *u == 0 { unit } else { *u },
+                            at: match pos {
+                                Some(pos) => transform(node, pos.number(1), pos.number(2)),
+                                None => transform(node, 0.0, 0.0),
+                            },
+                        }
+                    })
+                    .collect();
+                symbols
+                    .entry(reference)
+                    .or_default()
+                    .push(NetlistSymbol {
+                        lib_id: lib_id.to_string(),
+                        unit,
+                        power: lib_symbol.flag("power"),
+                        exclude_from_sim: node.flag("exclude_from_sim"),
+                        pin_count: all_pins.len(),
+                        properties,
+                        pins,
+                    });
+            }
+            Some("wire") => {
+                let pts: Vec<Point> = node
+                    .child("pts")
+                    .map(|pts| {
+                        pts.children("xy")
+                            .map(|xy| Point::new(xy.number(1), xy.number(2)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if pts.len() == 2 {
+                    elements.push(Element::Wire(pts[0], pts[1]));
+                }
+            }
+            Some("junction") => elements.push(Element::Junction(at(node))),
+            Some("no_connect") => elements.push(Element::NoConnect(at(node))),
+            Some("label") => elements.push(Element::Label(
+                node.atom(1).unwrap_or_default().to_string(),
+                at(node),
+            )),
+            Some("hierarchical_label") => {
+                return Err(Error::Hierarchy(format!(
+                    "hierarchical label {} in {}",
+                    node.atom(1).unwrap_or_default(),
+                    filename
+                )))
+            }
+            Some("sheet") => {
+                //KiCad 6 names the property "Sheet file", KiCad 7/8 "Sheetfile"
+                let file = node
+                    .children("property")
+                    .find(|p| matches!(p.atom(1), Some("Sheet file") | Some("Sheetfile")))
+                    .and_then(|p| p.atom(2))
+                    .unwrap_or_default();
+                return Err(Error::Hierarchy(format!("sub-sheet {} in {}", file, filename)));
+            }
+            Some("global_label") => elements.push(Element::GlobalLabel(
+                node.atom(1).unwrap_or_default().to_string(),
+                at(node),
+            )),
+            _ => {}
+        }
+    }
+    Ok((symbols, elements))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Sexp};
+
+    #[test]
+    fn parse_sexp() {
+        let sexp = parse("(kicad_sch (version 20231120) (title \"a \\\"b\\\"\") (hide yes) dnp)")
+            .unwrap();
+        assert_eq!(Some("kicad_sch"), sexp.name());
+        assert_eq!(Some("a \"b\""), sexp.child("title").unwrap().atom(1));
+        assert_eq!(20231120.0, sexp.child("version").unwrap().number(1));
+        assert!(sexp.flag("hide"));
+        assert!(sexp.flag("dnp"));
+        assert!(!sexp.flag("exclude_from_sim"));
+        assert_eq!(Sexp::Atom(String::from("dnp")), sexp.items()[4]);
+    }
+}
@@ -0,0 +1,115 @@
+use std::f64::consts::PI;
+
+use num_complex::Complex64;
+
+use crate::{error::Error, node::IntoNode, Circuit};
+
+/// A transfer function in the s-domain, the coefficients start with the
+/// highest power of s.
+///
+/// The block is simulated with the XSPICE `s_xfer` model.
+///
+/// ```ignore
+/// //a first order lowpass with the corner frequency of 1kHz
+/// let tau = 1.0 / (2.0 * PI * 1e3);
+/// let lowpass = TransferFunction::new(vec![1.0], vec![tau, 1.0])?;
+/// lowpass.build(&mut circuit, "A1", "in", "out")?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferFunction {
+    pub numerator: Vec<f64>,
+    pub denominator: Vec<f64>,
+    pub gain: f64,
+}
+
+/// The coefficients as XSPICE vector.
+fn coefficients(values: &[f64]) -> String {
+    let values: Vec<String> = values.iter().map(|v| format!("{:e}", v)).collect();
+    format!("[{}]", values.join(" "))
+}
+
+impl TransferFunction {
+    /// The transfer function must not have more zeros than poles.
+    pub fn new(numerator: Vec<f64>, denominator: Vec<f64>) -> Result<Self, Error> {
+        if denominator.first().cloned().unwrap_or_default() == 0.0 {
+            return Err(Error::InvalidValue(format!(
+                "denominator {:?}",
+                denominator
+            )));
+        }
+        if numerator.is_empty() || numerator.len() > denominator.len() {
+            return Err(Error::InvalidValue(format!("numerator {:?}", numerator)));
+        }
+        Ok(Self {
+            numerator,
+            denominator,
+            gain: 1.0,
+        })
+    }
+    pub fn set_gain(&mut self, gain: f64) {
+        self.gain = gain;
+    }
+    /// The complex response at the frequency in Hz.
+    pub fn response(&self, frequency: f64) -> Complex64 {
+        let s = Complex64::new(0.0, 2.0 * PI * frequency);
+        let polynomial = |c: &[f64]| c.iter().fold(Complex64::new(0.0, 0.0), |a, c| a * s + c);
+        self.gain * polynomial(&self.numerator) / polynomial(&self.denominator)
+    }
+    /// Add the block between the input and the output node, the output is
+    /// driven by an ideal voltage source.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        reference: &str,
+        input: impl IntoNode,
+        output: impl IntoNode,
+    ) -> Result<(), Error> {
+        let input = String::from(input.into_node()?);
+        let output = String::from(output.into_node()?);
+        let model = format!("{}_xfer", reference).to_lowercase();
+        circuit.xspice(reference.to_string(), vec![input, output], model.clone())?;
+        circuit.model(
+            model,
+            String::from("s_xfer"),
+            format!(
+                "gain={:e} num_coeff={} den_coeff={} int_ic={}",
+                self.gain,
+                coefficients(&self.numerator),
+                coefficients(&self.denominator),
+                coefficients(&vec![0.0; self.denominator.len() - 1]),
+            ),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TransferFunction;
+    use crate::Circuit;
+
+    #[test]
+    fn lowpass() {
+        let tau = 1.0 / (2.0 * std::f64::consts::PI * 1e3);
+        let mut lowpass = TransferFunction::new(vec![1.0], vec![tau, 1.0]).unwrap();
+        lowpass.set_gain(2.0);
+        assert!((lowpass.response(0.0).norm() - 2.0).abs() < 1e-12);
+        assert!((lowpass.response(1e3).norm() - 2.0_f64.sqrt()).abs() < 1e-12);
+        assert!((lowpass.response(1e3).arg().to_degrees() + 45.0).abs() < 1e-9);
+        assert!(TransferFunction::new(vec![1.0, 0.0, 0.0], vec![1.0, 1.0]).is_err());
+        assert!(TransferFunction::new(vec![1.0], vec![0.0, 1.0]).is_err());
+
+        let mut circuit = Circuit::new(String::from("laplace"), Vec::new());
+        TransferFunction::new(vec![1.0, 0.0], vec![2.0, 1.0])
+            .unwrap()
+            .build(&mut circuit, "A1", "in", "out")
+            .unwrap();
+        assert_eq!(
+            vec![
+                "A1 in out a1_xfer",
+                ".model a1_xfer s_xfer(gain=1e0 num_coeff=[1e0 0e0] den_coeff=[2e0 1e0] int_ic=[0e0])",
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+}
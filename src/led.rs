@@ -0,0 +1,121 @@
+use crate::{error::Error, linear::solve, Circuit, ESeries, Value};
+
+/// The thermal voltage at 27°C.
+const THERMAL_VOLTAGE: f64 = 0.025852;
+
+/// A LED diode model from the forward voltage and current of the datasheet.
+///
+/// The emission coefficient, the saturation current and the series
+/// resistance are fitted to the points, two points fit the model without
+/// series resistance.
+///
+/// ```ignore
+/// let led = Led::new(vec![(1.8, 1e-3), (2.0, 10e-3), (2.2, 30e-3)])?;
+/// led.model(&mut circuit, "red");
+/// circuit.diode(String::from("D1"), "anode", "GND", String::from("red"))?;
+/// let r = led.series_resistor(9.0, 10e-3, ESeries::E12)?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Led {
+    /// The saturation current.
+    pub is: f64,
+    /// The emission coefficient.
+    pub n: f64,
+    /// The series resistance.
+    pub rs: f64,
+}
+
+impl Led {
+    /// Fit the model to the forward voltage and current points.
+    pub fn new(mut points: Vec<(f64, f64)>) -> Result<Self, Error> {
+        points.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if points.len() < 2
+            || points.iter().any(|(v, i)| *v <= 0.0 || *i <= 0.0)
+            || points
+                .windows(2)
+                .any(|w| w[0].0 >= w[1].0 || w[0].1 >= w[1].1)
+        {
+            return Err(Error::InvalidValue(format!("{:?}", points)));
+        }
+        //least squares of V = a ln(I) + b + RS I, without RS for two points
+        let columns = if points.len() > 2 { 3 } else { 2 };
+        let row = |i: f64| [i.ln(), 1.0, i];
+        let mut a = vec![vec![0.0; columns]; columns];
+        let mut y = vec![0.0; columns];
+        for (v, i) in &points {
+            let r = row(*i);
+            for j in 0..columns {
+                for k in 0..columns {
+                    a[j][k] += r[j] * r[k];
+                }
+                y[j] += r[j] * v;
+            }
+        }
+        let x = solve(a, y).ok_or_else(|| Error::InvalidValue(format!("{:?}", points)))?;
+        let n = x[0] / THERMAL_VOLTAGE;
+        let is = (-x[1] / x[0]).exp();
+        let rs = x.get(2).cloned().unwrap_or_default().max(0.0);
+        Ok(Self { is, n, rs })
+    }
+    /// The forward voltage at the current.
+    pub fn forward_voltage(&self, current: f64) -> f64 {
+        self.n * THERMAL_VOLTAGE * (current / self.is + 1.0).ln() + current * self.rs
+    }
+    /// Add the diode model with the name to the circuit.
+    pub fn model(&self, circuit: &mut Circuit, name: &str) {
+        circuit.model(
+            name.to_string(),
+            String::from("D"),
+            format!(
+                "IS={:.4e} N={:.4} RS={}",
+                self.is,
+                self.n,
+                Value::new(self.rs)
+            ),
+        );
+    }
+    /// The series resistor for the current at the supply voltage, the
+    /// resistor is rounded up to the series.
+    pub fn series_resistor(
+        &self,
+        supply: f64,
+        current: f64,
+        series: ESeries,
+    ) -> Result<Value, Error> {
+        let forward = self.forward_voltage(current);
+        if current <= 0.0 || supply <= forward {
+            return Err(Error::InvalidValue(format!(
+                "{}V supply for the forward voltage {}V",
+                supply, forward
+            )));
+        }
+        Ok(Value::new((supply - forward) / current).above(series))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Led;
+    use crate::{Circuit, ESeries};
+
+    #[test]
+    fn led() {
+        let led = Led::new(vec![(2.2, 30e-3), (1.8, 1e-3), (2.0, 10e-3)]).unwrap();
+        assert!((led.forward_voltage(1e-3) - 1.8).abs() < 1e-3);
+        assert!((led.forward_voltage(30e-3) - 2.2).abs() < 1e-6);
+        let mut circuit = Circuit::new(String::from("indicator"), Vec::new());
+        led.model(&mut circuit, "red");
+        assert_eq!(
+            vec![".model red D(IS=1.5749e-16 N=2.3532 RS=6.6583698532498525)"],
+            circuit.to_str(false).unwrap()
+        );
+        assert_eq!(
+            "820",
+            led.series_resistor(9.0, 10e-3, ESeries::E12)
+                .unwrap()
+                .to_string()
+        );
+        assert!(led.series_resistor(1.5, 10e-3, ESeries::E12).is_err());
+        assert!(Led::new(vec![(2.0, 10e-3)]).is_err());
+    }
+}
@@ -1,6 +1,126 @@
 mod circuit;
+mod definition;
+mod ltspice;
 mod netlist;
+mod parser;
 mod error;
+mod kicad;
+mod spice;
+mod erc;
+mod check;
+mod graph;
+mod node;
+mod value;
+mod tolerance;
+mod montecarlo;
+mod corner;
+mod sampling;
+mod stats;
+mod yields;
+mod optimizer;
+mod filter;
+mod matching;
+mod measurement;
+mod step;
+mod frequency;
+mod fft;
+mod distortion;
+mod intermodulation;
+mod result;
+mod impedance;
+mod loopgain;
+mod rejection;
+mod power;
+mod op;
+mod converter;
+mod linear;
+mod pss;
+mod thermal;
+mod jitter;
+mod stimulus;
+mod digital;
+mod laplace;
+mod opamp;
+mod potentiometer;
+mod transformer;
+mod tube;
+mod vactrol;
+mod thermistor;
+mod crystal;
+mod loudspeaker;
+mod battery;
+mod led;
+mod relay;
+mod config;
+mod overrides;
+mod merge;
+mod extract;
+mod flatten;
+mod hierarchy;
+mod component;
+mod diff;
+mod compare;
+mod assertions;
+mod pretty;
+mod messages;
+mod recovery;
+mod accuracy;
+mod convergence;
+mod tran;
 
 pub use self::circuit::{Circuit, Simulation, Cb};
-pub use self::netlist::{Netlist, Point, Node, Erc};
+pub use self::netlist::{Netlist, Point, Net, Erc, SymbolPin};
+#[allow(deprecated)]
+pub use self::netlist::Node;
+pub use self::erc::{ErcConfig, ErcReport, ErcRule, PinMatrix, PinType, Severity};
+pub use self::definition::{Definition, Analysis};
+pub use self::check::Diagnostic;
+pub use self::node::{IntoNode, NodeName};
+pub use self::value::{ESeries, Value};
+pub use self::tolerance::{Distribution, Tolerance};
+pub use self::montecarlo::{Ensemble, MonteCarlo};
+pub use self::corner::{Corners, Extreme, Measure, WorstCase, MAX_CORNER_COMPONENTS};
+pub use self::sampling::Sampling;
+pub use self::stats::{Histogram, Statistics};
+pub use self::yields::{Spec, YieldReport};
+pub use self::optimizer::{Optimizer, Optimum};
+pub use self::filter::{Band, Filter, Response, Topology};
+pub use self::matching::{Matching, Reactance};
+pub use self::measurement::{Edge, Function, Measurement, Trigger};
+pub use self::step::{StepResponse, Thresholds};
+pub use self::frequency::FrequencyResponse;
+pub use self::fft::{Fft, Spectrum, Window};
+pub use self::distortion::{thd, Distortion, Noise, Thd};
+pub use self::intermodulation::{Intermodulation, TwoTone};
+pub use self::result::{AnalysisResult, Bode, GroupDelay, Nyquist};
+pub use self::impedance::{Impedance, Port};
+pub use self::loopgain::LoopGain;
+pub use self::rejection::Rejection;
+pub use self::power::{Dissipation, PowerReport};
+pub use self::op::{Annotation, OperatingPoint};
+pub use self::converter::{Converter, Efficiency};
+pub use self::pss::{Shooting, SteadyState};
+pub use self::thermal::{Thermal, ThermalPath, ThermalResult};
+pub use self::jitter::Jitter;
+pub use self::stimulus::BitPattern;
+pub use self::digital::{DigitalSource, Logic};
+pub use self::laplace::TransferFunction;
+pub use self::opamp::OpAmp;
+pub use self::transformer::Transformer;
+pub use self::vactrol::Vactrol;
+pub use self::thermistor::Thermistor;
+pub use self::crystal::Crystal;
+pub use self::loudspeaker::Loudspeaker;
+pub use self::battery::Battery;
+pub use self::led::Led;
+pub use self::relay::Relay;
+pub use self::config::Config;
+pub use self::hierarchy::HierarchicalName;
+pub use self::component::{Component, ComponentKind};
+pub use self::diff::{Change, NetlistDiff};
+pub use self::compare::{Compare, Comparison, Deviation};
+pub use self::assertions::{assert_gain_db, assert_rises_within, assert_voltage_between};
+pub use self::messages::SpiceMessage;
+pub use self::recovery::{Recovered, Recovery};
+pub use self::accuracy::Accuracy;
+pub use self::tran::Tran;
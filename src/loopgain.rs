@@ -0,0 +1,122 @@
+use num_complex::Complex64;
+
+use crate::{
+    error::Error, frequency::FrequencyResponse, impedance::branch, result::AnalysisResult,
+    Simulation,
+};
+
+const PROBE_NODE: &str = "loop_x";
+const PROBE_VOLTAGE: &str = "VLOOP_I";
+const PROBE_CURRENT: &str = "ILOOP_I";
+
+/// The loop gain of a feedback loop, the closed loop gain is `1 / (1 + T)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopGain {
+    /// The frequency in Hz.
+    pub frequency: Vec<f64>,
+    pub gain: Vec<Complex64>,
+}
+
+impl LoopGain {
+    /// The bandwidth and the stability margins of the loop.
+    pub fn response(&self) -> Result<FrequencyResponse, Error> {
+        FrequencyResponse::new(&self.frequency, &self.gain)
+    }
+}
+
+/// Combine the voltage and the current injection with the formula of Tian.
+fn tian(voltage: &AnalysisResult, current: &AnalysisResult) -> Result<LoopGain, Error> {
+    let branch = branch(PROBE_VOLTAGE);
+    let (a, b) = (voltage.get(PROBE_NODE)?, voltage.get(&branch)?);
+    let (c, d) = (current.get(PROBE_NODE)?, current.get(&branch)?);
+    let gain = (0..a.len())
+        .map(|i| {
+            let m = 2.0 * (b[i] * c[i] - a[i] * d[i]) + a[i] + d[i];
+            -1.0 / (1.0 - 1.0 / m)
+        })
+        .collect();
+    Ok(LoopGain {
+        frequency: voltage.frequency()?,
+        gain,
+    })
+}
+
+impl Simulation {
+    /// Get the loop gain with the probe of Tian.
+    ///
+    /// The loop is broken at the pin of the element connected to the node,
+    /// the node is driven by the loop and the element is the input of the
+    /// feedback path. A 0V source and a current source are inserted at the
+    /// pin and two AC analyses with voltage and current injection are run,
+    /// the result includes the loading of both sides of the loop.
+    ///
+    /// ```ignore
+    /// //break the loop between the op-amp output and the feedback resistor
+    /// let loop_gain = simulation.loop_gain("RF", "out", "1", "100Meg", 20, "dec")?;
+    /// assert!(loop_gain.response()?.phase_margin.unwrap() > 45.0);
+    /// ```
+    pub fn loop_gain(
+        &mut self,
+        element: &str,
+        node: &str,
+        start: &str,
+        stop: &str,
+        points: u32,
+        variation: &str,
+    ) -> Result<LoopGain, Error> {
+        let mut circuit = self.circuit.without_ac();
+        circuit.reconnect(element, node, PROBE_NODE)?;
+        circuit.voltage(
+            String::from(PROBE_VOLTAGE),
+            PROBE_NODE,
+            node,
+            String::from("DC 0"),
+        )?;
+        circuit.current(
+            String::from(PROBE_CURRENT),
+            "0",
+            PROBE_NODE,
+            String::from("DC 0"),
+        )?;
+        let mut results = Vec::new();
+        for source in [PROBE_VOLTAGE, PROBE_CURRENT] {
+            let mut circuit = circuit.clone();
+            circuit.set_ac(source, "1")?;
+            let mut simulation = Simulation::new(circuit);
+            results.push(simulation.ac_complex(start, stop, points, variation)?);
+            self.buffer = simulation.buffer;
+        }
+        tian(&results[0], &results[1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use num_complex::Complex64;
+
+    use super::tian;
+    use crate::result::AnalysisResult;
+
+    #[test]
+    fn loop_gain() {
+        //a driver with the gain g and the output impedance zo, loaded by zi
+        let (g, zo, zi) = (
+            Complex64::new(-5.0, 2.0),
+            Complex64::new(100.0, 50.0),
+            Complex64::new(300.0, -20.0),
+        );
+        let probe = |v: f64, i: f64| {
+            let x = (i + v / zo) / ((1.0 - g) / zo + 1.0 / zi);
+            AnalysisResult::from(HashMap::from([
+                (String::from("frequency"), vec![Complex64::new(1e3, 0.0)]),
+                (String::from("loop_x"), vec![x]),
+                (String::from("vloop_i#branch"), vec![i - x / zi]),
+            ]))
+        };
+        let result = tian(&probe(1.0, 0.0), &probe(0.0, 1.0)).unwrap();
+        let expected = -g * zi / (zo + zi);
+        assert!((result.gain[0] - expected).norm() < 1e-9);
+    }
+}
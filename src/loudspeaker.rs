@@ -0,0 +1,172 @@
+use std::f64::consts::PI;
+
+use num_complex::Complex64;
+
+use crate::{error::Error, node::IntoNode, Circuit, Value};
+
+/// The electrical equivalent of a loudspeaker driver from the Thiele-Small
+/// parameters.
+///
+/// The voice coil is the resistance `Re` and the inductance `Le`, the
+/// mechanical resonance is a parallel RLC. In a closed box the air spring
+/// is an inductance in parallel to the compliance of the suspension.
+///
+/// ```ignore
+/// let mut woofer = Loudspeaker::new(5.6, 0.5e-3, 38.0, 3.2, 0.42, 45e-3);
+/// woofer.set_enclosure(20e-3);
+/// woofer.build(&mut circuit, "SPK1", "out", "GND")?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loudspeaker {
+    /// The DC resistance of the voice coil.
+    pub re: f64,
+    /// The inductance of the voice coil in H.
+    pub le: f64,
+    /// The resonance frequency in Hz.
+    pub fs: f64,
+    /// The mechanical Q factor.
+    pub qms: f64,
+    /// The electrical Q factor.
+    pub qes: f64,
+    /// The equivalent volume of the compliance in m³.
+    pub vas: f64,
+    /// The volume of the closed box in m³, `None` is free air.
+    pub enclosure: Option<f64>,
+}
+
+impl Loudspeaker {
+    pub fn new(re: f64, le: f64, fs: f64, qms: f64, qes: f64, vas: f64) -> Self {
+        Self {
+            re,
+            le,
+            fs,
+            qms,
+            qes,
+            vas,
+            enclosure: None,
+        }
+    }
+    /// Mount the driver in a closed box with the volume in m³.
+    pub fn set_enclosure(&mut self, volume: f64) {
+        self.enclosure = Some(volume);
+    }
+    /// The resistance, inductance and capacitance of the mechanical resonance.
+    pub fn motional(&self) -> Result<(f64, f64, f64), Error> {
+        if [self.re, self.fs, self.qms, self.qes, self.vas]
+            .iter()
+            .chain(self.enclosure.iter())
+            .any(|v| *v <= 0.0)
+            || self.le < 0.0
+        {
+            return Err(Error::InvalidValue(format!("{:?}", self)));
+        }
+        let omega = 2.0 * PI * self.fs;
+        let capacitance = self.qes / (omega * self.re);
+        Ok((
+            self.re * self.qms / self.qes,
+            1.0 / (omega.powi(2) * capacitance),
+            capacitance,
+        ))
+    }
+    /// The inductance of the air spring in the closed box.
+    fn air_spring(&self, compliance: f64) -> Option<f64> {
+        self.enclosure.map(|volume| compliance * volume / self.vas)
+    }
+    /// The electrical impedance at the frequency.
+    pub fn impedance(&self, frequency: f64) -> Result<Complex64, Error> {
+        let (r, l, c) = self.motional()?;
+        let s = Complex64::new(0.0, 2.0 * PI * frequency);
+        let mut admittance = 1.0 / r + s * c + 1.0 / (s * l);
+        if let Some(box_l) = self.air_spring(l) {
+            admittance += 1.0 / (s * box_l);
+        }
+        Ok(self.re + s * self.le + 1.0 / admittance)
+    }
+    /// Add the equivalent circuit, the elements are named with the reference
+    /// and the suffix `E` for the voice coil, `M` for the resonance and `B`
+    /// for the box.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        reference: &str,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+    ) -> Result<(), Error> {
+        let (r, l, c) = self.motional()?;
+        let n1 = String::from(n1.into_node()?);
+        let coil = format!("{}_e", reference).to_lowercase();
+        let mut motional = format!("{}_m", reference).to_lowercase();
+        circuit.resistor(
+            format!("R{}E", reference),
+            n0,
+            coil.as_str(),
+            Value::new(self.re).to_string(),
+        )?;
+        if self.le > 0.0 {
+            circuit.inductor(
+                format!("L{}E", reference),
+                coil,
+                motional.as_str(),
+                Value::new(self.le).to_string(),
+            )?;
+        } else {
+            motional = coil;
+        }
+        circuit.resistor(
+            format!("R{}M", reference),
+            motional.as_str(),
+            n1.as_str(),
+            Value::new(r).to_string(),
+        )?;
+        circuit.inductor(
+            format!("L{}M", reference),
+            motional.as_str(),
+            n1.as_str(),
+            Value::new(l).to_string(),
+        )?;
+        circuit.capacitor(
+            format!("C{}M", reference),
+            motional.as_str(),
+            n1.as_str(),
+            Value::new(c).to_string(),
+        )?;
+        if let Some(box_l) = self.air_spring(l) {
+            circuit.inductor(
+                format!("L{}B", reference),
+                motional,
+                n1,
+                Value::new(box_l).to_string(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Loudspeaker;
+    use crate::Circuit;
+
+    #[test]
+    fn loudspeaker() {
+        let mut speaker = Loudspeaker::new(6.0, 0.0, 50.0, 4.0, 0.5, 40e-3);
+        let impedance = speaker.impedance(50.0).unwrap();
+        assert!((impedance.re - 54.0).abs() < 1e-9 && impedance.im.abs() < 1e-9);
+        let mut circuit = Circuit::new(String::from("crossover"), Vec::new());
+        speaker.set_enclosure(40e-3);
+        speaker.build(&mut circuit, "SPK1", "out", "GND").unwrap();
+        assert_eq!(
+            vec![
+                "RSPK1E out spk1_e 6",
+                "RSPK1M spk1_e 0 48",
+                "LSPK1M spk1_e 0 38.19718634205487m",
+                "CSPK1M spk1_e 0 265.25823848649226u",
+                "LSPK1B spk1_e 0 38.19718634205487m",
+            ],
+            circuit.to_str(false).unwrap()
+        );
+        //the box with the volume of Vas raises the resonance by sqrt(2)
+        let impedance = speaker.impedance(50.0 * 2.0_f64.sqrt()).unwrap();
+        assert!((impedance.re - 54.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,378 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{error::Error, parser, Circuit};
+
+/// A symbol pin with the position relative to the symbol origin.
+#[derive(Debug, Clone, PartialEq)]
+struct AsyPin {
+    x: i64,
+    y: i64,
+    order: u32,
+}
+
+/// The symbol definition from a .asy file or the builtin table.
+#[derive(Debug, Clone, PartialEq)]
+struct Asy {
+    prefix: String,
+    model: Option<String>,
+    pins: Vec<AsyPin>,
+}
+
+/// A placed symbol of the schematic.
+#[derive(Debug, Clone, PartialEq)]
+struct AscSymbol {
+    name: String,
+    x: i64,
+    y: i64,
+    rotation: String,
+    attributes: HashMap<String, String>,
+}
+
+/// The pin positions and prefixes of the LTspice builtin symbols.
+fn builtin(name: &str) -> Option<Asy> {
+    let pin = |x, y, order| AsyPin { x, y, order };
+    let (prefix, pins) = match name.to_lowercase().as_str() {
+        "res" | "res2" => ("R", vec![pin(16, 16, 1), pin(16, 96, 2)]),
+        "cap" | "polcap" => ("C", vec![pin(16, 0, 1), pin(16, 64, 2)]),
+        "ind" | "ind2" => ("L", vec![pin(16, 16, 1), pin(16, 96, 2)]),
+        "diode" | "zener" | "schottky" | "led" => ("D", vec![pin(16, 0, 1), pin(16, 64, 2)]),
+        "voltage" | "battery" => ("V", vec![pin(0, 16, 1), pin(0, 96, 2)]),
+        "current" => ("I", vec![pin(0, 0, 1), pin(0, 80, 2)]),
+        "npn" => ("Q", vec![pin(64, 0, 1), pin(0, 48, 2), pin(64, 96, 3)]),
+        "pnp" => ("Q", vec![pin(64, 96, 1), pin(0, 48, 2), pin(64, 0, 3)]),
+        _ => return None,
+    };
+    Some(Asy {
+        prefix: prefix.to_string(),
+        model: None,
+        pins,
+    })
+}
+
+/// LTspice writes the files either in UTF-16LE or in a single byte encoding.
+fn read_file(path: &Path) -> Result<String, Error> {
+    let bytes = fs::read(path)?;
+    if bytes.len() >= 2 && (bytes.starts_with(&[0xff, 0xfe]) || bytes[1] == 0) {
+        let start = if bytes.starts_with(&[0xff, 0xfe]) { 2 } else { 0 };
+        let words: Vec<u16> = bytes[start..]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        Ok(String::from_utf16_lossy(&words))
+    } else {
+        Ok(bytes.iter().map(|b| *b as char).collect())
+    }
+}
+
+fn parse_asy(content: &str) -> Asy {
+    let mut asy = Asy {
+        prefix: String::from("X"),
+        model: None,
+        pins: Vec::new(),
+    };
+    for line in content.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["PIN", x, y, ..] => asy.pins.push(AsyPin {
+                x: x.parse().unwrap_or(0),
+                y: y.parse().unwrap_or(0),
+                order: asy.pins.len() as u32 + 1,
+            }),
+            ["PINATTR", "SpiceOrder", order] => {
+                if let Some(pin) = asy.pins.last_mut() {
+                    pin.order = order.parse().unwrap_or(pin.order);
+                }
+            }
+            ["SYMATTR", "Prefix", prefix] => asy.prefix = prefix.to_uppercase(),
+            ["SYMATTR", "SpiceModel", model] => asy.model = Some(model.to_string()),
+            ["SYMATTR", "Value", value] if asy.model.is_none() => {
+                asy.model = Some(value.to_string())
+            }
+            _ => {}
+        }
+    }
+    asy.pins.sort_by_key(|p| p.order);
+    asy
+}
+
+/// Transform the pin position with the symbol orientation.
+fn transform(rotation: &str, x: i64, y: i64) -> (i64, i64) {
+    match rotation {
+        "R90" => (-y, x),
+        "R180" => (-x, -y),
+        "R270" => (y, -x),
+        "M0" => (-x, y),
+        "M90" => (-y, -x),
+        "M180" => (x, -y),
+        "M270" => (y, x),
+        _ => (x, y),
+    }
+}
+
+/// Check if the point is on the wire segment.
+fn on_wire(wire: &((i64, i64), (i64, i64)), p: (i64, i64)) -> bool {
+    let ((x0, y0), (x1, y1)) = *wire;
+    let cross = (x1 - x0) * (p.1 - y0) - (y1 - y0) * (p.0 - x0);
+    cross == 0
+        && p.0 >= x0.min(x1)
+        && p.0 <= x0.max(x1)
+        && p.1 >= y0.min(y1)
+        && p.1 <= y0.max(y1)
+}
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[i] = root;
+    root
+}
+
+/// Import a LTspice schematic.
+///
+/// The builtin symbols (resistor, capacitor, inductor, diode, voltage and current source,
+/// bipolar transistor) are known, all other symbols are searched as `.asy` files in the directory of the
+/// schematic and in the pathlist. The directives of the text fields are
+/// parsed and relative includes are resolved against the directory of the
+/// schematic.
+fn load(filename: &str, pathlist: Vec<String>) -> Result<Circuit, Error> {
+    let path = Path::new(filename);
+    let content = read_file(path)?;
+    let mut search: Vec<PathBuf> = Vec::new();
+    if let Some(parent) = path.parent() {
+        search.push(parent.to_path_buf());
+    }
+    for p in &pathlist {
+        search.push(PathBuf::from(p));
+    }
+
+    let mut wires: Vec<((i64, i64), (i64, i64))> = Vec::new();
+    let mut flags: Vec<((i64, i64), String)> = Vec::new();
+    let mut symbols: Vec<AscSymbol> = Vec::new();
+    let mut directives: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let number = |i: usize| -> Result<i64, Error> {
+            tokens
+                .get(i)
+                .and_then(|t| t.parse::<i64>().ok())
+                .ok_or_else(|| Error::Spice(line.to_string()))
+        };
+        match tokens.first() {
+            Some(&"WIRE") => wires.push(((number(1)?, number(2)?), (number(3)?, number(4)?))),
+            Some(&"FLAG") => flags.push(((number(1)?, number(2)?), tokens[3..].join(" "))),
+            Some(&"SYMBOL") => symbols.push(AscSymbol {
+                name: tokens.get(1).unwrap_or(&"").to_string(),
+                x: number(2)?,
+                y: number(3)?,
+                rotation: tokens.get(4).unwrap_or(&"R0").to_string(),
+                attributes: HashMap::new(),
+            }),
+            Some(&"SYMATTR") => {
+                if let Some(symbol) = symbols.last_mut() {
+                    if tokens.len() > 1 {
+                        symbol
+                            .attributes
+                            .insert(tokens[1].to_string(), tokens[2..].join(" "));
+                    }
+                }
+            }
+            Some(&"TEXT") => {
+                //the text follows the position, alignment and size, comments start with `;`
+                let mut text = line;
+                for _ in 0..5 {
+                    text = text.trim_start();
+                    text = &text[text.find(char::is_whitespace).unwrap_or(text.len())..];
+                }
+                if let Some(text) = text.trim_start().strip_prefix('!') {
+                    for directive in text.split("\\n") {
+                        directives.push(directive.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    //resolve the symbol definitions and pin positions.
+    let mut definitions: Vec<(Asy, Vec<(i64, i64)>)> = Vec::new();
+    for symbol in &symbols {
+        //the library path is given with backslashes
+        let basename = symbol.name.rsplit(['\\', '/']).next().unwrap_or("");
+        let mut asy = None;
+        for dir in &search {
+            let file = dir.join(format!("{}.asy", basename));
+            if file.is_file() {
+                asy = Some(parse_asy(&read_file(&file)?));
+                break;
+            }
+        }
+        let asy = match asy.or_else(|| builtin(basename)) {
+            Some(asy) => asy,
+            None => return Err(Error::SymbolNotFound(symbol.name.clone())),
+        };
+        let pins = asy
+            .pins
+            .iter()
+            .map(|p| {
+                let (x, y) = transform(&symbol.rotation, p.x, p.y);
+                (symbol.x + x, symbol.y + y)
+            })
+            .collect();
+        definitions.push((asy, pins));
+    }
+
+    //connect all the points which are on the same wire.
+    let mut points: Vec<(i64, i64)> = Vec::new();
+    for wire in &wires {
+        points.push(wire.0);
+        points.push(wire.1);
+    }
+    for (point, _) in &flags {
+        points.push(*point);
+    }
+    for (_, pins) in &definitions {
+        points.extend(pins);
+    }
+    points.sort_unstable();
+    points.dedup();
+    let mut parents: Vec<usize> = (0..points.len()).collect();
+    for wire in &wires {
+        let mut connected = points
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| on_wire(wire, **p))
+            .map(|(i, _)| i);
+        if let Some(first) = connected.next() {
+            for other in connected {
+                let a = find(&mut parents, first);
+                let b = find(&mut parents, other);
+                parents[b] = a;
+            }
+        }
+    }
+
+    //name the nets from the flags or number them.
+    let mut names: HashMap<usize, String> = HashMap::new();
+    for (point, name) in &flags {
+        let index = points.binary_search(point).unwrap();
+        let root = find(&mut parents, index);
+        names.insert(root, name.clone());
+    }
+    let mut index: u32 = 1;
+    let mut net = |point: &(i64, i64), parents: &mut [usize]| -> String {
+        let root = find(parents, points.binary_search(point).unwrap());
+        names
+            .entry(root)
+            .or_insert_with(|| {
+                let name = format!("N{:03}", index);
+                index += 1;
+                name
+            })
+            .clone()
+    };
+
+    let mut circuit = Circuit::new(
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        pathlist,
+    );
+    for (symbol, (asy, pins)) in symbols.iter().zip(definitions.iter()) {
+        let reference = match symbol.attributes.get("InstName") {
+            Some(name) => name.clone(),
+            None => return Err(Error::PropertyNotFound(String::from("InstName"), symbol.name.clone())),
+        };
+        let nodes: Vec<String> = pins.iter().map(|p| net(p, &mut parents)).collect();
+        let mut value = symbol
+            .attributes
+            .get("SpiceModel")
+            .or_else(|| symbol.attributes.get("Value"))
+            .cloned()
+            .or_else(|| asy.model.clone())
+            .unwrap_or_default();
+        if let Some(line) = symbol.attributes.get("SpiceLine") {
+            value = format!("{} {}", value, line);
+        }
+        let expected = match asy.prefix.as_str() {
+            "R" | "C" | "L" | "D" | "V" | "I" => 2,
+            "Q" => 3,
+            "X" => nodes.len(),
+            _ => return Err(Error::UnknownCircuitElement(reference)),
+        };
+        if nodes.len() != expected {
+            return Err(Error::NodeCount(reference, expected, nodes.len()));
+        }
+        match asy.prefix.as_str() {
+            "R" => circuit.resistor(reference, nodes[0].clone(), nodes[1].clone(), value)?,
+            "C" => circuit.capacitor(reference, nodes[0].clone(), nodes[1].clone(), value)?,
+            "L" => circuit.inductor(reference, nodes[0].clone(), nodes[1].clone(), value)?,
+            "D" => circuit.diode(reference, nodes[0].clone(), nodes[1].clone(), value)?,
+            "V" => circuit.voltage(reference, nodes[0].clone(), nodes[1].clone(), value)?,
+            "I" => circuit.current(reference, nodes[0].clone(), nodes[1].clone(), value)?,
+            "Q" => circuit.bjt(
+                reference,
+                nodes[0].clone(),
+                nodes[1].clone(),
+                nodes[2].clone(),
+                value,
+            )?,
+            _ => circuit.circuit(reference, nodes, value)?,
+        }
+    }
+
+    let mut lines = directives.into_iter();
+    parser::parse_lines(&mut circuit, &mut lines)?;
+    if let Some(directory) = path.parent() {
+        parser::resolve_includes(&mut circuit, directory);
+    }
+    Ok(circuit)
+}
+
+impl Circuit {
+    /// Import a LTspice schematic (.asc) into a new circuit.
+    pub fn load_asc(filename: &str, pathlist: Vec<String>) -> Result<Circuit, Error> {
+        load(filename, pathlist)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Circuit;
+
+    #[test]
+    fn load_asc() {
+        let circuit = Circuit::load_asc("files/ltspice/divider.asc", Vec::new()).unwrap();
+        assert_eq!(
+            vec![
+                String::from("V1 in 0 SINE(0 1 1k)"),
+                String::from("R1 in out 10k"),
+                String::from("R2 out 0 10k"),
+                String::from("R3 0 out 1k"),
+                String::from("XU1 in buf buffer"),
+                String::from(".include files/ltspice/buffer.lib"),
+                String::from(".end"),
+            ],
+            circuit.to_str(true).unwrap()
+        );
+    }
+
+    #[test]
+    fn inductor() {
+        let circuit = Circuit::load_asc("files/ltspice/inductor.asc", Vec::new()).unwrap();
+        assert_eq!(
+            vec![
+                String::from("I1 in 0 1m"),
+                String::from("L1 in 0 10m"),
+                String::from(".end"),
+            ],
+            circuit.to_str(true).unwrap()
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use std::f64::consts::PI;
+
+use num_complex::Complex64;
+
+use crate::{error::Error, filter::Builder, node::IntoNode, Circuit, Simulation};
+
+/// An element of the matching network with the reactance in ohm,
+/// positive reactances are inductors and negative capacitors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reactance {
+    Series(f64),
+    Shunt(f64),
+}
+
+/// Synthesize lossless networks which conjugate match the source to the load.
+///
+/// The networks are listed from the source to the load side. A shunt
+/// element without susceptance is returned with an infinite reactance
+/// and is not added to the circuit.
+///
+/// ```ignore
+/// let matching = Matching::new(Complex64::new(50.0, 0.0), Complex64::new(10.0, -15.0), 10e6);
+/// let network = matching.l_network()?.remove(0);
+/// matching.build(&network, &mut circuit, "M1", "in", "out")?;
+/// assert!(matching.verify(&network)? > 0.99);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matching {
+    pub source: Complex64,
+    pub load: Complex64,
+    /// The frequency in Hz.
+    pub frequency: f64,
+}
+
+/// The L-network with the shunt element on the load side, the load resistance
+/// must not be lower than the source resistance.
+fn shunt_at_load(source: Complex64, load: Complex64) -> Vec<Vec<Reactance>> {
+    let (rs, xs) = (source.re, source.im);
+    let (rl, xl) = (load.re, load.im);
+    let magnitude = rl * rl + xl * xl;
+    let root = (rl / rs).sqrt() * (magnitude - rs * rl).max(0.0).sqrt();
+    [1.0, -1.0]
+        .iter()
+        .map(|sign| (xl + sign * root) / magnitude)
+        .filter(|b| b.abs() > f64::EPSILON)
+        .map(|b| {
+            let x = 1.0 / b + xl * rs / rl - rs / (b * rl);
+            vec![Reactance::Series(x - xs), Reactance::Shunt(-1.0 / b)]
+        })
+        .collect()
+}
+
+impl Matching {
+    pub fn new(source: Complex64, load: Complex64, frequency: f64) -> Self {
+        Self {
+            source,
+            load,
+            frequency,
+        }
+    }
+    fn check(&self) -> Result<(), Error> {
+        if self.source.re <= 0.0 || self.load.re <= 0.0 {
+            return Err(Error::Matching(String::from(
+                "the source and load resistance must be positive",
+            )));
+        }
+        Ok(())
+    }
+    /// The two solutions of the L-network.
+    pub fn l_network(&self) -> Result<Vec<Vec<Reactance>>, Error> {
+        self.check()?;
+        if self.load.re >= self.source.re {
+            Ok(shunt_at_load(self.source, self.load))
+        } else {
+            //the network matches in both directions
+            Ok(shunt_at_load(self.load, self.source)
+                .into_iter()
+                .map(|network| network.into_iter().rev().collect())
+                .collect())
+        }
+    }
+    /// The low pass Pi-network with the loaded quality factor.
+    ///
+    /// The quality factor must be higher than the one of the L-network.
+    pub fn pi_network(&self, q: f64) -> Result<Vec<Reactance>, Error> {
+        self.check()?;
+        let (ys, yl) = (1.0 / self.source, 1.0 / self.load);
+        let (r1, r2) = (1.0 / ys.re, 1.0 / yl.re);
+        let virtual_resistance = r1.max(r2) / (q * q + 1.0);
+        if virtual_resistance >= r1.min(r2) {
+            return Err(Error::Matching(format!(
+                "the quality factor must be higher than {}",
+                (r1.max(r2) / r1.min(r2) - 1.0).sqrt()
+            )));
+        }
+        let q1 = (r1 / virtual_resistance - 1.0).sqrt();
+        let q2 = (r2 / virtual_resistance - 1.0).sqrt();
+        //the shunt elements also compensate the source and load susceptance
+        let shunt = |b: f64| Reactance::Shunt(if b == 0.0 { f64::INFINITY } else { -1.0 / b });
+        Ok(vec![
+            shunt(q1 / r1 - ys.im),
+            Reactance::Series((q1 + q2) * virtual_resistance),
+            shunt(q2 / r2 - yl.im),
+        ])
+    }
+    /// The impedance the source sees when the network is terminated with the load.
+    pub fn input_impedance(&self, network: &[Reactance]) -> Complex64 {
+        network
+            .iter()
+            .rev()
+            .fold(self.load, |z, element| match element {
+                Reactance::Series(x) => z + Complex64::new(0.0, *x),
+                Reactance::Shunt(x) if x.is_infinite() => z,
+                Reactance::Shunt(x) => 1.0 / (1.0 / z + 1.0 / Complex64::new(0.0, *x)),
+            })
+    }
+    /// Add the network between the source side input and the load side output.
+    ///
+    /// Series elements without reactance are added as 0V sources.
+    pub fn build(
+        &self,
+        network: &[Reactance],
+        circuit: &mut Circuit,
+        name: &str,
+        input: impl IntoNode,
+        output: impl IntoNode,
+    ) -> Result<(), Error> {
+        let output = String::from(output.into_node()?);
+        let mut node = String::from(input.into_node()?);
+        let mut builder = Builder {
+            circuit,
+            name: name.to_string(),
+            opamp: String::new(),
+            count: 0,
+        };
+        let w = 2.0 * PI * self.frequency;
+        let series = network
+            .iter()
+            .filter(|e| matches!(e, Reactance::Series(_)))
+            .count();
+        let mut added = 0;
+        for element in network {
+            match element {
+                Reactance::Shunt(x) if x.is_infinite() => {}
+                Reactance::Shunt(x) if *x > 0.0 => builder.element('L', &node, "0", x / w)?,
+                Reactance::Shunt(x) => builder.element('C', &node, "0", -1.0 / (w * x))?,
+                Reactance::Series(x) => {
+                    added += 1;
+                    let next = if added == series {
+                        output.clone()
+                    } else {
+                        builder.node()
+                    };
+                    if x.abs() < 1e-12 {
+                        let index = builder.next();
+                        builder.circuit.voltage(
+                            format!("V{}_{}", name, index),
+                            node.as_str(),
+                            next.as_str(),
+                            String::from("0"),
+                        )?;
+                    } else if *x > 0.0 {
+                        builder.element('L', &node, &next, x / w)?;
+                    } else {
+                        builder.element('C', &node, &next, -1.0 / (w * x))?;
+                    }
+                    node = next;
+                }
+            }
+        }
+        if series == 0 {
+            let index = builder.next();
+            builder.circuit.voltage(
+                format!("V{}_{}", name, index),
+                node.as_str(),
+                output.as_str(),
+                String::from("0"),
+            )?;
+        }
+        Ok(())
+    }
+    /// Simulate the network at the frequency and get the ratio of the power
+    /// delivered to the load and the available power of the source.
+    pub fn verify(&self, network: &[Reactance]) -> Result<f64, Error> {
+        let mut circuit = Circuit::new(String::from("matching"), Vec::new());
+        circuit.voltage(String::from("VSRC"), "src", "0", String::from("DC 0 AC 1"))?;
+        let mut builder = Builder {
+            circuit: &mut circuit,
+            name: String::from("TERM"),
+            opamp: String::new(),
+            count: 0,
+        };
+        let w = 2.0 * PI * self.frequency;
+        for (z, n0, n1, n2) in [
+            (self.source, "src", "src_x", "in"),
+            (self.load, "out", "load_x", "0"),
+        ] {
+            builder.element('R', n0, n1, z.re)?;
+            if z.im > 0.0 {
+                builder.element('L', n1, n2, z.im / w)?;
+            } else if z.im < 0.0 {
+                builder.element('C', n1, n2, -1.0 / (w * z.im))?;
+            } else {
+                let index = builder.next();
+                builder
+                    .circuit
+                    .voltage(format!("VTERM_{}", index), n1, n2, String::from("0"))?;
+            }
+        }
+        self.build(network, &mut circuit, "M", "in", "out")?;
+        let frequency = self.frequency.to_string();
+        let result = Simulation::new(circuit).ac_complex(&frequency, &frequency, 1, "lin")?;
+        let out = result
+            .get("out")?
+            .first()
+            .ok_or_else(|| Error::Matching(String::from("no output vector")))?;
+        let current = out / self.load;
+        let power = current.norm_sqr() * self.load.re / 2.0;
+        Ok(power / (1.0 / (8.0 * self.source.re)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex64;
+
+    use super::{Matching, Reactance};
+    use crate::Circuit;
+
+    #[test]
+    fn l_network() {
+        let matching = Matching::new(Complex64::new(50.0, 0.0), Complex64::new(200.0, 0.0), 1e6);
+        let networks = matching.l_network().unwrap();
+        assert_eq!(2, networks.len());
+        let Reactance::Series(x) = networks[0][0] else {
+            panic!("expected series element");
+        };
+        assert!((x - 86.6).abs() < 0.1);
+        for (source, load) in [((50.0, 0.0), (10.0, -15.0)), ((25.0, 30.0), (300.0, 80.0))] {
+            let matching = Matching::new(
+                Complex64::new(source.0, source.1),
+                Complex64::new(load.0, load.1),
+                1e6,
+            );
+            for network in matching.l_network().unwrap() {
+                let z = matching.input_impedance(&network);
+                assert!((z - matching.source.conj()).norm() < 1e-6);
+            }
+            let network = matching.pi_network(5.0).unwrap();
+            let z = matching.input_impedance(&network);
+            assert!((z - matching.source.conj()).norm() < 1e-6);
+        }
+        assert!(matching.pi_network(0.1).is_err());
+
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        matching
+            .build(&networks[0], &mut circuit, "M1", "in", "out")
+            .unwrap();
+        assert_eq!(
+            vec!["LM1_1 in out 13.783222385544802u", "CM1_2 out 0 1.3783222385544803n", ".end"],
+            circuit.to_str(true).unwrap()
+        );
+    }
+}
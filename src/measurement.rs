@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use elektron_ngspice::NgSpice;
+
+use crate::{
+    circuit::{ngspice_error, Cb},
+    definition::Analysis,
+    error::Error,
+    Simulation,
+};
+
+/// The crossing of a signal to trigger a measurement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Rise,
+    Fall,
+    Cross,
+}
+
+/// The point where the vector crosses the value, e.g. the second rising
+/// edge of `v(out)` through 2.5V.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trigger {
+    pub vector: String,
+    pub value: f64,
+    pub edge: Edge,
+    /// The occurrence of the edge, starting with 1.
+    pub occurrence: usize,
+}
+
+impl Trigger {
+    pub fn new(vector: &str, value: f64, edge: Edge, occurrence: usize) -> Self {
+        Self {
+            vector: vector.to_string(),
+            value,
+            edge,
+            occurrence,
+        }
+    }
+    fn edge(&self) -> String {
+        let edge = match self.edge {
+            Edge::Rise => "RISE",
+            Edge::Fall => "FALL",
+            Edge::Cross => "CROSS",
+        };
+        format!("{}={}", edge, self.occurrence)
+    }
+    fn condition(&self) -> String {
+        format!("VAL={} {}", self.value, self.edge())
+    }
+}
+
+/// The functions of a vector between two points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Avg,
+    Rms,
+    Pp,
+    Min,
+    Max,
+    Integ,
+}
+
+/// A `meas` command of ngspice.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Measurement {
+    /// The time or frequency between the trigger and the target.
+    Delay {
+        name: String,
+        trig: Trigger,
+        targ: Trigger,
+    },
+    /// The time or frequency of the crossing.
+    When { name: String, when: Trigger },
+    /// The value of the vector at the crossing of the trigger.
+    Find {
+        name: String,
+        vector: String,
+        when: Trigger,
+    },
+    /// The value of the vector at the time or frequency.
+    FindAt {
+        name: String,
+        vector: String,
+        at: f64,
+    },
+    /// The function of the vector, optionally limited to the range.
+    Range {
+        name: String,
+        function: Function,
+        vector: String,
+        from: Option<f64>,
+        to: Option<f64>,
+    },
+}
+
+impl Measurement {
+    pub fn name(&self) -> &str {
+        match self {
+            Measurement::Delay { name, .. }
+            | Measurement::When { name, .. }
+            | Measurement::Find { name, .. }
+            | Measurement::FindAt { name, .. }
+            | Measurement::Range { name, .. } => name,
+        }
+    }
+    /// The ngspice command for the analysis type, `tran` or `ac`.
+    pub fn command(&self, analysis: &str) -> String {
+        match self {
+            Measurement::Delay { name, trig, targ } => format!(
+                "meas {} {} TRIG {} {} TARG {} {}",
+                analysis,
+                name,
+                trig.vector,
+                trig.condition(),
+                targ.vector,
+                targ.condition()
+            ),
+            Measurement::When { name, when } => format!(
+                "meas {} {} WHEN {}={} {}",
+                analysis,
+                name,
+                when.vector,
+                when.value,
+                when.edge()
+            ),
+            Measurement::Find { name, vector, when } => format!(
+                "meas {} {} FIND {} WHEN {}={} {}",
+                analysis,
+                name,
+                vector,
+                when.vector,
+                when.value,
+                when.edge()
+            ),
+            Measurement::FindAt { name, vector, at } => {
+                format!("meas {} {} FIND {} AT={}", analysis, name, vector, at)
+            }
+            Measurement::Range {
+                name,
+                function,
+                vector,
+                from,
+                to,
+            } => {
+                let function = match function {
+                    Function::Avg => "AVG",
+                    Function::Rms => "RMS",
+                    Function::Pp => "PP",
+                    Function::Min => "MIN",
+                    Function::Max => "MAX",
+                    Function::Integ => "INTEG",
+                };
+                let mut command = format!("meas {} {} {} {}", analysis, name, function, vector);
+                if let Some(from) = from {
+                    command += &format!(" FROM={}", from);
+                }
+                if let Some(to) = to {
+                    command += &format!(" TO={}", to);
+                }
+                command
+            }
+        }
+    }
+}
+
+/// Get the result of the measurement from the ngspice output,
+/// e.g. `stdout delay = 1.23e-06 targ= 2.23e-06 trig= 1e-06`.
+fn parse(output: &[String], name: &str) -> Option<f64> {
+    output.iter().rev().find_map(|line| {
+        let line = line
+            .trim_start_matches("stdout")
+            .trim_start_matches("stderr")
+            .trim();
+        let (key, value) = line.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case(name) {
+            return None;
+        }
+        value.split_whitespace().next()?.parse::<f64>().ok()
+    })
+}
+
+impl Simulation {
+    /// Run the analysis and the measurements.
+    ///
+    /// The results are returned by the name of the measurement, failed
+    /// measurements, e.g. when the signal never crosses the value, are NaN.
+    ///
+    /// ```ignore
+    /// let rise = Measurement::Delay {
+    ///     name: String::from("rise"),
+    ///     trig: Trigger::new("v(out)", 0.1, Edge::Rise, 1),
+    ///     targ: Trigger::new("v(out)", 0.9, Edge::Rise, 1),
+    /// };
+    /// let result = simulation.measure(&analysis, &[rise])?;
+    /// ```
+    pub fn measure(
+        &mut self,
+        analysis: &Analysis,
+        measurements: &[Measurement],
+    ) -> Result<HashMap<String, f64>, Error> {
+        let mut c = Cb::new();
+        let ngspice = NgSpice::new(&mut c).map_err(ngspice_error)?;
+        ngspice.circuit(self.deck()?).map_err(ngspice_error)?;
+        ngspice
+            .command(analysis.command().as_str())
+            .map_err(ngspice_error)?;
+        let kind = match analysis {
+            Analysis::Tran { .. } => "tran",
+            Analysis::Ac { .. } => "ac",
+        };
+        for measurement in measurements {
+            ngspice
+                .command(measurement.command(kind).as_str())
+                .map_err(ngspice_error)?;
+        }
+        let output = c.strs.clone();
+        self.buffer = Some(output.clone());
+        Ok(measurements
+            .iter()
+            .map(|m| {
+                (
+                    m.name().to_string(),
+                    parse(&output, m.name()).unwrap_or(f64::NAN),
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Edge, Function, Measurement, Trigger};
+
+    #[test]
+    fn measurement() {
+        let delay = Measurement::Delay {
+            name: String::from("rise"),
+            trig: Trigger::new("v(out)", 0.1, Edge::Rise, 1),
+            targ: Trigger::new("v(out)", 0.9, Edge::Rise, 1),
+        };
+        assert_eq!(
+            "meas tran rise TRIG v(out) VAL=0.1 RISE=1 TARG v(out) VAL=0.9 RISE=1",
+            delay.command("tran")
+        );
+        let find = Measurement::Find {
+            name: String::from("vin"),
+            vector: String::from("v(in)"),
+            when: Trigger::new("v(out)", 2.5, Edge::Fall, 2),
+        };
+        assert_eq!(
+            "meas tran vin FIND v(in) WHEN v(out)=2.5 FALL=2",
+            find.command("tran")
+        );
+        let max = Measurement::Range {
+            name: String::from("peak"),
+            function: Function::Max,
+            vector: String::from("v(out)"),
+            from: Some(1e-3),
+            to: None,
+        };
+        assert_eq!("meas tran peak MAX v(out) FROM=0.001", max.command("tran"));
+
+        let output = vec![
+            String::from("stdout Doing analysis at TEMP = 27.000000 and TNOM = 27.000000"),
+            String::from("stdout rise                =  1.234560e-06 targ=  2.234560e-06 trig=  1.000000e-06"),
+            String::from("stdout peak                =  4.990000e+00 at=  2.000000e-03"),
+        ];
+        assert_eq!(Some(1.23456e-6), parse(&output, "rise"));
+        assert_eq!(Some(4.99), parse(&output, "PEAK"));
+        assert_eq!(None, parse(&output, "vin"));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{circuit::CircuitItem, error::Error, hierarchy::HierarchicalName, Circuit};
+
+impl Circuit {
+    /// Import the elements, models, includes and subcircuits of the other
+    /// circuit.
+    ///
+    /// The references and the nets of the other circuit get the prefix, the
+    /// shared nets and the ground keep their names and connect the circuits.
+    /// Elements with the same name are an error, as are models, subcircuits
+    /// and parameters with the same name and a different definition. The
+    /// circuit is unchanged when the merge fails.
+    ///
+    /// ```ignore
+    /// let mut bench = Circuit::new(String::from("bench"), Vec::new());
+    /// bench.merge(&dut, "dut_", &["in", "out"])?;
+    /// bench.merge(&stimulus, "stim_", &["in"])?;
+    /// ```
+    pub fn merge(&mut self, other: &Circuit, prefix: &str, shared: &[&str]) -> Result<(), Error> {
+        let mut merged = self.clone();
+        //the couplings use the element name of the prefixed inductor, like flatten does
+        let inductor = |name: &str| -> String {
+            let reference = other
+                .items
+                .iter()
+                .filter(|i| matches!(i, CircuitItem::L(..)))
+                .find(|i| {
+                    i.reference().is_some_and(|r| r.eq_ignore_ascii_case(name))
+                        || i.name().is_some_and(|n| n.eq_ignore_ascii_case(name))
+                })
+                .and_then(|i| i.reference())
+                .map(|r| r.as_str())
+                .unwrap_or(name);
+            HierarchicalName::new(Vec::new(), &format!("{}{}", prefix, reference)).element('L')
+        };
+        for item in &other.items {
+            let mut item = item.clone();
+            if let Some(reference) = item.reference_mut() {
+                *reference = format!("{}{}", prefix, reference);
+            }
+            if let CircuitItem::K(_, l1, l2, _) = &mut item {
+                *l1 = inductor(l1);
+                *l2 = inductor(l2);
+            }
+            for node in item.nodes_mut() {
+                if other.is_ground(node) {
+                    if !merged.is_ground(node) {
+                        *node = String::from("0");
+                    }
+                } else if !shared.contains(&node.as_str()) {
+                    *node = format!("{}{}", prefix, node);
+                }
+            }
+            match &item {
+                CircuitItem::Model(name, _, _) => {
+                    let existing = merged.items.iter().find(
+                        |i| matches!(i, CircuitItem::Model(n, _, _) if n.eq_ignore_ascii_case(name)),
+                    );
+                    match existing {
+                        Some(existing) if *existing == item => {}
+                        Some(_) => return Err(Error::DuplicateDefinition(name.clone())),
+                        None => merged.items.push(item),
+                    }
+                }
+                CircuitItem::Include(..) => {
+                    if !merged.items.contains(&item) {
+                        merged.items.push(item);
+                    }
+                }
+                _ => merged.insert(item)?,
+            }
+        }
+        for (name, subcircuit) in &other.subcircuits {
+            match merged.subcircuits.get(name) {
+                Some(existing) if existing == subcircuit => {}
+                Some(_) => return Err(Error::DuplicateDefinition(name.clone())),
+                None => {
+                    merged.subcircuits.insert(name.clone(), subcircuit.clone());
+                }
+            }
+        }
+        for (name, value) in &other.params {
+            match merged.params.iter().find(|(n, _)| n == name) {
+                Some((_, existing)) if existing == value => {}
+                Some(_) => return Err(Error::DuplicateDefinition(name.clone())),
+                None => merged.params.push((name.clone(), value.clone())),
+            }
+        }
+        for filename in &other.osdi {
+            if !merged.osdi.contains(filename) {
+                merged.osdi.push(filename.clone());
+            }
+        }
+        *self = merged;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, Circuit};
+
+    #[test]
+    fn merge() {
+        let mut dut = Circuit::new(String::from("dut"), Vec::new());
+        dut.resistor(String::from("R1"), "in", "mid", String::from("10k"))
+            .unwrap();
+        dut.diode(String::from("D1"), "mid", "GND", String::from("DMOD"))
+            .unwrap();
+        dut.model(
+            String::from("DMOD"),
+            String::from("D"),
+            String::from("IS=1e-14"),
+        );
+        let mut stimulus = Circuit::new(String::from("stimulus"), Vec::new());
+        stimulus
+            .voltage(String::from("V1"), "in", "GND", String::from("DC 5"))
+            .unwrap();
+        stimulus
+            .resistor(String::from("R1"), "in", "GND", String::from("1Meg"))
+            .unwrap();
+        let mut bench = Circuit::new(String::from("bench"), Vec::new());
+        bench.merge(&dut, "", &[]).unwrap();
+        bench.merge(&stimulus, "stim_", &["in"]).unwrap();
+        assert_eq!(
+            vec![
+                "R1 in mid 10k",
+                "D1 mid 0 DMOD",
+                ".model DMOD D(IS=1e-14)",
+                "Vstim_V1 in 0 DC 5",
+                "Rstim_R1 in 0 1Meg",
+            ],
+            bench.to_str(false).unwrap()
+        );
+        assert!(matches!(
+            bench.merge(&dut, "", &[]),
+            Err(Error::DuplicateReference(_))
+        ));
+        let mut other = Circuit::new(String::from("other"), Vec::new());
+        other.model(
+            String::from("dmod"),
+            String::from("D"),
+            String::from("IS=2e-14"),
+        );
+        assert!(matches!(
+            bench.merge(&other, "x_", &[]),
+            Err(Error::DuplicateDefinition(_))
+        ));
+        assert_eq!(5, bench.to_str(false).unwrap().len());
+    }
+    #[test]
+    fn coupling() {
+        let mut dut = Circuit::new(String::from("dut"), Vec::new());
+        dut.inductor(String::from("L1"), "in", "GND", String::from("1m"))
+            .unwrap();
+        dut.inductor(String::from("2"), "out", "GND", String::from("1m"))
+            .unwrap();
+        dut.coupling(
+            String::from("K1"),
+            String::from("L1"),
+            String::from("L2"),
+            String::from("0.9"),
+        )
+        .unwrap();
+        let mut bench = Circuit::new(String::from("bench"), Vec::new());
+        bench.merge(&dut, "dut_", &["in"]).unwrap();
+        assert_eq!(
+            vec![
+                "Ldut_L1 in 0 1m",
+                "Ldut_2 dut_out 0 1m",
+                "Kdut_K1 LDUT_L1 LDUT_2 0.9",
+            ],
+            bench.to_str(false).unwrap()
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::Simulation;
+
+lazy_static! {
+    static ref RE_CHECK_NODE: Regex = Regex::new(r#"(?i)check nodes?\s+"?([^"\s]+)"?"#).unwrap();
+    static ref RE_TROUBLE_NODE: Regex =
+        Regex::new(r#"(?i)trouble with node\s+"?([^"\s]+)"?"#).unwrap();
+}
+
+/// The convergence problems reported by ngspice.
+const CONVERGENCE: [&str; 6] = [
+    "timestep too small",
+    "stepping failed",
+    "iteration limit",
+    "solution failed",
+    "no convergence",
+    "too many iterations",
+];
+
+/// A message of ngspice from the console output of the simulation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpiceMessage {
+    /// An error, e.g. an unknown model or a syntax error in the netlist.
+    Error(String),
+    Warning(String),
+    /// The simulation did not converge, with the node when ngspice names it.
+    Convergence(String, Option<String>),
+    /// The matrix is singular, with the node ngspice suggests to check.
+    SingularMatrix(String),
+    Note(String),
+}
+
+impl std::fmt::Display for SpiceMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpiceMessage::Error(message) => write!(f, "error: {}", message),
+            SpiceMessage::Warning(message) => write!(f, "warning: {}", message),
+            SpiceMessage::Convergence(message, Some(node)) => {
+                write!(f, "convergence at node {}: {}", node, message)
+            }
+            SpiceMessage::Convergence(message, None) => write!(f, "convergence: {}", message),
+            SpiceMessage::SingularMatrix(node) => write!(f, "singular matrix at node {}", node),
+            SpiceMessage::Note(message) => write!(f, "note: {}", message),
+        }
+    }
+}
+
+/// Strip the label like `Error:` from the message.
+fn strip_label<'a>(message: &'a str, label: &str) -> &'a str {
+    message[label.len()..].trim_start_matches(':').trim()
+}
+
+impl SpiceMessage {
+    /// The message of a console line, other output of ngspice is `None`.
+    pub fn parse(line: &str) -> Option<SpiceMessage> {
+        let message = line
+            .strip_prefix("stdout")
+            .or_else(|| line.strip_prefix("stderr"))
+            .unwrap_or(line)
+            .trim();
+        let lower = message.to_lowercase();
+        if lower.contains("singular matrix") {
+            return Some(match RE_CHECK_NODE.captures(message) {
+                Some(node) => SpiceMessage::SingularMatrix(node[1].to_string()),
+                None => SpiceMessage::Warning(message.to_string()),
+            });
+        }
+        if CONVERGENCE.iter().any(|c| lower.contains(c)) {
+            let node = RE_TROUBLE_NODE
+                .captures(message)
+                .map(|node| node[1].to_string());
+            return Some(SpiceMessage::Convergence(message.to_string(), node));
+        }
+        if lower.starts_with("error") {
+            Some(SpiceMessage::Error(
+                strip_label(message, "error").to_string(),
+            ))
+        } else if lower.starts_with("warning") {
+            Some(SpiceMessage::Warning(
+                strip_label(message, "warning").to_string(),
+            ))
+        } else if lower.starts_with("note") {
+            Some(SpiceMessage::Note(strip_label(message, "note").to_string()))
+        } else {
+            None
+        }
+    }
+    /// The messages of the console output.
+    pub fn parse_all(lines: &[String]) -> Vec<SpiceMessage> {
+        lines.iter().filter_map(|line| Self::parse(line)).collect()
+    }
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            SpiceMessage::Error(_)
+                | SpiceMessage::Convergence(..)
+                | SpiceMessage::SingularMatrix(_)
+        )
+    }
+}
+
+impl Simulation {
+    /// The messages of ngspice from the last analysis.
+    pub fn messages(&self) -> Vec<SpiceMessage> {
+        self.buffer
+            .as_deref()
+            .map(SpiceMessage::parse_all)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpiceMessage;
+
+    #[test]
+    fn messages() {
+        let lines: Vec<String> = [
+            "stdout Circuit: divider",
+            "stderr Warning: singular matrix:  check node out",
+            "stderr doAnalyses: TRAN:  Timestep too small; time = 1e-06, timestep = 1.25e-20: trouble with node \"mid\"",
+            "stderr Error: unknown subckt: x1 in out opamp",
+            "stderr Note: Starting dynamic gmin stepping",
+            "stdout Reference value :  0.00000e+00",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        let messages = SpiceMessage::parse_all(&lines);
+        assert_eq!(
+            vec![
+                SpiceMessage::SingularMatrix(String::from("out")),
+                SpiceMessage::Convergence(
+                    String::from("doAnalyses: TRAN:  Timestep too small; time = 1e-06, timestep = 1.25e-20: trouble with node \"mid\""),
+                    Some(String::from("mid"))
+                ),
+                SpiceMessage::Error(String::from("unknown subckt: x1 in out opamp")),
+                SpiceMessage::Note(String::from("Starting dynamic gmin stepping")),
+            ],
+            messages
+        );
+        assert_eq!(3, messages.iter().filter(|m| m.is_error()).count());
+        assert_eq!("singular matrix at node out", messages[0].to_string());
+    }
+}
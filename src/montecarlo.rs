@@ -0,0 +1,357 @@
+use std::{
+    collections::HashMap,
+    fs,
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use num_complex::Complex64;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    circuit::Vectors,
+    definition::Analysis,
+    error::Error,
+    sampling::{self, Sampling},
+    tolerance::Distribution,
+    Circuit, Simulation,
+};
+
+/// Counter to give the files of concurrent batch runs unique names.
+static BATCH: AtomicUsize = AtomicUsize::new(0);
+
+/// The results of a Monte Carlo analysis.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ensemble {
+    /// The component values of each run.
+    pub values: Vec<HashMap<String, f64>>,
+    /// The simulated vectors, one entry per run.
+    pub vectors: HashMap<String, Vec<Vec<f64>>>,
+}
+
+impl Ensemble {
+    /// The number of runs.
+    pub fn runs(&self) -> usize {
+        self.values.len()
+    }
+    /// The results of the vector for all runs.
+    pub fn vector(&self, name: &str) -> Option<&Vec<Vec<f64>>> {
+        self.vectors.get(name)
+    }
+    /// The simulated vectors of the run.
+    pub fn run(&self, index: usize) -> HashMap<String, Vec<f64>> {
+        self.vectors
+            .iter()
+            .filter_map(|(name, runs)| runs.get(index).map(|v| (name.clone(), v.clone())))
+            .collect()
+    }
+    /// Apply the measurement to every run.
+    pub fn measure<F>(&self, measure: F) -> Vec<f64>
+    where
+        F: Fn(&HashMap<String, Vec<f64>>) -> f64,
+    {
+        (0..self.runs()).map(|i| measure(&self.run(i))).collect()
+    }
+    fn push(&mut self, values: HashMap<String, f64>, vectors: Vectors) {
+        self.values.push(values);
+        for (name, data) in vectors {
+            self.vectors.entry(name).or_default().push(data);
+        }
+    }
+}
+
+/// Run an analysis with randomized component values.
+///
+/// The values of the resistors, capacitors and inductors with a
+/// tolerance are drawn from the tolerance distribution for every run.
+///
+/// ```ignore
+/// circuit.set_tolerance("R1", Tolerance::new(0.05))?;
+/// let mut montecarlo = MonteCarlo::new(circuit, analysis, 100);
+/// montecarlo.set_seed(1);
+/// let ensemble = montecarlo.run()?;
+/// let out = ensemble.vector("out");
+/// ```
+#[derive(Debug, Clone)]
+pub struct MonteCarlo {
+    circuit: Circuit,
+    analysis: Analysis,
+    runs: usize,
+    seed: Option<u64>,
+    workers: usize,
+    sampling: Sampling,
+}
+
+impl MonteCarlo {
+    pub fn new(circuit: Circuit, analysis: Analysis, runs: usize) -> Self {
+        Self {
+            circuit,
+            analysis,
+            runs,
+            seed: None,
+            workers: 0,
+            sampling: Sampling::Random,
+        }
+    }
+    /// Set the seed of the random generator to get reproducible runs.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+    /// Run the simulations in parallel ngspice processes.
+    ///
+    /// The ngspice shared library can only run one simulation per process,
+    /// the workers call the `ngspice` executable in batch mode.
+    pub fn set_workers(&mut self, workers: usize) {
+        self.workers = workers;
+    }
+    /// Set the sampling strategy, the default are independent random samples.
+    pub fn set_sampling(&mut self, sampling: Sampling) {
+        self.sampling = sampling;
+    }
+    /// Draw the component values for all runs.
+    pub fn samples(&self) -> Result<Vec<HashMap<String, f64>>, Error> {
+        let components = self.circuit.toleranced()?;
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let points = sampling::points(self.sampling, self.runs, components.len(), &mut rng)?;
+        let mut result = Vec::new();
+        for point in points {
+            let mut values = HashMap::new();
+            for ((reference, nominal, tolerance), u) in components.iter().zip(point) {
+                let (min, max) = tolerance.limits(*nominal);
+                let value = match tolerance.distribution {
+                    Distribution::Uniform => min + u * (max - min),
+                    Distribution::Gaussian => {
+                        let sigma = nominal * tolerance.relative / 3.0;
+                        nominal + sigma * sampling::normal_quantile(u)
+                    }
+                };
+                values.insert(reference.clone(), value);
+            }
+            result.push(values);
+        }
+        Ok(result)
+    }
+    /// Run the analysis for all samples.
+    pub fn run(&self) -> Result<Ensemble, Error> {
+        let samples = self.samples()?;
+        let mut ensemble = Ensemble::default();
+        if self.workers < 2 {
+            for values in samples {
+                let mut simulation = Simulation::new(self.circuit.with_values(&values)?);
+                let vectors = self.analysis.run(&mut simulation)?;
+                ensemble.push(values, vectors);
+            }
+            return Ok(ensemble);
+        }
+
+        let next = AtomicUsize::new(0);
+        let results: Vec<(usize, Result<Vectors, Error>)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::SeqCst);
+                            if index >= samples.len() {
+                                break;
+                            }
+                            let result = self
+                                .circuit
+                                .with_values(&samples[index])
+                                .and_then(|c| batch(&c, &self.analysis, index));
+                            results.push((index, result));
+                        }
+                        results
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap_or_default())
+                .collect()
+        });
+        let mut runs: Vec<Option<Result<Vectors, Error>>> = vec![None; samples.len()];
+        for (index, result) in results {
+            runs[index] = Some(result);
+        }
+        for (run, values) in runs.into_iter().zip(samples) {
+            let vectors = run.unwrap_or_else(|| {
+                Err(Error::Simulation(
+                    String::from("monte carlo worker failed"),
+                    Vec::new(),
+                ))
+            });
+            ensemble.push(values, vectors?);
+        }
+        Ok(ensemble)
+    }
+}
+
+/// Simulate the circuit with the ngspice executable in batch mode.
+fn batch(circuit: &Circuit, analysis: &Analysis, run: usize) -> Result<Vectors, Error> {
+    let base = std::env::temp_dir().join(format!(
+        "elektron_mc_{}_{}_{}",
+        std::process::id(),
+        BATCH.fetch_add(1, Ordering::SeqCst),
+        run
+    ));
+    let netlist = base.with_extension("cir");
+    let raw = base.with_extension("raw");
+    let mut lines = vec![format!("* monte carlo run {}", run)];
+    for line in circuit.to_str(true)? {
+        if line == ".end" {
+            lines.push(format!(".{}", analysis.command()));
+        }
+        lines.push(line);
+    }
+    fs::write(&netlist, lines.join("\n") + "\n")?;
+    let output = Command::new("ngspice")
+        .env("SPICE_ASCIIRAWFILE", "1")
+        .arg("-b")
+        .arg("-r")
+        .arg(&raw)
+        .arg(&netlist)
+        .output();
+    let result = match output {
+        Ok(output) if output.status.success() => fs::read_to_string(&raw)
+            .map_err(Error::from)
+            .and_then(|content| parse_raw(&content)),
+        Ok(output) => Err(Error::Simulation(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+            Vec::new(),
+        )),
+        Err(err) => Err(Error::from(err)),
+    };
+    let _ = fs::remove_file(&netlist);
+    let _ = fs::remove_file(&raw);
+    result
+}
+
+/// Parse the first plot of an ASCII raw file.
+///
+/// The vectors are named like the vectors of the shared library, node
+/// voltages without `v()` and currents as `name#branch`. Complex values
+/// are returned as magnitudes like the vectors of the shared library.
+fn parse_raw(content: &str) -> Result<Vectors, Error> {
+    let mut names: Vec<String> = Vec::new();
+    let mut lines = content.lines();
+    for line in lines.by_ref() {
+        if line.starts_with("Variables:") {
+            break;
+        }
+    }
+    for line in lines.by_ref() {
+        if line.starts_with("Values:") {
+            break;
+        }
+        let mut parts = line.split_whitespace();
+        if let (Some(_), Some(name)) = (parts.next(), parts.next()) {
+            let name = name.to_lowercase();
+            let name = if let Some(node) = name.strip_prefix("v(").and_then(|n| n.strip_suffix(')'))
+            {
+                node.to_string()
+            } else if let Some(element) = name.strip_prefix("i(").and_then(|n| n.strip_suffix(')'))
+            {
+                format!("{}#branch", element)
+            } else {
+                name
+            };
+            names.push(name);
+        }
+    }
+    if names.is_empty() {
+        return Err(Error::Simulation(
+            String::from("no vectors in raw file"),
+            Vec::new(),
+        ));
+    }
+    let mut vectors: Vec<Vec<f64>> = vec![Vec::new(); names.len()];
+    let mut tokens = lines.flat_map(|l| l.split_whitespace());
+    'points: while tokens.next().is_some() {
+        for vector in vectors.iter_mut() {
+            let Some(token) = tokens.next() else {
+                break 'points;
+            };
+            //complex values are written as real,imaginary
+            let parse = |value: &str| {
+                value.parse::<f64>().map_err(|_| {
+                    Error::Simulation(format!("can not parse raw value {}", token), Vec::new())
+                })
+            };
+            let value = match token.split_once(',') {
+                Some((real, imag)) => Complex64::new(parse(real)?, parse(imag)?).norm(),
+                None => parse(token)?,
+            };
+            vector.push(value);
+        }
+    }
+    Ok(names.into_iter().zip(vectors).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_raw, MonteCarlo};
+    use crate::{Analysis, Circuit, Sampling, Tolerance};
+
+    #[test]
+    fn samples() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(
+                String::from("R1"),
+                String::from("1"),
+                String::from("0"),
+                String::from("1k"),
+            )
+            .unwrap();
+        circuit
+            .resistor(
+                String::from("R2"),
+                String::from("1"),
+                String::from("0"),
+                String::from("1k"),
+            )
+            .unwrap();
+        circuit.set_tolerance("R1", Tolerance::new(0.05)).unwrap();
+        let analysis = Analysis::Tran {
+            step: String::from("1u"),
+            stop: String::from("1m"),
+            start: String::from("0"),
+        };
+        let mut montecarlo = MonteCarlo::new(circuit, analysis, 50);
+        montecarlo.set_seed(1);
+        let samples = montecarlo.samples().unwrap();
+        assert_eq!(50, samples.len());
+        assert!(samples.iter().all(|s| !s.contains_key("R2")));
+        assert!(samples.iter().all(|s| (950.0..=1050.0).contains(&s["R1"])));
+        assert_eq!(samples, montecarlo.samples().unwrap());
+        montecarlo.set_sampling(Sampling::LatinHypercube);
+        let mut strata: Vec<usize> = montecarlo
+            .samples()
+            .unwrap()
+            .iter()
+            .map(|s| ((s["R1"] - 950.0) / 2.0) as usize)
+            .collect();
+        strata.sort_unstable();
+        assert_eq!((0..50).collect::<Vec<usize>>(), strata);
+    }
+    #[test]
+    fn raw_file() {
+        let raw = "Title: test\nPlotname: Transient Analysis\nFlags: real\nNo. Variables: 3\nNo. Points: 2\nVariables:\n\t0\ttime\ttime\n\t1\tv(out)\tvoltage\n\t2\ti(v1)\tcurrent\nValues:\n 0\t0.0\n\t1.0\n\t-1e-3\n\n 1\t1e-6\n\t2.0\n\t-2e-3\n";
+        let vectors = parse_raw(raw).unwrap();
+        assert_eq!(vec![0.0, 1e-6], vectors["time"]);
+        assert_eq!(vec![1.0, 2.0], vectors["out"]);
+        assert_eq!(vec![-1e-3, -2e-3], vectors["v1#branch"]);
+    }
+    #[test]
+    fn raw_file_complex() {
+        let raw = "Title: test\nPlotname: AC Analysis\nFlags: complex\nNo. Variables: 2\nNo. Points: 1\nVariables:\n\t0\tfrequency\tfrequency\n\t1\tv(out)\tvoltage\nValues:\n 0\t1.0e3,0.0\n\t3.0,-4.0\n";
+        let vectors = parse_raw(raw).unwrap();
+        assert_eq!(vec![1e3], vectors["frequency"]);
+        assert_eq!(vec![5.0], vectors["out"]);
+    }
+}
@@ -5,9 +5,13 @@ use std::{
 
 use ndarray::Array1;
 
-use crate::error::Error;
+use crate::{
+    erc::{ErcConfig, PinMatrix, PinType, Severity},
+    error::Error,
+    kicad, node, spice,
+};
 use elektron_sexp::{
-        Pin, SchemaElement, Symbol, Wire,
+        SchemaElement, Symbol,
         Schema, Shape, Transform,
 };
 
@@ -15,14 +19,110 @@ use super::Circuit;
 
 #[derive(Clone, Debug)]
 pub enum Erc {
-    PinNotConnected(Pin),
-    WireNotConnected(Wire),
+    PinNotConnected(SymbolPin),
+    WireNotConnected(Point, Point),
+    /// An input or passive pin is the only pin on the net.
+    SinglePinNet(String, SymbolPin),
+    /// A power input pin is on a net without a power output or power flag.
+    PowerNotDriven(String, SymbolPin),
+    /// Two pins with conflicting types are on the same net.
+    PinConflict(String, SymbolPin, SymbolPin),
+    /// A violation of a custom `ErcRule`.
+    Custom {
+        code: String,
+        message: String,
+        net: Option<String>,
+        pins: Vec<SymbolPin>,
+    },
+}
+
+impl Erc {
+    /// The machine readable code of the violation, named like the KiCad ERC codes.
+    pub fn code(&self) -> &str {
+        match self {
+            Erc::PinNotConnected(_) => "pin_not_connected",
+            Erc::WireNotConnected(_, _) => "wire_dangling",
+            Erc::SinglePinNet(_, _) => "single_pin_net",
+            Erc::PowerNotDriven(_, _) => "power_pin_not_driven",
+            Erc::PinConflict(_, _, _) => "pin_to_pin",
+            Erc::Custom { code, .. } => code,
+        }
+    }
+    /// The pins of the violation.
+    pub fn pins(&self) -> Vec<&SymbolPin> {
+        match self {
+            Erc::PinNotConnected(pin) | Erc::SinglePinNet(_, pin) | Erc::PowerNotDriven(_, pin) => {
+                vec![pin]
+            }
+            Erc::PinConflict(_, a, b) => vec![a, b],
+            Erc::Custom { pins, .. } => pins.iter().collect(),
+            Erc::WireNotConnected(_, _) => Vec::new(),
+        }
+    }
+    /// The net of the violation.
+    pub fn net(&self) -> Option<&String> {
+        match self {
+            Erc::SinglePinNet(net, _) | Erc::PowerNotDriven(net, _) | Erc::PinConflict(net, _, _) => {
+                Some(net)
+            }
+            Erc::Custom { net, .. } => net.as_ref(),
+            Erc::PinNotConnected(_) | Erc::WireNotConnected(_, _) => None,
+        }
+    }
+    /// The symbol references of the violation.
+    pub fn references(&self) -> Vec<&String> {
+        match self {
+            Erc::PinNotConnected(pin) | Erc::SinglePinNet(_, pin) | Erc::PowerNotDriven(_, pin) => {
+                vec![&pin.reference]
+            }
+            Erc::PinConflict(_, a, b) => vec![&a.reference, &b.reference],
+            Erc::Custom { pins, .. } => pins.iter().map(|p| &p.reference).collect(),
+            Erc::WireNotConnected(_, _) => Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for Erc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Erc::PinNotConnected(pin) => {
+                write!(f, "Pin {} of {} is not connected", pin.number, pin.reference)
+            }
+            Erc::WireNotConnected(p0, p1) => write!(
+                f,
+                "Wire from ({}, {}) to ({}, {}) is not connected",
+                p0.x, p0.y, p1.x, p1.y
+            ),
+            Erc::SinglePinNet(net, pin) => write!(
+                f,
+                "Pin {} of {} is the only pin on net {}",
+                pin.number, pin.reference, net
+            ),
+            Erc::PowerNotDriven(net, pin) => write!(
+                f,
+                "Power input pin {} of {} on net {} is not driven",
+                pin.number, pin.reference, net
+            ),
+            Erc::PinConflict(net, a, b) => write!(
+                f,
+                "Pin {} of {} ({}) conflicts with pin {} of {} ({}) on net {}",
+                a.number,
+                a.reference,
+                a.electrical_type,
+                b.number,
+                b.reference,
+                b.electrical_type,
+                net
+            ),
+            Erc::Custom { message, .. } => write!(f, "{}", message),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct Point {
-    x: f64,
-    y: f64,
+    pub x: f64,
+    pub y: f64,
 }
 impl Point {
     pub fn new(x: f64, y: f64) -> Point {
@@ -47,22 +147,83 @@ impl std::convert::From<Array1<f64>> for Point {
     }
 }
 
+/// A pin of a placed symbol with the position in the schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolPin {
+    pub reference: String,
+    pub number: String,
+    pub name: String,
+    pub electrical_type: String,
+    pub unit: u32,
+    pub at: Point,
+}
+
+/// A placed symbol unit with the resolved pins.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct NetlistSymbol {
+    pub lib_id: String,
+    pub unit: u32,
+    pub power: bool,
+    pub exclude_from_sim: bool,
+    /// the number of pins of all units in the library symbol.
+    pub pin_count: usize,
+    pub properties: HashMap<String, String>,
+    pub pins: Vec<SymbolPin>,
+}
+
+impl NetlistSymbol {
+    pub fn get_property(&self, key: &str) -> Option<String> {
+        self.properties.get(key).cloned()
+    }
+    /// The net name of a power symbol, the power flags do not name the net.
+    pub fn net_name(&self) -> Option<String> {
+        if !self.power {
+            return None;
+        }
+        self.get_property("Value").filter(|v| v != "PWR_FLAG")
+    }
+}
+
+/// The schema elements used for the netlist.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Element {
+    Wire(Point, Point),
+    Junction(Point),
+    NoConnect(Point),
+    Label(String, Point),
+    GlobalLabel(String, Point),
+}
+
+/// A net of the schema with the connected points and pins.
 #[derive(Clone, Debug)]
-pub struct Node<'a> {
+pub struct Net {
     identifier: Option<String>,
     points: Vec<Point>,
-    pins: Vec<&'a Pin>,
+    pins: Vec<SymbolPin>,
 }
 
-/// create a new node with values.
-impl<'a> Node<'a> {
-    pub fn from(identifier: Option<String>, points: Vec<Point>, pins: Vec<&'a Pin>) -> Self {
+/// The net was named `Node` before the circuit nodes were validated.
+#[deprecated(note = "renamed to `Net`")]
+pub type Node = Net;
+
+/// create a new net with values.
+impl Net {
+    pub fn from(identifier: Option<String>, points: Vec<Point>, pins: Vec<SymbolPin>) -> Self {
         Self {
             identifier,
             points,
             pins,
         }
     }
+    pub fn identifier(&self) -> Option<&String> {
+        self.identifier.as_ref()
+    }
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+    pub fn pins(&self) -> &[SymbolPin] {
+        &self.pins
+    }
 }
 
 macro_rules! insert_or_update {
@@ -75,7 +236,7 @@ macro_rules! insert_or_update {
                                         if node_id == identifier {
                                             n.identifier = Some(identifier.to_string());
                                             n.points.append(&mut $next_pos);
-                                            n.pins.push($pin);
+                                            n.pins.push($pin.clone());
                                             found_existing_node = true;
                                         }
                                     }
@@ -83,10 +244,10 @@ macro_rules! insert_or_update {
                             }
                         }
                         if !found_existing_node {
-                            $self.nodes.push(Node::from(
+                            $self.nodes.push(Net::from(
                                 $identifier.clone(),
                                 $next_pos,
-                                vec![$pin],
+                                vec![$pin.clone()],
                             ))
                         }
     };
@@ -97,25 +258,25 @@ macro_rules! insert_or_update {
 ///
 /// Create a netlist as a graph.
 ///
-pub struct Netlist<'a> {
-    schema: &'a Schema,
-    elements: Vec<&'a SchemaElement>,
-    used_elements: Vec<&'a Wire>,
-    symbols: HashMap<String, Vec<&'a Symbol>>,
-    nodes: Vec<Node<'a>>,
+pub struct Netlist {
+    elements: Vec<Element>,
+    used_elements: Vec<usize>,
+    symbols: HashMap<String, Vec<NetlistSymbol>>,
+    nodes: Vec<Net>,
 }
 
-impl<'a> Netlist<'a> {
-    pub fn from(schema: &'a Schema) -> Result<Self, Error> {
-        let mut elements: Vec<&'a SchemaElement> = Vec::new();
-        let mut symbols: HashMap<String, Vec<&Symbol>> = HashMap::new();
+impl Netlist {
+    pub fn from(schema: &Schema) -> Result<Self, Error> {
+        let mut elements: Vec<Element> = Vec::new();
+        let mut symbols: HashMap<String, Vec<NetlistSymbol>> = HashMap::new();
 
         for page in 0..schema.pages() {
             for node in schema.iter(page).unwrap() {
                 match node {
                     SchemaElement::Symbol(symbol) => {
                         if let Some(reference) = symbol.get_property("Reference") {
-                            symbols.entry(reference).or_insert(Vec::new()).push(symbol);
+                            let netlist_symbol = Self::symbol(schema, symbol, &reference)?;
+                            symbols.entry(reference).or_default().push(netlist_symbol);
                         } else {
                             return Err(Error::PropertyNotFound(
                                 symbol.lib_id.to_string(),
@@ -123,23 +284,85 @@ impl<'a> Netlist<'a> {
                             ));
                         }
                     }
-                    SchemaElement::NoConnect(_) => elements.push(node),
-                    SchemaElement::Junction(_) => elements.push(node),
-                    SchemaElement::Wire(_) => elements.push(node),
-                    SchemaElement::Label(_) => elements.push(node),
-                    SchemaElement::GlobalLabel(_) => elements.push(node),
+                    SchemaElement::NoConnect(nc) => {
+                        elements.push(Element::NoConnect(Point::new(nc.at[0], nc.at[1])))
+                    }
+                    SchemaElement::Junction(junction) => elements.push(Element::Junction(
+                        Point::new(junction.at[0], junction.at[1]),
+                    )),
+                    SchemaElement::Wire(wire) => elements.push(Element::Wire(
+                        Point::new(wire.pts.row(0)[0], wire.pts.row(0)[1]),
+                        Point::new(wire.pts.row(1)[0], wire.pts.row(1)[1]),
+                    )),
+                    SchemaElement::Label(label) => elements.push(Element::Label(
+                        label.text.clone(),
+                        Point::new(label.at[0], label.at[1]),
+                    )),
+                    SchemaElement::GlobalLabel(label) => elements.push(Element::GlobalLabel(
+                        label.text.clone(),
+                        Point::new(label.at[0], label.at[1]),
+                    )),
                     SchemaElement::Bus(_) => todo!(),
                     SchemaElement::BusEntry(_) => todo!(),
-                    SchemaElement::HierarchicalLabel(_) => todo!(),
+                    SchemaElement::HierarchicalLabel(label) => {
+                        return Err(Error::Hierarchy(format!("hierarchical label {}", label.text)))
+                    }
                     SchemaElement::Text(_) => {}
                     SchemaElement::Polyline(_) => {}
-                    SchemaElement::Sheet(_) => {}
+                    SchemaElement::Sheet(_) => {
+                        return Err(Error::Hierarchy(String::from("sub-sheet")))
+                    }
                 }
             }
         }
+        Self::build(symbols, elements)
+    }
+
+    /// Load the netlist from a KiCad schema file (version 6 to 8).
+    pub fn load(filename: &str) -> Result<Self, Error> {
+        let (symbols, elements) = kicad::load(filename)?;
+        Self::build(symbols, elements)
+    }
 
+    /// Resolve the pins of the schema symbol.
+    fn symbol(schema: &Schema, symbol: &Symbol, reference: &str) -> Result<NetlistSymbol, Error> {
+        let lib_symbol = match schema.get_library(symbol.lib_id.as_str()) {
+            Some(lib_symbol) => lib_symbol,
+            None => return Err(Error::LibraryNotFound(symbol.lib_id.clone())),
+        };
+        let pin_names = lib_symbol.pin_names().unwrap();
+        let mut pins = Vec::new();
+        for pin in lib_symbol.pins(symbol.unit).unwrap() {
+            pins.push(SymbolPin {
+                reference: reference.to_string(),
+                number: pin.number.0.clone(),
+                name: pin.name.0.clone(),
+                electrical_type: pin.electrical_type.clone(),
+                unit: symbol.unit,
+                at: Shape::transform(symbol, &pin.at).into(),
+            });
+        }
+        let properties: HashMap<String, String> = symbol
+            .property
+            .iter()
+            .map(|property| (property.key.clone(), property.value.clone()))
+            .collect();
+        Ok(NetlistSymbol {
+            lib_id: symbol.lib_id.clone(),
+            unit: symbol.unit,
+            power: lib_symbol.power,
+            exclude_from_sim: false,
+            pin_count: pin_names.len(),
+            properties,
+            pins,
+        })
+    }
+
+    fn build(
+        symbols: HashMap<String, Vec<NetlistSymbol>>,
+        elements: Vec<Element>,
+    ) -> Result<Self, Error> {
         let mut netlist = Self {
-            schema,
             elements,
             used_elements: Vec::new(),
             symbols,
@@ -148,154 +371,131 @@ impl<'a> Netlist<'a> {
 
         for (reference, symbols) in &netlist.symbols {
             for symbol in symbols.iter() {
-                if let Some(lib_symbol) = schema.get_library(symbol.lib_id.as_str()) {
-                    let identifier: Option<String> = if lib_symbol.power {
-                        symbol.get_property("Value")
-                    } else {
-                        None
-                    };
-                    for pin in lib_symbol.pins(symbol.unit).unwrap() {
-                        let point: Point = Shape::transform(*symbol, &pin.at).into();
-
-                        //search the netlist if we have already found this pin position
-                        let mut found_node: Option<&mut Node> = None;
-                        for node in &mut netlist.nodes {
-                            if node.points.contains(&point) {
-                                found_node = Some(node);
-                                break;
-                            }
+                let identifier: Option<String> = symbol.net_name();
+                for pin in &symbol.pins {
+                    let point: Point = pin.at;
+
+                    //search the netlist if we have already found this pin position
+                    let mut found_node: Option<&mut Net> = None;
+                    for node in &mut netlist.nodes {
+                        if node.points.contains(&point) {
+                            found_node = Some(node);
+                            break;
                         }
+                    }
 
-                        // use the existing netlist or create a new one.
-                        if let Some(found_node) = found_node {
-                            found_node.pins.push(pin);
-                            if identifier.is_some() {
-                                found_node.identifier = identifier.clone();
-                            }
-                        } else {
-                            let next_pos = netlist.next_pos(point, netlist.used_elements.clone());
-                            if let Some(mut next_pos) = next_pos {
-                                netlist.used_elements = next_pos.1;
-                                next_pos.0.push(point);
-                                let mut found_existing_node = false;
-                                if let Some(identifier) = &identifier {
-                                    if netlist.has_node(identifier.to_string()) {
-                                        for n in &mut netlist.nodes {
-                                            if let Some(node_id) = &n.identifier {
-                                                if node_id == identifier {
-                                                    n.identifier = Some(identifier.to_string());
-                                                    n.points.append(&mut next_pos.0);
-                                                    n.pins.push(pin);
-                                                    found_existing_node = true;
-                                                }
+                    // use the existing netlist or create a new one.
+                    if let Some(found_node) = found_node {
+                        found_node.pins.push(pin.clone());
+                        if identifier.is_some() {
+                            found_node.identifier = identifier.clone();
+                        }
+                    } else {
+                        let next_pos = netlist.next_pos(point, netlist.used_elements.clone());
+                        if let Some(mut next_pos) = next_pos {
+                            netlist.used_elements = next_pos.1;
+                            next_pos.0.push(point);
+                            let mut found_existing_node = false;
+                            if let Some(identifier) = &identifier {
+                                if netlist.has_node(identifier.to_string()) {
+                                    for n in &mut netlist.nodes {
+                                        if let Some(node_id) = &n.identifier {
+                                            if node_id == identifier {
+                                                n.identifier = Some(identifier.to_string());
+                                                n.points.append(&mut next_pos.0);
+                                                n.pins.push(pin.clone());
+                                                found_existing_node = true;
                                             }
                                         }
                                     }
                                 }
-                                if !found_existing_node {
-                                    netlist.nodes.push(Node::from(
-                                        identifier.clone(),
-                                        next_pos.0,
-                                        vec![pin],
-                                    ))
-                                }
-                            } else {
-                                let mut found = false;
-                                for (subref, subsymbol) in &netlist.symbols {
-                                    if subref != reference {
-                                        for sym in subsymbol {
-                                            if let Some(sub_lib_symbol) =
-                                                schema.get_library(sym.lib_id.as_str())
-                                            {
-                                                for pin in sub_lib_symbol.pins(sym.unit).unwrap() {
-                                                    let subpts = Shape::transform(*sym, &pin.at);
-                                                    let subpoint = Point::new(subpts[0], subpts[1]);
-                                                    if subpoint == point {
-                                                        if lib_symbol.power {
-                                                            insert_or_update!(netlist, &symbol.get_property("Value"), vec![point], pin);
-                                                        } else if sub_lib_symbol.power {
-                                                            insert_or_update!(netlist, &sym.get_property("Value"), vec![point], pin);
-                                                        } else {
-                                                            insert_or_update!(netlist, &identifier, vec![point], pin);
-                                                        }
-                                                        found = true;
-                                                    }
+                            }
+                            if !found_existing_node {
+                                netlist.nodes.push(Net::from(
+                                    identifier.clone(),
+                                    next_pos.0,
+                                    vec![pin.clone()],
+                                ))
+                            }
+                        } else {
+                            let mut found = false;
+                            for (subref, subsymbol) in &netlist.symbols {
+                                if subref != reference {
+                                    for sym in subsymbol {
+                                        for pin in &sym.pins {
+                                            if pin.at == point {
+                                                if symbol.power {
+                                                    insert_or_update!(netlist, &symbol.net_name(), vec![point], pin);
+                                                } else if sym.power {
+                                                    insert_or_update!(netlist, &sym.net_name(), vec![point], pin);
+                                                } else {
+                                                    insert_or_update!(netlist, &identifier, vec![point], pin);
                                                 }
+                                                found = true;
                                             }
                                         }
                                     }
                                 }
-                                for el in &netlist.elements {
-                                    if let SchemaElement::NoConnect(nc) = el {
-                                        let ncpoint = Point::new(nc.at[0], nc.at[1]);
-                                        if ncpoint == point {
-                                            let mut found_existing_node = false;
-                                            if netlist.has_node(String::from("NC")) {
-                                                for n in &mut netlist.nodes {
-                                                    if let Some(node_id) = &n.identifier {
-                                                        if node_id == "NC" {
-                                                            n.points.push(point);
-                                                            n.pins.push(pin);
-                                                            found_existing_node = true;
-                                                        }
+                            }
+                            for el in &netlist.elements {
+                                if let Element::NoConnect(ncpoint) = el {
+                                    if *ncpoint == point {
+                                        let mut found_existing_node = false;
+                                        if netlist.has_node(String::from("NC")) {
+                                            for n in &mut netlist.nodes {
+                                                if let Some(node_id) = &n.identifier {
+                                                    if node_id == "NC" {
+                                                        n.points.push(point);
+                                                        n.pins.push(pin.clone());
+                                                        found_existing_node = true;
                                                     }
                                                 }
                                             }
-                                            if !found_existing_node {
-                                                netlist.nodes.push(Node::from(
-                                                    Some(String::from("NC")),
-                                                    vec![point],
-                                                    vec![pin],
-                                                ));
-                                            }
-                                            found = true;
                                         }
+                                        if !found_existing_node {
+                                            netlist.nodes.push(Net::from(
+                                                Some(String::from("NC")),
+                                                vec![point],
+                                                vec![pin.clone()],
+                                            ));
+                                        }
+                                        found = true;
                                     }
                                 }
-                                if !found {
-                                    let mut found_existing_node = false;
-                                    if netlist.has_node(String::from("UNCONNECTED")) {
-                                        for n in &mut netlist.nodes {
-                                            if let Some(node_id) = &n.identifier {
-                                                if node_id == "UNCONNECTED" {
-                                                    n.points.push(point);
-                                                    n.pins.push(pin);
-                                                    found_existing_node = true;
-                                                }
+                            }
+                            if !found {
+                                let mut found_existing_node = false;
+                                if netlist.has_node(String::from("UNCONNECTED")) {
+                                    for n in &mut netlist.nodes {
+                                        if let Some(node_id) = &n.identifier {
+                                            if node_id == "UNCONNECTED" {
+                                                n.points.push(point);
+                                                n.pins.push(pin.clone());
+                                                found_existing_node = true;
                                             }
                                         }
                                     }
-                                    if !found_existing_node {
-                                        netlist.nodes.push(Node::from(
-                                            Some(String::from("UNCONNECTED")),
-                                            vec![point],
-                                            vec![pin],
-                                        ));
-                                    }
+                                }
+                                if !found_existing_node {
+                                    netlist.nodes.push(Net::from(
+                                        Some(String::from("UNCONNECTED")),
+                                        vec![point],
+                                        vec![pin.clone()],
+                                    ));
                                 }
                             }
                         }
                     }
-                } else {
-                    return Err(Error::LibraryNotFound(symbol.lib_id.clone()));
                 }
             }
         }
 
         //search the labels
         for element in &netlist.elements {
-            if let SchemaElement::Label(label) = element {
-                let point = Point::new(label.at[0], label.at[1]);
-                for node in &mut netlist.nodes {
-                    if node.points.contains(&point) {
-                        node.identifier = Some(label.text.clone());
-                    }
-                }
-            } else if let SchemaElement::GlobalLabel(label) = element {
-                let point = Point::new(label.at[0], label.at[1]);
+            if let Element::Label(text, point) | Element::GlobalLabel(text, point) = element {
                 for node in &mut netlist.nodes {
-                    if node.points.contains(&point) {
-                        node.identifier = Some(label.text.clone());
+                    if node.points.contains(point) {
+                        node.identifier = Some(text.clone());
                     }
                 }
             }
@@ -333,22 +533,20 @@ impl<'a> Netlist<'a> {
     fn next_pos(
         &self,
         pos: Point,
-        mut used_wires: Vec<&'a Wire>,
-    ) -> Option<(Vec<Point>, Vec<&'a Wire>)> {
+        mut used_wires: Vec<usize>,
+    ) -> Option<(Vec<Point>, Vec<usize>)> {
         let mut points: Vec<Point> = Vec::new();
-        for element in &self.elements {
-            if let SchemaElement::Wire(wire) = element {
-                if !used_wires.contains(&wire) {
-                    let p0 = Point::new(wire.pts.row(0)[0], wire.pts.row(0)[1]);
-                    let p1 = Point::new(wire.pts.row(1)[0], wire.pts.row(1)[1]);
-                    if let Some(nextpos) = if p0 == pos {
-                        used_wires.push(wire);
-                        points.push(p1);
-                        Some(p1)
-                    } else if p1 == pos {
-                        used_wires.push(wire);
-                        points.push(p0);
-                        Some(p0)
+        for (index, element) in self.elements.iter().enumerate() {
+            if let Element::Wire(p0, p1) = element {
+                if !used_wires.contains(&index) {
+                    if let Some(nextpos) = if *p0 == pos {
+                        used_wires.push(index);
+                        points.push(*p1);
+                        Some(*p1)
+                    } else if *p1 == pos {
+                        used_wires.push(index);
+                        points.push(*p0);
+                        Some(*p0)
                     } else {
                         None
                     } {
@@ -360,8 +558,8 @@ impl<'a> Netlist<'a> {
                         }
                     }
                 }
-            } else if let SchemaElement::Junction(junction) = element {
-                if Point::new(junction.at[0], junction.at[1]) == pos {
+            } else if let Element::Junction(junction) = element {
+                if *junction == pos {
                     points.push(pos);
                 }
             }
@@ -372,183 +570,275 @@ impl<'a> Netlist<'a> {
             Some((points, used_wires))
         }
     }
-    pub fn circuit(&self, circuit: &mut Circuit) -> Result<(), Error> {
+    /// Get the spice node names for the pin numbers.
+    ///
+    /// Every not connected pin gets an own node. Pins of units that are not placed in the schema
+    /// are skipped.
+    fn pin_nodes(&self, symbols: &[NetlistSymbol], numbers: &[String]) -> Vec<String> {
+        let mut nodes = Vec::new();
+        for number in numbers {
+            let pin = symbols
+                .iter()
+                .flat_map(|s| s.pins.iter())
+                .find(|p| &p.number == number);
+            if let Some(pin) = pin {
+                let node_name = match self.node_name(&pin.at).as_deref() {
+                    Some("NC") | Some("UNCONNECTED") | None => {
+                        format!("NC_{}_{}", pin.reference, pin.number)
+                    }
+                    Some(name) => name.replace(|c| !node::valid_char(c), "_"),
+                };
+                nodes.push(node_name);
+            }
+        }
+        nodes
+    }
+    /// Create the spice circuit from the netlist.
+    ///
+    /// The element type is taken from the spice fields of the symbol or
+    /// from the reference prefix, the symbol pins are ordered by the pin
+    /// sequence and the ground net is connected to node `0`.
+    pub fn circuit(&self, pathlist: Vec<String>) -> Result<Circuit, Error> {
+        let mut circuit = Circuit::new(String::from("netlist"), pathlist);
+        let mut references: Vec<&String> = self.symbols.keys().collect();
+        references.sort();
+        let mut libraries: Vec<String> = Vec::new();
         //Create a spice entry for each referenca
-        for (reference, symbols) in &self.symbols {
+        for reference in references {
             //but not for the power symbols
             if reference.starts_with('#') {
                 continue;
             }
 
+            let symbols = &self.symbols[reference];
             let first_symbol = &symbols[0];
-
-            //skip symbol when Netlist_Enabled is 'N'
-            let netlist_enabled = first_symbol.get_property("Spice_Netlist_Enabled");
-            if let Some(enabled) = netlist_enabled {
-                if enabled == "N" {
-                    continue;
-                }
+            if !spice::enabled(first_symbol) {
+                continue;
             }
 
             //create the pin order
-            let my_pins = self
-                .schema
-                .get_library(&first_symbol.lib_id)
-                .unwrap()
-                .pin_names()
-                .unwrap();
-            let mut pin_sequence: Vec<usize> = (0..my_pins.len()).collect();
-
-            //when Node_Sequence is defined, use it
-            let netlist_sequence = first_symbol.get_property("Spice_Node_Sequence");
-            if let Some(sequence) = netlist_sequence {
-                pin_sequence.clear();
-                let splits: Vec<&str> = sequence.split(' ').collect();
-                for s in splits {
-                    pin_sequence.push(s.parse::<usize>().unwrap());
+            let pin_sequence = spice::pin_sequence(first_symbol)?;
+            let nodes = self.pin_nodes(symbols, &pin_sequence);
+
+            //the library with the model
+            if let Some(library) = first_symbol
+                .get_property("Spice_Lib_File")
+                .or_else(|| first_symbol.get_property("Sim.Library"))
+            {
+                if !library.is_empty() && !libraries.contains(&library) {
+                    circuit.include(library.clone());
+                    libraries.push(library);
                 }
             }
 
             //write the spice netlist item
-            let spice_primitive = first_symbol.get_property("Spice_Primitive");
-            let spice_model = first_symbol.get_property("Spice_Model");
-            let spice_value = first_symbol.get_property("Value");
-            match spice_primitive {
-                Some(primitive) => {
-                    if primitive == "X" {
-                        let mut seq_string = String::new();
-                        for seq in pin_sequence {
-                            let real_pin = (&seq + 1).to_string();
-                            let pin = my_pins.get(&real_pin).unwrap();
-                            //get the symbol from the unit number
-                            for s in symbols.iter() {
-                                if s.unit == pin.1 {
-                                    let pts = Shape::transform(*s, &pin.0.at);
-                                    let p0 = Point::new(pts[0], pts[1]);
-
-                                    if let Some(node_name) = self.node_name(&p0) {
-                                        seq_string += &node_name;
-                                    } else {
-                                        seq_string += "NaN"
-                                    }
-                                    seq_string += " ";
-                                }
-                            }
-                        }
-                        if primitive == "X" {
-                            let nodes = seq_string.split(' ').map(|s| s.to_string()).collect();
-                            circuit.circuit(reference.to_string(), nodes, spice_model.unwrap())?;
-                        } else {
-                            println!(
-                                "=> {}{} - {}- {}",
-                                primitive,
-                                reference,
-                                seq_string,
-                                spice_model.unwrap()
-                            );
-                        }
-                    } else if primitive == "Q" {
-                        let mut seq_string = String::new();
-                        for seq in pin_sequence {
-                            let real_pin = (&seq + 1).to_string();
-                            let pin = my_pins.get(&real_pin).unwrap();
-                            //get the symbol from the unit number
-                            for s in symbols.iter() {
-                                if s.unit == pin.1 {
-                                    let pts = Shape::transform(*s, &pin.0.at);
-                                    let p0 = Point::new(pts[0], pts[1]);
-
-                                    if let Some(node_name) = self.node_name(&p0) {
-                                        seq_string += &node_name;
-                                    } else {
-                                        seq_string += "NaN"
-                                    }
-                                    seq_string += " ";
-                                }
-                            }
-                        }
-                        let nodes: Vec<String> = seq_string.split(' ').map(|s| s.to_string()).collect();
-                        circuit.bjt(reference.to_string(), nodes[0].clone(), nodes[1].clone(), nodes[2].clone(), spice_model.unwrap());
+            let primitive = spice::primitive(reference, first_symbol);
+            let value = spice::value(first_symbol);
+            let expected = match primitive.as_str() {
+                "R" | "C" | "L" | "D" | "V" => 2,
+                "Q" => 3,
+                "X" => nodes.len(),
+                _ => {
+                    log::warn!("unknown reference: {} {:?} {:?}", reference, nodes, value);
+                    continue;
+                }
+            };
+            if nodes.len() < expected {
+                return Err(Error::NodeCount(reference.clone(), expected, nodes.len()));
+            }
+            let value = match value {
+                Some(value) => value,
+                None => {
+                    return Err(Error::PropertyNotFound(
+                        String::from("Value"),
+                        reference.clone(),
+                    ))
+                }
+            };
+            match primitive.as_str() {
+                "R" => circuit.resistor(reference.clone(), nodes[0].clone(), nodes[1].clone(), value)?,
+                "C" => circuit.capacitor(reference.clone(), nodes[0].clone(), nodes[1].clone(), value)?,
+                "L" => circuit.inductor(reference.clone(), nodes[0].clone(), nodes[1].clone(), value)?,
+                "D" => circuit.diode(reference.clone(), nodes[0].clone(), nodes[1].clone(), value)?,
+                "V" => circuit.voltage(reference.clone(), nodes[0].clone(), nodes[1].clone(), value)?,
+                "Q" => circuit.bjt(
+                    reference.clone(),
+                    nodes[0].clone(),
+                    nodes[1].clone(),
+                    nodes[2].clone(),
+                    value,
+                )?,
+                _ => circuit.circuit(reference.clone(), nodes, value)?,
+            }
+        }
+
+        Ok(circuit)
+    }
+    /// The pins of the placed symbols by net name, sorted by net and pin.
+    pub fn nets(&self) -> Vec<(String, Vec<&SymbolPin>)> {
+        let mut nets: HashMap<String, Vec<&SymbolPin>> = HashMap::new();
+        for pin in self.symbols.values().flatten().flat_map(|s| s.pins.iter()) {
+            if let Some(net) = self.node_name(&pin.at) {
+                let pins = nets.entry(net).or_default();
+                //the common pins are in every unit
+                if !pins
+                    .iter()
+                    .any(|p| p.reference == pin.reference && p.number == pin.number)
+                {
+                    pins.push(pin);
+                }
+            }
+        }
+        let mut nets: Vec<(String, Vec<&SymbolPin>)> = nets.into_iter().collect();
+        for (_, pins) in nets.iter_mut() {
+            pins.sort_by(|a, b| (&a.reference, &a.number).cmp(&(&b.reference, &b.number)));
+        }
+        nets.sort_by(|a, b| a.0.cmp(&b.0));
+        nets
+    }
+    /// Check the pin types of every net with the conflict matrix.
+    ///
+    /// A power symbol is the source of the rail when no pin drives the net,
+    /// so an output tied to a power rail is reported as well.
+    fn pin_conflicts(&self, matrix: &PinMatrix) -> Vec<Erc> {
+        let mut result = Vec::new();
+        for (name, pins) in self.nets() {
+            let driven = pins
+                .iter()
+                .any(|p| !p.reference.starts_with('#') && p.electrical_type == "power_out");
+            let pin_type = |pin: &SymbolPin| {
+                if pin.reference.starts_with('#') {
+                    if driven || !self.is_power_net(&name) {
+                        PinType::from(&pin.electrical_type)
                     } else {
-                        println!("-> {}{} - - {}", primitive, reference, spice_value.unwrap());
+                        PinType::PowerOut
                     }
+                } else {
+                    PinType::from(&pin.electrical_type)
                 }
-                None => {
-                    let mut seq_string = String::new();
-                    for seq in pin_sequence {
-                        let real_pin = (&seq + 1).to_string();
-                        let pin = my_pins.get(&real_pin).unwrap();
-                        //get the symbol from the unit number
-                        for s in symbols.iter() {
-                            if s.unit == pin.1 {
-                                let pts = Shape::transform(*s, &pin.0.at);
-                                let p0 = Point::new(pts[0], pts[1]);
-                                if let Some(node_name) = self.node_name(&p0) {
-                                    seq_string += &node_name;
-                                } else {
-                                    seq_string += "NaN"
-                                }
-                                seq_string += " ";
-                            }
-                        }
+            };
+            for (i, a) in pins.iter().enumerate() {
+                for b in pins.iter().skip(i + 1) {
+                    if a.reference.starts_with('#') && b.reference.starts_with('#') {
+                        continue;
                     }
-                    if reference.starts_with('R') {
-                        let nodes: Vec<String> =
-                            seq_string.split(' ').map(|s| s.to_string()).collect();
-                        circuit.resistor(
-                            reference.clone(),
-                            nodes[0].clone(),
-                            nodes[1].clone(),
-                            spice_value.unwrap(),
-                        );
-                    } else if reference.starts_with('C') {
-                        let nodes: Vec<String> =
-                            seq_string.split(' ').map(|s| s.to_string()).collect();
-                        circuit.capacitor(
-                            reference.clone(),
-                            nodes[0].clone(),
-                            nodes[1].clone(),
-                            spice_value.unwrap(),
-                        );
-                    } else if std::env::var("ELEKTRON_DEBUG").is_ok() {
-                        println!(
-                            "Unkknwon Reference: {} {}{}",
-                            reference,
-                            seq_string,
-                            spice_value.unwrap()
-                        );
+                    if matrix.conflict(pin_type(a), pin_type(b)) {
+                        result.push(Erc::PinConflict(name.clone(), (*a).clone(), (*b).clone()));
                     }
                 }
             }
         }
-
-        Ok(())
+        result
+    }
+    /// Check if the net is named by a power symbol.
+    fn is_power_net(&self, name: &str) -> bool {
+        self.symbols
+            .values()
+            .flatten()
+            .any(|s| s.net_name().as_deref() == Some(name))
+    }
+    /// The nodes of the netlist.
+    pub fn nodes(&self) -> &[Net] {
+        &self.nodes
+    }
+    /// The references of the placed symbols, sorted.
+    pub fn references(&self) -> Vec<&String> {
+        let mut references: Vec<&String> = self.symbols.keys().collect();
+        references.sort();
+        references
+    }
+    /// The library id of the symbol.
+    pub fn lib_id(&self, reference: &str) -> Option<&String> {
+        self.symbols.get(reference).map(|s| &s[0].lib_id)
     }
+    /// The property value of the symbol.
+    pub fn property(&self, reference: &str, key: &str) -> Option<String> {
+        self.symbols.get(reference).and_then(|s| s[0].get_property(key))
+    }
+    /// Run the electrical rules check with the default pin conflict matrix.
     pub fn erc(&self) -> Vec<Erc> {
+        self.erc_with_matrix(&PinMatrix::default())
+    }
+    /// Run the electrical rules check with the configuration.
+    ///
+    /// Returns the violations with the configured severity, ignored and
+    /// waived violations are removed.
+    pub fn erc_with_config(&self, config: &ErcConfig) -> Vec<(Severity, Erc)> {
+        let mut result = self.erc_with_matrix(&config.matrix);
+        for rule in config.rules() {
+            result.append(&mut rule.check(self));
+        }
+        result
+            .into_iter()
+            .map(|erc| (config.check(&erc), erc))
+            .filter(|(severity, _)| *severity != Severity::Ignore)
+            .collect()
+    }
+    /// Run the electrical rules check with a custom pin conflict matrix.
+    pub fn erc_with_matrix(&self, matrix: &PinMatrix) -> Vec<Erc> {
         let mut result = Vec::new();
         if self.has_node(String::from("UNCONNECTED")) {
             for n in &self.nodes {
                 if let Some(node_id) = &n.identifier {
                     if node_id == "UNCONNECTED" {
-                        for p in &n.pins {
-                            let pin = *p;
+                        for pin in &n.pins {
                             result.push(Erc::PinNotConnected(pin.clone()));
                         }
                     }
                 }
             }
         }
-        for element in &self.elements {
-            if let SchemaElement::Wire(wire) = element {
-                let mut found = false;
-                for used_elements in &self.used_elements {
-                    if *used_elements == wire {
-                        found = true;
-                        break;
+        //nets with the same name are connected by the labels,
+        //global labels and power symbols are connected outside of the schema.
+        let mut global_nets: Vec<String> = self
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                Element::GlobalLabel(text, _) => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        global_nets.extend(self.symbols.values().flatten().filter_map(|s| s.net_name()));
+        let mut nets: HashMap<&String, Vec<&SymbolPin>> = HashMap::new();
+        for n in &self.nodes {
+            if let Some(node_id) = &n.identifier {
+                if node_id != "UNCONNECTED" && node_id != "NC" && !global_nets.contains(node_id) {
+                    let pins = nets.entry(node_id).or_default();
+                    for pin in &n.pins {
+                        if !pins
+                            .iter()
+                            .any(|p| p.reference == pin.reference && p.number == pin.number)
+                        {
+                            pins.push(pin);
+                        }
                     }
                 }
-                if !found {
-                    result.push(Erc::WireNotConnected(wire.clone()));
+            }
+        }
+        let mut names: Vec<&&String> = nets.keys().collect();
+        names.sort();
+        for name in names {
+            let pins = &nets[*name];
+            if pins.len() == 1
+                && (pins[0].electrical_type == "input" || pins[0].electrical_type == "passive")
+            {
+                result.push(Erc::SinglePinNet(name.to_string(), pins[0].clone()));
+            }
+        }
+        //every net with a power input must be driven by a power output.
+        for (name, pins) in self.nets() {
+            if pins.iter().any(|p| p.electrical_type == "power_out") {
+                continue;
+            }
+            if let Some(pin) = pins.iter().find(|p| p.electrical_type == "power_in") {
+                result.push(Erc::PowerNotDriven(name.clone(), (*pin).clone()));
+            }
+        }
+        result.append(&mut self.pin_conflicts(matrix));
+        for (index, element) in self.elements.iter().enumerate() {
+            if let Element::Wire(p0, p1) = element {
+                if !self.used_elements.contains(&index) {
+                    result.push(Erc::WireNotConnected(*p0, *p1));
                 }
             }
         }
@@ -558,32 +848,27 @@ impl<'a> Netlist<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Netlist, Node};
-    use crate::{
-        netlist::Point, Circuit,
-    };
-    use elektron_sexp::{Schema, Shape, Transform};
+    use super::{Erc, Net, Netlist};
+    use std::sync::Arc;
+
+    use crate::{error::Error, netlist::Point, ErcConfig, ErcRule, PinMatrix, PinType, Severity};
+    use elektron_sexp::Schema;
 
     #[test]
     fn test_next_pos() {
         let schema = Schema::load("files/summe/summe.kicad_sch").unwrap();
         let netlist = Netlist::from(&schema).unwrap();
-        let mut nodes: Vec<Node> = Vec::new();
+        let mut nodes: Vec<Net> = Vec::new();
         for (reference, symbols) in &netlist.symbols {
             if reference == "R4" {
                 for symbol in symbols.iter() {
-                    if let Some(lib_symbol) = schema.get_library(symbol.lib_id.as_str()) {
-                        for pin in lib_symbol.pins(symbol.unit).unwrap() {
-                            let pts = Shape::transform(*symbol, &pin.at);
-                            let next_pos = netlist.next_pos(Point::new(pts[0], pts[1]), Vec::new());
-                            if let Some(next_pos) = next_pos {
-                                nodes.push(Node::from(None, next_pos.0, vec![pin]))
-                            } else {
-                                panic!("no pos found for: {}", reference);
-                            }
+                    for pin in &symbol.pins {
+                        let next_pos = netlist.next_pos(pin.at, Vec::new());
+                        if let Some(next_pos) = next_pos {
+                            nodes.push(Net::from(None, next_pos.0, vec![pin.clone()]))
+                        } else {
+                            panic!("no pos found for: {}", reference);
                         }
-                    } else {
-                        panic!("library symbol not found!");
                     }
                 }
             }
@@ -637,11 +922,116 @@ mod tests {
     fn test_circuit() {
         let schema = Schema::load("files/summe/summe.kicad_sch").unwrap();
         let netlist = Netlist::from(&schema).unwrap();
-        let mut circuit = Circuit::new(
-            String::from("summe"),
-            vec![String::from("files/spice/")],
+        let circuit = netlist
+            .circuit(vec![String::from("files/spice/")])
+            .unwrap();
+        circuit.save(std::io::stdout()).unwrap();
+    }
+    #[test]
+    fn load_schema() {
+        let netlist = Netlist::load("files/summe/summe.kicad_sch").unwrap();
+        assert_eq!(14, netlist.used_elements.len());
+        assert_eq!(0, netlist.erc().len());
+        let netlist = Netlist::load("files/summe/summe_unconnected.kicad_sch").unwrap();
+        assert_eq!(1, netlist.erc().len());
+    }
+    #[test]
+    fn hierarchy() {
+        assert!(matches!(
+            Netlist::load("files/multipage/multipage.kicad_sch"),
+            Err(Error::Hierarchy(_))
+        ));
+        assert!(matches!(
+            Netlist::load("files/multipage/subsheet.kicad_sch"),
+            Err(Error::Hierarchy(_))
+        ));
+    }
+    #[test]
+    fn pin_conflicts() {
+        let netlist = Netlist::load("files/kicad8/divider.kicad_sch").unwrap();
+        let mut matrix = PinMatrix::new();
+        matrix.set(PinType::Passive, PinType::Passive, true);
+        let conflicts: Vec<String> = netlist
+            .erc_with_matrix(&matrix)
+            .iter()
+            .filter_map(|e| match e {
+                Erc::PinConflict(net, a, b) => Some(format!(
+                    "{} {}:{} {}:{}",
+                    net, a.reference, a.number, b.reference, b.number
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![String::from("OUT R1:2 R2:1")], conflicts);
+    }
+    #[test]
+    fn erc_config() {
+        let netlist = Netlist::load("files/kicad8/divider.kicad_sch").unwrap();
+        let mut config = ErcConfig::new();
+        config.waive_net("GND");
+        let erc = netlist.erc_with_config(&config);
+        assert_eq!(1, erc.len());
+        assert_eq!((Severity::Warning, "single_pin_net"), (erc[0].0, erc[0].1.code()));
+        config.waive_reference("R1");
+        assert!(netlist.erc_with_config(&config).is_empty());
+    }
+    #[test]
+    fn erc_rule() {
+        struct ResistorCount;
+        impl ErcRule for ResistorCount {
+            fn check(&self, netlist: &Netlist) -> Vec<Erc> {
+                let count = netlist
+                    .references()
+                    .iter()
+                    .filter(|r| netlist.lib_id(r).map(|l| l.as_str()) == Some("Device:R"))
+                    .count();
+                vec![Erc::Custom {
+                    code: String::from("resistor_count"),
+                    message: format!("{} resistors", count),
+                    net: None,
+                    pins: Vec::new(),
+                }]
+            }
+        }
+        let netlist = Netlist::load("files/kicad8/divider.kicad_sch").unwrap();
+        let mut config = ErcConfig::new();
+        config.add_rule(Arc::new(ResistorCount));
+        config.set_severity("resistor_count", Severity::Warning);
+        let erc = netlist.erc_with_config(&config);
+        let (severity, custom) = erc.last().unwrap();
+        assert_eq!(Severity::Warning, *severity);
+        assert_eq!("resistor_count", custom.code());
+        assert_eq!("2 resistors", custom.to_string());
+    }
+    #[test]
+    fn load_kicad8() {
+        let netlist = Netlist::load("files/kicad8/divider.kicad_sch").unwrap();
+        let erc = netlist.erc();
+        assert_eq!(
+            vec!["single_pin_net", "power_pin_not_driven"],
+            erc.iter().map(|e| e.code()).collect::<Vec<&str>>()
+        );
+        if let Erc::SinglePinNet(net, pin) = &erc[0] {
+            assert_eq!("IN", net);
+            assert_eq!(("R1", "1"), (pin.reference.as_str(), pin.number.as_str()));
+            assert_eq!(Point::new(100.0, 46.19), pin.at);
+        } else {
+            panic!("expected a single pin net: {:?}", erc);
+        }
+        if let Erc::PowerNotDriven(net, pin) = &erc[1] {
+            assert_eq!("GND", net);
+            assert_eq!("#PWR01", pin.reference);
+        } else {
+            panic!("expected a not driven power net: {:?}", erc);
+        }
+        let circuit = netlist.circuit(Vec::new()).unwrap();
+        assert_eq!(
+            vec![
+                String::from("R1 in out 10k"),
+                String::from("R2 out 0 4k7"),
+                String::from(".end"),
+            ],
+            circuit.to_str(true).unwrap()
         );
-        netlist.circuit(&mut circuit).unwrap();
-        circuit.save(None).unwrap();
     }
 }
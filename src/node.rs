@@ -0,0 +1,94 @@
+use crate::error::Error;
+
+/// A node name of the circuit.
+///
+/// Spice node names are not case sensitive, the name is stored in lower
+/// case. Whitespace and the characters used by the spice syntax are not
+/// allowed, they would silently split or disconnect the net.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeName(String);
+
+impl NodeName {
+    pub fn new(name: &str) -> Result<Self, Error> {
+        if name.is_empty() || !name.chars().all(valid_char) {
+            return Err(Error::InvalidNode(name.to_string()));
+        }
+        Ok(Self(name.to_lowercase()))
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Characters that are allowed in a node name.
+pub(crate) fn valid_char(c: char) -> bool {
+    !c.is_whitespace() && !"()=,;{}\"'".contains(c)
+}
+
+impl std::fmt::Display for NodeName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::str::FromStr for NodeName {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NodeName::new(s)
+    }
+}
+
+impl From<NodeName> for String {
+    fn from(node: NodeName) -> Self {
+        node.0
+    }
+}
+
+/// Types that can be used as node argument of the circuit builder methods.
+pub trait IntoNode {
+    fn into_node(self) -> Result<NodeName, Error>;
+}
+
+impl IntoNode for NodeName {
+    fn into_node(self) -> Result<NodeName, Error> {
+        Ok(self)
+    }
+}
+
+impl IntoNode for &NodeName {
+    fn into_node(self) -> Result<NodeName, Error> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoNode for String {
+    fn into_node(self) -> Result<NodeName, Error> {
+        NodeName::new(&self)
+    }
+}
+
+impl IntoNode for &String {
+    fn into_node(self) -> Result<NodeName, Error> {
+        NodeName::new(self)
+    }
+}
+
+impl IntoNode for &str {
+    fn into_node(self) -> Result<NodeName, Error> {
+        NodeName::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeName;
+
+    #[test]
+    fn node() {
+        assert_eq!("out", NodeName::new("OUT").unwrap().to_string());
+        assert_eq!("+15v", NodeName::new("+15V").unwrap().as_str());
+        assert!(NodeName::new("").is_err());
+        assert!(NodeName::new("in put").is_err());
+        assert!("v(out)".parse::<NodeName>().is_err());
+    }
+}
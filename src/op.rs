@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+
+use elektron_ngspice::NgSpice;
+
+use crate::{
+    circuit::{ngspice_error, vectors, Cb, CircuitItem},
+    error::Error,
+    Circuit, Simulation, Value,
+};
+
+/// The result of the operating point analysis.
+///
+/// ```ignore
+/// let op = simulation.op()?;
+/// let gm = op.device_param("Q1", "gm")?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OperatingPoint {
+    /// The node voltages and branch currents.
+    pub vectors: HashMap<String, f64>,
+    /// The internal parameters of the devices by device name.
+    pub devices: HashMap<String, HashMap<String, f64>>,
+}
+
+/// The operating point of a circuit element, e.g. `VCE` and `IC` of a transistor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub reference: String,
+    /// The names and values of the voltages and currents.
+    pub values: Vec<(String, f64)>,
+}
+
+impl std::fmt::Display for Annotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:", self.reference)?;
+        for (name, value) in &self.values {
+            let unit = if name.starts_with('I') { "A" } else { "V" };
+            write!(f, " {}={}{}", name, Value::new(*value), unit)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the device tables of the ngspice `show` command.
+///
+/// The tables start with a `device` row with the device names, the
+/// following rows have the parameter name and the values of the devices.
+fn parse(output: &[String]) -> HashMap<String, HashMap<String, f64>> {
+    let mut devices: HashMap<String, HashMap<String, f64>> = HashMap::new();
+    let mut names: Vec<String> = Vec::new();
+    for line in output {
+        let line = line
+            .trim_start_matches("stdout")
+            .trim_start_matches("stderr");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["device", rest @ ..] => {
+                names = rest.iter().map(|n| n.to_lowercase()).collect();
+            }
+            [param, values @ ..] if !names.is_empty() && values.len() == names.len() => {
+                for (name, value) in names.iter().zip(values) {
+                    if let Ok(value) = value.parse::<f64>() {
+                        devices
+                            .entry(name.clone())
+                            .or_default()
+                            .insert(param.to_lowercase(), value);
+                    }
+                }
+            }
+            _ => names.clear(),
+        }
+    }
+    devices
+}
+
+impl OperatingPoint {
+    /// Get the node voltage or branch current.
+    pub fn get(&self, name: &str) -> Result<f64, Error> {
+        self.vectors
+            .get(&name.to_lowercase())
+            .cloned()
+            .ok_or_else(|| Error::Signal(format!("no vector {}", name)))
+    }
+    /// Get the internal parameter of the device, e.g. `gm` of a transistor.
+    pub fn device_param(&self, device: &str, param: &str) -> Result<f64, Error> {
+        let parameters = self
+            .devices
+            .get(&device.to_lowercase())
+            .ok_or_else(|| Error::UnknownCircuitElement(device.to_string()))?;
+        parameters
+            .get(&param.to_lowercase())
+            .cloned()
+            .ok_or_else(|| Error::InvalidProperty(device.to_string(), param.to_string()))
+    }
+    /// The voltage of the node, ground is 0V.
+    fn voltage(&self, circuit: &Circuit, node: &str) -> Result<f64, Error> {
+        if circuit.is_ground(node) {
+            Ok(0.0)
+        } else {
+            self.get(node)
+        }
+    }
+    /// The terminal voltages and currents of the circuit elements.
+    ///
+    /// Currents which are not reported by ngspice are left out, subcircuits
+    /// are annotated with the voltages of their pins.
+    ///
+    /// ```ignore
+    /// for annotation in simulation.op()?.annotate(&circuit)? {
+    ///     println!("{}", annotation); //Q1: VCE=4.5V VBE=650.1mV IC=1.2mA IB=6.1uA
+    /// }
+    /// ```
+    pub fn annotate(&self, circuit: &Circuit) -> Result<Vec<Annotation>, Error> {
+        let mut result = Vec::new();
+        for item in &circuit.items {
+            let Some(reference) = item.reference() else {
+                continue;
+            };
+            let param = |param: &str| self.device_param(reference, param).ok();
+            let between = |a: &str, b: &str| -> Result<f64, Error> {
+                Ok(self.voltage(circuit, a)? - self.voltage(circuit, b)?)
+            };
+            let mut values = Vec::new();
+            match item {
+                CircuitItem::R(_, n0, n1, _, _)
+                | CircuitItem::C(_, n0, n1, _, _)
+                | CircuitItem::L(_, n0, n1, _, _)
+                | CircuitItem::I(_, n0, n1, _)
+                | CircuitItem::B(_, n0, n1, _)
+                | CircuitItem::S(_, n0, n1, _, _, _) => {
+                    values.push((String::from("V"), between(n0, n1)?));
+                    values.extend(param("i").map(|i| (String::from("I"), i)));
+                }
+                CircuitItem::D(_, n0, n1, _) => {
+                    values.push((String::from("VD"), between(n0, n1)?));
+                    values.extend(param("id").map(|i| (String::from("ID"), i)));
+                }
+                CircuitItem::Q(_, c, b, e, _) => {
+                    values.push((String::from("VCE"), between(c, e)?));
+                    values.push((String::from("VBE"), between(b, e)?));
+                    values.extend(param("ic").map(|i| (String::from("IC"), i)));
+                    values.extend(param("ib").map(|i| (String::from("IB"), i)));
+                }
+                CircuitItem::V(_, n0, n1, _) | CircuitItem::E(_, n0, n1, _, _, _) => {
+                    values.push((String::from("V"), between(n0, n1)?));
+                    let name = item.name().unwrap_or_default().to_lowercase();
+                    values.extend(
+                        self.get(&format!("{}#branch", name))
+                            .ok()
+                            .map(|i| (String::from("I"), i)),
+                    );
+                }
+                CircuitItem::X(_, nodes, _, _) | CircuitItem::N(_, nodes, _) => {
+                    for node in nodes {
+                        values.push((format!("V({})", node), self.voltage(circuit, node)?));
+                    }
+                }
+                //digital nodes have no voltage and the coupling has no nodes
+                CircuitItem::A(..)
+                | CircuitItem::K(..)
+                | CircuitItem::Model(..)
+                | CircuitItem::Include(..)
+                | CircuitItem::Comment(_)
+                | CircuitItem::Raw(_) => continue,
+            }
+            result.push(Annotation {
+                reference: reference.clone(),
+                values,
+            });
+        }
+        Ok(result)
+    }
+}
+
+impl Simulation {
+    /// Run the operating point analysis, the parameters of the devices are
+    /// read with the `show` command.
+    ///
+    /// The devices are also available by the reference of the circuit element.
+    pub fn op(&mut self) -> Result<OperatingPoint, Error> {
+        let mut c = Cb::new();
+        let ngspice = NgSpice::new(&mut c).map_err(ngspice_error)?;
+        ngspice.circuit(self.deck()?).map_err(ngspice_error)?;
+        ngspice.command("op").map_err(ngspice_error)?;
+        let vectors = vectors(&ngspice)?;
+        ngspice.command("show").map_err(ngspice_error)?;
+        self.buffer = Some(c.strs.clone());
+        let mut devices = parse(&c.strs);
+        for item in &self.circuit.items {
+            if let (Some(reference), Some(name)) = (item.reference(), item.name()) {
+                if let Some(parameters) = devices.get(&name.to_lowercase()).cloned() {
+                    devices
+                        .entry(reference.to_lowercase())
+                        .or_insert(parameters);
+                }
+            }
+        }
+        Ok(OperatingPoint {
+            vectors: vectors
+                .into_iter()
+                .filter_map(|(name, values)| values.first().map(|v| (name.to_lowercase(), *v)))
+                .collect(),
+            devices,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{parse, OperatingPoint};
+    use crate::Circuit;
+
+    #[test]
+    fn device_param() {
+        let output: Vec<String> = [
+            "stdout  BJT: Bipolar Junction Transistor",
+            "stdout      device                   q1                   q2",
+            "stdout       model                 qmod                 qmod",
+            "stdout          ic          1.23000e-03          2.45000e-03",
+            "stdout          gm          4.70000e-02          9.40000e-02",
+            "stdout ",
+            "stdout  Resistor: Simple linear resistor",
+            "stdout      device                   r1",
+            "stdout  resistance                 1000",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let op = OperatingPoint {
+            devices: parse(&output),
+            ..Default::default()
+        };
+        assert_eq!(0.094, op.device_param("Q2", "gm").unwrap());
+        assert_eq!(1.23e-3, op.device_param("q1", "IC").unwrap());
+        assert_eq!(1000.0, op.device_param("R1", "resistance").unwrap());
+        assert!(op.device_param("Q1", "model").is_err());
+        assert!(op.device_param("Q3", "gm").is_err());
+    }
+    #[test]
+    fn annotate() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .voltage(String::from("V1"), "vcc", "0", String::from("DC 10"))
+            .unwrap();
+        circuit
+            .resistor(
+                String::from("R1"),
+                String::from("vcc"),
+                String::from("c"),
+                String::from("4.7k"),
+            )
+            .unwrap();
+        circuit.model(String::from("NPN1"), String::from("NPN"), String::new());
+        circuit
+            .bjt(
+                String::from("Q1"),
+                String::from("c"),
+                String::from("b"),
+                String::from("0"),
+                String::from("NPN1"),
+            )
+            .unwrap();
+        let op = OperatingPoint {
+            vectors: HashMap::from([
+                (String::from("vcc"), 10.0),
+                (String::from("c"), 4.5),
+                (String::from("b"), 0.65),
+                (String::from("v1#branch"), -1.2e-3),
+            ]),
+            devices: HashMap::from([(
+                String::from("q1"),
+                HashMap::from([(String::from("ic"), 1.2e-3)]),
+            )]),
+        };
+        let annotations: Vec<String> = op
+            .annotate(&circuit)
+            .unwrap()
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "V1: V=10V I=-1.2mA",
+                "R1: V=5.5V",
+                "Q1: VCE=4.5V VBE=650mV IC=1.2mA"
+            ],
+            annotations
+        );
+    }
+}
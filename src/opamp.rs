@@ -0,0 +1,176 @@
+use std::f64::consts::PI;
+
+use crate::{error::Error, Circuit, Value};
+
+/// The resistor of the dominant pole.
+const POLE_RESISTANCE: f64 = 1e3;
+
+/// A behavioral op-amp with the nodes `inp inn out`.
+///
+/// The ideal op-amp is a voltage controlled voltage source with a high gain.
+/// With the gain bandwidth product the gain rolls off with a single pole,
+/// the slew rate and the output swing are limited with the XSPICE `slew`
+/// and `limit` models.
+///
+/// ```ignore
+/// let mut opamp = OpAmp::new(100e3, 1e6);
+/// opamp.set_slew_rate(0.5e6);
+/// opamp.set_rails(-13.5, 13.5);
+/// opamp.add(&mut circuit, "opamp")?;
+/// circuit.circuit(String::from("U1"), vec!["in", "out", "out"], String::from("opamp"))?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpAmp {
+    /// The open loop gain.
+    pub gain: f64,
+    /// The gain bandwidth product in Hz.
+    pub gbw: Option<f64>,
+    /// The slew rate in V/s.
+    pub slew_rate: Option<f64>,
+    /// The minimum and maximum output voltage.
+    pub rails: Option<(f64, f64)>,
+}
+
+impl OpAmp {
+    /// An op-amp with the gain of 1e6 and no limits.
+    pub fn ideal() -> Self {
+        Self {
+            gain: 1e6,
+            gbw: None,
+            slew_rate: None,
+            rails: None,
+        }
+    }
+    /// A one pole op-amp with the open loop gain and the gain bandwidth product.
+    pub fn new(gain: f64, gbw: f64) -> Self {
+        Self {
+            gain,
+            gbw: Some(gbw),
+            slew_rate: None,
+            rails: None,
+        }
+    }
+    pub fn set_slew_rate(&mut self, slew_rate: f64) {
+        self.slew_rate = Some(slew_rate);
+    }
+    pub fn set_rails(&mut self, low: f64, high: f64) {
+        self.rails = Some((low, high));
+    }
+    /// The circuit of the subcircuit.
+    pub fn subcircuit(&self, name: &str) -> Result<Circuit, Error> {
+        if self.gain <= 0.0 || self.gbw.is_some_and(|gbw| gbw <= 0.0) {
+            return Err(Error::InvalidValue(format!(
+                "gain {} and gbw {:?}",
+                self.gain, self.gbw
+            )));
+        }
+        let mut circuit = Circuit::new(name.to_string(), Vec::new());
+        let buffered = self.gbw.is_some() || self.slew_rate.is_some() || self.rails.is_some();
+        let mut last = if buffered { "gain" } else { "out" };
+        circuit.vcvs(
+            String::from("E1"),
+            last,
+            "0",
+            "inp",
+            "inn",
+            format!("{:e}", self.gain),
+        )?;
+        if let Some(gbw) = self.gbw {
+            let pole = gbw / self.gain;
+            circuit.resistor(
+                String::from("R1"),
+                "gain",
+                "pole",
+                Value::new(POLE_RESISTANCE).to_string(),
+            )?;
+            circuit.capacitor(
+                String::from("C1"),
+                "pole",
+                "0",
+                Value::new(1.0 / (2.0 * PI * POLE_RESISTANCE * pole)).to_string(),
+            )?;
+            last = "pole";
+        }
+        if let Some((low, high)) = self.rails {
+            circuit.xspice(
+                String::from("A1"),
+                vec![last.to_string(), String::from("limited")],
+                String::from("rails"),
+            )?;
+            circuit.model(
+                String::from("rails"),
+                String::from("limit"),
+                format!(
+                    "gain=1 out_lower_limit={} out_upper_limit={} limit_range={}",
+                    Value::new(low),
+                    Value::new(high),
+                    Value::new((high - low) * 0.01)
+                ),
+            );
+            last = "limited";
+        }
+        if let Some(slew_rate) = self.slew_rate {
+            circuit.xspice(
+                String::from("A2"),
+                vec![last.to_string(), String::from("slewed")],
+                String::from("slew_rate"),
+            )?;
+            circuit.model(
+                String::from("slew_rate"),
+                String::from("slew"),
+                format!(
+                    "rise_slope={} fall_slope={}",
+                    Value::new(slew_rate),
+                    Value::new(slew_rate)
+                ),
+            );
+            last = "slewed";
+        }
+        if buffered {
+            circuit.vcvs(String::from("E2"), "out", "0", last, "0", String::from("1"))?;
+        }
+        Ok(circuit)
+    }
+    /// Add the subcircuit with the name to the circuit.
+    pub fn add(&self, circuit: &mut Circuit, name: &str) -> Result<(), Error> {
+        circuit.subcircuit(
+            name.to_string(),
+            vec!["inp", "inn", "out"],
+            self.subcircuit(name)?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpAmp;
+
+    #[test]
+    fn subcircuits() {
+        assert_eq!(
+            vec!["E1 out 0 inp inn 1e6"],
+            OpAmp::ideal()
+                .subcircuit("ideal")
+                .unwrap()
+                .to_str(false)
+                .unwrap()
+        );
+        let mut opamp = OpAmp::new(100e3, 1e6);
+        opamp.set_rails(-12.0, 12.0);
+        opamp.set_slew_rate(0.5e6);
+        assert_eq!(
+            vec![
+                "E1 gain 0 inp inn 1e5",
+                "R1 gain pole 1k",
+                "C1 pole 0 15.915494309189537u",
+                "A1 pole limited rails",
+                ".model rails limit(gain=1 out_lower_limit=-12 out_upper_limit=12 limit_range=240m)",
+                "A2 limited slewed slew_rate",
+                ".model slew_rate slew(rise_slope=500k fall_slope=500k)",
+                "E2 out 0 slewed 0 1",
+            ],
+            opamp.subcircuit("opamp").unwrap().to_str(false).unwrap()
+        );
+        assert!(OpAmp::new(100e3, -1.0).subcircuit("opamp").is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use crate::{circuit::Vectors, definition::Analysis, error::Error, Circuit, Simulation};
+
+/// The result of the optimization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Optimum {
+    /// The optimized component values.
+    pub values: HashMap<String, f64>,
+    pub cost: f64,
+    pub iterations: usize,
+}
+
+/// Fit component values to minimize a cost function over the simulation results.
+///
+/// The values are varied with the Nelder-Mead simplex method, which needs
+/// no derivatives. The search runs on the logarithm of the values, so
+/// components with values in different decades are handled alike, and
+/// the values are kept within the given limits.
+///
+/// ```ignore
+/// let mut optimizer = Optimizer::new(circuit, analysis);
+/// optimizer.add_parameter("R1", 100.0, 100e3)?;
+/// optimizer.add_parameter("C1", 1e-9, 1e-6)?;
+/// let optimum = optimizer.run(|v| (v["out"][10] - 0.707).powi(2))?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Optimizer {
+    circuit: Circuit,
+    analysis: Analysis,
+    parameters: Vec<(String, f64, f64)>,
+    max_iterations: usize,
+    tolerance: f64,
+}
+
+impl Optimizer {
+    pub fn new(circuit: Circuit, analysis: Analysis) -> Self {
+        Self {
+            circuit,
+            analysis,
+            parameters: Vec::new(),
+            max_iterations: 200,
+            tolerance: 1e-6,
+        }
+    }
+    /// Vary the value of the resistor, capacitor or inductor within the limits.
+    pub fn add_parameter(&mut self, reference: &str, min: f64, max: f64) -> Result<(), Error> {
+        if min <= 0.0 || max < min {
+            return Err(Error::InvalidValue(format!("{}..{}", min, max)));
+        }
+        self.circuit.nominal(reference)?;
+        self.parameters.push((reference.to_string(), min, max));
+        Ok(())
+    }
+    /// Set the maximum number of iterations, the default is 200.
+    pub fn set_max_iterations(&mut self, iterations: usize) {
+        self.max_iterations = iterations;
+    }
+    /// Stop when the costs of the simplex differ less than the tolerance.
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        self.tolerance = tolerance;
+    }
+    /// Minimize the cost function with ngspice simulations.
+    pub fn run<F>(&self, cost: F) -> Result<Optimum, Error>
+    where
+        F: Fn(&HashMap<String, Vec<f64>>) -> f64,
+    {
+        self.evaluate(
+            |circuit| {
+                let mut simulation = Simulation::new(circuit);
+                self.analysis.run(&mut simulation)
+            },
+            cost,
+        )
+    }
+    fn values(&self, x: &[f64]) -> HashMap<String, f64> {
+        self.parameters
+            .iter()
+            .zip(x)
+            .map(|((reference, min, max), x)| (reference.clone(), x.exp().clamp(*min, *max)))
+            .collect()
+    }
+    fn evaluate<S, F>(&self, mut simulate: S, cost: F) -> Result<Optimum, Error>
+    where
+        S: FnMut(Circuit) -> Result<Vectors, Error>,
+        F: Fn(&HashMap<String, Vec<f64>>) -> f64,
+    {
+        let mut start = Vec::new();
+        for (reference, min, max) in &self.parameters {
+            start.push(self.circuit.nominal(reference)?.clamp(*min, *max).ln());
+        }
+        let (x, cost, iterations) = nelder_mead(
+            |x| {
+                let vectors = self
+                    .circuit
+                    .with_values(&self.values(x))
+                    .and_then(&mut simulate)?;
+                Ok(cost(&vectors))
+            },
+            &start,
+            self.max_iterations,
+            self.tolerance,
+        )?;
+        Ok(Optimum {
+            values: self.values(&x),
+            cost,
+            iterations,
+        })
+    }
+}
+
+/// Minimize the function with the Nelder-Mead simplex method.
+///
+/// Returns the minimum, the cost and the number of iterations, the
+/// search stops at the first error of the function.
+fn nelder_mead<F>(
+    mut f: F,
+    start: &[f64],
+    max_iterations: usize,
+    tolerance: f64,
+) -> Result<(Vec<f64>, f64, usize), Error>
+where
+    F: FnMut(&[f64]) -> Result<f64, Error>,
+{
+    let n = start.len();
+    let mut simplex: Vec<(Vec<f64>, f64)> = vec![(start.to_vec(), f(start)?)];
+    for i in 0..n {
+        let mut x = start.to_vec();
+        x[i] += if x[i] == 0.0 { 0.1 } else { 0.1 * x[i].abs() };
+        let cost = f(&x)?;
+        simplex.push((x, cost));
+    }
+    let mut iterations = 0;
+    while iterations < max_iterations {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if (simplex[n].1 - simplex[0].1).abs() <= tolerance {
+            break;
+        }
+        iterations += 1;
+        let centroid: Vec<f64> = (0..n)
+            .map(|i| simplex[..n].iter().map(|(x, _)| x[i]).sum::<f64>() / n as f64)
+            .collect();
+        let point = |factor: f64, x: &[f64]| -> Vec<f64> {
+            centroid
+                .iter()
+                .zip(x)
+                .map(|(c, x)| c + factor * (x - c))
+                .collect()
+        };
+        let worst = simplex[n].0.clone();
+        let reflected = point(-1.0, &worst);
+        let reflected_cost = f(&reflected)?;
+        if reflected_cost < simplex[0].1 {
+            let expanded = point(-2.0, &worst);
+            let expanded_cost = f(&expanded)?;
+            simplex[n] = if expanded_cost < reflected_cost {
+                (expanded, expanded_cost)
+            } else {
+                (reflected, reflected_cost)
+            };
+        } else if reflected_cost < simplex[n - 1].1 {
+            simplex[n] = (reflected, reflected_cost);
+        } else {
+            let contracted = if reflected_cost < simplex[n].1 {
+                point(-0.5, &worst)
+            } else {
+                point(0.5, &worst)
+            };
+            let contracted_cost = f(&contracted)?;
+            if contracted_cost < simplex[n].1.min(reflected_cost) {
+                simplex[n] = (contracted, contracted_cost);
+            } else {
+                //shrink towards the best point
+                let best = simplex[0].0.clone();
+                for (x, cost) in simplex.iter_mut().skip(1) {
+                    *x = best
+                        .iter()
+                        .zip(x.iter())
+                        .map(|(b, x)| b + 0.5 * (x - b))
+                        .collect();
+                    *cost = f(x)?;
+                }
+            }
+        }
+    }
+    simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let (x, cost) = simplex.swap_remove(0);
+    Ok((x, cost, iterations))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, str::FromStr};
+
+    use super::{nelder_mead, Optimizer};
+    use crate::{circuit::CircuitItem, error::Error, Analysis, Circuit, Value};
+
+    #[test]
+    fn rosenbrock() {
+        let (x, cost, _) = nelder_mead(
+            |x| Ok((1.0 - x[0]).powi(2) + 100.0 * (x[1] - x[0] * x[0]).powi(2)),
+            &[-1.2, 1.0],
+            2000,
+            1e-12,
+        )
+        .unwrap();
+        assert!(cost < 1e-6);
+        assert!((x[0] - 1.0).abs() < 1e-2);
+        assert!((x[1] - 1.0).abs() < 1e-2);
+    }
+    #[test]
+    fn fit_divider() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(
+                String::from("R1"),
+                String::from("in"),
+                String::from("out"),
+                String::from("1k"),
+            )
+            .unwrap();
+        circuit
+            .resistor(
+                String::from("R2"),
+                String::from("out"),
+                String::from("0"),
+                String::from("1k"),
+            )
+            .unwrap();
+        let analysis = Analysis::Tran {
+            step: String::from("1u"),
+            stop: String::from("1m"),
+            start: String::from("0"),
+        };
+        let mut optimizer = Optimizer::new(circuit, analysis);
+        optimizer.add_parameter("R2", 100.0, 100e3).unwrap();
+        assert!(optimizer.add_parameter("V1", 100.0, 100e3).is_err());
+        let optimum = optimizer
+            .evaluate(
+                |c| {
+                    let mut r = HashMap::new();
+                    for item in &c.items {
+                        if let CircuitItem::R(reference, _, _, value, _) = item {
+                            r.insert(reference.clone(), Value::from_str(value).unwrap().as_f64());
+                        }
+                    }
+                    let out = r["R2"] / (r["R1"] + r["R2"]);
+                    Ok(HashMap::from([(String::from("out"), vec![out])]))
+                },
+                |v| (v["out"][0] - 0.25).powi(2),
+            )
+            .unwrap();
+        assert!((optimum.values["R2"] - 333.3).abs() < 1.0);
+        let mut runs = 0;
+        let result = optimizer.evaluate(
+            |_| {
+                runs += 1;
+                Err(Error::Spice(String::from("singular matrix")))
+            },
+            |v| v["out"][0],
+        );
+        assert!(matches!(result, Err(Error::Spice(_))));
+        assert_eq!(1, runs);
+    }
+}
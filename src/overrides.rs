@@ -0,0 +1,158 @@
+use std::fs;
+
+use crate::{
+    circuit::CircuitItem,
+    error::Error,
+    node::IntoNode,
+    parser::{logical_lines, model},
+    Circuit,
+};
+
+/// Split the model parameters in name and value, spaces around `=` and
+/// commas are allowed.
+fn parameters(parameters: &str) -> Vec<(String, String)> {
+    let joined = parameters
+        .replace(',', " ")
+        .split('=')
+        .map(|part| part.trim())
+        .collect::<Vec<&str>>()
+        .join("=");
+    joined
+        .split_whitespace()
+        .map(|token| match token.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => (token.to_string(), String::new()),
+        })
+        .collect()
+}
+
+impl Circuit {
+    /// Add a bipolar transistor with model parameters for this instance.
+    ///
+    /// ```ignore
+    /// circuit.bjt_with(String::from("Q1"), "c", "b", "e", String::from("BC547B"), &[("BF", "300")])?;
+    /// ```
+    pub fn bjt_with(
+        &mut self,
+        reference: String,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+        n2: impl IntoNode,
+        model: String,
+        overrides: &[(&str, &str)],
+    ) -> Result<(), Error> {
+        let model = self.derive_model(&reference, &model, overrides)?;
+        self.bjt(reference, n0, n1, n2, model)
+    }
+    /// Add a diode with model parameters for this instance.
+    pub fn diode_with(
+        &mut self,
+        reference: String,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+        model: String,
+        overrides: &[(&str, &str)],
+    ) -> Result<(), Error> {
+        let model = self.derive_model(&reference, &model, overrides)?;
+        self.diode(reference, n0, n1, model)
+    }
+    /// Add a copy of the model with the parameters overridden, the name of
+    /// the new model is the model name and the reference.
+    ///
+    /// The model is taken from the circuit or from the libraries of the
+    /// pathlist.
+    pub fn derive_model(
+        &mut self,
+        reference: &str,
+        name: &str,
+        overrides: &[(&str, &str)],
+    ) -> Result<String, Error> {
+        let (kind, params) = self.find_model(name)?;
+        let mut params = parameters(&params);
+        for (key, value) in overrides {
+            match params.iter_mut().find(|(p, _)| p.eq_ignore_ascii_case(key)) {
+                Some(param) => param.1 = value.to_string(),
+                None => params.push((key.to_string(), value.to_string())),
+            }
+        }
+        let params = params
+            .iter()
+            .map(|(key, value)| match value.is_empty() {
+                true => key.clone(),
+                false => format!("{}={}", key, value),
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        let derived = format!("{}_{}", name, reference);
+        self.model(derived.clone(), kind, params);
+        Ok(derived)
+    }
+    /// The type and the parameters of the model.
+    fn find_model(&self, name: &str) -> Result<(String, String), Error> {
+        for item in &self.items {
+            if let CircuitItem::Model(model, kind, params) = item {
+                if model.eq_ignore_ascii_case(name) {
+                    return Ok((kind.clone(), params.clone()));
+                }
+            }
+        }
+        let includes = self.get_includes(name.to_string())?;
+        let (filename, section) = &includes[name];
+        let content = fs::read_to_string(filename)?;
+        let mut current = None;
+        for line in logical_lines(&content) {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.first().map(|t| t.to_lowercase()).as_deref() {
+                Some(".lib") if tokens.len() == 2 => current = Some(tokens[1].to_string()),
+                Some(".endl") => current = None,
+                Some(".model") if current == *section => {
+                    let (model, kind, params) = model(&tokens, &line)?;
+                    if model.eq_ignore_ascii_case(name) {
+                        return Ok((kind, params));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(Error::SpiceModelNotFound(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Circuit;
+
+    #[test]
+    fn overrides() {
+        let mut circuit = Circuit::new(String::from("test"), vec![String::from("files/spice/")]);
+        circuit.model(
+            String::from("DMOD"),
+            String::from("D"),
+            String::from("IS = 1e-14, N=1.5"),
+        );
+        circuit
+            .diode_with(
+                String::from("D1"),
+                "a",
+                "GND",
+                String::from("dmod"),
+                &[("n", "1.8"), ("RS", "2")],
+            )
+            .unwrap();
+        circuit
+            .bjt_with(
+                String::from("Q1"),
+                "c",
+                "b",
+                "GND",
+                String::from("BC547B"),
+                &[("BF", "300")],
+            )
+            .unwrap();
+        let lines = circuit.to_str(false).unwrap();
+        assert!(lines.contains(&String::from(".model dmod_D1 D(IS=1e-14 N=1.8 RS=2)")));
+        assert!(lines.contains(&String::from(".model BC547B_Q1 NPN(BF=300 NE=1.3 ISE=9.72F IKF=80M IS=20F VAF=50V BR=10 NC=2 ISC=47P IKR=12M VAR=10 RB=280 RE=1 RC=40 TR=.3U CJE=12P VJE=.48 MJE=0.5 CJC=6P VJC=.7 MJC=.33 TF=.5N)")));
+        assert!(lines.contains(&String::from("Q1 c b 0 BC547B_Q1")));
+        assert!(!lines.iter().any(|line| line.starts_with(".include")));
+    }
+}
@@ -0,0 +1,382 @@
+use std::{fs, path::Path};
+
+use crate::{
+    circuit::{split_params, CircuitItem},
+    error::Error,
+    Circuit,
+};
+
+/// The start of an inline comment, a ';' or a '$' after a whitespace.
+fn inline_comment(line: &str) -> Option<usize> {
+    let bytes = line.as_bytes();
+    bytes.iter().enumerate().position(|(i, c)| {
+        *c == b';' || (*c == b'$' && (i == 0 || bytes[i - 1].is_ascii_whitespace()))
+    })
+}
+
+/// Join the `+` continuation lines and remove the comments.
+pub(crate) fn logical_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let line = match inline_comment(line) {
+            Some(pos) => &line[..pos],
+            None => line,
+        };
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('*') {
+            continue;
+        }
+        if let Some(continuation) = trimmed.strip_prefix('+') {
+            if let Some(last) = lines.last_mut() {
+                last.push(' ');
+                last.push_str(continuation.trim());
+                continue;
+            }
+        }
+        lines.push(trimmed.to_string());
+    }
+    lines
+}
+
+/// Split a `.model` statement in name, type and parameters.
+pub(crate) fn model(tokens: &[&str], line: &str) -> Result<(String, String, String), Error> {
+    if tokens.len() < 3 {
+        return Err(Error::Spice(line.to_string()));
+    }
+    let rest = tokens[2..].join(" ");
+    if let Some(start) = rest.find('(') {
+        let kind = rest[..start].trim().to_string();
+        let end = rest.rfind(')').unwrap_or(rest.len());
+        let parameters = if end > start { rest[start + 1..end].trim() } else { "" };
+        Ok((tokens[1].to_string(), kind, parameters.to_string()))
+    } else {
+        Ok((
+            tokens[1].to_string(),
+            tokens[2].to_string(),
+            tokens[3..].join(" "),
+        ))
+    }
+}
+
+/// Add the spice statements to the circuit until the closing `.ends`.
+pub(crate) fn parse_lines(
+    circuit: &mut Circuit,
+    lines: &mut std::vec::IntoIter<String>,
+) -> Result<(), Error> {
+    while let Some(line) = lines.next() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.is_empty() {
+            continue;
+        }
+        let first = tokens[0].to_lowercase();
+        if first.starts_with('.') {
+            match first.as_str() {
+                ".subckt" => {
+                    if tokens.len() < 2 {
+                        return Err(Error::Spice(line.to_string()));
+                    }
+                    let mut subcircuit = Circuit::new(tokens[1].to_string(), Vec::new());
+                    subcircuit.set_pathlist(circuit.pathlist.clone());
+                    let (nodes, params) = split_params(&tokens[2..]);
+                    for (name, value) in params {
+                        subcircuit.add_parameter(&name, &value);
+                    }
+                    parse_lines(&mut subcircuit, lines)?;
+                    let nodes = nodes.iter().map(|n| n.to_string()).collect();
+                    circuit.subcircuit(tokens[1].to_string(), nodes, subcircuit)?;
+                }
+                ".ends" => return Ok(()),
+                ".model" => {
+                    let (name, kind, parameters) = model(&tokens, &line)?;
+                    circuit.model(name, kind, parameters);
+                }
+                ".include" | ".inc" => {
+                    let filename = tokens[1..].join(" ");
+                    circuit.include(filename.trim_matches('"'));
+                }
+                ".lib" => {
+                    if tokens.len() < 2 {
+                        return Err(Error::Spice(line.to_string()));
+                    }
+                    let filename = tokens[1].trim_matches('"').to_string();
+                    match tokens.get(2) {
+                        Some(section) => circuit.lib(filename, section.to_string()),
+                        None => circuit.include(filename),
+                    }
+                }
+                ".end" => return Ok(()),
+                ".param" => {
+                    for (name, value) in split_params(&tokens[1..]).1 {
+                        circuit.add_parameter(&name, &value);
+                    }
+                }
+                ".control" => {
+                    //the control section is not part of the circuit,
+                    //only the OSDI models are loaded
+                    for line in lines.by_ref() {
+                        let lower = line.to_lowercase();
+                        if lower.starts_with(".endc") {
+                            break;
+                        } else if let Some(filename) = lower.strip_prefix("pre_osdi") {
+                            let filename = &line[line.len() - filename.len()..];
+                            circuit.osdi(filename.trim().trim_matches('"'));
+                        }
+                    }
+                }
+                //analyses and options are controlled by the Simulation.
+                ".tran" | ".ac" | ".dc" | ".op" | ".noise" | ".options" | ".option"
+                | ".title" | ".probe" | ".save" | ".print" | ".plot" | ".temp" => {}
+                //other directives like .ic, .nodeset or .func are kept unchanged
+                _ => circuit.raw(&line),
+            }
+            continue;
+        }
+
+        let reference = tokens[0].to_string();
+        let element = first.chars().next().unwrap();
+        let nodes = match element {
+            //the coupling has the names of the inductors
+            'r' | 'c' | 'l' | 'd' | 'v' | 'i' | 'b' | 'k' => 2,
+            'q' => 3,
+            'e' | 's' => 4,
+            'x' => split_params(&tokens).0.len().saturating_sub(2),
+            'a' | 'n' => tokens.len().saturating_sub(2),
+            //elements without a circuit item like M, J or T are kept unchanged
+            _ => {
+                circuit.raw(&line);
+                continue;
+            }
+        };
+        if tokens.len() < nodes + 2 {
+            return Err(Error::Spice(line.to_string()));
+        }
+        let value = tokens[nodes + 1..].join(" ");
+        let node = |i: usize| tokens[i + 1].to_string();
+        match element {
+            'r' => circuit.resistor(reference, node(0), node(1), value)?,
+            'c' => circuit.capacitor(reference, node(0), node(1), value)?,
+            'l' => circuit.inductor(reference, node(0), node(1), value)?,
+            'd' => circuit.diode(reference, node(0), node(1), value)?,
+            'v' => circuit.voltage(reference, node(0), node(1), value)?,
+            'i' => circuit.current(reference, node(0), node(1), value)?,
+            'b' => circuit.behavioral(reference, node(0), node(1), value)?,
+            'q' => circuit.bjt(reference, node(0), node(1), node(2), value)?,
+            'e' => circuit.vcvs(reference, node(0), node(1), node(2), node(3), value)?,
+            'a' => circuit.xspice(reference, (0..nodes).map(node).collect(), value)?,
+            'n' => circuit.device(reference, (0..nodes).map(node).collect(), value)?,
+            'k' => circuit.coupling(reference, node(0), node(1), value)?,
+            's' => circuit.switch(reference, node(0), node(1), node(2), node(3), value)?,
+            _ => {
+                let nodes = (0..nodes).map(node).collect();
+                circuit.circuit(reference, nodes, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse a spice deck, the first line is the title of the circuit.
+pub(crate) fn parse(content: &str, pathlist: Vec<String>) -> Result<Circuit, Error> {
+    let mut lines = content.lines();
+    let title = lines.next().unwrap_or_default().trim();
+    let title = title.strip_prefix('*').unwrap_or(title).trim();
+    let mut circuit = Circuit::new(title.to_string(), pathlist);
+    let rest: Vec<&str> = lines.collect();
+    let mut lines = logical_lines(&rest.join("\n")).into_iter();
+    parse_lines(&mut circuit, &mut lines)?;
+    Ok(circuit)
+}
+
+/// Resolve the relative include paths against the directory of the deck.
+pub(crate) fn resolve_includes(circuit: &mut Circuit, directory: &Path) {
+    for item in circuit.items.iter_mut() {
+        if let CircuitItem::Include(filename, _) = item {
+            if filename.is_relative() {
+                *filename = directory.join(&filename);
+            }
+        }
+    }
+    for (_, body) in circuit.subcircuits.values_mut() {
+        resolve_includes(body, directory);
+    }
+}
+
+impl Circuit {
+    /// Load a spice deck (.cir/.sp) into a new circuit.
+    ///
+    /// Only the circuit elements are imported; analyses, options and
+    /// control sections are skipped and must be run with a `Simulation`.
+    /// Unknown elements and directives are kept as raw lines and relative
+    /// includes are resolved against the directory of the deck.
+    pub fn load(filename: &str, pathlist: Vec<String>) -> Result<Circuit, Error> {
+        let content = fs::read_to_string(filename)?;
+        let mut circuit = parse(&content, pathlist)?;
+        if let Some(directory) = Path::new(filename).parent() {
+            resolve_includes(&mut circuit, directory);
+        }
+        Ok(circuit)
+    }
+
+    /// Parse a spice deck from a string.
+    pub fn parse(content: &str, pathlist: Vec<String>) -> Result<Circuit, Error> {
+        parse(content, pathlist)
+    }
+}
+
+/// Parse a spice deck without a library pathlist.
+impl std::str::FromStr for Circuit {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Circuit;
+
+    #[test]
+    fn parse_deck() {
+        let mut circuit = Circuit::parse(
+            "* test deck\n\
+             .include files/spice/TL072.lib\n\
+             .subckt divider in out\n\
+             R1 in out 10k\n\
+             R2 out 0 10k\n\
+             .ends divider\n\
+             .model DMOD D(IS=1e-14\n\
+             + N=1.5)\n\
+             V1 in 0 DC 5 ; supply\n\
+             D1 in a DMOD\n\
+             XU1 in out divider\n\
+             .tran 1u 1m\n\
+             .end\n",
+            vec![String::from("files/spice/")],
+        )
+        .unwrap();
+        circuit.set_value("V1", "DC 10").unwrap();
+        assert_eq!(
+            vec![
+                String::from(".subckt divider in out"),
+                String::from("R1 in out 10k"),
+                String::from("R2 out 0 10k"),
+                String::from(".ends"),
+                String::from(".include files/spice/TL072.lib"),
+                String::from(".model DMOD D(IS=1e-14 N=1.5)"),
+                String::from("V1 in 0 DC 10"),
+                String::from("D1 in a DMOD"),
+                String::from("XU1 in out divider"),
+                String::from(".end"),
+            ],
+            circuit.to_str(true).unwrap()
+        );
+    }
+    #[test]
+    fn round_trip() {
+        let circuit: Circuit = "* divider\n\
+                                .model DMOD D(IS=1e-14)\n\
+                                V1 in 0 DC 5\n\
+                                R1 in out 10k\n\
+                                D1 out 0 DMOD\n"
+            .parse()
+            .unwrap();
+        let deck = circuit.to_string();
+        assert_eq!(
+            "* divider\n\
+             .model DMOD D(IS=1e-14)\n\
+             V1 in 0 DC 5\n\
+             R1 in out 10k\n\
+             D1 out 0 DMOD\n\
+             .end\n",
+            deck
+        );
+        assert_eq!(circuit, deck.parse().unwrap());
+    }
+    #[test]
+    fn osdi_device() {
+        let circuit = Circuit::parse(
+            "* osdi\n\
+             .control\n\
+             pre_osdi models/BSIMCMG.osdi\n\
+             .endc\n\
+             .model nmos1 bsimcmg type=1\n\
+             N1 d g s 0 nmos1\n",
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                String::from(".control"),
+                String::from("pre_osdi models/BSIMCMG.osdi"),
+                String::from(".endc"),
+                String::from(".model nmos1 bsimcmg(type=1)"),
+                String::from("N1 d g s 0 nmos1"),
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+    #[test]
+    fn subcircuit_params() {
+        let circuit = Circuit::parse(
+            "* params\n\
+             .param gain=2\n\
+             .subckt lowpass in out params: r=10k c=1n\n\
+             R1 in out {r}\n\
+             C1 out 0 {c}\n\
+             .ends\n\
+             X1 a b lowpass params: r=4.7k\n\
+             X2 b c lowpass c=2.2n\n",
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                String::from(".subckt lowpass in out params: r=10k c=1n"),
+                String::from("R1 in out {r}"),
+                String::from("C1 out 0 {c}"),
+                String::from(".ends"),
+                String::from(".param gain=2"),
+                String::from("X1 a b lowpass params: r=4.7k"),
+                String::from("X2 b c lowpass params: c=2.2n"),
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+    #[test]
+    fn raw_lines() {
+        let circuit = Circuit::parse(
+            "* raw\n\
+             .global vcc\n\
+             M1 d g s s NMOS w=1u $ transistor\n\
+             J1 d g s JMOD\n\
+             R1 d vcc 1k $ load\n\
+             .ic v(d)=0\n\
+             .meas tran vmax MAX v(d)\n",
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                String::from(".global vcc"),
+                String::from("M1 d g s s NMOS w=1u"),
+                String::from("J1 d g s JMOD"),
+                String::from("R1 d vcc 1k"),
+                String::from(".ic v(d)=0"),
+                String::from(".meas tran vmax MAX v(d)"),
+            ],
+            circuit.to_str(false).unwrap()
+        );
+    }
+    #[test]
+    fn relative_include() {
+        let directory = std::env::temp_dir().join("elektron_spice_relative_include");
+        std::fs::create_dir_all(&directory).unwrap();
+        let deck = directory.join("deck.cir");
+        std::fs::write(&deck, "* deck\n.include models/opamp.lib\nR1 a 0 1k\n").unwrap();
+        let circuit = Circuit::load(deck.to_str().unwrap(), Vec::new()).unwrap();
+        assert_eq!(
+            format!(".include {}", directory.join("models/opamp.lib").display()),
+            circuit.to_str(false).unwrap()[0]
+        );
+    }
+}
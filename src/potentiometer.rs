@@ -0,0 +1,157 @@
+use elektron_ngspice::NgSpice;
+
+use crate::{
+    circuit::{ngspice_error, vectors, Cb, CircuitItem, Vectors},
+    error::Error,
+    node::IntoNode,
+    Analysis, Circuit, Simulation, Value,
+};
+
+/// The smallest resistance of a track, ngspice does not accept 0Ω.
+const MIN_RESISTANCE: f64 = 1e-3;
+
+/// The resistances from the first node to the wiper and from the wiper
+/// to the second node.
+fn tracks(total: f64, position: f64) -> Result<(f64, f64), Error> {
+    if !(0.0..=1.0).contains(&position) || total <= 0.0 {
+        return Err(Error::InvalidValue(format!(
+            "{} at position {}",
+            Value::new(total),
+            position
+        )));
+    }
+    Ok((
+        (total * position).max(MIN_RESISTANCE),
+        (total * (1.0 - position)).max(MIN_RESISTANCE),
+    ))
+}
+
+/// The references of the two resistors of the potentiometer.
+fn references(reference: &str) -> (String, String) {
+    (format!("{}A", reference), format!("{}B", reference))
+}
+
+impl Circuit {
+    /// Add a potentiometer as two resistors, the position goes from 0 at
+    /// the first node to 1 at the second node.
+    ///
+    /// The resistors are the reference with the suffix `A` between the first
+    /// node and the wiper and `B` between the wiper and the second node.
+    pub fn potentiometer(
+        &mut self,
+        reference: &str,
+        n1: impl IntoNode,
+        wiper: impl IntoNode,
+        n2: impl IntoNode,
+        total: f64,
+        position: f64,
+    ) -> Result<(), Error> {
+        let (a, b) = tracks(total, position)?;
+        let wiper = String::from(wiper.into_node()?);
+        let (ref_a, ref_b) = references(reference);
+        self.resistor(ref_a, n1, wiper.as_str(), Value::new(a).to_string())?;
+        self.resistor(ref_b, wiper.as_str(), n2, Value::new(b).to_string())?;
+        self.potentiometers.insert(reference.to_string(), total);
+        Ok(())
+    }
+    /// Move the wiper of the potentiometer.
+    pub fn set_wiper(&mut self, reference: &str, position: f64) -> Result<(), Error> {
+        let (ref_a, ref_b) = references(reference);
+        let total = self.potentiometer_total(reference)?;
+        let (a, b) = tracks(total, position)?;
+        self.set_value(&ref_a, Value::new(a))?;
+        self.set_value(&ref_b, Value::new(b))
+    }
+    /// The total resistance of the potentiometer, the sum of the resistors
+    /// when the potentiometer was not added with `potentiometer`.
+    fn potentiometer_total(&self, reference: &str) -> Result<f64, Error> {
+        if let Some(total) = self.potentiometers.get(reference) {
+            return Ok(*total);
+        }
+        let (ref_a, ref_b) = references(reference);
+        Ok(self.nominal(&ref_a)? + self.nominal(&ref_b)?)
+    }
+}
+
+impl Simulation {
+    /// Run the analysis for every position of the potentiometer.
+    ///
+    /// The circuit is loaded once and the resistors are changed with `alter`
+    /// between the runs.
+    ///
+    /// ```ignore
+    /// circuit.potentiometer("RV1", "in", "out", "GND", 100e3, 0.5)?;
+    /// let mut simulation = Simulation::new(circuit);
+    /// let results = simulation.sweep_potentiometer("RV1", &[0.1, 0.5, 0.9], &analysis)?;
+    /// ```
+    pub fn sweep_potentiometer(
+        &mut self,
+        reference: &str,
+        positions: &[f64],
+        analysis: &Analysis,
+    ) -> Result<Vec<(f64, Vectors)>, Error> {
+        let (ref_a, ref_b) = references(reference);
+        let total = self.circuit.potentiometer_total(reference)?;
+        let name = |reference: &str| {
+            self.circuit
+                .items
+                .iter()
+                .find(|item| matches!(item, CircuitItem::R(r, ..) if r == reference))
+                .and_then(|item| item.name())
+                .map(|name| name.to_lowercase())
+                .ok_or_else(|| Error::UnknownCircuitElement(reference.to_string()))
+        };
+        let (name_a, name_b) = (name(&ref_a)?, name(&ref_b)?);
+        let mut c = Cb::new();
+        let ngspice = NgSpice::new(&mut c).map_err(ngspice_error)?;
+        ngspice.circuit(self.deck()?).map_err(ngspice_error)?;
+        let mut result = Vec::new();
+        for position in positions {
+            let (a, b) = tracks(total, *position)?;
+            for (name, value) in [(&name_a, a), (&name_b, b)] {
+                ngspice
+                    .command(format!("alter {} = {}", name, Value::new(value)).as_str())
+                    .map_err(ngspice_error)?;
+            }
+            ngspice
+                .command(analysis.command().as_str())
+                .map_err(ngspice_error)?;
+            result.push((*position, vectors(&ngspice)?));
+        }
+        self.buffer = Some(c.strs.clone());
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Circuit;
+
+    #[test]
+    fn potentiometer() {
+        let mut circuit = Circuit::new(String::from("volume"), Vec::new());
+        circuit
+            .potentiometer("RV1", "in", "out", "GND", 100e3, 0.25)
+            .unwrap();
+        assert_eq!(
+            vec!["RV1A in out 25k", "RV1B out 0 75k"],
+            circuit.to_str(false).unwrap()
+        );
+        circuit.set_wiper("RV1", 1.0).unwrap();
+        assert_eq!(
+            vec!["RV1A in out 100k", "RV1B out 0 1m"],
+            circuit.to_str(false).unwrap()
+        );
+        circuit.set_wiper("RV1", 0.0).unwrap();
+        circuit.set_wiper("RV1", 0.5).unwrap();
+        assert_eq!(
+            vec!["RV1A in out 50k", "RV1B out 0 50k"],
+            circuit.to_str(false).unwrap()
+        );
+        assert!(circuit.set_wiper("RV1", 1.5).is_err());
+        assert!(circuit.set_wiper("RV2", 0.5).is_err());
+        assert!(circuit
+            .potentiometer("RV3", "a", "b", "c", 10e3, -0.1)
+            .is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    circuit::{CircuitItem, Vectors},
+    error::Error,
+    Circuit, Simulation,
+};
+
+/// The power dissipated in a component, sources delivering power have
+/// a negative dissipation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dissipation {
+    pub reference: String,
+    /// The instantaneous power in watt.
+    pub power: Vec<f64>,
+    /// The power averaged over the time.
+    pub average: f64,
+    pub peak: f64,
+}
+
+/// The power dissipation of the components over a transient analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerReport {
+    pub time: Vec<f64>,
+    pub components: Vec<Dissipation>,
+}
+
+impl PowerReport {
+    /// Get the dissipation of the component.
+    pub fn get(&self, reference: &str) -> Option<&Dissipation> {
+        self.components.iter().find(|c| c.reference == reference)
+    }
+    /// Calculate the power from the node voltages and the device currents.
+    pub(crate) fn new(circuit: &Circuit, vectors: &Vectors) -> Result<Self, Error> {
+        let time = get(vectors, "time")?.clone();
+        let voltage = |a: &str, b: &str| -> Result<Vec<f64>, Error> {
+            let node = |n: &str| -> Result<Vec<f64>, Error> {
+                if circuit.is_ground(n) {
+                    Ok(vec![0.0; time.len()])
+                } else {
+                    Ok(get(vectors, &n.to_lowercase())?.clone())
+                }
+            };
+            Ok(node(a)?.iter().zip(node(b)?).map(|(a, b)| a - b).collect())
+        };
+        let product = |v: Vec<f64>, i: &Vec<f64>| -> Vec<f64> {
+            v.iter().zip(i).map(|(v, i)| v * i).collect()
+        };
+        let mut components = Vec::new();
+        for item in &circuit.items {
+            let (Some(reference), Some(name)) = (item.reference(), item.name()) else {
+                continue;
+            };
+            let name = name.to_lowercase();
+            let power = match item {
+                CircuitItem::R(_, n0, n1, _, _)
+                | CircuitItem::C(_, n0, n1, _, _)
+                | CircuitItem::L(_, n0, n1, _, _) => {
+                    product(voltage(n0, n1)?, get(vectors, &format!("@{}[i]", name))?)
+                }
+                CircuitItem::D(_, n0, n1, _) => {
+                    product(voltage(n0, n1)?, get(vectors, &format!("@{}[id]", name))?)
+                }
+                CircuitItem::I(_, n0, n1, _) => product(
+                    voltage(n0, n1)?,
+                    get(vectors, &format!("@{}[current]", name))?,
+                ),
+                CircuitItem::V(_, n0, n1, _) | CircuitItem::E(_, n0, n1, _, _, _) => {
+                    product(voltage(n0, n1)?, get(vectors, &format!("{}#branch", name))?)
+                }
+                CircuitItem::Q(_, c, b, e, _) => {
+                    let collector =
+                        product(voltage(c, e)?, get(vectors, &format!("@{}[ic]", name))?);
+                    let base = product(voltage(b, e)?, get(vectors, &format!("@{}[ib]", name))?);
+                    collector.iter().zip(base).map(|(c, b)| c + b).collect()
+                }
+                _ => continue,
+            };
+            components.push(Dissipation {
+                reference: reference.clone(),
+                average: average(&time, &power),
+                peak: power.iter().cloned().fold(f64::MIN, f64::max),
+                power,
+            });
+        }
+        Ok(Self { time, components })
+    }
+}
+
+pub(crate) fn get<'a>(vectors: &'a Vectors, name: &str) -> Result<&'a Vec<f64>, Error> {
+    vectors
+        .get(name)
+        .ok_or_else(|| Error::Signal(format!("no vector {}", name)))
+}
+
+/// The time weighted average, the time steps of ngspice are not equidistant.
+pub(crate) fn average(time: &[f64], values: &[f64]) -> f64 {
+    if time.len() < 2 || time[time.len() - 1] == time[0] {
+        return values.first().cloned().unwrap_or(0.0);
+    }
+    let duration = time[time.len() - 1] - time[0];
+    let area: f64 = time
+        .windows(2)
+        .zip(values.windows(2))
+        .map(|(t, v)| (t[1] - t[0]) * (v[0] + v[1]) / 2.0)
+        .sum();
+    area / duration
+}
+
+impl Simulation {
+    /// Run the transient analysis and get the power dissipation of the
+    /// components, the currents are saved with `.options savecurrents`.
+    ///
+    /// Subcircuits are not included in the report.
+    ///
+    /// ```ignore
+    /// let report = simulation.power("1u", "10m", "0")?;
+    /// assert!(report.get("Q1").unwrap().average < 0.25);
+    /// ```
+    pub fn power(&mut self, step: &str, stop: &str, start: &str) -> Result<PowerReport, Error> {
+        let vectors = self.run_with(
+            &[String::from(".options savecurrents")],
+            &format!("tran {} {} {}", step, stop, start),
+        )?;
+        PowerReport::new(&self.circuit, &vectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::PowerReport;
+    use crate::Circuit;
+
+    #[test]
+    fn power() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .voltage(
+                String::from("V1"),
+                "in",
+                "0",
+                String::from("PULSE(0 10 0 1n 1n 5u 10u)"),
+            )
+            .unwrap();
+        circuit
+            .resistor(
+                String::from("R1"),
+                String::from("in"),
+                String::from("0"),
+                String::from("100"),
+            )
+            .unwrap();
+        let vectors = HashMap::from([
+            (String::from("time"), vec![0.0, 5e-6, 5e-6, 10e-6]),
+            (String::from("in"), vec![10.0, 10.0, 0.0, 0.0]),
+            (String::from("@r1[i]"), vec![0.1, 0.1, 0.0, 0.0]),
+            (String::from("v1#branch"), vec![-0.1, -0.1, 0.0, 0.0]),
+        ]);
+        let report = PowerReport::new(&circuit, &vectors).unwrap();
+        let r1 = report.get("R1").unwrap();
+        assert_eq!(vec![1.0, 1.0, 0.0, 0.0], r1.power);
+        assert!((r1.average - 0.5).abs() < 1e-12);
+        assert_eq!(1.0, r1.peak);
+        assert!((report.get("V1").unwrap().average + 0.5).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{error::Error, Circuit};
+
+/// The number of nodes of the elements with a fixed number of nodes.
+fn node_count(line: &str) -> usize {
+    match line.chars().next().map(|c| c.to_ascii_uppercase()) {
+        Some('R' | 'C' | 'L' | 'D' | 'V' | 'I' | 'B') => 2,
+        Some('Q') => 3,
+        Some('E' | 'S') => 4,
+        _ => 0,
+    }
+}
+
+/// Align the reference and the node columns of the element lines, comments
+/// and continuation lines are not changed.
+fn align(lines: &[String]) -> Vec<String> {
+    let split: Vec<Vec<&str>> = lines
+        .iter()
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    let columns = |tokens: &Vec<&str>| match tokens.first() {
+        Some(first) if !first.starts_with(['*', '+']) => (node_count(first) + 1).min(tokens.len() - 1),
+        _ => 0,
+    };
+    let mut widths: Vec<usize> = Vec::new();
+    for tokens in &split {
+        for (i, token) in tokens.iter().take(columns(tokens)).enumerate() {
+            if widths.len() <= i {
+                widths.push(0);
+            }
+            widths[i] = widths[i].max(token.len());
+        }
+    }
+    split
+        .iter()
+        .zip(lines)
+        .map(|(tokens, line)| {
+            let count = columns(tokens);
+            if count == 0 {
+                return line.to_string();
+            }
+            let mut line = String::new();
+            for (i, token) in tokens.iter().enumerate() {
+                if i < count {
+                    line.push_str(&format!("{:<width$} ", token, width = widths[i]));
+                } else {
+                    line.push_str(token);
+                    line.push(' ');
+                }
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Add the section with the comment banner.
+fn section(result: &mut Vec<String>, title: &str, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    result.push(String::new());
+    result.push(format!("* ---- {} ----", title));
+    result.extend_from_slice(lines);
+}
+
+impl Circuit {
+    /// The netlist formatted to be read by humans.
+    ///
+    /// The netlist starts with the `.title` and a comment with the version
+    /// of the generator. The statements are grouped in sections with a
+    /// comment banner and the columns of the elements are aligned.
+    pub fn to_pretty(&self) -> Result<Vec<String>, Error> {
+        let lines: Vec<String> = self
+            .to_str(false)?
+            .into_iter()
+            .map(|line| line.trim_end().to_string())
+            .collect();
+        let (mut control, mut includes, mut params, mut models) =
+            (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        let (mut subcircuits, mut elements) = (Vec::new(), Vec::new());
+        let mut lines = lines.into_iter();
+        while let Some(line) = lines.next() {
+            let keyword = line
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+            match keyword.as_str() {
+                ".control" => {
+                    control.push(line);
+                    for line in lines.by_ref() {
+                        let end = line.eq_ignore_ascii_case(".endc");
+                        control.push(line);
+                        if end {
+                            break;
+                        }
+                    }
+                }
+                ".subckt" => {
+                    let mut body = Vec::new();
+                    for line in lines.by_ref() {
+                        if line.to_lowercase().starts_with(".ends") {
+                            break;
+                        }
+                        body.push(line);
+                    }
+                    subcircuits.push(String::new());
+                    subcircuits.push(line);
+                    let (statements, items): (Vec<String>, Vec<String>) =
+                        body.into_iter().partition(|l| l.starts_with('.'));
+                    subcircuits.extend(statements);
+                    subcircuits.extend(align(&items));
+                    subcircuits.push(String::from(".ends"));
+                }
+                ".include" | ".lib" => includes.push(line),
+                ".param" => params.push(line),
+                ".model" => models.push(line),
+                _ => elements.push(line),
+            }
+        }
+        let mut result = vec![
+            format!(".title {}", self.name),
+            format!(
+                "* generated by elektron_spice {}",
+                env!("CARGO_PKG_VERSION")
+            ),
+        ];
+        section(&mut result, "control", &control);
+        section(&mut result, "libraries", &includes);
+        section(&mut result, "parameters", &params);
+        if !subcircuits.is_empty() {
+            result.push(String::new());
+            result.push(String::from("* ---- subcircuits ----"));
+            result.extend(subcircuits.into_iter().skip(1));
+        }
+        section(&mut result, "models", &models);
+        section(&mut result, "elements", &align(&elements));
+        result.push(String::new());
+        result.push(String::from(".end"));
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Circuit;
+
+    #[test]
+    fn pretty() {
+        let circuit = Circuit::parse(
+            "* divider\n\
+             .param gain=2\n\
+             .subckt buffer in out\n\
+             E1 out 0 in 0 1\n\
+             .ends\n\
+             .model DMOD D(IS=1e-14)\n\
+             V1 input 0 DC 5\n\
+             R1 input out 10k\n\
+             D1 out 0 DMOD\n\
+             X1 out buffered buffer\n",
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                String::from(".title divider"),
+                format!(
+                    "* generated by elektron_spice {}",
+                    env!("CARGO_PKG_VERSION")
+                ),
+                String::new(),
+                String::from("* ---- parameters ----"),
+                String::from(".param gain=2"),
+                String::new(),
+                String::from("* ---- subcircuits ----"),
+                String::from(".subckt buffer in out"),
+                String::from("E1 out 0 in 0 1"),
+                String::from(".ends"),
+                String::new(),
+                String::from("* ---- models ----"),
+                String::from(".model DMOD D(IS=1e-14)"),
+                String::new(),
+                String::from("* ---- elements ----"),
+                String::from("V1 input 0   DC 5"),
+                String::from("R1 input out 10k"),
+                String::from("D1 out   0   DMOD"),
+                String::from("X1 out buffered buffer"),
+                String::new(),
+                String::from(".end"),
+            ],
+            circuit.to_pretty().unwrap()
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+
+use crate::{
+    circuit::{CircuitItem, Vectors},
+    error::Error,
+    linear::solve,
+    power::get,
+    Circuit, Simulation, Value,
+};
+
+/// The periodic steady state of a circuit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SteadyState {
+    /// The vectors of one period in the steady state, the time starts at 0.
+    pub vectors: HashMap<String, Vec<f64>>,
+    /// The capacitor node voltages and inductor currents at the start of the period.
+    pub state: HashMap<String, f64>,
+    pub iterations: usize,
+    /// The largest change of the state over the last period.
+    pub residual: f64,
+}
+
+/// Find the periodic steady state with the shooting method.
+///
+/// The circuit is simulated over one period, starting with the capacitor
+/// node voltages and inductor currents of the state. The state is corrected
+/// with Newton's method until it is repeated after the period. The jacobian
+/// is estimated by perturbing every state variable, so every iteration
+/// needs one transient per state variable.
+///
+/// ```ignore
+/// let mut shooting = Shooting::new(10e-6, "10n");
+/// shooting.set_settle(20);
+/// let steady = shooting.run(&mut simulation)?;
+/// let ripple = &steady.vectors["out"];
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shooting {
+    /// The period in seconds.
+    pub period: f64,
+    /// The time step of the transient.
+    pub step: String,
+    /// The number of periods simulated before the first iteration.
+    pub settle: usize,
+    pub max_iterations: usize,
+    pub tolerance: f64,
+}
+
+/// The state variables of the circuit, the nodes of the capacitors
+/// and the branches of the inductors.
+fn states(circuit: &Circuit) -> Vec<String> {
+    let mut states = Vec::new();
+    for item in &circuit.items {
+        let names = match item {
+            CircuitItem::C(_, n0, n1, _, _) => [n0, n1]
+                .iter()
+                .filter(|n| !circuit.is_ground(n))
+                .map(|n| n.to_lowercase())
+                .collect(),
+            CircuitItem::L(..) => {
+                vec![format!(
+                    "{}#branch",
+                    item.name().unwrap_or_default().to_lowercase()
+                )]
+            }
+            _ => continue,
+        };
+        for name in names {
+            if !states.contains(&name) {
+                states.push(name);
+            }
+        }
+    }
+    states
+}
+
+/// The values of the state variables at the end of the transient.
+fn last(vectors: &Vectors, states: &[String]) -> Result<Vec<f64>, Error> {
+    states
+        .iter()
+        .map(|name| {
+            get(vectors, name)?
+                .last()
+                .cloned()
+                .ok_or_else(|| Error::Signal(format!("empty vector {}", name)))
+        })
+        .collect()
+}
+
+/// Set the initial conditions, the inductor currents are set at the
+/// elements and the node voltages with `.ic` lines.
+fn initial(circuit: &Circuit, states: &[String], state: &[f64]) -> (Circuit, Vec<String>) {
+    let mut circuit = circuit.clone();
+    let mut lines = Vec::new();
+    for (name, value) in states.iter().zip(state) {
+        match name.strip_suffix("#branch") {
+            Some(inductor) => {
+                for item in circuit.items.iter_mut() {
+                    let matches =
+                        item.name().map(|n| n.to_lowercase()).as_deref() == Some(inductor);
+                    if let (true, CircuitItem::L(_, _, _, ref mut v, _)) = (matches, item) {
+                        *v = format!("{} IC={}", v, value);
+                    }
+                }
+            }
+            None => lines.push(format!(".ic v({})={}", name, value)),
+        }
+    }
+    (circuit, lines)
+}
+
+impl Shooting {
+    pub fn new(period: f64, step: &str) -> Self {
+        Self {
+            period,
+            step: step.to_string(),
+            settle: 5,
+            max_iterations: 20,
+            tolerance: 1e-6,
+        }
+    }
+    /// Set the number of periods before the first iteration, the default is 5.
+    pub fn set_settle(&mut self, periods: usize) {
+        self.settle = periods.max(1);
+    }
+    /// Set the maximum number of Newton iterations, the default is 20.
+    pub fn set_max_iterations(&mut self, iterations: usize) {
+        self.max_iterations = iterations;
+    }
+    /// Stop when the state changes less than the tolerance relative to its value.
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        self.tolerance = tolerance;
+    }
+    /// Find the steady state with ngspice transients.
+    pub fn run(&self, simulation: &mut Simulation) -> Result<SteadyState, Error> {
+        let states = states(&simulation.circuit);
+        if states.is_empty() {
+            return Err(Error::Signal(String::from(
+                "the circuit has no capacitors or inductors",
+            )));
+        }
+        let vectors = simulation.run_with(
+            &[],
+            &format!(
+                "tran {} {} 0",
+                self.step,
+                Value::new(self.settle as f64 * self.period)
+            ),
+        )?;
+        let start = last(&vectors, &states)?;
+        let base = simulation.circuit.clone();
+        self.evaluate(&states, start, |state| {
+            let (circuit, lines) = initial(&base, &states, state);
+            let mut simulation = simulation.with_circuit(circuit);
+            simulation.run_with(
+                &lines,
+                &format!("tran {} {} 0 uic", self.step, Value::new(self.period)),
+            )
+        })
+    }
+    fn evaluate<S>(
+        &self,
+        states: &[String],
+        mut x: Vec<f64>,
+        mut simulate: S,
+    ) -> Result<SteadyState, Error>
+    where
+        S: FnMut(&[f64]) -> Result<Vectors, Error>,
+    {
+        for iteration in 1..=self.max_iterations {
+            let vectors = simulate(&x)?;
+            let end = last(&vectors, states)?;
+            let residual: Vec<f64> = end.iter().zip(&x).map(|(e, x)| e - x).collect();
+            let converged = residual
+                .iter()
+                .zip(&x)
+                .all(|(r, x)| r.abs() <= self.tolerance * (1.0 + x.abs()));
+            if converged {
+                return Ok(SteadyState {
+                    state: states.iter().cloned().zip(x).collect(),
+                    vectors,
+                    iterations: iteration,
+                    residual: residual.iter().fold(0.0, |m, r| f64::max(m, r.abs())),
+                });
+            }
+            //the columns of the jacobian of the period map minus the identity
+            let mut jacobian = vec![vec![0.0; x.len()]; x.len()];
+            for i in 0..x.len() {
+                let delta = 1e-4 * (1.0 + x[i].abs());
+                let mut perturbed = x.clone();
+                perturbed[i] += delta;
+                let shifted = last(&simulate(&perturbed)?, states)?;
+                for (row, value) in jacobian.iter_mut().enumerate() {
+                    value[i] = (shifted[row] - end[row]) / delta - if row == i { 1.0 } else { 0.0 };
+                }
+            }
+            match solve(jacobian, residual.iter().map(|r| -r).collect()) {
+                Some(dx) => x.iter_mut().zip(dx).for_each(|(x, dx)| *x += dx),
+                //fall back to the next period
+                None => x = end,
+            }
+        }
+        Err(Error::Signal(format!(
+            "no steady state after {} iterations",
+            self.max_iterations
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{initial, states, Shooting};
+    use crate::Circuit;
+
+    #[test]
+    fn states_and_initial() {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .inductor(
+                String::from("L1"),
+                String::from("sw"),
+                String::from("out"),
+                String::from("10u"),
+            )
+            .unwrap();
+        circuit
+            .capacitor(
+                String::from("C1"),
+                String::from("out"),
+                String::from("GND"),
+                String::from("100u"),
+            )
+            .unwrap();
+        let states = states(&circuit);
+        assert_eq!(vec!["l1#branch", "out"], states);
+        let (circuit, lines) = initial(&circuit, &states, &[0.5, 5.0]);
+        assert_eq!(vec![".ic v(out)=5"], lines);
+        assert!(circuit
+            .to_str(false)
+            .unwrap()
+            .contains(&String::from("L1 sw out 10u IC=0.5")));
+    }
+    #[test]
+    fn shooting() {
+        //a linear period map with the fixed point (2, -1)
+        let map = |x: &[f64]| vec![0.5 * x[0] + 0.1 * x[1] + 1.1, 0.2 * x[0] + 0.9 * x[1] - 0.5];
+        let states = vec![String::from("a"), String::from("b")];
+        let mut runs = 0;
+        let steady = Shooting::new(1e-3, "1u")
+            .evaluate(&states, vec![0.0, 0.0], |x| {
+                runs += 1;
+                let end = map(x);
+                Ok(HashMap::from([
+                    (String::from("a"), vec![x[0], end[0]]),
+                    (String::from("b"), vec![x[1], end[1]]),
+                ]))
+            })
+            .unwrap();
+        assert_eq!(2, steady.iterations);
+        assert_eq!(4, runs);
+        assert!((steady.state["a"] - 2.0).abs() < 1e-6);
+        assert!((steady.state["b"] + 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{circuit::Vectors, error::Error, Simulation, SpiceMessage};
+
+/// The retries of a transient analysis that does not converge.
+///
+/// Every retry adds its options to the options of the retries before. The
+/// default policy switches to the gear integration, raises the iteration
+/// limit of a time point and then relaxes the tolerances.
+///
+/// ```ignore
+/// let recovered = simulation.tran_recover("1u", "10m", "0", &Recovery::default())?;
+/// println!("converged with {:?} after {} retries", recovered.options, recovered.retries);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovery {
+    pub steps: Vec<Vec<(String, String)>>,
+}
+
+impl Default for Recovery {
+    fn default() -> Self {
+        let mut recovery = Self::new();
+        recovery.add_step(&[("method", "gear")]);
+        recovery.add_step(&[("itl4", "100")]);
+        recovery.add_step(&[("reltol", "0.01"), ("abstol", "1e-10"), ("vntol", "1e-4")]);
+        recovery
+    }
+}
+
+impl Recovery {
+    /// A policy without retries.
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+    /// Add a retry with the options.
+    pub fn add_step(&mut self, options: &[(&str, &str)]) {
+        self.steps.push(
+            options
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        );
+    }
+    /// The options of the retry, the first run has no options. Later
+    /// options replace options with the same name.
+    pub fn options(&self, retry: usize) -> Vec<(String, String)> {
+        let mut result: Vec<(String, String)> = Vec::new();
+        for (name, value) in self.steps.iter().take(retry).flatten() {
+            match result
+                .iter_mut()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            {
+                Some(option) => option.1 = value.clone(),
+                None => result.push((name.clone(), value.clone())),
+            }
+        }
+        result
+    }
+}
+
+/// The result of the transient analysis with the options that converged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovered {
+    pub vectors: Vectors,
+    pub options: Vec<(String, String)>,
+    pub retries: usize,
+}
+
+/// The `.options` line of the options.
+fn options_line(options: &[(String, String)]) -> Vec<String> {
+    if options.is_empty() {
+        return Vec::new();
+    }
+    let options: Vec<String> = options
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    vec![format!(".options {}", options.join(" "))]
+}
+
+/// The convergence messages, ngspice can return the vectors up to the
+/// failing time point.
+fn convergence(messages: &[SpiceMessage]) -> Vec<SpiceMessage> {
+    messages
+        .iter()
+        .filter(|m| matches!(m, SpiceMessage::Convergence(..)))
+        .cloned()
+        .collect()
+}
+
+impl Simulation {
+    /// Run the transient analysis and retry with the options of the recovery
+    /// policy when it does not converge.
+    ///
+    /// The error of the last retry is returned when no options converge.
+    pub fn tran_recover(
+        &mut self,
+        step: &str,
+        stop: &str,
+        start: &str,
+        recovery: &Recovery,
+    ) -> Result<Recovered, Error> {
+        let command = format!("tran {} {} {}", step, stop, start);
+        let mut retry = 0;
+        loop {
+            let options = recovery.options(retry);
+            let error = match self.run_with(&options_line(&options), &command) {
+                Ok(vectors) => {
+                    let messages = convergence(&self.messages());
+                    if messages.is_empty() {
+                        if retry > 0 {
+                            log::info!("transient analysis converged with {:?}", options);
+                        }
+                        return Ok(Recovered {
+                            vectors,
+                            options,
+                            retries: retry,
+                        });
+                    }
+                    Error::Simulation(
+                        String::from("transient analysis did not converge"),
+                        messages,
+                    )
+                }
+                Err(err @ Error::Simulation(..)) => err,
+                Err(err) => return Err(err),
+            };
+            if retry >= recovery.steps.len() {
+                return Err(error);
+            }
+            retry += 1;
+            log::warn!("{}, retry with {:?}", error, recovery.options(retry));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{options_line, Recovery};
+
+    #[test]
+    fn recovery() {
+        let mut recovery = Recovery::default();
+        recovery.add_step(&[("method", "trap"), ("itl4", "500")]);
+        assert!(recovery.options(0).is_empty());
+        assert!(options_line(&recovery.options(0)).is_empty());
+        assert_eq!(
+            vec![String::from(".options method=gear itl4=100")],
+            options_line(&recovery.options(2))
+        );
+        assert_eq!(
+            vec![String::from(
+                ".options method=trap itl4=500 reltol=0.01 abstol=1e-10 vntol=1e-4"
+            )],
+            options_line(&recovery.options(4))
+        );
+        assert_eq!(recovery.options(4), recovery.options(10));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{error::Error, result::AnalysisResult, Simulation};
+
+/// The rejection of a disturbance against the frequency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejection {
+    /// The frequency in Hz.
+    pub frequency: Vec<f64>,
+    /// The rejection in dB, positive values attenuate the disturbance.
+    pub ratio: Vec<f64>,
+}
+
+impl Rejection {
+    /// The rejection of the 1V disturbance at the output node.
+    fn new(result: &AnalysisResult, output: &str) -> Result<Self, Error> {
+        Ok(Self {
+            frequency: result.frequency()?,
+            ratio: result
+                .get(output)?
+                .iter()
+                .map(|v| -20.0 * v.norm().log10())
+                .collect(),
+        })
+    }
+    /// The ratio of the differential and the common mode gain at the output node.
+    fn common_mode(
+        differential: &AnalysisResult,
+        common: &AnalysisResult,
+        output: &str,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            frequency: differential.frequency()?,
+            ratio: differential
+                .get(output)?
+                .iter()
+                .zip(common.get(output)?)
+                .map(|(d, c)| 20.0 * (d.norm() / c.norm()).log10())
+                .collect(),
+        })
+    }
+}
+
+impl Simulation {
+    /// Get the power supply rejection of the output.
+    ///
+    /// The supply source gets an AC stimulus of 1V, all other AC
+    /// sources are removed.
+    ///
+    /// ```ignore
+    /// let psrr = simulation.psrr("VCC", "out", "10", "1Meg", 20, "dec")?;
+    /// ```
+    pub fn psrr(
+        &mut self,
+        supply: &str,
+        output: &str,
+        start: &str,
+        stop: &str,
+        points: u32,
+        variation: &str,
+    ) -> Result<Rejection, Error> {
+        let mut circuit = self.circuit.without_ac();
+        circuit.set_ac(supply, "1")?;
+        let mut simulation = Simulation::new(circuit);
+        let result = simulation.ac_complex(start, stop, points, variation)?;
+        self.buffer = simulation.buffer;
+        Rejection::new(&result, output)
+    }
+    /// Get the common mode rejection of a differential stage.
+    ///
+    /// The sources at the positive and negative input are driven with
+    /// a differential and a common mode signal of 1V in two AC analyses,
+    /// all other AC sources are removed.
+    ///
+    /// ```ignore
+    /// let cmrr = simulation.cmrr(("VP", "VN"), "out", "10", "1Meg", 20, "dec")?;
+    /// ```
+    pub fn cmrr(
+        &mut self,
+        inputs: (&str, &str),
+        output: &str,
+        start: &str,
+        stop: &str,
+        points: u32,
+        variation: &str,
+    ) -> Result<Rejection, Error> {
+        let circuit = self.circuit.without_ac();
+        let mut results = Vec::new();
+        for (positive, negative) in [("0.5", "0.5 180"), ("1", "1")] {
+            let mut circuit = circuit.clone();
+            circuit.set_ac(inputs.0, positive)?;
+            circuit.set_ac(inputs.1, negative)?;
+            let mut simulation = Simulation::new(circuit);
+            results.push(simulation.ac_complex(start, stop, points, variation)?);
+            self.buffer = simulation.buffer;
+        }
+        Rejection::common_mode(&results[0], &results[1], output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use num_complex::Complex64;
+
+    use super::Rejection;
+    use crate::result::AnalysisResult;
+
+    #[test]
+    fn rejection() {
+        let result = AnalysisResult::from(HashMap::from([
+            (
+                String::from("frequency"),
+                vec![Complex64::new(100.0, 0.0), Complex64::new(1e3, 0.0)],
+            ),
+            (
+                String::from("out"),
+                vec![Complex64::new(1e-3, 0.0), Complex64::new(0.0, 1e-2)],
+            ),
+        ]));
+        let rejection = Rejection::new(&result, "out").unwrap();
+        assert!((rejection.ratio[0] - 60.0).abs() < 1e-9);
+        assert!((rejection.ratio[1] - 40.0).abs() < 1e-9);
+        assert!(Rejection::new(&result, "in").is_err());
+
+        let differential = AnalysisResult::from(HashMap::from([
+            (String::from("frequency"), vec![Complex64::new(100.0, 0.0)]),
+            (String::from("out"), vec![Complex64::new(1e4, 0.0)]),
+        ]));
+        let cmrr = Rejection::common_mode(&differential, &result, "out").unwrap();
+        assert!((cmrr.ratio[0] - 140.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{error::Error, node::IntoNode, Circuit, Value};
+
+/// A relay with a normally open contact.
+///
+/// The coil is the resistance and the inductance, the contact operates when
+/// the voltage of the coil current over the coil resistance rises above the
+/// operate voltage and releases when it falls below the release voltage.
+/// With contact bounce the contact opens the given number of times within
+/// the bounce duration after it closes.
+///
+/// ```ignore
+/// let mut relay = Relay::new(720.0, 0.3, 9.0, 1.2);
+/// relay.set_bounce(3, 1e-3);
+/// relay.build(&mut circuit, "K1", ("coil", "GND"), ("in", "out"))?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relay {
+    /// The resistance of the coil.
+    pub resistance: f64,
+    /// The inductance of the coil in H.
+    pub inductance: f64,
+    /// The coil voltage the contact closes.
+    pub operate: f64,
+    /// The coil voltage the contact opens.
+    pub release: f64,
+    /// The resistance of the closed and the open contact.
+    pub contact: (f64, f64),
+    /// The number of bounces and the duration of the bouncing.
+    pub bounce: Option<(u32, f64)>,
+}
+
+impl Relay {
+    pub fn new(resistance: f64, inductance: f64, operate: f64, release: f64) -> Self {
+        Self {
+            resistance,
+            inductance,
+            operate,
+            release,
+            contact: (50e-3, 1e12),
+            bounce: None,
+        }
+    }
+    /// Set the resistance of the closed and the open contact, the default
+    /// is 50mΩ and 1TΩ.
+    pub fn set_contact(&mut self, closed: f64, open: f64) {
+        self.contact = (closed, open);
+    }
+    /// Bounce the contact when it closes.
+    pub fn set_bounce(&mut self, count: u32, duration: f64) {
+        self.bounce = Some((count, duration));
+    }
+    /// Add the relay, the elements are named with the reference and a suffix.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        reference: &str,
+        coil: (impl IntoNode, impl IntoNode),
+        contact: (impl IntoNode, impl IntoNode),
+    ) -> Result<(), Error> {
+        if self.resistance <= 0.0
+            || self.inductance < 0.0
+            || self.release <= 0.0
+            || self.operate <= self.release
+            || self.contact.0 <= 0.0
+            || self.contact.1 <= self.contact.0
+            || self
+                .bounce
+                .is_some_and(|(count, duration)| count == 0 || duration <= 0.0)
+        {
+            return Err(Error::InvalidValue(format!("{:?}", self)));
+        }
+        let node = |suffix: &str| format!("{}_{}", reference, suffix).to_lowercase();
+        //the coil with the current sense
+        circuit.resistor(
+            format!("R{}C", reference),
+            coil.0,
+            node("r"),
+            Value::new(self.resistance).to_string(),
+        )?;
+        let mut sense = node("r");
+        if self.inductance > 0.0 {
+            circuit.inductor(
+                format!("L{}C", reference),
+                node("r"),
+                node("l"),
+                Value::new(self.inductance).to_string(),
+            )?;
+            sense = node("l");
+        }
+        circuit.voltage(
+            format!("V{}C", reference),
+            sense,
+            coil.1,
+            String::from("DC 0"),
+        )?;
+        circuit.behavioral(
+            format!("B{}C", reference),
+            node("s"),
+            "0",
+            format!("V=abs(i(V{}C))*{}", reference, Value::new(self.resistance)),
+        )?;
+        //the armature state with the hysteresis, the node is low when operated
+        circuit.voltage(
+            format!("V{}K", reference),
+            node("v"),
+            "0",
+            String::from("DC 1"),
+        )?;
+        circuit.resistor(
+            format!("R{}K", reference),
+            node("v"),
+            node("k"),
+            String::from("1k"),
+        )?;
+        circuit.switch(
+            format!("S{}K", reference),
+            node("k"),
+            "0",
+            node("s"),
+            "0",
+            node("coil"),
+        )?;
+        circuit.model(
+            node("coil"),
+            String::from("SW"),
+            format!(
+                "VT={} VH={} RON=1m ROFF=1G",
+                Value::new((self.operate + self.release) / 2.0),
+                Value::new((self.operate - self.release) / 2.0)
+            ),
+        );
+        let operated = format!("(V({})<0.5)", node("k"));
+        let closed = if let Some((count, duration)) = self.bounce {
+            //the time since the contact closed is the voltage of the timer
+            let timer = node("t");
+            circuit.capacitor(
+                format!("C{}T", reference),
+                timer.as_str(),
+                "0",
+                String::from("1"),
+            )?;
+            circuit.behavioral(
+                format!("B{}T", reference),
+                "0",
+                timer.as_str(),
+                format!("I={}?1:-V({})*1e6", operated, timer),
+            )?;
+            let slot = format!("floor(V({})*{:e})", timer, 2.0 * count as f64 / duration);
+            let parity = format!("{0}-2*floor({0}/2)", slot);
+            format!(
+                "{}&&((V({})>={:e})||({}<0.5))",
+                operated, timer, duration, parity
+            )
+        } else {
+            operated
+        };
+        circuit.behavioral(
+            format!("B{}B", reference),
+            node("b"),
+            "0",
+            format!("V=({})?1:0", closed),
+        )?;
+        circuit.switch(
+            format!("S{}", reference),
+            contact.0,
+            contact.1,
+            node("b"),
+            "0",
+            node("contact"),
+        )?;
+        circuit.model(
+            node("contact"),
+            String::from("SW"),
+            format!(
+                "VT=0.5 VH=0 RON={} ROFF={}",
+                Value::new(self.contact.0),
+                Value::new(self.contact.1)
+            ),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Relay;
+    use crate::Circuit;
+
+    #[test]
+    fn relay() {
+        let mut relay = Relay::new(720.0, 0.0, 9.0, 1.0);
+        let mut circuit = Circuit::new(String::from("bypass"), Vec::new());
+        relay
+            .build(&mut circuit, "K1", ("coil", "GND"), ("in", "out"))
+            .unwrap();
+        assert_eq!(
+            vec![
+                "RK1C coil k1_r 720",
+                "VK1C k1_r 0 DC 0",
+                "BK1C k1_s 0 V=abs(i(VK1C))*720",
+                "VK1K k1_v 0 DC 1",
+                "RK1K k1_v k1_k 1k",
+                "SK1K k1_k 0 k1_s 0 k1_coil",
+                ".model k1_coil SW(VT=5 VH=4 RON=1m ROFF=1G)",
+                "BK1B k1_b 0 V=((V(k1_k)<0.5))?1:0",
+                "SK1 in out k1_b 0 k1_contact",
+                ".model k1_contact SW(VT=0.5 VH=0 RON=50m ROFF=1T)",
+            ],
+            circuit.to_str(false).unwrap()
+        );
+        relay.set_bounce(2, 1e-3);
+        let mut circuit = Circuit::new(String::from("bypass"), Vec::new());
+        relay
+            .build(&mut circuit, "K1", ("coil", "GND"), ("in", "out"))
+            .unwrap();
+        let lines = circuit.to_str(false).unwrap();
+        assert!(lines.contains(&String::from(
+            "BK1B k1_b 0 V=((V(k1_k)<0.5)&&((V(k1_t)>=1e-3)||(floor(V(k1_t)*4e3)-2*floor(floor(V(k1_t)*4e3)/2)<0.5)))?1:0"
+        )));
+        relay.set_bounce(0, 1e-3);
+        assert!(relay
+            .build(&mut circuit, "K2", ("coil", "GND"), ("in", "out"))
+            .is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, f64::consts::PI};
+
+use num_complex::Complex64;
+
+use crate::{
+    error::Error,
+    frequency::{gain_db, unwrap_phase},
+};
+
+/// The complex vectors of an AC analysis.
+///
+/// Real vectors like the frequency have no imaginary part.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnalysisResult {
+    pub vectors: HashMap<String, Vec<Complex64>>,
+}
+
+/// The magnitude and phase of a node against the frequency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bode {
+    /// The frequency in Hz.
+    pub frequency: Vec<f64>,
+    /// The magnitude in dB.
+    pub magnitude: Vec<f64>,
+    /// The unwrapped phase in degrees.
+    pub phase: Vec<f64>,
+}
+
+/// The group delay against the frequency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupDelay {
+    /// The frequency in Hz.
+    pub frequency: Vec<f64>,
+    /// The delay in seconds.
+    pub delay: Vec<f64>,
+}
+
+impl GroupDelay {
+    /// Smooth the delay with a centered moving average over the number of points.
+    pub fn smooth(&self, points: usize) -> Self {
+        let half = points / 2;
+        let delay = (0..self.delay.len())
+            .map(|i| {
+                let window =
+                    &self.delay[i.saturating_sub(half)..(i + half + 1).min(self.delay.len())];
+                window.iter().sum::<f64>() / window.len() as f64
+            })
+            .collect();
+        Self {
+            frequency: self.frequency.clone(),
+            delay,
+        }
+    }
+}
+
+/// The real and imaginary part of a loop gain against the frequency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nyquist {
+    /// The frequency in Hz.
+    pub frequency: Vec<f64>,
+    pub real: Vec<f64>,
+    pub imag: Vec<f64>,
+}
+
+impl Nyquist {
+    /// The number of clockwise encirclements of the point -1.
+    ///
+    /// The negative frequencies are the mirror of the simulated ones, the
+    /// loop gain must be real at the lowest and near zero at the highest
+    /// frequency to close the contour. With the number of open loop poles
+    /// in the right half plane P, the closed loop has `P + N` unstable poles.
+    pub fn encirclements(&self) -> i32 {
+        let mut angle = 0.0;
+        for i in 1..self.real.len() {
+            let a = Complex64::new(self.real[i - 1] + 1.0, self.imag[i - 1]);
+            let b = Complex64::new(self.real[i] + 1.0, self.imag[i]);
+            angle += (b / a).arg();
+        }
+        //the mirrored path doubles the angle
+        -(angle / PI).round() as i32
+    }
+}
+
+impl From<HashMap<String, Vec<Complex64>>> for AnalysisResult {
+    fn from(vectors: HashMap<String, Vec<Complex64>>) -> Self {
+        Self { vectors }
+    }
+}
+
+impl AnalysisResult {
+    /// Get the vector by name.
+    pub fn get(&self, name: &str) -> Result<&Vec<Complex64>, Error> {
+        self.vectors
+            .get(name)
+            .ok_or_else(|| Error::Signal(format!("no vector {}", name)))
+    }
+    /// The frequency vector in Hz.
+    pub fn frequency(&self) -> Result<Vec<f64>, Error> {
+        Ok(self.get("frequency")?.iter().map(|f| f.re).collect())
+    }
+    /// The bode plot data of the node.
+    ///
+    /// ```ignore
+    /// let result = simulation.ac_complex("10", "100k", 20, "dec")?;
+    /// let bode = result.bode("out")?;
+    /// ```
+    pub fn bode(&self, node: &str) -> Result<Bode, Error> {
+        let vector = self.get(node)?;
+        Ok(Bode {
+            frequency: self.frequency()?,
+            magnitude: gain_db(vector),
+            phase: unwrap_phase(vector),
+        })
+    }
+    /// The Nyquist plot data of the loop gain at the node.
+    pub fn nyquist(&self, node: &str) -> Result<Nyquist, Error> {
+        let vector = self.get(node)?;
+        Ok(Nyquist {
+            frequency: self.frequency()?,
+            real: vector.iter().map(|v| v.re).collect(),
+            imag: vector.iter().map(|v| v.im).collect(),
+        })
+    }
+    /// The group delay `-dφ/dω` of the node.
+    ///
+    /// The derivative of the unwrapped phase is taken with central
+    /// differences, the simulation needs enough points per decade to
+    /// follow the phase.
+    pub fn group_delay(&self, node: &str) -> Result<GroupDelay, Error> {
+        let frequency = self.frequency()?;
+        if frequency.len() < 2 {
+            return Err(Error::Signal(String::from("not enough values")));
+        }
+        let phase = unwrap_phase(self.get(node)?);
+        let last = frequency.len() - 1;
+        let delay = (0..=last)
+            .map(|i| {
+                let (a, b) = (i.saturating_sub(1), (i + 1).min(last));
+                let omega = 2.0 * PI * (frequency[b] - frequency[a]);
+                -(phase[b] - phase[a]).to_radians() / omega
+            })
+            .collect();
+        Ok(GroupDelay { frequency, delay })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, f64::consts::PI};
+
+    use num_complex::Complex64;
+
+    use super::AnalysisResult;
+
+    /// Third order low pass with the corner at 1kHz from 10Hz to 100kHz.
+    fn lowpass(points: usize) -> AnalysisResult {
+        lowpass_gain(points, 1.0)
+    }
+    fn lowpass_gain(points: usize, gain: f64) -> AnalysisResult {
+        let frequency: Vec<f64> = (0..=4 * points)
+            .map(|i| 10f64.powf(1.0 + i as f64 / points as f64))
+            .collect();
+        let out = frequency
+            .iter()
+            .map(|f| gain / (1.0 + Complex64::new(0.0, f / 1e3)).powi(3))
+            .collect();
+        AnalysisResult::from(HashMap::from([
+            (
+                String::from("frequency"),
+                frequency.iter().map(|f| Complex64::new(*f, 0.0)).collect(),
+            ),
+            (String::from("out"), out),
+        ]))
+    }
+
+    #[test]
+    fn bode() {
+        let result = lowpass(10);
+        let bode = result.bode("out").unwrap();
+        assert_eq!(41, bode.frequency.len());
+        assert!((bode.frequency[20] - 1e3).abs() < 1e-9);
+        assert!((bode.magnitude[20] + 9.031).abs() < 1e-3);
+        assert!((bode.phase[20] + 135.0).abs() < 1e-9);
+        //the phase continues below -180 degrees
+        assert!(bode.phase[40] < -260.0);
+        assert!(result.bode("in").is_err());
+    }
+    #[test]
+    fn group_delay() {
+        let result = lowpass(100);
+        //three times the delay of the RC at the corner frequency
+        let expected = 3.0 / (2.0 * 2.0 * PI * 1e3);
+        let delay = result.group_delay("out").unwrap();
+        assert!((delay.delay[200] - expected).abs() / expected < 1e-3);
+        let smooth = delay.smooth(5);
+        assert!((smooth.delay[200] - expected).abs() / expected < 1e-3);
+        assert!((smooth.delay[0] - delay.delay[0..3].iter().sum::<f64>() / 3.0).abs() < 1e-15);
+    }
+    #[test]
+    fn nyquist() {
+        //the closed loop is unstable for a gain above 8
+        let nyquist = lowpass_gain(20, 4.0).nyquist("out").unwrap();
+        assert!((nyquist.real[0] - 4.0).abs() < 1e-2);
+        assert_eq!(0, nyquist.encirclements());
+        let nyquist = lowpass_gain(20, 20.0).nyquist("out").unwrap();
+        assert_eq!(2, nyquist.encirclements());
+    }
+}
@@ -0,0 +1,204 @@
+use rand::{seq::SliceRandom, Rng};
+
+use crate::error::Error;
+
+/// The strategy to draw the Monte Carlo samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sampling {
+    /// Independent random samples.
+    #[default]
+    Random,
+    /// Latin hypercube, every component range is split into one stratum per run
+    /// and every stratum is used once.
+    LatinHypercube,
+    /// Sobol low discrepancy sequence, deterministic and limited to 21 components.
+    Sobol,
+}
+
+/// The primitive polynomials and initial direction numbers of the Sobol
+/// sequence from Joe and Kuo, `(degree, coefficients, m)`.
+const SOBOL: [(u32, u32, &[u32]); 20] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+];
+
+const BITS: usize = 32;
+
+/// The direction numbers of the dimension, the first dimension is the van der Corput sequence.
+fn directions(dimension: usize) -> [u32; BITS] {
+    let mut v = [0_u32; BITS];
+    if dimension == 0 {
+        for (k, v) in v.iter_mut().enumerate() {
+            *v = 1 << (BITS - 1 - k);
+        }
+        return v;
+    }
+    let (s, a, m) = SOBOL[dimension - 1];
+    let s = s as usize;
+    for k in 0..BITS {
+        if k < s {
+            v[k] = m[k] << (BITS - 1 - k);
+        } else {
+            let mut value = v[k - s] ^ (v[k - s] >> s);
+            for i in 1..s {
+                if (a >> (s - 1 - i)) & 1 == 1 {
+                    value ^= v[k - i];
+                }
+            }
+            v[k] = value;
+        }
+    }
+    v
+}
+
+/// Get the points in the unit hypercube, one row per run.
+///
+/// The coordinates are in the open interval (0, 1) so they can be
+/// mapped with the quantile function of the distribution.
+pub(crate) fn points<R: Rng>(
+    sampling: Sampling,
+    runs: usize,
+    dimensions: usize,
+    rng: &mut R,
+) -> Result<Vec<Vec<f64>>, Error> {
+    let open = |u: f64| u.clamp(f64::EPSILON, 1.0 - f64::EPSILON);
+    match sampling {
+        Sampling::Random => Ok((0..runs)
+            .map(|_| (0..dimensions).map(|_| open(rng.gen::<f64>())).collect())
+            .collect()),
+        Sampling::LatinHypercube => {
+            let mut result = vec![vec![0.0; dimensions]; runs];
+            for d in 0..dimensions {
+                let mut strata: Vec<usize> = (0..runs).collect();
+                strata.shuffle(rng);
+                for (row, stratum) in result.iter_mut().zip(strata) {
+                    row[d] = open((stratum as f64 + rng.gen::<f64>()) / runs as f64);
+                }
+            }
+            Ok(result)
+        }
+        Sampling::Sobol => {
+            if dimensions > SOBOL.len() + 1 {
+                return Err(Error::Sampling(format!(
+                    "Sobol sequence supports {} components, found {}",
+                    SOBOL.len() + 1,
+                    dimensions
+                )));
+            }
+            let directions: Vec<[u32; BITS]> = (0..dimensions).map(directions).collect();
+            let mut x = vec![0_u32; dimensions];
+            let mut result = Vec::new();
+            //the first point is the origin and skipped
+            for n in 0..runs {
+                let c = (!n).trailing_zeros() as usize;
+                for (x, v) in x.iter_mut().zip(&directions) {
+                    *x ^= v[c.min(BITS - 1)];
+                }
+                result.push(
+                    x.iter()
+                        .map(|x| open(*x as f64 / (1_u64 << BITS) as f64))
+                        .collect(),
+                );
+            }
+            Ok(result)
+        }
+    }
+}
+
+/// The quantile function of the standard normal distribution.
+///
+/// Rational approximation by Peter Acklam, the relative error is below 1.2e-9.
+pub(crate) fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const LOW: f64 = 0.02425;
+    if p < LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -normal_quantile(1.0 - p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{normal_quantile, points, Sampling};
+
+    #[test]
+    fn sampling() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let lhs = points(Sampling::LatinHypercube, 10, 3, &mut rng).unwrap();
+        for d in 0..3 {
+            let mut strata: Vec<usize> = lhs.iter().map(|p| (p[d] * 10.0) as usize).collect();
+            strata.sort_unstable();
+            assert_eq!((0..10).collect::<Vec<usize>>(), strata);
+        }
+        let sobol = points(Sampling::Sobol, 4, 2, &mut rng).unwrap();
+        assert_eq!(
+            vec![
+                vec![0.5, 0.5],
+                vec![0.75, 0.25],
+                vec![0.25, 0.75],
+                vec![0.375, 0.375]
+            ],
+            sobol
+        );
+        assert!(points(Sampling::Sobol, 4, 30, &mut rng).is_err());
+        assert!(normal_quantile(0.5).abs() < 1e-9);
+        assert!((normal_quantile(0.99865) - 3.0).abs() < 1e-3);
+        assert!((normal_quantile(0.00135) + 3.0).abs() < 1e-3);
+    }
+}
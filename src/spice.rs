@@ -0,0 +1,167 @@
+use crate::{error::Error, netlist::NetlistSymbol};
+
+/// Check if the symbol is part of the simulation.
+pub(crate) fn enabled(symbol: &NetlistSymbol) -> bool {
+    if symbol.exclude_from_sim {
+        return false;
+    }
+    if let Some(enabled) = symbol.get_property("Spice_Netlist_Enabled") {
+        if enabled.trim().eq_ignore_ascii_case("N") {
+            return false;
+        }
+    }
+    if let Some(enabled) = symbol.get_property("Sim.Enable") {
+        if enabled.trim() == "0" {
+            return false;
+        }
+    }
+    true
+}
+
+/// The spice primitive, when not overridden it is the first letter of the reference.
+pub(crate) fn primitive(reference: &str, symbol: &NetlistSymbol) -> String {
+    if let Some(primitive) = symbol.get_property("Spice_Primitive") {
+        if !primitive.trim().is_empty() {
+            return primitive.trim().to_uppercase();
+        }
+    }
+    if let Some(device) = symbol.get_property("Sim.Device") {
+        match device.trim().to_uppercase().as_str() {
+            "R" => return String::from("R"),
+            "C" => return String::from("C"),
+            "L" => return String::from("L"),
+            "D" => return String::from("D"),
+            "V" => return String::from("V"),
+            "NPN" | "PNP" => return String::from("Q"),
+            "SUBCKT" => return String::from("X"),
+            _ => {}
+        }
+    }
+    prefix(reference)
+}
+
+/// Map the reference prefix of the schema to the spice element letter.
+///
+/// Integrated circuits are expected to be subcircuits and the
+/// transistors and batteries use their own prefix in the schema.
+fn prefix(reference: &str) -> String {
+    let prefix: String = reference
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_uppercase();
+    match prefix.as_str() {
+        "U" | "IC" | "A" => String::from("X"),
+        "T" | "TR" => String::from("Q"),
+        "BT" | "BAT" => String::from("V"),
+        "LED" => String::from("D"),
+        _ => reference.chars().take(1).collect::<String>().to_uppercase(),
+    }
+}
+
+/// The model name or value of the symbol.
+///
+/// `Spice_Model` replaces the value like the KiCad exporter does,
+/// `Sim.Name` is the model name of the KiCad 7/8 fields.
+pub(crate) fn value(symbol: &NetlistSymbol) -> Option<String> {
+    symbol
+        .get_property("Spice_Model")
+        .or_else(|| symbol.get_property("Sim.Name"))
+        .or_else(|| symbol.get_property("Value"))
+}
+
+/// The order of the symbol pins for the spice line.
+///
+/// `Spice_Node_Sequence` contains the zero based pin indexes, `Sim.Pins`
+/// maps the symbol pins to the model pins, e.g. `1=+ 2=-`.
+pub(crate) fn pin_sequence(symbol: &NetlistSymbol) -> Result<Vec<String>, Error> {
+    if let Some(sequence) = symbol.get_property("Spice_Node_Sequence") {
+        let mut result = Vec::new();
+        for s in sequence
+            .split(|c: char| c.is_whitespace() || "{}:,;".contains(c))
+            .filter(|s| !s.is_empty())
+        {
+            let index = s.parse::<usize>().map_err(|_| {
+                Error::InvalidProperty(String::from("Spice_Node_Sequence"), sequence.clone())
+            })?;
+            result.push((index + 1).to_string());
+        }
+        return Ok(result);
+    }
+    if let Some(pins) = symbol.get_property("Sim.Pins") {
+        let mut mapping: Vec<(String, String)> = pins
+            .split_whitespace()
+            .filter_map(|p| p.split_once('='))
+            .map(|(pin, model)| (pin.to_string(), model.to_string()))
+            .collect();
+        mapping.sort_by_key(|(_, model)| model_pin_order(model));
+        return Ok(mapping.into_iter().map(|(pin, _)| pin).collect());
+    }
+    Ok((1..=symbol.pin_count).map(|n| n.to_string()).collect())
+}
+
+/// Sort key for the model pin names, numbers are sorted by value and the
+/// named pins of the primitives in the spice order.
+fn model_pin_order(model: &str) -> usize {
+    if let Ok(number) = model.parse::<usize>() {
+        return number;
+    }
+    match model.to_uppercase().as_str() {
+        "+" | "A" | "C" | "D" => 1,
+        "-" | "K" | "B" | "G" => 2,
+        "E" | "S" => 3,
+        _ => usize::MAX,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{enabled, pin_sequence, primitive, value};
+    use crate::netlist::NetlistSymbol;
+
+    fn symbol(properties: &[(&str, &str)]) -> NetlistSymbol {
+        NetlistSymbol {
+            lib_id: String::from("Device:Q_NPN_BCE"),
+            unit: 1,
+            power: false,
+            exclude_from_sim: false,
+            pin_count: 3,
+            properties: properties
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<String, String>>(),
+            pins: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn spice_fields() {
+        let q = symbol(&[
+            ("Value", "BC547"),
+            ("Spice_Primitive", "Q"),
+            ("Spice_Model", "BC547B"),
+            ("Spice_Node_Sequence", "1 0 2"),
+        ]);
+        assert!(enabled(&q));
+        assert_eq!("Q", primitive("T1", &q));
+        assert_eq!(Some(String::from("BC547B")), value(&q));
+        assert_eq!(vec!["2", "1", "3"], pin_sequence(&q).unwrap());
+
+        let q = symbol(&[
+            ("Value", "BC547"),
+            ("Sim.Device", "NPN"),
+            ("Sim.Pins", "1=B 2=C 3=E"),
+        ]);
+        assert_eq!("Q", primitive("T1", &q));
+        assert_eq!(vec!["2", "1", "3"], pin_sequence(&q).unwrap());
+
+        assert!(!enabled(&symbol(&[("Spice_Netlist_Enabled", "N")])));
+        assert!(!enabled(&symbol(&[("Sim.Enable", "0")])));
+        assert_eq!("C", primitive("C1", &symbol(&[])));
+        assert_eq!("X", primitive("U1", &symbol(&[])));
+        assert_eq!("Q", primitive("T1", &symbol(&[])));
+        assert_eq!(vec!["1", "2", "3"], pin_sequence(&symbol(&[])).unwrap());
+    }
+}
@@ -0,0 +1,107 @@
+/// Statistics of a measurement over the runs of a Monte Carlo analysis or sweep.
+///
+/// ```ignore
+/// let ensemble = montecarlo.run()?;
+/// let stats = Statistics::new(&ensemble.measure(|v| v["out"][0]));
+/// println!("{} +/- {}, 99%: {}", stats.mean(), stats.stddev(), stats.percentile(99.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    sorted: Vec<f64>,
+    mean: f64,
+    stddev: f64,
+}
+
+/// The number of samples within equally sized bins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// The bin edges, there is one edge more than bins.
+    pub edges: Vec<f64>,
+    pub counts: Vec<usize>,
+}
+
+impl Statistics {
+    /// Calculate the statistics, NaN values are ignored.
+    pub fn new(samples: &[f64]) -> Self {
+        let mut sorted: Vec<f64> = samples.iter().filter(|s| !s.is_nan()).cloned().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let count = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / count;
+        //sample standard deviation
+        let stddev = if sorted.len() > 1 {
+            (sorted.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (count - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+        Self {
+            sorted,
+            mean,
+            stddev,
+        }
+    }
+    pub fn count(&self) -> usize {
+        self.sorted.len()
+    }
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+    pub fn stddev(&self) -> f64 {
+        self.stddev
+    }
+    pub fn min(&self) -> f64 {
+        self.sorted.first().cloned().unwrap_or(f64::NAN)
+    }
+    pub fn max(&self) -> f64 {
+        self.sorted.last().cloned().unwrap_or(f64::NAN)
+    }
+    /// The percentile from 0 to 100, interpolated between the samples.
+    pub fn percentile(&self, percent: f64) -> f64 {
+        if self.sorted.is_empty() {
+            return f64::NAN;
+        }
+        let position = percent.clamp(0.0, 100.0) / 100.0 * (self.sorted.len() - 1) as f64;
+        let low = position.floor() as usize;
+        let high = position.ceil() as usize;
+        self.sorted[low] + (self.sorted[high] - self.sorted[low]) * (position - low as f64)
+    }
+    pub fn median(&self) -> f64 {
+        self.percentile(50.0)
+    }
+    /// Count the samples in bins between the minimum and maximum.
+    pub fn histogram(&self, bins: usize) -> Histogram {
+        let bins = bins.max(1);
+        let (min, max) = (self.min(), self.max());
+        let width = if max > min {
+            (max - min) / bins as f64
+        } else {
+            1.0
+        };
+        let edges = (0..=bins).map(|i| min + width * i as f64).collect();
+        let mut counts = vec![0; bins];
+        for sample in &self.sorted {
+            let bin = ((sample - min) / width) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        Histogram { edges, counts }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Statistics;
+
+    #[test]
+    fn statistics() {
+        let stats = Statistics::new(&[4.0, 2.0, 5.0, 4.0, 5.0, 7.0, 9.0, 4.0, f64::NAN]);
+        assert_eq!(8, stats.count());
+        assert_eq!(5.0, stats.mean());
+        assert!((stats.stddev() - 2.138).abs() < 1e-3);
+        assert_eq!(2.0, stats.min());
+        assert_eq!(9.0, stats.max());
+        assert_eq!(4.5, stats.median());
+        assert_eq!(9.0, stats.percentile(100.0));
+        let histogram = stats.histogram(7);
+        assert_eq!(vec![1, 0, 3, 2, 0, 1, 1], histogram.counts);
+        assert_eq!(8, histogram.edges.len());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::error::Error;
+
+/// The levels of the step response, relative to the step amplitude.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// The lower level of the rise and fall time, 10% by default.
+    pub low: f64,
+    /// The upper level of the rise and fall time, 90% by default.
+    pub high: f64,
+    /// The level for the propagation delay, 50% by default.
+    pub delay: f64,
+    /// The error band of the settling time, 2% by default.
+    pub settling: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            low: 0.1,
+            high: 0.9,
+            delay: 0.5,
+            settling: 0.02,
+        }
+    }
+}
+
+/// The metrics of a transient step response.
+///
+/// The step goes from the first to the last value of the vector. Rise or
+/// fall time is set depending on the direction of the step.
+///
+/// ```ignore
+/// let result = simulation.tran("1u", "10m", "0");
+/// let step = StepResponse::with_input(&result["time"], &result["in"], &result["out"], &Thresholds::default())?;
+/// println!("tr = {:?}, overshoot = {}%", step.rise_time, step.overshoot);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepResponse {
+    pub initial: f64,
+    pub last: f64,
+    pub rise_time: Option<f64>,
+    pub fall_time: Option<f64>,
+    /// The peak beyond the final value in percent of the step.
+    pub overshoot: f64,
+    /// The peak beyond the initial value in percent of the step.
+    pub undershoot: f64,
+    /// The time from the step until the signal stays within the error band,
+    /// `None` when the signal does not settle.
+    pub settling_time: Option<f64>,
+    /// The time between the input and the output crossing the delay level.
+    pub propagation_delay: Option<f64>,
+}
+
+/// The interpolated time the vector first crosses the level after `from`.
+pub(crate) fn crossing(
+    time: &[f64],
+    vector: &[f64],
+    level: f64,
+    rising: bool,
+    from: f64,
+) -> Option<f64> {
+    time.windows(2)
+        .zip(vector.windows(2))
+        .filter(|(t, _)| t[1] >= from)
+        .find(|(_, v)| {
+            if rising {
+                v[0] < level && v[1] >= level
+            } else {
+                v[0] > level && v[1] <= level
+            }
+        })
+        .map(|(t, v)| t[0] + (level - v[0]) / (v[1] - v[0]) * (t[1] - t[0]))
+}
+
+fn check(time: &[f64], vector: &[f64]) -> Result<(), Error> {
+    if time.len() != vector.len() {
+        return Err(Error::Signal(format!(
+            "{} time points for {} values",
+            time.len(),
+            vector.len()
+        )));
+    }
+    if vector.len() < 2 {
+        return Err(Error::Signal(String::from("not enough values")));
+    }
+    Ok(())
+}
+
+impl StepResponse {
+    /// Evaluate the response, the settling time is measured from the first time point.
+    pub fn new(time: &[f64], output: &[f64], thresholds: &Thresholds) -> Result<Self, Error> {
+        check(time, output)?;
+        let initial = output[0];
+        let last = output[output.len() - 1];
+        let step = last - initial;
+        if step == 0.0 {
+            return Err(Error::Signal(String::from("the signal has no step")));
+        }
+        let rising = step > 0.0;
+        let level = |fraction: f64| initial + fraction * step;
+        let low = crossing(time, output, level(thresholds.low), rising, time[0]);
+        let high = low.and_then(|low| crossing(time, output, level(thresholds.high), rising, low));
+        let transition = match (low, high) {
+            (Some(low), Some(high)) => Some(high - low),
+            _ => None,
+        };
+        let (max, min) = output.iter().fold((f64::MIN, f64::MAX), |(max, min), v| {
+            (max.max(*v), min.min(*v))
+        });
+        let (peak, dip) = if rising {
+            (max - last, initial - min)
+        } else {
+            (last - min, max - initial)
+        };
+        let band = thresholds.settling * step.abs();
+        let settling_time = match output.iter().rposition(|v| (v - last).abs() > band) {
+            None => Some(0.0),
+            Some(i) if i + 1 < output.len() => Some(time[i + 1] - time[0]),
+            Some(_) => None,
+        };
+        Ok(Self {
+            initial,
+            last,
+            rise_time: transition.filter(|_| rising),
+            fall_time: transition.filter(|_| !rising),
+            overshoot: 100.0 * peak.max(0.0) / step.abs(),
+            undershoot: 100.0 * dip.max(0.0) / step.abs(),
+            settling_time,
+            propagation_delay: None,
+        })
+    }
+    /// Evaluate the response to the input step, the settling time is measured
+    /// from the input crossing the delay level.
+    pub fn with_input(
+        time: &[f64],
+        input: &[f64],
+        output: &[f64],
+        thresholds: &Thresholds,
+    ) -> Result<Self, Error> {
+        check(time, input)?;
+        let mut response = Self::new(time, output, thresholds)?;
+        let (first, last) = (input[0], input[input.len() - 1]);
+        if first == last {
+            return Err(Error::Signal(String::from("the input has no step")));
+        }
+        let Some(start) = crossing(
+            time,
+            input,
+            first + thresholds.delay * (last - first),
+            last > first,
+            time[0],
+        ) else {
+            return Ok(response);
+        };
+        let step = response.last - response.initial;
+        response.propagation_delay = crossing(
+            time,
+            output,
+            response.initial + thresholds.delay * step,
+            step > 0.0,
+            start,
+        )
+        .map(|t| t - start);
+        response.settling_time = response
+            .settling_time
+            .map(|settling| (settling + time[0] - start).max(0.0));
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StepResponse, Thresholds};
+
+    #[test]
+    fn step_response() {
+        //second order step response with zeta = 0.5 and wn = 1 rad/s
+        let (zeta, wn) = (0.5_f64, 1.0_f64);
+        let wd = wn * (1.0 - zeta * zeta).sqrt();
+        let time: Vec<f64> = (0..=40000).map(|i| i as f64 * 1e-3).collect();
+        let output: Vec<f64> = time
+            .iter()
+            .map(|t| {
+                let t = t - 10.0;
+                if t <= 0.0 {
+                    0.0
+                } else {
+                    1.0 - (-zeta * wn * t).exp()
+                        * ((wd * t).cos() + zeta / (1.0 - zeta * zeta).sqrt() * (wd * t).sin())
+                }
+            })
+            .collect();
+        let input: Vec<f64> = time
+            .iter()
+            .map(|t| if *t < 10.0 { 0.0 } else { 1.0 })
+            .collect();
+        let step =
+            StepResponse::with_input(&time, &input, &output, &Thresholds::default()).unwrap();
+        assert!((step.overshoot - 16.3).abs() < 0.1);
+        assert!((step.rise_time.unwrap() - 1.64).abs() < 0.01);
+        assert_eq!(None, step.fall_time);
+        assert!((step.settling_time.unwrap() - 8.07).abs() < 0.05);
+        assert!((step.propagation_delay.unwrap() - 1.29).abs() < 0.01);
+
+        let inverted: Vec<f64> = output.iter().map(|v| 1.0 - v).collect();
+        let step = StepResponse::new(&time, &inverted, &Thresholds::default()).unwrap();
+        assert_eq!(None, step.rise_time);
+        assert!((step.fall_time.unwrap() - 1.64).abs() < 0.01);
+        assert!((step.settling_time.unwrap() - 18.07).abs() < 0.05);
+        assert!(StepResponse::new(&time, &vec![1.0; time.len()], &Thresholds::default()).is_err());
+    }
+}
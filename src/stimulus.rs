@@ -0,0 +1,175 @@
+use crate::{error::Error, node::IntoNode, Circuit, Value};
+
+/// A bit pattern as PWL voltage source.
+///
+/// The pattern is a pseudo random bit sequence or an arbitrary sequence of
+/// bits. Every bit lasts one unit interval, the transitions between the
+/// levels start at the bit boundaries.
+///
+/// ```ignore
+/// let mut pattern = BitPattern::prbs(7, 127, 1e9)?;
+/// pattern.set_levels(0.0, 3.3);
+/// pattern.set_transition(50e-12, 50e-12);
+/// pattern.build(&mut circuit, "VDATA", "data", "GND")?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitPattern {
+    pub bits: Vec<bool>,
+    /// The bit rate in bit/s.
+    pub rate: f64,
+    pub low: f64,
+    pub high: f64,
+    pub rise: f64,
+    pub fall: f64,
+    pub delay: f64,
+    /// Repeat the pattern for the whole transient.
+    pub repeat: bool,
+}
+
+/// The feedback taps of the PRBS polynomials.
+fn taps(order: u32) -> Option<(u32, u32)> {
+    match order {
+        7 => Some((7, 6)),
+        15 => Some((15, 14)),
+        31 => Some((31, 28)),
+        _ => None,
+    }
+}
+
+/// The bits of the linear feedback shift register, starting with all ones.
+fn prbs(order: u32, count: usize) -> Result<Vec<bool>, Error> {
+    let (a, b) = taps(order).ok_or_else(|| Error::InvalidValue(format!("PRBS-{}", order)))?;
+    let mask = (1u64 << order) - 1;
+    let mut register = mask;
+    Ok((0..count)
+        .map(|_| {
+            let bit = ((register >> (a - 1)) ^ (register >> (b - 1))) & 1;
+            register = ((register << 1) | bit) & mask;
+            bit == 1
+        })
+        .collect())
+}
+
+impl BitPattern {
+    /// A pattern of the PRBS-7, PRBS-15 or PRBS-31 sequence with the number of bits.
+    pub fn prbs(order: u32, count: usize, rate: f64) -> Result<Self, Error> {
+        Ok(Self::new(prbs(order, count)?, rate))
+    }
+    /// A pattern from a string of `0` and `1`, other characters are ignored.
+    pub fn bits(pattern: &str, rate: f64) -> Self {
+        Self::new(
+            pattern
+                .chars()
+                .filter(|c| *c == '0' || *c == '1')
+                .map(|c| c == '1')
+                .collect(),
+            rate,
+        )
+    }
+    fn new(bits: Vec<bool>, rate: f64) -> Self {
+        Self {
+            bits,
+            rate,
+            low: 0.0,
+            high: 1.0,
+            rise: 0.1 / rate,
+            fall: 0.1 / rate,
+            delay: 0.0,
+            repeat: false,
+        }
+    }
+    /// Set the voltage of the low and high bits, the default is 0V and 1V.
+    pub fn set_levels(&mut self, low: f64, high: f64) {
+        self.low = low;
+        self.high = high;
+    }
+    /// Set the rise and fall time, the default is 10% of the unit interval.
+    pub fn set_transition(&mut self, rise: f64, fall: f64) {
+        self.rise = rise;
+        self.fall = fall;
+    }
+    /// Delay the start of the pattern.
+    pub fn set_delay(&mut self, delay: f64) {
+        self.delay = delay;
+    }
+    pub fn set_repeat(&mut self, repeat: bool) {
+        self.repeat = repeat;
+    }
+    /// The value of the PWL source.
+    pub fn pwl(&self) -> Result<String, Error> {
+        let interval = 1.0 / self.rate;
+        if self.rise >= interval || self.fall >= interval {
+            return Err(Error::InvalidValue(format!(
+                "the transition is longer than the unit interval {}",
+                Value::new(interval)
+            )));
+        }
+        let level = |bit: bool| if bit { self.high } else { self.low };
+        let first = level(self.bits.first().cloned().unwrap_or_default());
+        let mut points = vec![(0.0, first)];
+        if self.delay > 0.0 {
+            //the start of the repetition must be a point of the source
+            points.push((self.delay, first));
+        }
+        for (i, pair) in self.bits.windows(2).enumerate() {
+            if pair[0] != pair[1] {
+                let start = self.delay + (i + 1) as f64 * interval;
+                let transition = if pair[1] { self.rise } else { self.fall };
+                points.push((start, level(pair[0])));
+                points.push((start + transition, level(pair[1])));
+            }
+        }
+        //hold the last bit to the end of the pattern
+        let end = self.delay + self.bits.len() as f64 * interval;
+        points.push((end, level(self.bits.last().cloned().unwrap_or_default())));
+        let points: Vec<String> = points
+            .iter()
+            .map(|(t, v)| format!("{} {}", Value::new(*t), Value::new(*v)))
+            .collect();
+        let repeat = if self.repeat {
+            format!(" r={}", Value::new(self.delay))
+        } else {
+            String::new()
+        };
+        Ok(format!("PWL({}){}", points.join(" "), repeat))
+    }
+    /// Add the voltage source with the pattern to the circuit.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        reference: &str,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+    ) -> Result<(), Error> {
+        circuit.voltage(reference.to_string(), n0, n1, self.pwl()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prbs, BitPattern};
+
+    #[test]
+    fn prbs7() {
+        let bits = prbs(7, 254).unwrap();
+        //the sequence repeats after 127 bits with 64 ones
+        assert_eq!(bits[..127], bits[127..]);
+        assert_eq!(64, bits[..127].iter().filter(|b| **b).count());
+        assert!(prbs(9, 10).is_err());
+        let ones = prbs(31, 1000).unwrap().iter().filter(|b| **b).count();
+        assert!(ones > 400 && ones < 600);
+    }
+    #[test]
+    fn pwl() {
+        let mut pattern = BitPattern::bits("0110", 1e6);
+        pattern.set_levels(0.0, 5.0);
+        assert_eq!(
+            "PWL(0 0 1u 0 1.1u 5 3u 5 3.1u 0 4u 0)",
+            pattern.pwl().unwrap()
+        );
+        pattern.set_repeat(true);
+        assert!(pattern.pwl().unwrap().ends_with(" r=0"));
+        pattern.set_transition(2e-6, 1e-9);
+        assert!(pattern.pwl().is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use crate::{
+    circuit::{CircuitItem, Vectors},
+    error::Error,
+    tran::Tran,
+    Circuit, PowerReport, Simulation,
+};
+
+/// The thermal path from the junction of a device to the ambient.
+///
+/// The stages are the thermal resistance in K/W and the thermal
+/// capacitance in J/K of a Cauer ladder, starting at the junction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalPath {
+    pub reference: String,
+    pub stages: Vec<(f64, f64)>,
+}
+
+impl ThermalPath {
+    /// The thermal resistance from the junction to the ambient.
+    pub fn resistance(&self) -> f64 {
+        self.stages.iter().map(|(r, _)| r).sum()
+    }
+}
+
+/// The junction temperatures of the devices after the co-simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalResult {
+    /// The temperatures in °C by reference.
+    pub temperatures: HashMap<String, f64>,
+    /// The power dissipation at the final temperatures.
+    pub power: PowerReport,
+    pub iterations: usize,
+}
+
+/// Electro-thermal co-simulation.
+///
+/// The transient is simulated with the devices at their junction
+/// temperature, the power waveform of every device drives the thermal
+/// network of its path, starting at the ambient temperature. The junction
+/// temperature at the end of the thermal transient is used for the next
+/// simulation, which is repeated until the temperatures change less than
+/// the tolerance. The temperatures are set with the `TEMP` instance
+/// parameter of the resistors, diodes and transistors.
+///
+/// ```ignore
+/// let mut thermal = Thermal::new(25.0);
+/// thermal.add("Q1", vec![(1.5, 0.01), (40.0, 2.0)])?;
+/// let result = thermal.run(&mut simulation, "1u", "10m")?;
+/// assert!(result.temperatures["Q1"] < 125.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thermal {
+    /// The ambient temperature in °C.
+    pub ambient: f64,
+    pub paths: Vec<ThermalPath>,
+    pub max_iterations: usize,
+    /// The tolerance of the temperatures in K.
+    pub tolerance: f64,
+}
+
+/// Set the temperature of the device with the `TEMP` instance parameter.
+fn set_temperature(circuit: &mut Circuit, reference: &str, temperature: f64) -> Result<(), Error> {
+    for item in circuit.items.iter_mut() {
+        if item.reference().map(|r| r.as_str()) != Some(reference) {
+            continue;
+        }
+        return match item {
+            CircuitItem::R(_, _, _, ref mut value, _)
+            | CircuitItem::D(_, _, _, ref mut value)
+            | CircuitItem::Q(_, _, _, _, ref mut value) => {
+                *value = format!("{} TEMP={:.3}", value, temperature);
+                Ok(())
+            }
+            _ => Err(Error::InvalidProperty(
+                reference.to_string(),
+                String::from("TEMP"),
+            )),
+        };
+    }
+    Err(Error::UnknownCircuitElement(reference.to_string()))
+}
+
+impl Thermal {
+    pub fn new(ambient: f64) -> Self {
+        Self {
+            ambient,
+            paths: Vec::new(),
+            max_iterations: 20,
+            tolerance: 0.1,
+        }
+    }
+    /// Add the thermal path of the device.
+    pub fn add(&mut self, reference: &str, stages: Vec<(f64, f64)>) -> Result<(), Error> {
+        if stages.is_empty() || stages.iter().any(|(r, c)| *r <= 0.0 || *c < 0.0) {
+            return Err(Error::InvalidValue(format!("{:?}", stages)));
+        }
+        self.paths.push(ThermalPath {
+            reference: reference.to_string(),
+            stages,
+        });
+        Ok(())
+    }
+    /// Set the maximum number of simulations, the default is 20.
+    pub fn set_max_iterations(&mut self, iterations: usize) {
+        self.max_iterations = iterations;
+    }
+    /// Stop when the temperatures change less than the tolerance, the default is 0.1K.
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        self.tolerance = tolerance;
+    }
+    /// The thermal network of the device driven by the power waveform.
+    ///
+    /// The voltages are the temperatures in °C, the junction is the node `j`
+    /// and the power is injected with a PWL current source. The initial
+    /// conditions set the nodes to the ambient temperature.
+    pub fn network(&self, reference: &str, time: &[f64], power: &[f64]) -> Result<Circuit, Error> {
+        let path = self
+            .paths
+            .iter()
+            .find(|p| p.reference == reference)
+            .ok_or_else(|| Error::UnknownCircuitElement(reference.to_string()))?;
+        let mut circuit = Circuit::new(format!("thermal {}", reference), Vec::new());
+        let pwl: Vec<String> = time
+            .iter()
+            .zip(power)
+            .map(|(t, p)| format!("{} {}", t, p))
+            .collect();
+        circuit.current(
+            String::from("ITH"),
+            "0",
+            "j",
+            format!("PWL({})", pwl.join(" ")),
+        )?;
+        let mut node = String::from("j");
+        let mut initial = Vec::new();
+        for (i, (r, c)) in path.stages.iter().enumerate() {
+            initial.push(format!("v({})={}", node, self.ambient));
+            let next = format!("t{}", i + 1);
+            circuit.capacitor(
+                format!("CTH{}", i + 1),
+                node.clone(),
+                String::from("0"),
+                c.to_string(),
+            )?;
+            circuit.resistor(format!("RTH{}", i + 1), node, next.clone(), r.to_string())?;
+            node = next;
+        }
+        circuit.voltage(
+            String::from("VAMB"),
+            node.as_str(),
+            "0",
+            format!("DC {}", self.ambient),
+        )?;
+        circuit.raw(&format!(".ic {}", initial.join(" ")));
+        Ok(circuit)
+    }
+    /// Run the transients until the junction temperatures converge.
+    pub fn run(
+        &self,
+        simulation: &mut Simulation,
+        step: &str,
+        stop: &str,
+    ) -> Result<ThermalResult, Error> {
+        let base = simulation.circuit.clone();
+        let tran = Tran::new(step, stop).uic();
+        self.evaluate(
+            &base,
+            |circuit| simulation.with_circuit(circuit).power(step, stop, "0"),
+            |network| Simulation::new(network).transient(&tran),
+        )
+    }
+    fn evaluate<S, T>(
+        &self,
+        circuit: &Circuit,
+        mut simulate: S,
+        mut heat: T,
+    ) -> Result<ThermalResult, Error>
+    where
+        S: FnMut(Circuit) -> Result<PowerReport, Error>,
+        T: FnMut(Circuit) -> Result<Vectors, Error>,
+    {
+        let mut temperatures: HashMap<String, f64> = self
+            .paths
+            .iter()
+            .map(|p| (p.reference.clone(), self.ambient))
+            .collect();
+        for iteration in 1..=self.max_iterations {
+            let mut circuit = circuit.clone();
+            for (reference, temperature) in &temperatures {
+                set_temperature(&mut circuit, reference, *temperature)?;
+            }
+            let power = simulate(circuit)?;
+            let mut change: f64 = 0.0;
+            for path in &self.paths {
+                let dissipation = power
+                    .get(&path.reference)
+                    .ok_or_else(|| Error::UnknownCircuitElement(path.reference.clone()))?;
+                let network = self.network(&path.reference, &power.time, &dissipation.power)?;
+                let temperature = heat(network)?
+                    .get("j")
+                    .and_then(|j| j.last().copied())
+                    .ok_or_else(|| Error::Signal(String::from("no junction temperature")))?;
+                let old = temperatures.insert(path.reference.clone(), temperature);
+                change = change.max((temperature - old.unwrap_or(self.ambient)).abs());
+            }
+            if change < self.tolerance {
+                return Ok(ThermalResult {
+                    temperatures,
+                    power,
+                    iterations: iteration,
+                });
+            }
+        }
+        Err(Error::Signal(format!(
+            "the temperatures do not converge after {} iterations",
+            self.max_iterations
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::Thermal;
+    use crate::{power::Dissipation, Circuit, PowerReport};
+
+    fn circuit() -> Circuit {
+        let mut circuit = Circuit::new(String::from("test"), Vec::new());
+        circuit
+            .resistor(
+                String::from("R1"),
+                String::from("in"),
+                String::from("0"),
+                String::from("10"),
+            )
+            .unwrap();
+        circuit
+    }
+
+    #[test]
+    fn network() {
+        let mut thermal = Thermal::new(25.0);
+        thermal.add("R1", vec![(2.0, 0.01), (30.0, 1.0)]).unwrap();
+        assert_eq!(32.0, thermal.paths[0].resistance());
+        assert!(thermal.add("R2", vec![(-1.0, 0.0)]).is_err());
+        let network = thermal.network("R1", &[0.0, 1e-3], &[0.0, 2.0]).unwrap();
+        assert_eq!(
+            vec![
+                "ITH 0 j PWL(0 0 0.001 2)",
+                "CTH1 j 0 0.01",
+                "RTH1 j t1 2",
+                "CTH2 t1 0 1",
+                "RTH2 t1 t2 30",
+                "VAMB t2 0 DC 25",
+                ".ic v(j)=25 v(t1)=25",
+            ],
+            network.to_str(false).unwrap()
+        );
+    }
+    #[test]
+    fn converge() {
+        let mut thermal = Thermal::new(25.0);
+        thermal.add("R1", vec![(10.0, 0.1)]).unwrap();
+        //the power rises by 1% per K
+        let result = thermal
+            .evaluate(
+                &circuit(),
+                |circuit| {
+                    let line = circuit.to_str(false)?[0].clone();
+                    let temperature: f64 = line.split("TEMP=").nth(1).unwrap().parse().unwrap();
+                    let power = 1.0 + 0.01 * (temperature - 25.0);
+                    Ok(PowerReport {
+                        time: vec![0.0, 1.0],
+                        components: vec![Dissipation {
+                            reference: String::from("R1"),
+                            power: vec![power, power],
+                            average: power,
+                            peak: power,
+                        }],
+                    })
+                },
+                |network| {
+                    //the thermal network settles within the transient
+                    let line = network.to_str(false)?[0].clone();
+                    let power: f64 = line
+                        .trim_end_matches(')')
+                        .rsplit(' ')
+                        .next()
+                        .unwrap()
+                        .parse()
+                        .unwrap();
+                    let settled = 25.0 + 10.0 * power;
+                    Ok(HashMap::from([(String::from("j"), vec![25.0, settled])]))
+                },
+            )
+            .unwrap();
+        assert!((result.temperatures["R1"] - 36.111).abs() < 0.1);
+        assert!(result.iterations > 2);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{error::Error, node::IntoNode, Circuit, Value};
+
+/// The offset from °C to K.
+const KELVIN: f64 = 273.15;
+
+/// A temperature dependent resistor.
+///
+/// The resistance follows the circuit temperature `temper`, the thermistor
+/// works with the `.temp` statement and with the temperature sweep of the
+/// simulation. The self heating is not modelled.
+///
+/// ```ignore
+/// let ntc = Thermistor::Beta { r25: 10e3, beta: 3950.0 };
+/// ntc.build(&mut circuit, "RT1", "sense", "GND")?;
+/// let results = simulation.sweep_temperature(&[-20.0, 25.0, 85.0], &analysis)?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Thermistor {
+    /// The resistance at 25°C and the beta value in K, a negative beta is a PTC.
+    Beta { r25: f64, beta: f64 },
+    /// The Steinhart-Hart coefficients of `1/T = A + B ln(R) + C ln(R)^3`.
+    SteinhartHart { a: f64, b: f64, c: f64 },
+}
+
+impl Thermistor {
+    /// The resistance at the temperature in °C.
+    pub fn resistance(&self, temperature: f64) -> Result<Value, Error> {
+        self.validate()?;
+        let kelvin = temperature + KELVIN;
+        let resistance = match self {
+            Thermistor::Beta { r25, beta } => {
+                r25 * (beta * (1.0 / kelvin - 1.0 / (25.0 + KELVIN))).exp()
+            }
+            Thermistor::SteinhartHart { a, b, c } => {
+                let x = (a - 1.0 / kelvin) / c;
+                let y = ((b / (3.0 * c)).powi(3) + x.powi(2) / 4.0).sqrt();
+                ((y - x / 2.0).cbrt() - (y + x / 2.0).cbrt()).exp()
+            }
+        };
+        Ok(Value::new(resistance))
+    }
+    /// The resistance as an expression of the circuit temperature.
+    pub fn expression(&self) -> Result<String, Error> {
+        self.validate()?;
+        let kelvin = format!("(temper+{})", KELVIN);
+        Ok(match self {
+            Thermistor::Beta { r25, beta } => format!(
+                "{:e}*exp({:e}*(1/{}-1/{}))",
+                r25,
+                beta,
+                kelvin,
+                25.0 + KELVIN
+            ),
+            Thermistor::SteinhartHart { a, b, c } => {
+                //the real root of the cubic equation in ln(R)
+                let x = format!("({:e}-1/{})/{:e}", a, kelvin, c);
+                let y = format!("sqrt({:e}+pow({},2)/4)", (b / (3.0 * c)).powi(3), x);
+                format!("exp(pow({1}-({0})/2,1/3)-pow({1}+({0})/2,1/3))", x, y)
+            }
+        })
+    }
+    /// Add the thermistor as a behavioral current source between the nodes.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        reference: &str,
+        n0: impl IntoNode,
+        n1: impl IntoNode,
+    ) -> Result<(), Error> {
+        let (n0, n1) = (String::from(n0.into_node()?), String::from(n1.into_node()?));
+        let voltage = |node: &str| {
+            if circuit.is_ground(node) {
+                String::from("0")
+            } else {
+                node.to_string()
+            }
+        };
+        let value = format!(
+            "I=V({},{})/({})",
+            voltage(&n0),
+            voltage(&n1),
+            self.expression()?
+        );
+        circuit.behavioral(format!("B{}", reference), n0, n1, value)
+    }
+    fn validate(&self) -> Result<(), Error> {
+        let valid = match self {
+            Thermistor::Beta { r25, .. } => *r25 > 0.0,
+            Thermistor::SteinhartHart { c, .. } => *c != 0.0,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidValue(format!("{:?}", self)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Thermistor;
+    use crate::Circuit;
+
+    #[test]
+    fn thermistor() {
+        let ntc = Thermistor::Beta {
+            r25: 10e3,
+            beta: 3950.0,
+        };
+        assert_eq!("10k", ntc.resistance(25.0).unwrap().to_string());
+        assert_eq!("33.62060372143574k", ntc.resistance(0.0).unwrap().to_string());
+        let sh = Thermistor::SteinhartHart {
+            a: 1.009249522e-3,
+            b: 2.378405444e-4,
+            c: 2.019202697e-7,
+        };
+        assert_eq!("9.87664445098442k", sh.resistance(25.0).unwrap().to_string());
+        let mut circuit = Circuit::new(String::from("ntc"), Vec::new());
+        ntc.build(&mut circuit, "RT1", "sense", "GND").unwrap();
+        assert_eq!(
+            vec!["BRT1 sense 0 I=V(sense,0)/(1e4*exp(3.95e3*(1/(temper+273.15)-1/298.15)))"],
+            circuit.to_str(false).unwrap()
+        );
+        assert!(Thermistor::Beta {
+            r25: 0.0,
+            beta: 3950.0
+        }
+        .resistance(25.0)
+        .is_err());
+    }
+}
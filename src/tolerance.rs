@@ -0,0 +1,40 @@
+/// The distribution of the component values within the tolerance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    /// All values within the tolerance are equally likely.
+    Uniform,
+    /// Normal distribution, the tolerance is three standard deviations.
+    Gaussian,
+}
+
+/// The tolerance of a component value, e.g. `Tolerance::new(0.01)` for 1%.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// The relative tolerance, 0.05 is 5%.
+    pub relative: f64,
+    pub distribution: Distribution,
+}
+
+impl Tolerance {
+    /// Create a tolerance with a uniform distribution.
+    pub fn new(relative: f64) -> Self {
+        Self {
+            relative,
+            distribution: Distribution::Uniform,
+        }
+    }
+    /// Create a tolerance with a gaussian distribution.
+    pub fn gaussian(relative: f64) -> Self {
+        Self {
+            relative,
+            distribution: Distribution::Gaussian,
+        }
+    }
+    /// The lowest and highest value within the tolerance.
+    pub fn limits(&self, nominal: f64) -> (f64, f64) {
+        (
+            nominal * (1.0 - self.relative),
+            nominal * (1.0 + self.relative),
+        )
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use crate::{error::Error, Simulation};
+
+/// The settings of a transient analysis.
+///
+/// ```ignore
+/// let tran = Tran::new("1u", "10m").tmax("100n").uic();
+/// let result = simulation.transient(&tran)?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tran {
+    pub step: String,
+    pub stop: String,
+    pub start: String,
+    /// The largest internal time step.
+    pub tmax: Option<String>,
+    /// Start from the initial conditions without an operating point, e.g.
+    /// for the startup of oscillators.
+    pub uic: bool,
+    /// Interpolate the results on the time points of the step.
+    pub interpolate: bool,
+}
+
+impl Tran {
+    pub fn new(step: &str, stop: &str) -> Self {
+        Self {
+            step: step.to_string(),
+            stop: stop.to_string(),
+            start: String::from("0"),
+            tmax: None,
+            uic: false,
+            interpolate: false,
+        }
+    }
+    /// The time when the results start to be saved.
+    pub fn start(mut self, start: &str) -> Self {
+        self.start = start.to_string();
+        self
+    }
+    pub fn tmax(mut self, tmax: &str) -> Self {
+        self.tmax = Some(tmax.to_string());
+        self
+    }
+    pub fn uic(mut self) -> Self {
+        self.uic = true;
+        self
+    }
+    pub fn interpolate(mut self) -> Self {
+        self.interpolate = true;
+        self
+    }
+    /// The ngspice command of the analysis.
+    pub fn command(&self) -> String {
+        let mut command = format!("tran {} {} {}", self.step, self.stop, self.start);
+        if let Some(tmax) = &self.tmax {
+            command.push_str(&format!(" {}", tmax));
+        }
+        if self.uic {
+            command.push_str(" uic");
+        }
+        command
+    }
+}
+
+impl Simulation {
+    /// Run the transient analysis with the settings.
+    pub fn transient(&mut self, tran: &Tran) -> Result<HashMap<String, Vec<f64>>, Error> {
+        let lines = match tran.interpolate {
+            true => vec![String::from(".options interp")],
+            false => Vec::new(),
+        };
+        self.run_with(&lines, &tran.command())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tran;
+
+    #[test]
+    fn tran() {
+        assert_eq!("tran 1u 10m 0", Tran::new("1u", "10m").command());
+        let tran = Tran::new("1u", "10m")
+            .start("1m")
+            .tmax("100n")
+            .uic()
+            .interpolate();
+        assert_eq!("tran 1u 10m 1m 100n uic", tran.command());
+        assert!(tran.interpolate);
+    }
+}
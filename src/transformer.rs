@@ -0,0 +1,140 @@
+use crate::{error::Error, node::IntoNode, Circuit, Value};
+
+/// A transformer from two coupled inductors.
+///
+/// The secondary inductance is the magnetizing inductance divided by the
+/// square of the turns ratio. The leakage inductance is measured at the
+/// primary with the secondary shorted, the coupling factor is
+/// `sqrt(1 - leakage / magnetizing)`.
+///
+/// ```ignore
+/// //an audio output transformer 5k:8 with 20H primary inductance
+/// let mut transformer = Transformer::new((5000.0_f64 / 8.0).sqrt(), 20.0);
+/// transformer.set_leakage(10e-3);
+/// transformer.set_resistance(200.0, 0.5);
+/// transformer.build(&mut circuit, "T1", ("plate", "b_plus"), ("spk", "GND"))?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transformer {
+    /// The turns ratio of the primary to the secondary winding.
+    pub ratio: f64,
+    /// The primary inductance in H.
+    pub magnetizing: f64,
+    /// The leakage inductance referred to the primary in H.
+    pub leakage: f64,
+    /// The resistance of the primary and secondary winding.
+    pub resistance: (f64, f64),
+}
+
+impl Transformer {
+    pub fn new(ratio: f64, magnetizing: f64) -> Self {
+        Self {
+            ratio,
+            magnetizing,
+            leakage: 0.0,
+            resistance: (0.0, 0.0),
+        }
+    }
+    pub fn set_leakage(&mut self, leakage: f64) {
+        self.leakage = leakage;
+    }
+    /// Set the resistance of the windings, zero omits the resistor.
+    pub fn set_resistance(&mut self, primary: f64, secondary: f64) {
+        self.resistance = (primary, secondary);
+    }
+    /// The coupling factor of the inductors.
+    pub fn coupling(&self) -> Result<f64, Error> {
+        if self.ratio <= 0.0
+            || self.magnetizing <= 0.0
+            || !(0.0..self.magnetizing).contains(&self.leakage)
+        {
+            return Err(Error::InvalidValue(format!(
+                "ratio {}, magnetizing {}, leakage {}",
+                self.ratio,
+                Value::new(self.magnetizing),
+                Value::new(self.leakage)
+            )));
+        }
+        Ok((1.0 - self.leakage / self.magnetizing).sqrt())
+    }
+    /// Add the inductors `L<reference>P` and `L<reference>S`, the winding
+    /// resistors and the coupling `K<reference>` to the circuit.
+    pub fn build(
+        &self,
+        circuit: &mut Circuit,
+        reference: &str,
+        primary: (impl IntoNode, impl IntoNode),
+        secondary: (impl IntoNode, impl IntoNode),
+    ) -> Result<(), Error> {
+        let coupling = self.coupling()?;
+        let windings = [
+            (
+                "P",
+                primary.0.into_node()?,
+                primary.1.into_node()?,
+                self.magnetizing,
+                self.resistance.0,
+            ),
+            (
+                "S",
+                secondary.0.into_node()?,
+                secondary.1.into_node()?,
+                self.magnetizing / self.ratio.powi(2),
+                self.resistance.1,
+            ),
+        ];
+        for (winding, n0, n1, inductance, resistance) in windings {
+            let mut start = String::from(n0);
+            if resistance > 0.0 {
+                let node = format!("{}_{}", reference, winding).to_lowercase();
+                circuit.resistor(
+                    format!("R{}{}", reference, winding),
+                    start,
+                    node.clone(),
+                    Value::new(resistance).to_string(),
+                )?;
+                start = node;
+            }
+            circuit.inductor(
+                format!("L{}{}", reference, winding),
+                start,
+                String::from(n1),
+                Value::new(inductance).to_string(),
+            )?;
+        }
+        circuit.coupling(
+            format!("K{}", reference),
+            format!("L{}P", reference),
+            format!("L{}S", reference),
+            format!("{:.6}", coupling),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transformer;
+    use crate::Circuit;
+
+    #[test]
+    fn transformer() {
+        let mut transformer = Transformer::new(10.0, 1.0);
+        transformer.set_leakage(0.01);
+        transformer.set_resistance(10.0, 0.0);
+        let mut circuit = Circuit::new(String::from("transformer"), Vec::new());
+        transformer
+            .build(&mut circuit, "T1", ("in", "GND"), ("out", "GND"))
+            .unwrap();
+        assert_eq!(
+            vec![
+                "RT1P in t1_p 10",
+                "LT1P t1_p 0 1",
+                "LT1S out 0 10m",
+                "KT1 LT1P LT1S 0.994987",
+            ],
+            circuit.to_str(false).unwrap()
+        );
+        transformer.set_leakage(2.0);
+        assert!(transformer.coupling().is_err());
+    }
+}
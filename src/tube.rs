@@ -0,0 +1,67 @@
+use crate::{circuit::CircuitItem, error::Error, node::IntoNode, Circuit};
+
+impl Circuit {
+    /// Add a vacuum tube from a subcircuit library, e.g. the models of Koren.
+    ///
+    /// The pins are plate, grid and cathode for triodes and plate, screen
+    /// grid, control grid and cathode for pentodes and beam tetrodes. The
+    /// model must be defined in the circuit, in an explicit include or in
+    /// a library of the pathlist, the library is included in the netlist.
+    ///
+    /// ```ignore
+    /// let mut circuit = Circuit::new(String::from("preamp"), vec![String::from("models/tubes")]);
+    /// circuit.tube(String::from("V1"), vec!["plate", "grid", "cathode"], String::from("12AX7"))?;
+    /// circuit.tube(String::from("V2"), vec!["p", "g2", "g1", "k"], String::from("EL34"))?;
+    /// ```
+    pub fn tube<N: IntoNode>(
+        &mut self,
+        reference: String,
+        pins: Vec<N>,
+        model: String,
+    ) -> Result<(), Error> {
+        if pins.len() != 3 && pins.len() != 4 {
+            return Err(Error::NodeCount(reference, 3, pins.len()));
+        }
+        let explicit = self
+            .items
+            .iter()
+            .any(|item| matches!(item, CircuitItem::Include(..)));
+        if !self.defines(&model) && !explicit {
+            self.get_includes(model.clone())?;
+        }
+        self.circuit(reference, pins, model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, Circuit};
+
+    #[test]
+    fn tube() {
+        let mut circuit = Circuit::new(String::from("preamp"), vec![String::from("files/spice/")]);
+        circuit.set_library(Vec::new());
+        circuit
+            .tube(
+                String::from("V1"),
+                vec!["plate", "grid", "cathode"],
+                String::from("12AX7"),
+            )
+            .unwrap();
+        assert_eq!(
+            vec![
+                String::from(".include files/spice/12AX7.lib\n"),
+                String::from("XV1 plate grid cathode 12AX7"),
+            ],
+            circuit.to_str(false).unwrap()
+        );
+        assert!(matches!(
+            circuit.tube(String::from("V2"), vec!["p", "g", "k"], String::from("6V6")),
+            Err(Error::SpiceModelNotFound(_))
+        ));
+        assert!(matches!(
+            circuit.tube(String::from("V3"), vec!["p", "k"], String::from("12AX7")),
+            Err(Error::NodeCount(_, 3, 2))
+        ));
+    }
+}
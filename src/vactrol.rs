@@ -0,0 +1,152 @@
+use crate::{error::Error, Circuit, Value};
+
+/// An optocoupler of a LED and a light dependent resistor.
+///
+/// The brightness follows the LED current with the attack time constant
+/// when it rises and the decay time constant when it falls, full
+/// brightness is reached at the full current. The resistance goes
+/// logarithmically from the dark to the light resistance. The subcircuit
+/// has the nodes `anode cathode ldr1 ldr2`.
+///
+/// ```ignore
+/// let mut vactrol = Vactrol::new(1e3, 10e6);
+/// vactrol.set_times(2.5e-3, 35e-3);
+/// vactrol.add(&mut circuit, "vtl5c3")?;
+/// circuit.circuit(String::from("U1"), vec!["led", "GND", "in", "out"], String::from("vtl5c3"))?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vactrol {
+    /// The resistance at full brightness.
+    pub light: f64,
+    /// The resistance without light.
+    pub dark: f64,
+    /// The time constant of the rising brightness.
+    pub attack: f64,
+    /// The time constant of the falling brightness.
+    pub decay: f64,
+    /// The LED current for full brightness.
+    pub current: f64,
+}
+
+impl Vactrol {
+    pub fn new(light: f64, dark: f64) -> Self {
+        Self {
+            light,
+            dark,
+            attack: 5e-3,
+            decay: 50e-3,
+            current: 20e-3,
+        }
+    }
+    /// Set the attack and decay time constants, the default is 5ms and 50ms.
+    pub fn set_times(&mut self, attack: f64, decay: f64) {
+        self.attack = attack;
+        self.decay = decay;
+    }
+    /// Set the LED current for full brightness, the default is 20mA.
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+    /// The circuit of the subcircuit.
+    pub fn subcircuit(&self, name: &str) -> Result<Circuit, Error> {
+        if [self.light, self.dark, self.attack, self.decay, self.current]
+            .iter()
+            .any(|v| *v <= 0.0)
+        {
+            return Err(Error::InvalidValue(format!("{:?}", self)));
+        }
+        let mut circuit = Circuit::new(name.to_string(), Vec::new());
+        circuit.model(
+            String::from("led"),
+            String::from("D"),
+            String::from("IS=1e-22 N=1.5 RS=5"),
+        );
+        circuit.diode(
+            String::from("D1"),
+            String::from("anode"),
+            String::from("sense"),
+            String::from("led"),
+        )?;
+        circuit.voltage(String::from("V1"), "sense", "cathode", String::from("DC 0"))?;
+        //the brightness is the voltage of a 1F capacitor
+        let target = format!("min(max(i(V1),0)/{:e},1)", self.current);
+        circuit.behavioral(
+            String::from("B1"),
+            "0",
+            "light",
+            format!(
+                "I=({0}-V(light))/(({0}>V(light))?{1:e}:{2:e})",
+                target, self.attack, self.decay
+            ),
+        )?;
+        circuit.capacitor(
+            String::from("C1"),
+            String::from("light"),
+            String::from("0"),
+            String::from("1"),
+        )?;
+        circuit.resistor(
+            String::from("R1"),
+            String::from("light"),
+            String::from("0"),
+            String::from("1G"),
+        )?;
+        circuit.behavioral(
+            String::from("B2"),
+            "ldr1",
+            "ldr2",
+            format!(
+                "I=V(ldr1,ldr2)/({:e}*pow({:e},V(light)))",
+                self.dark,
+                self.light / self.dark
+            ),
+        )?;
+        Ok(circuit)
+    }
+    /// Add the subcircuit with the name to the circuit.
+    pub fn add(&self, circuit: &mut Circuit, name: &str) -> Result<(), Error> {
+        circuit.subcircuit(
+            name.to_string(),
+            vec!["anode", "cathode", "ldr1", "ldr2"],
+            self.subcircuit(name)?,
+        )
+    }
+    /// The resistance at the LED current in the steady state.
+    pub fn resistance(&self, current: f64) -> Value {
+        let brightness = (current / self.current).clamp(0.0, 1.0);
+        Value::new(self.dark * (self.light / self.dark).powf(brightness))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Vactrol;
+
+    #[test]
+    fn vactrol() {
+        let mut vactrol = Vactrol::new(1e3, 1e6);
+        vactrol.set_times(2e-3, 40e-3);
+        assert_eq!("1Meg", vactrol.resistance(0.0).to_string());
+        assert_eq!("31.622776601683792k", vactrol.resistance(10e-3).to_string());
+        assert_eq!("1k", vactrol.resistance(50e-3).to_string());
+        let lines = vactrol
+            .subcircuit("vactrol")
+            .unwrap()
+            .to_str(false)
+            .unwrap();
+        assert_eq!(
+            vec![
+                ".model led D(IS=1e-22 N=1.5 RS=5)",
+                "D1 anode sense led",
+                "V1 sense cathode DC 0",
+                "B1 0 light I=(min(max(i(V1),0)/2e-2,1)-V(light))/((min(max(i(V1),0)/2e-2,1)>V(light))?2e-3:4e-2)",
+                "C1 light 0 1",
+                "R1 light 0 1G",
+                "B2 ldr1 ldr2 I=V(ldr1,ldr2)/(1e6*pow(1e-3,V(light)))",
+            ],
+            lines
+        );
+        vactrol.set_current(0.0);
+        assert!(vactrol.subcircuit("vactrol").is_err());
+    }
+}
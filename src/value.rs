@@ -0,0 +1,254 @@
+use crate::error::Error;
+
+/// The scale factors of spice as powers of ten, `M` is milli and `Meg` is mega.
+const SCALES: [(&str, i32); 10] = [
+    ("meg", 6),
+    ("t", 12),
+    ("g", 9),
+    ("k", 3),
+    ("m", -3),
+    ("u", -6),
+    ("n", -9),
+    ("p", -12),
+    ("f", -15),
+    ("a", -18),
+];
+
+/// The `mil` scale factor, one thousandth of an inch in meter.
+const MIL: f64 = 25.4e-6;
+
+/// The prefixes used to format the values with the power of ten.
+const PREFIXES: [(&str, i32); 11] = [
+    ("T", 12),
+    ("G", 9),
+    ("Meg", 6),
+    ("k", 3),
+    ("", 0),
+    ("m", -3),
+    ("u", -6),
+    ("n", -9),
+    ("p", -12),
+    ("f", -15),
+    ("a", -18),
+];
+
+/// A numeric value with the spice scale factors.
+///
+/// Parses values like `4.7k`, `100n` or `2Meg` and formats them with
+/// engineering prefixes. Units after the scale factor are ignored like
+/// ngspice does, e.g. `10uF`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Value(f64);
+
+impl Value {
+    pub fn new(value: f64) -> Self {
+        Self(value)
+    }
+    pub fn as_f64(&self) -> f64 {
+        self.0
+    }
+}
+
+/// The standard series of preferred component values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ESeries {
+    E12,
+    E24,
+    E96,
+}
+
+const E12: [f64; 12] = [1.0, 1.2, 1.5, 1.8, 2.2, 2.7, 3.3, 3.9, 4.7, 5.6, 6.8, 8.2];
+const E24: [f64; 24] = [
+    1.0, 1.1, 1.2, 1.3, 1.5, 1.6, 1.8, 2.0, 2.2, 2.4, 2.7, 3.0, 3.3, 3.6, 3.9, 4.3, 4.7, 5.1,
+    5.6, 6.2, 6.8, 7.5, 8.2, 9.1,
+];
+const E96: [f64; 96] = [
+    1.00, 1.02, 1.05, 1.07, 1.10, 1.13, 1.15, 1.18, 1.21, 1.24, 1.27, 1.30, 1.33, 1.37, 1.40,
+    1.43, 1.47, 1.50, 1.54, 1.58, 1.62, 1.65, 1.69, 1.74, 1.78, 1.82, 1.87, 1.91, 1.96, 2.00,
+    2.05, 2.10, 2.15, 2.21, 2.26, 2.32, 2.37, 2.43, 2.49, 2.55, 2.61, 2.67, 2.74, 2.80, 2.87,
+    2.94, 3.01, 3.09, 3.16, 3.24, 3.32, 3.40, 3.48, 3.57, 3.65, 3.74, 3.83, 3.92, 4.02, 4.12,
+    4.22, 4.32, 4.42, 4.53, 4.64, 4.75, 4.87, 4.99, 5.11, 5.23, 5.36, 5.49, 5.62, 5.76, 5.90,
+    6.04, 6.19, 6.34, 6.49, 6.65, 6.81, 6.98, 7.15, 7.32, 7.50, 7.68, 7.87, 8.06, 8.25, 8.45,
+    8.66, 8.87, 9.09, 9.31, 9.53, 9.76,
+];
+
+impl ESeries {
+    /// The values of one decade.
+    pub fn values(&self) -> &'static [f64] {
+        match self {
+            ESeries::E12 => &E12,
+            ESeries::E24 => &E24,
+            ESeries::E96 => &E96,
+        }
+    }
+    /// The series values of the decade of the value and the neighbour decades.
+    fn candidates(&self, value: f64) -> Vec<f64> {
+        let decade = value.abs().log10().floor() as i32;
+        let mut result = Vec::new();
+        for d in decade - 1..=decade + 1 {
+            let scale = 10f64.powi(d);
+            for v in self.values() {
+                //round away the floating point noise of the scaling
+                result.push(format!("{:.3e}", v * scale).parse::<f64>().unwrap());
+            }
+        }
+        result
+    }
+}
+
+impl Value {
+    /// The series value with the smallest ratio to the value.
+    pub fn nearest(&self, series: ESeries) -> Value {
+        self.snap(series, |c| (c / self.0.abs()).ln().abs())
+    }
+    /// The smallest series value which is equal or above the value.
+    pub fn above(&self, series: ESeries) -> Value {
+        self.snap(series, |c| if c >= self.0.abs() * (1.0 - 1e-9) { c } else { f64::MAX })
+    }
+    /// The largest series value which is equal or below the value.
+    pub fn below(&self, series: ESeries) -> Value {
+        self.snap(series, |c| if c <= self.0.abs() * (1.0 + 1e-9) { -c } else { f64::MAX })
+    }
+    fn snap(&self, series: ESeries, cost: impl Fn(f64) -> f64) -> Value {
+        if self.0 == 0.0 || !self.0.is_finite() {
+            return *self;
+        }
+        let best = series
+            .candidates(self.0)
+            .into_iter()
+            .min_by(|a, b| cost(*a).total_cmp(&cost(*b)))
+            .unwrap();
+        Value(best.copysign(self.0))
+    }
+}
+
+impl std::str::FromStr for Value {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        //the number ends before the first letter which is not an exponent
+        let mut end = 0;
+        let chars: Vec<char> = text.chars().collect();
+        while end < chars.len() {
+            let c = chars[end];
+            if c.is_ascii_digit() || c == '.' || ((c == '+' || c == '-') && end == 0) {
+                end += 1;
+            } else if (c == 'e' || c == 'E')
+                && end > 0
+                && chars
+                    .get(end + 1)
+                    .map(|n| n.is_ascii_digit() || *n == '+' || *n == '-')
+                    .unwrap_or(false)
+            {
+                end += 2;
+            } else {
+                break;
+            }
+        }
+        let number: f64 = text[..end]
+            .parse()
+            .map_err(|_| Error::InvalidValue(s.to_string()))?;
+        let suffix = text[end..].to_lowercase();
+        if !suffix.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(Error::InvalidValue(s.to_string()));
+        }
+        if suffix.starts_with("mil") {
+            return Ok(Value(number * MIL));
+        }
+        let power = SCALES
+            .iter()
+            .find(|(prefix, _)| suffix.starts_with(prefix))
+            .map(|(_, power)| *power)
+            .unwrap_or(0);
+        //the scale is added to the exponent, a multiplication is not exact
+        let (mantissa, exponent) = match text[..end].split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().unwrap_or(0)),
+            None => (&text[..end], 0),
+        };
+        format!("{}e{}", mantissa, exponent + power)
+            .parse()
+            .map(Value)
+            .map_err(|_| Error::InvalidValue(s.to_string()))
+    }
+}
+
+/// The value with the engineering prefix, the digits are the shortest
+/// representation which parses to the same number.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0 == 0.0 || !self.0.is_finite() {
+            return write!(f, "{}", self.0);
+        }
+        //move the decimal point of the scientific notation to the prefix
+        let scientific = format!("{:e}", self.0.abs());
+        let (mantissa, exponent) = scientific.split_once('e').unwrap();
+        let exponent: i32 = exponent.parse().unwrap();
+        let (prefix, power) = PREFIXES
+            .iter()
+            .find(|(_, power)| exponent >= *power)
+            .unwrap_or(&PREFIXES[PREFIXES.len() - 1]);
+        let digits = mantissa.replace('.', "");
+        let point = exponent - power + 1;
+        let number = if point <= 0 {
+            format!("0.{}{}", "0".repeat(-point as usize), digits)
+        } else if point as usize >= digits.len() {
+            format!("{}{}", digits, "0".repeat(point as usize - digits.len()))
+        } else {
+            format!("{}.{}", &digits[..point as usize], &digits[point as usize..])
+        };
+        let sign = if self.0 < 0.0 { "-" } else { "" };
+        write!(f, "{}{}{}", sign, number, prefix)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value(value)
+    }
+}
+
+impl From<Value> for f64 {
+    fn from(value: Value) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ESeries, Value};
+
+    #[test]
+    fn e_series() {
+        assert_eq!("4.7k", Value::new(4520.0).nearest(ESeries::E12).to_string());
+        assert_eq!("5.6k", Value::new(4800.0).above(ESeries::E12).to_string());
+        assert_eq!("4.7k", Value::new(4700.0).above(ESeries::E12).to_string());
+        assert_eq!("4.3k", Value::new(4600.0).below(ESeries::E24).to_string());
+        assert_eq!("10k", Value::new(9900.0).nearest(ESeries::E24).to_string());
+        assert_eq!("1.02n", Value::new(1.021e-9).nearest(ESeries::E96).to_string());
+        assert_eq!("9.1", Value::new(9.99).below(ESeries::E24).to_string());
+    }
+    #[test]
+    fn parse_and_format() {
+        let value = |s: &str| s.parse::<Value>().unwrap().as_f64();
+        assert!((value("4.7k") - 4700.0).abs() < 1e-9);
+        assert!((value("100n") - 100e-9).abs() < 1e-18);
+        assert!((value("2Meg") - 2e6).abs() < 1e-6);
+        assert!((value("10uF") - 10e-6).abs() < 1e-15);
+        assert!((value("1e-3") - 1e-3).abs() < 1e-15);
+        assert!((value("3M") - 3e-3).abs() < 1e-15);
+        assert!("k1".parse::<Value>().is_err());
+        assert!("4k7".parse::<Value>().is_err());
+        assert_eq!("4.7k", Value::new(4700.0).to_string());
+        assert_eq!("100n", Value::new(100e-9).to_string());
+        assert_eq!("2Meg", Value::new(2e6).to_string());
+        assert_eq!("-1.5m", Value::new(-1.5e-3).to_string());
+        assert_eq!("0", Value::new(0.0).to_string());
+        assert_eq!("15.3f", Value::new(15.3e-15).to_string());
+        assert_eq!("2a", Value::new(2e-18).to_string());
+        assert_eq!("0.004a", Value::new(4e-21).to_string());
+        assert_eq!("1.5915494309189535n", Value::new(1.5915494309189535e-9).to_string());
+        for value in [1.0 / 3.0, 4.7e-16, 123456.789, -2.2e-19] {
+            assert_eq!(value, Value::new(value).to_string().parse::<Value>().unwrap().as_f64());
+        }
+    }
+}
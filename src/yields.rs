@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use crate::{corner::Measure, montecarlo::Ensemble, stats::Statistics};
+
+/// A measurement with the pass/fail limits.
+#[derive(Clone)]
+pub struct Spec {
+    pub name: String,
+    pub measure: Measure,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Spec {
+    pub fn new(name: &str, measure: Measure, min: Option<f64>, max: Option<f64>) -> Self {
+        Self {
+            name: name.to_string(),
+            measure,
+            min,
+            max,
+        }
+    }
+    /// Check if the value is within the limits, NaN always fails.
+    pub fn pass(&self, value: f64) -> bool {
+        !value.is_nan()
+            && !self.min.is_some_and(|min| value < min)
+            && !self.max.is_some_and(|max| value > max)
+    }
+}
+
+impl std::fmt::Debug for Spec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Spec")
+            .field("name", &self.name)
+            .field("min", &self.min)
+            .field("max", &self.max)
+            .finish()
+    }
+}
+
+/// The yield of a Monte Carlo analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct YieldReport {
+    pub runs: usize,
+    /// The runs which pass all specs.
+    pub passed: usize,
+    /// The failed runs per spec.
+    pub failures: Vec<(String, usize)>,
+    /// The correlation of the component values with the failed runs,
+    /// sorted by the absolute correlation.
+    pub correlations: Vec<(String, f64)>,
+}
+
+impl YieldReport {
+    /// The ratio of the passed runs.
+    pub fn ratio(&self) -> f64 {
+        if self.runs == 0 {
+            return f64::NAN;
+        }
+        self.passed as f64 / self.runs as f64
+    }
+    /// The failure rate of the spec.
+    pub fn failure_rate(&self, name: &str) -> Option<f64> {
+        self.failures
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, failed)| *failed as f64 / self.runs as f64)
+    }
+}
+
+/// Pearson correlation, zero when one of the series is constant.
+fn correlation(x: &[f64], y: &[f64]) -> f64 {
+    let (sx, sy) = (Statistics::new(x), Statistics::new(y));
+    if sx.stddev() == 0.0 || sy.stddev() == 0.0 {
+        return 0.0;
+    }
+    let covariance = x
+        .iter()
+        .zip(y)
+        .map(|(x, y)| (x - sx.mean()) * (y - sy.mean()))
+        .sum::<f64>()
+        / (x.len() - 1) as f64;
+    covariance / (sx.stddev() * sy.stddev())
+}
+
+impl Ensemble {
+    /// Check the specs for every run.
+    ///
+    /// ```ignore
+    /// let specs = vec![Spec::new("gain", Arc::new(|v| v["out"][0]), Some(0.5), Some(0.51))];
+    /// let report = montecarlo.run()?.yield_report(&specs);
+    /// println!("yield: {:.1}%", report.ratio() * 100.0);
+    /// ```
+    pub fn yield_report(&self, specs: &[Spec]) -> YieldReport {
+        let runs = self.runs();
+        let mut failed = vec![false; runs];
+        let mut failures = Vec::new();
+        for spec in specs {
+            let mut count = 0;
+            for (run, value) in self.measure(|v| (spec.measure)(v)).into_iter().enumerate() {
+                if !spec.pass(value) {
+                    failed[run] = true;
+                    count += 1;
+                }
+            }
+            failures.push((spec.name.clone(), count));
+        }
+        let indicator: Vec<f64> = failed.iter().map(|f| if *f { 1.0 } else { 0.0 }).collect();
+        let mut components: HashMap<&String, Vec<f64>> = HashMap::new();
+        for values in &self.values {
+            for (reference, value) in values {
+                components.entry(reference).or_default().push(*value);
+            }
+        }
+        let mut correlations: Vec<(String, f64)> = components
+            .into_iter()
+            .filter(|(_, values)| values.len() == runs)
+            .map(|(reference, values)| (reference.clone(), correlation(&values, &indicator)))
+            .collect();
+        correlations.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()).then(a.0.cmp(&b.0)));
+        YieldReport {
+            runs,
+            passed: failed.iter().filter(|f| !**f).count(),
+            failures,
+            correlations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::Spec;
+    use crate::Ensemble;
+
+    #[test]
+    fn yield_report() {
+        let mut ensemble = Ensemble::default();
+        for i in 0..10 {
+            let r1 = 900.0 + 20.0 * i as f64;
+            let r2 = if i % 2 == 0 { 1000.0 } else { 1010.0 };
+            ensemble.values.push(HashMap::from([
+                (String::from("R1"), r1),
+                (String::from("R2"), r2),
+            ]));
+            ensemble
+                .vectors
+                .entry(String::from("out"))
+                .or_default()
+                .push(vec![r2 / (r1 + r2)]);
+        }
+        let specs = vec![Spec::new("out", Arc::new(|v| v["out"][0]), Some(0.5), None)];
+        let report = ensemble.yield_report(&specs);
+        assert_eq!(10, report.runs);
+        assert_eq!(6, report.passed);
+        assert_eq!(0.6, report.ratio());
+        assert_eq!(Some(0.4), report.failure_rate("out"));
+        assert_eq!("R1", report.correlations[0].0);
+        assert!(report.correlations[0].1 > 0.8);
+    }
+}